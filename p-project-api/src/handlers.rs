@@ -1298,6 +1298,90 @@ pub async fn get_bridge_status(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RotateBridgeCommitteeRequest {
+    pub members: Vec<String>,
+    pub threshold: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BridgeCommitteeResponse {
+    pub epoch: u64,
+    pub members: Vec<String>,
+    pub threshold: u32,
+}
+
+pub async fn rotate_bridge_committee(
+    State(state): State<AppState>,
+    Extension(claims): Extension<crate::middleware::Claims>,
+    Json(req): Json<RotateBridgeCommitteeRequest>,
+) -> Result<Json<BridgeCommitteeResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ensure_roles(&claims, &["admin"])?;
+    let svc = BridgeService::new(state.db.clone());
+    match svc.rotate_committee(&req.members, req.threshold).await {
+        Ok(committee) => Ok(Json(BridgeCommitteeResponse {
+            epoch: committee.epoch,
+            members: committee.members,
+            threshold: committee.threshold,
+        })),
+        Err(e) => Err((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e }))),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BridgeRouteRequest {
+    pub from_chain: String,
+    pub to_chain: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BridgeRouteResponse {
+    pub from_chain: String,
+    pub to_chain: String,
+    pub paused: bool,
+}
+
+pub async fn pause_bridge_route(
+    State(state): State<AppState>,
+    Extension(claims): Extension<crate::middleware::Claims>,
+    Json(req): Json<BridgeRouteRequest>,
+) -> Result<Json<BridgeRouteResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ensure_roles(&claims, &["admin"])?;
+    let svc = BridgeService::new(state.db.clone());
+    let reason = req
+        .reason
+        .unwrap_or_else(|| format!("paused by {}", claims.sub));
+    match svc
+        .pause_route(&req.from_chain, &req.to_chain, &reason)
+        .await
+    {
+        Ok(()) => Ok(Json(BridgeRouteResponse {
+            from_chain: req.from_chain,
+            to_chain: req.to_chain,
+            paused: true,
+        })),
+        Err(e) => Err((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e }))),
+    }
+}
+
+pub async fn resume_bridge_route(
+    State(state): State<AppState>,
+    Extension(claims): Extension<crate::middleware::Claims>,
+    Json(req): Json<BridgeRouteRequest>,
+) -> Result<Json<BridgeRouteResponse>, (StatusCode, Json<ErrorResponse>)> {
+    ensure_roles(&claims, &["admin"])?;
+    let svc = BridgeService::new(state.db.clone());
+    match svc.resume_route(&req.from_chain, &req.to_chain).await {
+        Ok(_) => Ok(Json(BridgeRouteResponse {
+            from_chain: req.from_chain,
+            to_chain: req.to_chain,
+            paused: false,
+        })),
+        Err(e) => Err((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e }))),
+    }
+}
+
 // Metrics and dashboards
 pub async fn get_performance_metrics() -> Json<serde_json::Value> {
     Json(serde_json::json!({ "uptime": "unknown", "ok": true }))
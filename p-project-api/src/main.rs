@@ -357,6 +357,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "/innovation/partners/register",
             post(handlers::register_partner),
         )
+        .route(
+            "/bridge/committee/rotate",
+            post(handlers::rotate_bridge_committee),
+        )
+        .route("/bridge/routes/pause", post(handlers::pause_bridge_route))
+        .route("/bridge/routes/resume", post(handlers::resume_bridge_route))
         .route_layer(middleware::from_fn(crate::middleware::require_admin))
         .route_layer(middleware::from_fn(crate::middleware::require_admin_ip))
         .route_layer(middleware::from_fn(crate::middleware::require_jwt));
@@ -1,10 +1,13 @@
+use crate::committee::Release;
 use crate::error::BridgeError;
+use crate::finality::FinalityTag;
 use async_trait::async_trait;
 
 pub struct AdapterTxStatus {
     pub tx_id: String,
-    pub status: String,
+    pub status: String, // Pending, Success, Failed, or NotFound once a tx is dropped
     pub confirmations: u32,
+    pub finality: FinalityTag, // Most final block tag the tx's block has reached
 }
 
 #[async_trait]
@@ -17,6 +20,8 @@ pub trait ChainAdapter {
         amount: f64,
         to_chain: &str,
     ) -> Result<String, BridgeError>;
+    /// Mint a transfer on this chain or release a refunded lock. Implementations must
+    /// `verify` the release before moving any funds.
     async fn mint_or_release(
         &self,
         user: &str,
@@ -24,13 +29,45 @@ pub trait ChainAdapter {
         amount: f64,
         from_chain: &str,
         source_tx: &str,
-        lock_id: Option<&str>,
+        release: &Release<'_>,
     ) -> Result<String, BridgeError>;
     async fn get_tx_status(&self, tx_id: &str) -> Result<AdapterTxStatus, BridgeError>;
     async fn extract_lock_id(&self, _tx_hash: &str) -> Result<Option<String>, BridgeError> {
         Ok(None)
     }
+    /// Tx that minted `lock_id` on this chain, or None if no mint for it has landed
+    async fn find_mint(&self, _lock_id: &str) -> Result<Option<String>, BridgeError> {
+        Err(BridgeError::Unimplemented("mint lookup by lock_id"))
+    }
     fn supports_token(&self, _token: &str) -> bool {
         true
     }
+    /// Amount of `token` the bridge holds locked on this chain for transfers to
+    /// `to_chain`, or None if the adapter cannot report it
+    async fn locked_total(
+        &self,
+        _token: &str,
+        _to_chain: &str,
+    ) -> Result<Option<f64>, BridgeError> {
+        Ok(None)
+    }
+    /// Amount of `token` the bridge has minted on this chain for transfers from
+    /// `from_chain`, or None if the adapter cannot report it
+    async fn minted_total(
+        &self,
+        _token: &str,
+        _from_chain: &str,
+    ) -> Result<Option<f64>, BridgeError> {
+        Ok(None)
+    }
+    /// Amount of `token` the bridge holds locked on this chain over every route, for
+    /// adapters that can't tell the routes apart, or None if the adapter cannot report it
+    async fn chain_locked_total(&self, _token: &str) -> Result<Option<f64>, BridgeError> {
+        Ok(None)
+    }
+    /// Amount of `token` the bridge has minted on this chain over every route, for
+    /// adapters that can't tell the routes apart, or None if the adapter cannot report it
+    async fn chain_minted_total(&self, _token: &str) -> Result<Option<f64>, BridgeError> {
+        Ok(None)
+    }
 }
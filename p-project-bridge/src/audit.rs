@@ -0,0 +1,338 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Duration, NaiveDateTime};
+use p_project_core::models::{BridgeTx, BridgeTxStatus};
+
+use crate::adapter::ChainAdapter;
+use crate::store::BridgeStore;
+
+// Supply of one token on one route, as the ledger and the chains see it
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteSupply {
+    pub token: String,
+    pub from_chain: String,
+    pub to_chain: String,
+    pub ledger_locked: f64, // Still held on from_chain per the ledger, minted or not
+    pub ledger_minted: f64,
+    pub chain_locked: Option<f64>, // None when the adapter can't report its total
+    pub chain_minted: Option<f64>,
+}
+
+impl RouteSupply {
+    /// Locked on chain minus locked per the ledger
+    pub fn locked_drift(&self) -> Option<f64> {
+        self.chain_locked.map(|locked| locked - self.ledger_locked)
+    }
+
+    /// Minted on chain minus minted per the ledger
+    pub fn minted_drift(&self) -> Option<f64> {
+        self.chain_minted.map(|minted| minted - self.ledger_minted)
+    }
+
+    /// Minted on the destination beyond what the source holds locked
+    pub fn unbacked(&self) -> Option<f64> {
+        match (self.chain_locked, self.chain_minted) {
+            (Some(locked), Some(minted)) => Some((minted - locked).max(0.0)),
+            _ => None,
+        }
+    }
+
+    pub fn has_drift(&self, tolerance: f64) -> bool {
+        [self.locked_drift(), self.minted_drift(), self.unbacked()]
+            .into_iter()
+            .flatten()
+            .any(|drift| drift.abs() > tolerance)
+    }
+}
+
+// Supply of one token on a chain whose adapter only reports totals over every route,
+// against the ledger and the other chains' route totals summed the same way
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainSupply {
+    pub token: String,
+    pub chain: String,
+    pub ledger_locked: f64, // Held on this chain for every route per the ledger
+    pub ledger_minted: f64, // Minted on this chain from every route per the ledger
+    pub chain_locked: Option<f64>,
+    pub chain_minted: Option<f64>,
+    pub counterpart_minted: Option<f64>, // Minted from this chain, per the destinations
+    pub counterpart_locked: Option<f64>, // Locked for this chain, per the sources
+}
+
+impl ChainSupply {
+    /// Locked on chain minus locked per the ledger
+    pub fn locked_drift(&self) -> Option<f64> {
+        self.chain_locked.map(|locked| locked - self.ledger_locked)
+    }
+
+    /// Minted on chain minus minted per the ledger
+    pub fn minted_drift(&self) -> Option<f64> {
+        self.chain_minted.map(|minted| minted - self.ledger_minted)
+    }
+
+    /// The larger of what the other chains minted beyond this chain's locks and what
+    /// this chain minted beyond the other chains' locks
+    pub fn unbacked(&self) -> Option<f64> {
+        let outbound = self
+            .chain_locked
+            .zip(self.counterpart_minted)
+            .map(|(locked, minted)| minted - locked);
+        let inbound = self
+            .counterpart_locked
+            .zip(self.chain_minted)
+            .map(|(locked, minted)| minted - locked);
+        outbound
+            .into_iter()
+            .chain(inbound)
+            .reduce(f64::max)
+            .map(|excess| excess.max(0.0))
+    }
+
+    pub fn has_drift(&self, tolerance: f64) -> bool {
+        [self.locked_drift(), self.minted_drift(), self.unbacked()]
+            .into_iter()
+            .flatten()
+            .any(|drift| drift.abs() > tolerance)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrphanKind {
+    Lock, // Locked on the source chain but never minted
+    Mint, // Minted on the destination chain with no lock behind it
+}
+
+// A ledger record whose lock or mint has no counterpart
+#[derive(Clone, Debug)]
+pub struct Orphan {
+    pub kind: OrphanKind,
+    pub tx: BridgeTx,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SupplyReport {
+    pub routes: Vec<RouteSupply>,
+    pub chains: Vec<ChainSupply>, // Chains reconciled over every route at once
+    pub orphans: Vec<Orphan>,
+}
+
+impl SupplyReport {
+    /// Routes whose drift exceeds `tolerance`
+    pub fn drifted(&self, tolerance: f64) -> Vec<&RouteSupply> {
+        self.routes
+            .iter()
+            .filter(|route| route.has_drift(tolerance))
+            .collect()
+    }
+
+    /// Chains whose chain-wide drift exceeds `tolerance`
+    pub fn drifted_chains(&self, tolerance: f64) -> Vec<&ChainSupply> {
+        self.chains
+            .iter()
+            .filter(|chain| chain.has_drift(tolerance))
+            .collect()
+    }
+
+    /// Routes missing a chain total that no chain-wide total covers either, whose supply
+    /// couldn't be checked against the chains
+    pub fn unreported(&self) -> Vec<&RouteSupply> {
+        let covered = |name: &str, total: fn(&ChainSupply) -> Option<f64>| {
+            self.chains
+                .iter()
+                .any(|chain| chain.chain == name && total(chain).is_some())
+        };
+        self.routes
+            .iter()
+            .filter(|route| {
+                (route.chain_locked.is_none() && !covered(&route.from_chain, |c| c.chain_locked))
+                    || (route.chain_minted.is_none()
+                        && !covered(&route.to_chain, |c| c.chain_minted))
+            })
+            .collect()
+    }
+
+    pub fn orphans_of(&self, kind: OrphanKind) -> Vec<&Orphan> {
+        self.orphans.iter().filter(|o| o.kind == kind).collect()
+    }
+
+    /// No orphans, no drift, and every route checked against both chains' totals
+    pub fn is_clean(&self, tolerance: f64) -> bool {
+        self.orphans.is_empty()
+            && self.drifted(tolerance).is_empty()
+            && self.drifted_chains(tolerance).is_empty()
+            && self.unreported().is_empty()
+    }
+}
+
+/// Reconcile the ledger for `token` against the adapters' locked and minted totals on
+/// every route between them. Adapters that only report chain-wide totals are reconciled
+/// against the ledger summed over all their routes instead. Locks still unminted
+/// `orphan_after` after they were made are reported as orphaned, as are mints whose lock
+/// the ledger or the source chain no longer backs.
+pub(crate) async fn reconcile(
+    db: &(dyn BridgeStore + Send + Sync),
+    adapters: &HashMap<String, Box<dyn ChainAdapter + Send + Sync>>,
+    token: &str,
+    orphan_after: Duration,
+    now: NaiveDateTime,
+) -> Result<SupplyReport, String> {
+    let txs = db
+        .list_bridge_txs_by_token(token)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+
+    let mut routes: BTreeMap<(String, String), RouteSupply> = BTreeMap::new();
+    for from_chain in adapters.keys() {
+        for to_chain in adapters.keys().filter(|name| *name != from_chain) {
+            routes.insert(
+                (from_chain.clone(), to_chain.clone()),
+                empty_route(token, from_chain, to_chain),
+            );
+        }
+    }
+    for tx in &txs {
+        let route = routes
+            .entry((tx.from_chain.clone(), tx.to_chain.clone()))
+            .or_insert_with(|| empty_route(token, &tx.from_chain, &tx.to_chain));
+        if holds_lock(tx) {
+            route.ledger_locked += tx.amount;
+        }
+        if tx.dst_tx_hash.is_some() {
+            route.ledger_minted += tx.amount;
+        }
+    }
+    for route in routes.values_mut() {
+        if let Some(src) = adapters.get(&route.from_chain) {
+            route.chain_locked = src
+                .locked_total(token, &route.to_chain)
+                .await
+                .ok()
+                .flatten();
+        }
+        if let Some(dst) = adapters.get(&route.to_chain) {
+            route.chain_minted = dst
+                .minted_total(token, &route.from_chain)
+                .await
+                .ok()
+                .flatten();
+        }
+    }
+
+    // A chain-wide total covers every route, so the ledger and the other chains' route
+    // totals are summed over the same routes before comparing
+    let mut chains = Vec::new();
+    for (name, adapter) in adapters {
+        let chain_locked = adapter.chain_locked_total(token).await.ok().flatten();
+        let chain_minted = adapter.chain_minted_total(token).await.ok().flatten();
+        if chain_locked.is_none() && chain_minted.is_none() {
+            continue;
+        }
+        let outbound = || routes.values().filter(|route| &route.from_chain == name);
+        let inbound = || routes.values().filter(|route| &route.to_chain == name);
+        chains.push(ChainSupply {
+            token: token.to_string(),
+            chain: name.clone(),
+            ledger_locked: outbound().map(|route| route.ledger_locked).sum(),
+            ledger_minted: inbound().map(|route| route.ledger_minted).sum(),
+            chain_locked,
+            chain_minted,
+            counterpart_minted: outbound().map(|route| route.chain_minted).sum(),
+            counterpart_locked: inbound().map(|route| route.chain_locked).sum(),
+        });
+    }
+    chains.sort_by(|a, b| a.chain.cmp(&b.chain));
+
+    let mut orphans = Vec::new();
+    let mut minted_locks: HashMap<&str, &str> = HashMap::new();
+    for tx in &txs {
+        if let Some(reason) = orphaned_lock(tx, orphan_after, now) {
+            orphans.push(orphan(OrphanKind::Lock, tx, reason));
+        }
+        if tx.dst_tx_hash.is_none() {
+            continue;
+        }
+        let reason = match (tx.status, tx.lock_id.as_deref(), tx.src_tx_hash.as_deref()) {
+            (BridgeTxStatus::Minting | BridgeTxStatus::Minted, _, None) => {
+                Some("minted without a source lock tx".to_string())
+            }
+            (BridgeTxStatus::Minting | BridgeTxStatus::Minted, Some(lock_id), _)
+                if minted_locks.contains_key(lock_id) =>
+            {
+                Some(format!(
+                    "lock {} was already minted by tx {}",
+                    lock_id, minted_locks[lock_id]
+                ))
+            }
+            (BridgeTxStatus::Minting | BridgeTxStatus::Minted, lock_id, Some(src_tx)) => {
+                if let Some(lock_id) = lock_id {
+                    minted_locks.insert(lock_id, &tx.id);
+                }
+                source_missing(adapters, &tx.from_chain, src_tx).await
+            }
+            (status, _, _) => Some(format!("minted while the ledger shows {}", status.as_str())),
+        };
+        if let Some(reason) = reason {
+            orphans.push(orphan(OrphanKind::Mint, tx, reason));
+        }
+    }
+
+    Ok(SupplyReport {
+        routes: routes.into_values().collect(),
+        chains,
+        orphans,
+    })
+}
+
+fn empty_route(token: &str, from_chain: &str, to_chain: &str) -> RouteSupply {
+    RouteSupply {
+        token: token.to_string(),
+        from_chain: from_chain.to_string(),
+        to_chain: to_chain.to_string(),
+        ledger_locked: 0.0,
+        ledger_minted: 0.0,
+        chain_locked: None,
+        chain_minted: None,
+    }
+}
+
+// Whether the tx's amount sits locked on its source chain. A Pending tx with a source
+// hash was locked on chain before the ledger recorded it.
+fn holds_lock(tx: &BridgeTx) -> bool {
+    use BridgeTxStatus::*;
+    match tx.status {
+        Locked | Confirmed | Minting | Minted | DeadLetter | Refunding => true,
+        Pending => tx.src_tx_hash.is_some(),
+        Refunded | Failed => false,
+    }
+}
+
+fn orphaned_lock(tx: &BridgeTx, orphan_after: Duration, now: NaiveDateTime) -> Option<String> {
+    if !holds_lock(tx) || tx.dst_tx_hash.is_some() || tx.created_at + orphan_after > now {
+        return None;
+    }
+    Some(format!(
+        "locked {}s ago and still {} with no mint",
+        (now - tx.created_at).num_seconds(),
+        tx.status.as_str()
+    ))
+}
+
+// A reason if the source chain no longer knows the lock tx. Adapters that can't
+// answer are given the benefit of the doubt.
+async fn source_missing(
+    adapters: &HashMap<String, Box<dyn ChainAdapter + Send + Sync>>,
+    from_chain: &str,
+    src_tx: &str,
+) -> Option<String> {
+    let status = adapters.get(from_chain)?.get_tx_status(src_tx).await.ok()?;
+    (status.status == "NotFound").then(|| format!("lock tx {} not found on {}", src_tx, from_chain))
+}
+
+fn orphan(kind: OrphanKind, tx: &BridgeTx, reason: String) -> Orphan {
+    Orphan {
+        kind,
+        tx: tx.clone(),
+        reason,
+    }
+}
@@ -7,19 +7,29 @@ use std::{
 use async_trait::async_trait;
 use chrono::Utc;
 use p_project_bridge::{
-    AdapterTxStatus, BoxedBridgeError, BridgeService, BridgeStore, ChainAdapter,
+    AdapterTxStatus, BoxedBridgeError, BridgeService, BridgeStore, ChainAdapter, CommitteeSigner,
+    FinalityTag, Release,
+};
+use p_project_core::models::{
+    BridgeAttestation, BridgeCommittee, BridgeRoutePause, BridgeTx, BridgeTxStatus,
+    BridgeVolumeLimit,
 };
-use p_project_core::models::{BridgeTx, BridgeTxStatus};
 use p_project_core::utils;
 
 struct MockStore {
     inner: Mutex<HashMap<String, BridgeTx>>,
+    attestations: Mutex<Vec<BridgeAttestation>>,
+    committees: Mutex<Vec<BridgeCommittee>>,
+    pauses: Mutex<Vec<BridgeRoutePause>>,
 }
 
 impl Default for MockStore {
     fn default() -> Self {
         Self {
             inner: Mutex::new(HashMap::new()),
+            attestations: Mutex::new(Vec::new()),
+            committees: Mutex::new(Vec::new()),
+            pauses: Mutex::new(Vec::new()),
         }
     }
 }
@@ -29,14 +39,6 @@ impl MockStore {
         Utc::now().naive_utc()
     }
 
-    fn map_status(status: &str) -> BridgeTxStatus {
-        match status {
-            "Locked" => BridgeTxStatus::Locked,
-            "Minted" => BridgeTxStatus::Minted,
-            "Failed" => BridgeTxStatus::Failed,
-            _ => BridgeTxStatus::Pending,
-        }
-    }
 }
 
 #[async_trait]
@@ -64,8 +66,11 @@ impl BridgeStore for MockStore {
                 lock_id: None,
                 src_tx_hash: None,
                 dst_tx_hash: None,
-                status: MockStore::map_status(status),
+                status: BridgeTxStatus::from(status),
                 error_msg: None,
+                attempts: 0,
+                next_attempt_at: None,
+                refund_tx_hash: None,
                 created_at: MockStore::now(),
                 updated_at: MockStore::now(),
             },
@@ -104,7 +109,7 @@ impl BridgeStore for MockStore {
         error_msg: Option<&str>,
     ) -> Result<(), BoxedBridgeError> {
         if let Some(tx) = self.inner.lock().unwrap().get_mut(id) {
-            tx.status = MockStore::map_status(status);
+            tx.status = BridgeTxStatus::from(status);
             tx.error_msg = error_msg.map(|s| s.to_string());
             tx.updated_at = MockStore::now();
         }
@@ -133,6 +138,205 @@ impl BridgeStore for MockStore {
             .cloned()
             .collect::<Vec<_>>())
     }
+    async fn list_bridge_txs_by_status(
+        &self,
+        from_chain: &str,
+        status: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        let map = self.inner.lock().unwrap();
+        Ok(map
+            .values()
+            .filter(|tx| tx.from_chain == from_chain && tx.status.as_str() == status)
+            .cloned()
+            .collect::<Vec<_>>())
+    }
+
+    async fn list_bridge_txs_by_lock_id(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        let map = self.inner.lock().unwrap();
+        Ok(map
+            .values()
+            .filter(|tx| tx.lock_id.as_deref() == Some(lock_id))
+            .cloned()
+            .collect::<Vec<_>>())
+    }
+
+    async fn list_bridge_txs_by_token(
+        &self,
+        token: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        let map = self.inner.lock().unwrap();
+        let mut txs = map
+            .values()
+            .filter(|tx| tx.token == token)
+            .cloned()
+            .collect::<Vec<_>>();
+        txs.sort_by_key(|tx| tx.created_at);
+        Ok(txs)
+    }
+
+    async fn transition_bridge_status(
+        &self,
+        id: &str,
+        from_status: &str,
+        to_status: &str,
+        error_msg: Option<&str>,
+    ) -> Result<bool, BoxedBridgeError> {
+        match self.inner.lock().unwrap().get_mut(id) {
+            Some(tx) if tx.status.as_str() == from_status => {
+                tx.status = BridgeTxStatus::from(to_status);
+                tx.error_msg = error_msg.map(|s| s.to_string());
+                tx.updated_at = MockStore::now();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn set_bridge_retry(
+        &self,
+        id: &str,
+        attempts: u32,
+        next_attempt_at: Option<chrono::NaiveDateTime>,
+    ) -> Result<(), BoxedBridgeError> {
+        if let Some(tx) = self.inner.lock().unwrap().get_mut(id) {
+            tx.attempts = attempts;
+            tx.next_attempt_at = next_attempt_at;
+            tx.updated_at = MockStore::now();
+        }
+        Ok(())
+    }
+
+    async fn set_bridge_refund_tx(
+        &self,
+        id: &str,
+        refund_tx_hash: &str,
+    ) -> Result<(), BoxedBridgeError> {
+        if let Some(tx) = self.inner.lock().unwrap().get_mut(id) {
+            tx.refund_tx_hash = Some(refund_tx_hash.to_string());
+            tx.updated_at = MockStore::now();
+        }
+        Ok(())
+    }
+
+    async fn add_bridge_attestation(
+        &self,
+        attestation: &BridgeAttestation,
+    ) -> Result<bool, BoxedBridgeError> {
+        let mut attestations = self.attestations.lock().unwrap();
+        if attestations
+            .iter()
+            .any(|a| a.lock_id == attestation.lock_id && a.signer == attestation.signer)
+        {
+            return Ok(false);
+        }
+        attestations.push(attestation.clone());
+        Ok(true)
+    }
+
+    async fn list_bridge_attestations(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeAttestation>, BoxedBridgeError> {
+        let attestations = self.attestations.lock().unwrap();
+        Ok(attestations
+            .iter()
+            .filter(|a| a.lock_id == lock_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn save_bridge_committee(
+        &self,
+        committee: &BridgeCommittee,
+    ) -> Result<(), BoxedBridgeError> {
+        self.committees.lock().unwrap().push(committee.clone());
+        Ok(())
+    }
+
+    async fn latest_bridge_committee(&self) -> Result<Option<BridgeCommittee>, BoxedBridgeError> {
+        let committees = self.committees.lock().unwrap();
+        Ok(committees.iter().max_by_key(|c| c.epoch).cloned())
+    }
+
+    async fn sum_bridge_volume(
+        &self,
+        user_id: Option<&str>,
+        route: Option<(&str, &str)>,
+        since: chrono::NaiveDateTime,
+    ) -> Result<f64, BoxedBridgeError> {
+        let map = self.inner.lock().unwrap();
+        Ok(volume(&map, user_id, route, since))
+    }
+
+    async fn create_bridge_tx_within_limits(
+        &self,
+        tx: &BridgeTx,
+        limits: &[BridgeVolumeLimit],
+    ) -> Result<Option<(usize, f64)>, BoxedBridgeError> {
+        let mut map = self.inner.lock().unwrap();
+        for (index, limit) in limits.iter().enumerate() {
+            let route = limit.route.as_ref().map(|(from, to)| (from.as_str(), to.as_str()));
+            let used = volume(&map, limit.user_id.as_deref(), route, limit.since);
+            if used + tx.amount > limit.limit {
+                return Ok(Some((index, used)));
+            }
+        }
+        map.insert(tx.id.clone(), tx.clone());
+        Ok(None)
+    }
+
+    async fn pause_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+        reason: &str,
+    ) -> Result<(), BoxedBridgeError> {
+        let mut pauses = self.pauses.lock().unwrap();
+        pauses.retain(|p| !(p.from_chain == from_chain && p.to_chain == to_chain));
+        pauses.push(BridgeRoutePause {
+            from_chain: from_chain.to_string(),
+            to_chain: to_chain.to_string(),
+            reason: reason.to_string(),
+            paused_at: MockStore::now(),
+        });
+        Ok(())
+    }
+
+    async fn resume_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+    ) -> Result<bool, BoxedBridgeError> {
+        let mut pauses = self.pauses.lock().unwrap();
+        let before = pauses.len();
+        pauses.retain(|p| !(p.from_chain == from_chain && p.to_chain == to_chain));
+        Ok(pauses.len() < before)
+    }
+
+    async fn list_bridge_route_pauses(&self) -> Result<Vec<BridgeRoutePause>, BoxedBridgeError> {
+        Ok(self.pauses.lock().unwrap().clone())
+    }
+}
+
+// Amount bridged since `since`, leaving out failed and refunded transfers
+fn volume(
+    map: &HashMap<String, BridgeTx>,
+    user_id: Option<&str>,
+    route: Option<(&str, &str)>,
+    since: chrono::NaiveDateTime,
+) -> f64 {
+    map.values()
+        .filter(|tx| {
+            tx.created_at >= since
+                && !matches!(tx.status, BridgeTxStatus::Failed | BridgeTxStatus::Refunded)
+                && user_id.is_none_or(|u| tx.user_id == u)
+                && route.is_none_or(|(from, to)| tx.from_chain == from && tx.to_chain == to)
+        })
+        .map(|tx| tx.amount)
+        .sum()
 }
 
 struct MockAdapter {
@@ -163,17 +367,18 @@ impl ChainAdapter for MockAdapter {
 
     async fn mint_or_release(
         &self,
-        _user: &str,
-        _token: &str,
-        _amount: f64,
-        _from_chain: &str,
+        user: &str,
+        token: &str,
+        amount: f64,
+        from_chain: &str,
         _source_tx: &str,
-        lock_id: Option<&str>,
+        release: &Release<'_>,
     ) -> Result<String, p_project_bridge::BridgeError> {
+        release.verify(user, token, amount, from_chain)?;
         Ok(format!(
             "mock-{}-mint-{}",
             self.name,
-            lock_id.unwrap_or("none")
+            release.lock_id().unwrap_or("none")
         ))
     }
 
@@ -185,6 +390,7 @@ impl ChainAdapter for MockAdapter {
             tx_id: tx_id.to_string(),
             status: "Success".to_string(),
             confirmations: 5,
+            finality: FinalityTag::Finalized,
         })
     }
 
@@ -206,7 +412,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     );
     adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
 
-    let service = BridgeService::with_adapters(store.clone(), adapters);
+    let mut service = BridgeService::with_adapters(store.clone(), adapters);
+
+    // A 1-of-1 committee so the simulated relayer can approve its own mints
+    let signer = CommitteeSigner::new(&format!("0x{}", "01".repeat(32)))?;
+    service.rotate_committee(&[signer.address()], 1).await?;
+    service.set_committee_signer(signer);
     println!(
         "Simulating bridge: supported chains = {:?}",
         service.get_supported_chains()
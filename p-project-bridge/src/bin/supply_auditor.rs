@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+use p_project_bridge::{BridgeService, OrphanKind};
+use p_project_core::database::MySqlDatabase;
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Read configuration
+    let db_url = std::env::var("DATABASE_URL").map_err(|_| "DATABASE_URL not set")?;
+    let orphan_after = Duration::seconds(env_or("BRIDGE_AUDIT_ORPHAN_SECS", 3_600));
+    let tolerance = env_or("BRIDGE_AUDIT_TOLERANCE", 0.000_001);
+
+    let db = MySqlDatabase::new(&db_url).await?;
+    db.init_tables().await?;
+    let service = BridgeService::new(Arc::new(db));
+
+    let report = service.audit_supply(orphan_after).await?;
+    for route in &report.routes {
+        let fmt = |v: Option<f64>| v.map_or("n/a".to_string(), |v| v.to_string());
+        println!(
+            "[Audit] {} {} -> {}: ledger locked {} minted {}, chain locked {} minted {}{}",
+            route.token,
+            route.from_chain,
+            route.to_chain,
+            route.ledger_locked,
+            route.ledger_minted,
+            fmt(route.chain_locked),
+            fmt(route.chain_minted),
+            if route.has_drift(tolerance) {
+                format!(
+                    " DRIFT (locked {}, minted {}, unbacked {})",
+                    fmt(route.locked_drift()),
+                    fmt(route.minted_drift()),
+                    fmt(route.unbacked())
+                )
+            } else {
+                String::new()
+            }
+        );
+    }
+    for chain in &report.chains {
+        let fmt = |v: Option<f64>| v.map_or("n/a".to_string(), |v| v.to_string());
+        println!(
+            "[Audit] {} on {} (all routes): ledger locked {} minted {}, chain locked {} minted {}{}",
+            chain.token,
+            chain.chain,
+            chain.ledger_locked,
+            chain.ledger_minted,
+            fmt(chain.chain_locked),
+            fmt(chain.chain_minted),
+            if chain.has_drift(tolerance) {
+                format!(
+                    " DRIFT (locked {}, minted {}, unbacked {})",
+                    fmt(chain.locked_drift()),
+                    fmt(chain.minted_drift()),
+                    fmt(chain.unbacked())
+                )
+            } else {
+                String::new()
+            }
+        );
+    }
+    for orphan in &report.orphans {
+        let kind = match orphan.kind {
+            OrphanKind::Lock => "lock",
+            OrphanKind::Mint => "mint",
+        };
+        println!(
+            "[Audit] orphaned {} {} ({} {} -> {}): {}",
+            kind,
+            orphan.tx.id,
+            orphan.tx.amount,
+            orphan.tx.from_chain,
+            orphan.tx.to_chain,
+            orphan.reason
+        );
+    }
+
+    for route in report.unreported() {
+        println!(
+            "[Audit] {} {} -> {}: chain totals unavailable, route not reconciled",
+            route.token, route.from_chain, route.to_chain
+        );
+    }
+
+    // Fail the run so schedulers and alerting pick up any discrepancy, and any route
+    // the chains couldn't vouch for
+    if !report.is_clean(tolerance) {
+        return Err(format!(
+            "supply audit failed: {} drifted route(s), {} drifted chain(s), {} orphan(s), \
+             {} unreported route(s)",
+            report.drifted(tolerance).len(),
+            report.drifted_chains(tolerance).len(),
+            report.orphans.len(),
+            report.unreported().len()
+        )
+        .into());
+    }
+    println!("[Audit] supply reconciled");
+    Ok(())
+}
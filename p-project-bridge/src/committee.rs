@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use ethers::abi::{encode, Token};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, Signature};
+use ethers::utils::{hash_message, keccak256};
+use p_project_core::models::{BridgeAttestation, BridgeCommittee, BridgeTx};
+
+use crate::error::BridgeError;
+
+/// Digest committee members sign for a transfer: keccak256 of the abi-encoded
+/// (lock_id, from_chain, to_chain, user, token, amount). None until the lock_id is known.
+pub fn attestation_digest(tx: &BridgeTx) -> Option<[u8; 32]> {
+    let lock_id = tx.lock_id.as_deref()?;
+    let encoded = encode(&[
+        Token::String(lock_id.to_string()),
+        Token::String(tx.from_chain.clone()),
+        Token::String(tx.to_chain.clone()),
+        Token::String(tx.user_id.clone()),
+        Token::String(tx.token.clone()),
+        // Shortest round-trip form, so every relayer encodes the same amount
+        Token::String(tx.amount.to_string()),
+    ]);
+    Some(keccak256(encoded))
+}
+
+/// Whether `attestation` is its signer's valid signature over `tx`
+pub fn verify_attestation(tx: &BridgeTx, attestation: &BridgeAttestation) -> bool {
+    let Some(digest) = attestation_digest(tx) else {
+        return false;
+    };
+    let (Ok(signature), Ok(signer)) = (
+        attestation.signature.parse::<Signature>(),
+        attestation.signer.parse::<Address>(),
+    ) else {
+        return false;
+    };
+    tx.lock_id.as_deref() == Some(attestation.lock_id.as_str())
+        && signature.verify(&digest[..], signer).is_ok()
+}
+
+/// Committee members with a valid attestation for `tx`, each counted once
+pub fn valid_signers(
+    committee: &BridgeCommittee,
+    tx: &BridgeTx,
+    attestations: &[BridgeAttestation],
+) -> Vec<String> {
+    let mut seen = HashSet::new();
+    attestations
+        .iter()
+        .filter_map(|a| {
+            let signer = normalize_address(&a.signer).ok()?;
+            (committee.members.contains(&signer) && verify_attestation(tx, a)).then_some(signer)
+        })
+        .filter(|signer| seen.insert(signer.clone()))
+        .collect()
+}
+
+/// Build the committee for `epoch`: members must be distinct addresses and the threshold
+/// between 1 and the number of members
+pub fn new_committee(
+    epoch: u64,
+    members: &[String],
+    threshold: u32,
+) -> Result<BridgeCommittee, String> {
+    let members = members
+        .iter()
+        .map(|m| normalize_address(m))
+        .collect::<Result<Vec<_>, _>>()?;
+    let distinct: HashSet<&String> = members.iter().collect();
+    if distinct.len() != members.len() {
+        return Err("Duplicate committee member".to_string());
+    }
+    if threshold == 0 || threshold as usize > members.len() {
+        return Err(format!(
+            "Threshold must be between 1 and {}, got {}",
+            members.len(),
+            threshold
+        ));
+    }
+    Ok(BridgeCommittee {
+        epoch,
+        members,
+        threshold,
+        created_at: Utc::now().naive_utc(),
+    })
+}
+
+// Lowercase 0x-prefixed hex, the form members and signers are compared in
+fn normalize_address(address: &str) -> Result<String, String> {
+    address
+        .trim()
+        .parse::<Address>()
+        .map(|a| format!("{:?}", a))
+        .map_err(|_| format!("Invalid committee address: {}", address))
+}
+
+// A relayer's committee key, used to attest transfers it has seen confirmed
+#[derive(Clone, Debug)]
+pub struct CommitteeSigner {
+    wallet: LocalWallet,
+}
+
+impl CommitteeSigner {
+    pub fn new(private_key: &str) -> Result<Self, BridgeError> {
+        let wallet = private_key
+            .trim()
+            .parse::<LocalWallet>()
+            .map_err(|e| BridgeError::SigningError(e.to_string()))?;
+        Ok(Self { wallet })
+    }
+
+    pub fn address(&self) -> String {
+        format!("{:?}", self.wallet.address())
+    }
+
+    /// Sign the EIP-191 message of `tx`'s attestation digest
+    pub fn attest(&self, tx: &BridgeTx) -> Result<BridgeAttestation, BridgeError> {
+        let (Some(lock_id), Some(digest)) = (tx.lock_id.clone(), attestation_digest(tx)) else {
+            return Err(BridgeError::Other(format!(
+                "bridge tx {} has no lock_id to attest",
+                tx.id
+            )));
+        };
+        let signature = self
+            .wallet
+            .sign_hash(hash_message(digest))
+            .map_err(|e| BridgeError::SigningError(e.to_string()))?;
+        Ok(BridgeAttestation {
+            lock_id,
+            bridge_tx_id: tx.id.clone(),
+            signer: self.address(),
+            signature: signature.to_string(),
+            created_at: Utc::now().naive_utc(),
+        })
+    }
+}
+
+// What entitles an adapter to move funds in mint_or_release
+pub enum Release<'a> {
+    Mint(MintApproval<'a>), // Mint on the destination chain, approved by the committee
+    Refund { lock_id: Option<&'a str> }, // Return a lock to its owner on the source chain
+}
+
+// A transfer with the committee attestations collected for it
+pub struct MintApproval<'a> {
+    pub tx: &'a BridgeTx,
+    pub committee: &'a BridgeCommittee,
+    pub attestations: &'a [BridgeAttestation],
+}
+
+impl<'a> Release<'a> {
+    pub fn lock_id(&self) -> Option<&'a str> {
+        match self {
+            Release::Mint(approval) => approval.tx.lock_id.as_deref(),
+            Release::Refund { lock_id } => *lock_id,
+        }
+    }
+
+    /// Check a release of `amount` of `token` to `user` against its authority. A mint
+    /// must be the attested transfer from `counterpart_chain`, signed by at least the
+    /// committee threshold.
+    pub fn verify(
+        &self,
+        user: &str,
+        token: &str,
+        amount: f64,
+        counterpart_chain: &str,
+    ) -> Result<(), BridgeError> {
+        let Release::Mint(approval) = self else {
+            return Ok(());
+        };
+        let tx = approval.tx;
+        if tx.user_id != user
+            || tx.token != token
+            || tx.amount != amount
+            || tx.from_chain != counterpart_chain
+        {
+            return Err(BridgeError::Other(format!(
+                "mint does not match attested bridge tx {}",
+                tx.id
+            )));
+        }
+        let signers = valid_signers(approval.committee, tx, approval.attestations);
+        if signers.len() < approval.committee.threshold as usize {
+            return Err(BridgeError::Other(format!(
+                "bridge tx {} has {}/{} committee attestations",
+                tx.id,
+                signers.len(),
+                approval.committee.threshold
+            )));
+        }
+        Ok(())
+    }
+}
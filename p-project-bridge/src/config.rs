@@ -1,5 +1,9 @@
+use std::collections::HashMap;
 use std::env;
 
+use crate::finality::{FinalityPolicy, FinalityTag};
+use crate::risk::{RiskLimits, VolumeCap};
+
 fn upper_snake(name: &str) -> String {
     name.trim()
         .to_ascii_uppercase()
@@ -8,6 +12,58 @@ fn upper_snake(name: &str) -> String {
         .collect()
 }
 
+fn env_f64(key: &str) -> Option<f64> {
+    env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+// `<PREFIX>` sets the cap and `<PREFIX>_WINDOW_SECS` its window
+fn cap_from_env(prefix: &str, default_window_secs: i64) -> Option<VolumeCap> {
+    let limit = env_f64(prefix)?;
+    let window_secs = env::var(format!("{}_WINDOW_SECS", prefix))
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default_window_secs);
+    Some(VolumeCap::new(limit, window_secs))
+}
+
+// `<PREFIX>_FINALITY_TAG` and `<PREFIX>_REORG_WINDOW_SECS` on top of the chain's
+// configured confirmation count. A value that doesn't parse is logged and replaced by the
+// strictest setting rather than silently loosened: the finalized tag, and the default
+// reorg window.
+fn finality_from_env(prefix: &str, confirmations: u32) -> FinalityPolicy {
+    let defaults = FinalityPolicy::default();
+    let tag_key = format!("{}_FINALITY_TAG", prefix);
+    let tag = match env::var(&tag_key) {
+        Ok(v) => v.parse().unwrap_or_else(|e| {
+            println!(
+                "[Bridge] invalid {}={:?} ({}), requiring finalized blocks",
+                tag_key, v, e
+            );
+            FinalityTag::Finalized
+        }),
+        Err(_) => defaults.tag,
+    };
+    let window_key = format!("{}_REORG_WINDOW_SECS", prefix);
+    let reorg_window_secs = match env::var(&window_key) {
+        Ok(v) => match v.trim().parse::<i64>() {
+            Ok(secs) if secs >= 0 => secs,
+            _ => {
+                println!(
+                    "[Bridge] invalid {}={:?}, watching for reorgs for the default {}s",
+                    window_key, v, defaults.reorg_window_secs
+                );
+                defaults.reorg_window_secs
+            }
+        },
+        Err(_) => defaults.reorg_window_secs,
+    };
+    FinalityPolicy {
+        confirmations,
+        tag,
+        reorg_window_secs,
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct EthConfig {
     pub rpc_url: String,
@@ -64,6 +120,11 @@ pub struct BridgeConfig {
     pub evm: Vec<EvmConfig>,
     pub solana: Option<SolanaConfig>,
     pub sui: Option<SuiConfig>,
+    // Per-chain finality keyed by adapter name; unlisted chains use the default policy
+    pub finality: HashMap<String, FinalityPolicy>,
+    // Env var holding this relayer's committee signing key
+    pub committee_key_env: String,
+    pub risk: RiskLimits,
 }
 
 impl BridgeConfig {
@@ -148,6 +209,45 @@ impl BridgeConfig {
             _ => None,
         };
 
-        Self { eth, evm, solana, sui }
+        let mut finality = HashMap::new();
+        for c in &evm {
+            let prefix = format!("EVM_{}", upper_snake(&c.name));
+            finality.insert(
+                c.name.trim().to_string(),
+                finality_from_env(&prefix, c.confirmations),
+            );
+        }
+        // The single ETH_* config is only used when no EVM_NETWORKS are set
+        if let Some(c) = eth.as_ref().filter(|_| evm.is_empty()) {
+            finality.insert("Ethereum".to_string(), finality_from_env("ETH", c.confirmations));
+        }
+        if let Some(c) = solana.as_ref() {
+            finality.insert("Solana".to_string(), finality_from_env("SOLANA", c.confirmations));
+        }
+        if let Some(c) = sui.as_ref() {
+            finality.insert("Sui".to_string(), finality_from_env("SUI", c.confirmations));
+        }
+
+        let committee_key_env = env::var("BRIDGE_COMMITTEE_KEY_ENV")
+            .unwrap_or_else(|_| "BRIDGE_COMMITTEE_KEY".to_string());
+
+        let risk = RiskLimits {
+            max_transfer: env_f64("BRIDGE_MAX_TRANSFER"),
+            per_user: cap_from_env("BRIDGE_USER_CAP", 86_400),
+            per_route: cap_from_env("BRIDGE_ROUTE_CAP", 86_400),
+            global: cap_from_env("BRIDGE_GLOBAL_CAP", 86_400),
+            breaker_outflow: cap_from_env("BRIDGE_BREAKER_OUTFLOW", 3_600),
+            mint_tolerance: env_f64("BRIDGE_MINT_TOLERANCE").unwrap_or(0.0),
+        };
+
+        Self {
+            eth,
+            evm,
+            solana,
+            sui,
+            finality,
+            committee_key_env,
+            risk,
+        }
     }
 }
@@ -1,12 +1,16 @@
 use crate::adapter::{AdapterTxStatus, ChainAdapter};
+use crate::committee::Release;
 use crate::config::EthConfig;
 use crate::error::BridgeError;
+use crate::finality::FinalityTag;
 use async_trait::async_trait;
 use ethers::contract::abigen;
 use ethers::middleware::SignerMiddleware;
 use ethers::providers::{Http, Middleware, Provider};
 use ethers::signers::LocalWallet;
-use ethers::types::{Address, H256, U256};
+use ethers::types::{Address, BlockNumber, H256, U256, U64};
+use ethers::utils::format_units;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 abigen!(Bridge, "abi/Bridge.json");
@@ -73,6 +77,82 @@ impl EthereumAdapter {
     }
 }
 
+impl EthereumAdapter {
+    /// Most final block tag that `block` is at or behind. Nodes without `safe` or
+    /// `finalized` support leave the block at `Latest`.
+    async fn block_finality(provider: &Provider<Http>, block: U64) -> FinalityTag {
+        for (tag, number) in [
+            (FinalityTag::Finalized, BlockNumber::Finalized),
+            (FinalityTag::Safe, BlockNumber::Safe),
+        ] {
+            let head = provider.get_block(number).await.ok().flatten();
+            if head.and_then(|b| b.number).is_some_and(|n| n >= block) {
+                return tag;
+            }
+        }
+        FinalityTag::Latest
+    }
+
+    /// Bridged token still locked here and minted here for other chains, from the bridge
+    /// contract's events. A refund releases a lock through `mint` under the lock's own
+    /// lockId, so Minted events for locks made here count against the locked total.
+    /// The contract doesn't record the counterpart chain, so both cover every route and
+    /// are reported as chain-wide totals; per-route totals are left unreported.
+    async fn event_totals(&self) -> Result<(f64, f64), BridgeError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(BridgeError::ConfigMissing("ETH_RPC_URL"))?;
+        let bridge_addr = self
+            .bridge_address
+            .ok_or(BridgeError::ConfigMissing("ETH_BRIDGE_ADDRESS"))?;
+        let token_addr = self
+            .token_address
+            .ok_or(BridgeError::ConfigMissing("ETH_TOKEN_ADDRESS"))?;
+        let client = Arc::new(provider.clone());
+
+        let bridge = Bridge::new(bridge_addr, client.clone());
+        let locks = bridge
+            .event::<LockedFilter>()
+            .topic2(token_addr)
+            .from_block(0u64)
+            .query()
+            .await
+            .map_err(|e| BridgeError::RpcUnavailable(e.to_string()))?;
+        let mints = bridge
+            .event::<MintedFilter>()
+            .topic2(token_addr)
+            .from_block(0u64)
+            .query()
+            .await
+            .map_err(|e| BridgeError::RpcUnavailable(e.to_string()))?;
+        let decimals: u8 = Erc20::new(token_addr, client)
+            .decimals()
+            .call()
+            .await
+            .map_err(|e| BridgeError::RpcUnavailable(e.to_string()))?;
+
+        let lock_ids: HashSet<[u8; 32]> = locks.iter().map(|l| l.lock_id).collect();
+        let locked = locks.iter().fold(U256::zero(), |sum, l| sum + l.amount);
+        let (refunded, minted) = mints
+            .iter()
+            .fold((U256::zero(), U256::zero()), |(r, m), e| {
+                if lock_ids.contains(&e.lock_id) {
+                    (r + e.amount, m)
+                } else {
+                    (r, m + e.amount)
+                }
+            });
+        let scale = |amount: U256| {
+            format_units(amount, decimals as u32)
+                .ok()
+                .and_then(|units| units.parse::<f64>().ok())
+                .ok_or_else(|| BridgeError::Other(format!("Invalid token amount {}", amount)))
+        };
+        Ok((scale(locked.saturating_sub(refunded))?, scale(minted)?))
+    }
+}
+
 #[async_trait]
 impl ChainAdapter for EthereumAdapter {
     fn name(&self) -> &'static str {
@@ -138,12 +218,13 @@ impl ChainAdapter for EthereumAdapter {
     async fn mint_or_release(
         &self,
         recipient: &str,
-        _token: &str,
+        token: &str,
         amount: f64,
-        _from_chain: &str,
+        from_chain: &str,
         _source_tx: &str,
-        lock_id: Option<&str>,
+        release: &Release<'_>,
     ) -> Result<String, BridgeError> {
+        release.verify(recipient, token, amount, from_chain)?;
         let signer = self
             .signer
             .as_ref()
@@ -168,7 +249,7 @@ impl ChainAdapter for EthereumAdapter {
         let scaled = U256::from((amount * scale as f64).round() as u128);
 
         let bridge = Bridge::new(bridge_addr, signer.clone());
-        let h = if let Some(l) = lock_id {
+        let h = if let Some(l) = release.lock_id() {
             l.parse::<H256>()
                 .map_err(|_| BridgeError::Other("Invalid lockId".into()))?
         } else {
@@ -198,6 +279,7 @@ impl ChainAdapter for EthereumAdapter {
 
         let mut status = "Pending".to_string();
         let mut confirmations = 0u32;
+        let mut finality = FinalityTag::Latest;
         if let Some(r) = receipt {
             status = if r.status.unwrap_or_default().as_u64() == 1 {
                 "Success"
@@ -213,12 +295,23 @@ impl ChainAdapter for EthereumAdapter {
                 if latest > block {
                     confirmations = (latest - block).as_u32();
                 }
+                finality = Self::block_finality(provider, block).await;
+            }
+        } else {
+            // No receipt and no pending tx: dropped, or reorged out of the chain
+            let known = provider
+                .get_transaction(tx_hash)
+                .await
+                .map_err(|e| BridgeError::RpcUnavailable(e.to_string()))?;
+            if known.is_none() {
+                status = "NotFound".to_string();
             }
         }
         Ok(AdapterTxStatus {
             tx_id: tx_id.to_string(),
             status,
             confirmations,
+            finality,
         })
     }
 
@@ -256,4 +349,38 @@ impl ChainAdapter for EthereumAdapter {
         }
         Ok(None)
     }
+
+    async fn find_mint(&self, lock_id: &str) -> Result<Option<String>, BridgeError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(BridgeError::ConfigMissing("ETH_RPC_URL"))?;
+        let bridge_addr = self
+            .bridge_address
+            .ok_or(BridgeError::ConfigMissing("ETH_BRIDGE_ADDRESS"))?;
+        let id: H256 = lock_id
+            .parse()
+            .map_err(|_| BridgeError::Other("Invalid lockId".into()))?;
+
+        // The contract mints each lockId at most once, so one Minted event settles it
+        let bridge = Bridge::new(bridge_addr, Arc::new(provider.clone()));
+        let minted = bridge
+            .event::<MintedFilter>()
+            .topic1(id)
+            .from_block(0u64)
+            .query_with_meta()
+            .await
+            .map_err(|e| BridgeError::RpcUnavailable(e.to_string()))?;
+        Ok(minted
+            .first()
+            .map(|(_, meta)| format!("0x{:x}", meta.transaction_hash)))
+    }
+
+    async fn chain_locked_total(&self, _token: &str) -> Result<Option<f64>, BridgeError> {
+        Ok(Some(self.event_totals().await?.0))
+    }
+
+    async fn chain_minted_total(&self, _token: &str) -> Result<Option<f64>, BridgeError> {
+        Ok(Some(self.event_totals().await?.1))
+    }
 }
@@ -0,0 +1,70 @@
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::adapter::AdapterTxStatus;
+
+// How settled a source tx is, ordered from least to most final. EVM chains map these
+// to the `latest`, `safe` and `finalized` block tags; other chains report the closest
+// equivalent (e.g. Solana's `finalized` commitment).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FinalityTag {
+    #[default]
+    Latest, // Included in a block, nothing more
+    Safe,
+    Finalized,
+}
+
+impl FromStr for FinalityTag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "latest" => Ok(FinalityTag::Latest),
+            "safe" => Ok(FinalityTag::Safe),
+            "finalized" => Ok(FinalityTag::Finalized),
+            other => Err(format!("unknown finality tag: {}", other)),
+        }
+    }
+}
+
+// When the relayer treats a lock on a source chain as final, and how long it keeps
+// watching minted transfers for the lock being reorged away
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FinalityPolicy {
+    pub confirmations: u32,
+    pub tag: FinalityTag, // Block tag the lock's block must have reached
+    pub reorg_window_secs: i64,
+}
+
+impl Default for FinalityPolicy {
+    fn default() -> Self {
+        Self {
+            confirmations: 1,
+            tag: FinalityTag::Latest,
+            reorg_window_secs: 3_600,
+        }
+    }
+}
+
+impl FinalityPolicy {
+    pub fn new(confirmations: u32, tag: FinalityTag) -> Self {
+        Self {
+            confirmations,
+            tag,
+            ..Self::default()
+        }
+    }
+
+    /// Whether a successful source tx is deep enough to mint against
+    pub fn is_final(&self, status: &AdapterTxStatus) -> bool {
+        status.status == "Success"
+            && status.confirmations >= self.confirmations
+            && status.finality >= self.tag
+    }
+
+    /// Whether a tx last updated at `updated_at` is still worth re-checking for a reorg
+    pub fn within_reorg_window(&self, updated_at: NaiveDateTime, now: NaiveDateTime) -> bool {
+        updated_at + Duration::seconds(self.reorg_window_secs) >= now
+    }
+}
@@ -1,12 +1,21 @@
 use p_project_core::database::MySqlDatabase;
+use p_project_core::models::{
+    BridgeAttestation, BridgeCommittee, BridgeRoutePause, BridgeTx, BridgeTxStatus,
+    BridgeVolumeLimit,
+};
 use std::{collections::HashMap, sync::Arc};
 
 mod adapter;
+mod audit;
+mod committee;
 mod config;
 mod error;
 mod eth;
+mod finality;
 mod relayer;
+mod risk;
 mod solana;
+mod state_machine;
 mod store;
 mod sui;
 
@@ -17,11 +26,19 @@ mod solana_relayer;
 mod sui_relayer;
 
 pub use adapter::{AdapterTxStatus, ChainAdapter};
+pub use audit::{ChainSupply, Orphan, OrphanKind, RouteSupply, SupplyReport};
+pub use committee::{
+    attestation_digest, verify_attestation, CommitteeSigner, MintApproval, Release,
+};
 use config::{BridgeConfig, EvmConfig, EthConfig};
 pub use error::BridgeError;
 use eth::EthereumAdapter;
+pub use finality::{FinalityPolicy, FinalityTag};
 use relayer::Relayer;
+pub use risk::{RiskLimits, VolumeCap};
 use solana::SolanaAdapter;
+use state_machine::transition;
+pub use state_machine::{can_transition, RetryPolicy};
 pub use store::{BoxedBridgeError, BridgeStore};
 use sui::SuiAdapter;
 
@@ -31,6 +48,9 @@ pub use eth_listener::EthEventListener;
 pub use solana_relayer::SolanaRelayer;
 pub use sui_relayer::SuiRelayer;
 
+// The only token bridged today
+const BRIDGED_TOKEN: &str = "P";
+
 // Wrapper struct to implement BridgeStore for Arc<MySqlDatabase>
 struct DatabaseWrapper {
     db: Arc<MySqlDatabase>,
@@ -113,12 +133,152 @@ impl BridgeStore for DatabaseWrapper {
             .await
             .map_err(|e| Box::new(e) as BoxedBridgeError)
     }
+
+    async fn list_bridge_txs_by_status(
+        &self,
+        from_chain: &str,
+        status: &str,
+    ) -> Result<Vec<p_project_core::models::BridgeTx>, BoxedBridgeError> {
+        self.db
+            .as_ref()
+            .list_bridge_txs_by_status(from_chain, status)
+            .await
+    }
+
+    async fn list_bridge_txs_by_lock_id(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<p_project_core::models::BridgeTx>, BoxedBridgeError> {
+        self.db.as_ref().list_bridge_txs_by_lock_id(lock_id).await
+    }
+
+    async fn list_bridge_txs_by_token(
+        &self,
+        token: &str,
+    ) -> Result<Vec<p_project_core::models::BridgeTx>, BoxedBridgeError> {
+        self.db.as_ref().list_bridge_txs_by_token(token).await
+    }
+
+    async fn transition_bridge_status(
+        &self,
+        id: &str,
+        from_status: &str,
+        to_status: &str,
+        error_msg: Option<&str>,
+    ) -> Result<bool, BoxedBridgeError> {
+        self.db
+            .as_ref()
+            .transition_bridge_status(id, from_status, to_status, error_msg)
+            .await
+    }
+
+    async fn set_bridge_retry(
+        &self,
+        id: &str,
+        attempts: u32,
+        next_attempt_at: Option<chrono::NaiveDateTime>,
+    ) -> Result<(), BoxedBridgeError> {
+        self.db
+            .as_ref()
+            .set_bridge_retry(id, attempts, next_attempt_at)
+            .await
+    }
+
+    async fn set_bridge_refund_tx(
+        &self,
+        id: &str,
+        refund_tx_hash: &str,
+    ) -> Result<(), BoxedBridgeError> {
+        self.db
+            .as_ref()
+            .set_bridge_refund_tx(id, refund_tx_hash)
+            .await
+    }
+
+    async fn add_bridge_attestation(
+        &self,
+        attestation: &BridgeAttestation,
+    ) -> Result<bool, BoxedBridgeError> {
+        self.db.as_ref().add_bridge_attestation(attestation).await
+    }
+
+    async fn list_bridge_attestations(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeAttestation>, BoxedBridgeError> {
+        self.db.as_ref().list_bridge_attestations(lock_id).await
+    }
+
+    async fn save_bridge_committee(
+        &self,
+        committee: &BridgeCommittee,
+    ) -> Result<(), BoxedBridgeError> {
+        self.db.as_ref().save_bridge_committee(committee).await
+    }
+
+    async fn latest_bridge_committee(&self) -> Result<Option<BridgeCommittee>, BoxedBridgeError> {
+        self.db.as_ref().latest_bridge_committee().await
+    }
+
+    async fn sum_bridge_volume(
+        &self,
+        user_id: Option<&str>,
+        route: Option<(&str, &str)>,
+        since: chrono::NaiveDateTime,
+    ) -> Result<f64, BoxedBridgeError> {
+        self.db
+            .as_ref()
+            .sum_bridge_volume(user_id, route, since)
+            .await
+    }
+
+    async fn create_bridge_tx_within_limits(
+        &self,
+        tx: &BridgeTx,
+        limits: &[BridgeVolumeLimit],
+    ) -> Result<Option<(usize, f64)>, BoxedBridgeError> {
+        self.db
+            .as_ref()
+            .create_bridge_tx_within_limits(tx, limits)
+            .await
+    }
+
+    async fn pause_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+        reason: &str,
+    ) -> Result<(), BoxedBridgeError> {
+        self.db
+            .as_ref()
+            .pause_bridge_route(from_chain, to_chain, reason)
+            .await
+    }
+
+    async fn resume_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+    ) -> Result<bool, BoxedBridgeError> {
+        self.db
+            .as_ref()
+            .resume_bridge_route(from_chain, to_chain)
+            .await
+    }
+
+    async fn list_bridge_route_pauses(&self) -> Result<Vec<BridgeRoutePause>, BoxedBridgeError> {
+        BridgeStore::list_bridge_route_pauses(self.db.as_ref()).await
+    }
 }
 
 pub struct BridgeService {
     db: Arc<dyn BridgeStore + Send + Sync>,
     supported_chains: Vec<String>,
     adapters: HashMap<String, Box<dyn adapter::ChainAdapter + Send + Sync>>,
+    retry_policy: RetryPolicy,
+    finality: HashMap<String, FinalityPolicy>,
+    signer: Option<CommitteeSigner>,
+    risk_limits: RiskLimits,
 }
 
 impl BridgeService {
@@ -126,7 +286,23 @@ impl BridgeService {
         let cfg = BridgeConfig::from_env();
         let adapters = Self::build_default_adapters(&cfg);
         let db_wrapper = DatabaseWrapper { db };
-        Self::with_adapters(Arc::new(db_wrapper), adapters)
+        let mut service = Self::with_adapters(Arc::new(db_wrapper), adapters);
+        service.finality = cfg.finality;
+        service.risk_limits = cfg.risk;
+        service.signer = match std::env::var(&cfg.committee_key_env) {
+            Ok(key) => match CommitteeSigner::new(&key) {
+                Ok(signer) => Some(signer),
+                Err(e) => {
+                    println!(
+                        "[Bridge] invalid committee key in {} ({}), this relayer will not attest",
+                        cfg.committee_key_env, e
+                    );
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+        service
     }
 
     pub fn with_adapters(
@@ -138,9 +314,34 @@ impl BridgeService {
             db,
             supported_chains,
             adapters,
+            retry_policy: RetryPolicy::default(),
+            finality: HashMap::new(),
+            signer: None,
+            risk_limits: RiskLimits::default(),
         }
     }
 
+    /// Backoff and attempt limit the relayer applies to failed mints and refunds
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// When the relayer treats locks on `chain` as final; chains without a policy mint
+    /// after one confirmation
+    pub fn set_finality_policy(&mut self, chain: &str, policy: FinalityPolicy) {
+        self.finality.insert(chain.to_string(), policy);
+    }
+
+    /// Key this service's relayer attests transfers with while it sits on the committee
+    pub fn set_committee_signer(&mut self, signer: CommitteeSigner) {
+        self.signer = Some(signer);
+    }
+
+    /// Transfer maximum, rolling caps and circuit breaker applied to new transfers
+    pub fn set_risk_limits(&mut self, risk_limits: RiskLimits) {
+        self.risk_limits = risk_limits;
+    }
+
     fn build_default_adapters(
         cfg: &BridgeConfig,
     ) -> HashMap<String, Box<dyn adapter::ChainAdapter + Send + Sync>> {
@@ -176,10 +377,18 @@ impl BridgeService {
     }
 
     pub fn relayer(&self) -> Relayer<'_> {
-        Relayer::new(&self.adapters, self.db.as_ref())
+        let relayer = Relayer::new(&self.adapters, self.db.as_ref())
+            .with_retry_policy(self.retry_policy.clone())
+            .with_finality_policies(self.finality.clone())
+            .with_risk_limits(self.risk_limits.clone());
+        match self.signer.as_ref() {
+            Some(signer) => relayer.with_signer(signer),
+            None => relayer,
+        }
     }
 
-    /// Bridge tokens from one chain to another
+    /// Lock tokens on the source chain for a transfer to `to_chain`. The relayer mints
+    /// on the destination once the lock is confirmed; this only records and locks.
     pub async fn bridge_tokens(
         &self,
         user_id: &str,
@@ -199,32 +408,49 @@ impl BridgeService {
             .adapters
             .get(from_chain)
             .ok_or_else(|| format!("No adapter for {}", from_chain))?;
-        let dst = self
-            .adapters
-            .get(to_chain)
-            .ok_or_else(|| format!("No adapter for {}", to_chain))?;
+        if !self.adapters.contains_key(to_chain) {
+            return Err(format!("No adapter for {}", to_chain));
+        }
 
-        let token = "P";
+        risk::check_transfer(self.db.as_ref(), &self.risk_limits, from_chain, to_chain, amount)
+            .await?;
+
+        let token = BRIDGED_TOKEN;
 
         let tx_id = p_project_core::utils::generate_id();
 
-        if let Err(e) = self
-            .db
-            .create_bridge_tx(
-                &tx_id, user_id, token, from_chain, to_chain, amount, "Pending",
-            )
-            .await
-        {
-            return Err(format!("DB error: {}", e));
-        }
+        let now = chrono::Utc::now().naive_utc();
+        let tx = BridgeTx {
+            id: tx_id.clone(),
+            user_id: user_id.to_string(),
+            token: token.to_string(),
+            from_chain: from_chain.to_string(),
+            to_chain: to_chain.to_string(),
+            amount,
+            lock_id: None,
+            src_tx_hash: None,
+            dst_tx_hash: None,
+            status: BridgeTxStatus::Pending,
+            error_msg: None,
+            attempts: 0,
+            next_attempt_at: None,
+            refund_tx_hash: None,
+            created_at: now,
+            updated_at: now,
+        };
+        risk::record_transfer(self.db.as_ref(), &self.risk_limits, &tx, now).await?;
 
         let src_tx = match src.lock(user_id, token, amount, to_chain).await {
             Ok(h) => h,
             Err(e) => {
-                let _ = self
-                    .db
-                    .update_bridge_status(&tx_id, "Failed", Some(&format!("{:?}", e)))
-                    .await;
+                let _ = transition(
+                    self.db.as_ref(),
+                    &tx_id,
+                    BridgeTxStatus::Pending,
+                    BridgeTxStatus::Failed,
+                    Some(&format!("{:?}", e)),
+                )
+                .await;
                 return Err(match e {
                     BridgeError::Other(s) => s,
                     _ => format!("Bridge error: {:?}", e),
@@ -232,56 +458,270 @@ impl BridgeService {
             }
         };
         let _ = self.db.set_bridge_src_tx(&tx_id, &src_tx).await;
-        let _ = self.db.update_bridge_status(&tx_id, "Locked", None).await;
 
+        // Each lock is bridged by exactly one record
         if let Ok(Some(lock_id)) = src.extract_lock_id(&src_tx).await {
-            let _ = self.db.set_bridge_lock_id(&tx_id, &lock_id).await;
-        }
-
-        let lock_id_opt = match self.db.get_bridge_tx(&tx_id).await {
-            Ok(rec) => rec.lock_id,
-            Err(_) => None,
-        };
-        let dst_tx = match dst
-            .mint_or_release(
-                user_id,
-                token,
-                amount,
-                from_chain,
-                &src_tx,
-                lock_id_opt.as_deref(),
-            )
-            .await
-        {
-            Ok(h) => h,
-            Err(e) => {
+            if let Ok(existing) = self.db.list_bridge_txs_by_lock_id(&lock_id).await {
+                if let Some(other) = existing.iter().find(|rec| rec.id != tx_id) {
+                    let msg = format!("lock {} already bridged by tx {}", lock_id, other.id);
+                    let _ = self
+                        .db
+                        .update_bridge_status(&tx_id, BridgeTxStatus::Failed.as_str(), Some(&msg))
+                        .await;
+                    return Err(msg);
+                }
+            }
+            // The relayer won't mint a tx without its lock_id, and a unique violation
+            // here means another record took the lock in the meantime
+            if let Err(e) = self.db.set_bridge_lock_id(&tx_id, &lock_id).await {
+                let msg = format!("could not record lock {}: {}", lock_id, e);
                 let _ = self
                     .db
-                    .update_bridge_status(&tx_id, "Failed", Some(&format!("{:?}", e)))
+                    .update_bridge_status(&tx_id, BridgeTxStatus::Failed.as_str(), Some(&msg))
                     .await;
-                return Err(match e {
-                    BridgeError::Other(s) => s,
-                    _ => format!("Bridge error: {:?}", e),
-                });
+                return Err(format!("DB error: {}", e));
             }
-        };
-        let _ = self.db.set_bridge_dst_tx(&tx_id, &dst_tx).await;
-        let _ = self.db.update_bridge_status(&tx_id, "Minted", None).await;
+        }
+        let _ = transition(
+            self.db.as_ref(),
+            &tx_id,
+            BridgeTxStatus::Pending,
+            BridgeTxStatus::Locked,
+            None,
+        )
+        .await;
 
         Ok(tx_id)
     }
 
+    /// Put a dead-lettered transfer back in line for minting with a fresh retry budget
+    pub async fn retry_dead_letter(&self, tx_id: &str) -> Result<(), String> {
+        self.reopen_dead_letter(tx_id, BridgeTxStatus::Confirmed)
+            .await
+    }
+
+    /// Give up on minting a dead-lettered transfer and release the lock back to the user.
+    /// A tx can be dead-lettered after its mint went out, so the refund is refused unless
+    /// the destination positively reports that the lock was never minted and no other
+    /// record for the lock is minting or has minted it.
+    pub async fn refund_dead_letter(&self, tx_id: &str) -> Result<(), String> {
+        let rec = self
+            .db
+            .get_bridge_tx(tx_id)
+            .await
+            .map_err(|e| format!("DB error: {}", e))?;
+        if rec.status != BridgeTxStatus::DeadLetter {
+            return Err(format!("Bridge tx {} is not dead-lettered", tx_id));
+        }
+        if let Some(refund_tx) = rec.refund_tx_hash.as_deref() {
+            return Err(format!(
+                "Bridge tx {} was already refunded (refund tx {})",
+                tx_id, refund_tx
+            ));
+        }
+        if let Some(dst_tx) = rec.dst_tx_hash.as_deref() {
+            return Err(format!(
+                "Bridge tx {} was minted (dst tx {}), not refunding",
+                tx_id, dst_tx
+            ));
+        }
+        // The relayer never mints a tx without a lock_id, so there is no mint to rule out
+        if let Some(lock_id) = rec.lock_id.as_deref() {
+            let others = self
+                .db
+                .list_bridge_txs_by_lock_id(lock_id)
+                .await
+                .map_err(|e| format!("DB error: {}", e))?;
+            if let Some(other) = others.iter().find(|other| {
+                other.id != rec.id
+                    && matches!(
+                        other.status,
+                        BridgeTxStatus::Minting | BridgeTxStatus::Minted
+                    )
+            }) {
+                return Err(format!(
+                    "Lock {} is minted by bridge tx {}, not refunding",
+                    lock_id, other.id
+                ));
+            }
+            let dst = self
+                .adapters
+                .get(&rec.to_chain)
+                .ok_or_else(|| format!("No adapter for {}", rec.to_chain))?;
+            match dst.find_mint(lock_id).await {
+                Ok(None) => {}
+                Ok(Some(dst_tx)) => {
+                    return Err(format!(
+                        "Lock {} was minted on {} (dst tx {}), not refunding",
+                        lock_id, rec.to_chain, dst_tx
+                    ))
+                }
+                Err(e) => {
+                    return Err(format!(
+                        "Could not rule out a mint of lock {} on {}: {:?}",
+                        lock_id, rec.to_chain, e
+                    ))
+                }
+            }
+        }
+        self.reopen_dead_letter(tx_id, BridgeTxStatus::Refunding)
+            .await
+    }
+
+    async fn reopen_dead_letter(&self, tx_id: &str, to: BridgeTxStatus) -> Result<(), String> {
+        let rec = self
+            .db
+            .get_bridge_tx(tx_id)
+            .await
+            .map_err(|e| format!("DB error: {}", e))?;
+        if rec.status != BridgeTxStatus::DeadLetter {
+            return Err(format!("Bridge tx {} is not dead-lettered", tx_id));
+        }
+        self.db
+            .set_bridge_retry(tx_id, 0, None)
+            .await
+            .map_err(|e| format!("DB error: {}", e))?;
+        match transition(
+            self.db.as_ref(),
+            tx_id,
+            BridgeTxStatus::DeadLetter,
+            to,
+            None,
+        )
+        .await
+        {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(format!("Bridge tx {} is not dead-lettered", tx_id)),
+            Err(e) => Err(format!("DB error: {}", e)),
+        }
+    }
+
+    /// Stop accepting transfers and minting on `from_chain -> to_chain` until resumed
+    pub async fn pause_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+        reason: &str,
+    ) -> Result<(), String> {
+        for chain in [from_chain, to_chain] {
+            if !self.supported_chains.contains(&chain.to_string()) {
+                return Err(format!("Unsupported chain: {}", chain));
+            }
+        }
+        self.db
+            .pause_bridge_route(from_chain, to_chain, reason)
+            .await
+            .map_err(|e| format!("DB error: {}", e))
+    }
+
+    /// Reopen a route paused by an operator or the circuit breaker. Returns false if
+    /// the route was not paused.
+    pub async fn resume_route(&self, from_chain: &str, to_chain: &str) -> Result<bool, String> {
+        self.db
+            .resume_bridge_route(from_chain, to_chain)
+            .await
+            .map_err(|e| format!("DB error: {}", e))
+    }
+
+    pub async fn paused_routes(&self) -> Result<Vec<BridgeRoutePause>, String> {
+        self.db
+            .list_bridge_route_pauses()
+            .await
+            .map_err(|e| format!("DB error: {}", e))
+    }
+
+    /// Reconcile the bridged token's ledger against every chain's locked and minted
+    /// totals. Locks still unminted after `orphan_after` are reported as orphaned.
+    pub async fn audit_supply(
+        &self,
+        orphan_after: chrono::Duration,
+    ) -> Result<SupplyReport, String> {
+        audit::reconcile(
+            self.db.as_ref(),
+            &self.adapters,
+            BRIDGED_TOKEN,
+            orphan_after,
+            chrono::Utc::now().naive_utc(),
+        )
+        .await
+    }
+
+    /// The relayer committee currently allowed to attest mints, if one has been set up.
+    /// Nothing is minted until a committee exists.
+    pub async fn current_committee(&self) -> Result<Option<BridgeCommittee>, String> {
+        self.db
+            .latest_bridge_committee()
+            .await
+            .map_err(|e| format!("DB error: {}", e))
+    }
+
+    /// Replace the relayer committee with `members`, `threshold` of whom must attest each
+    /// mint. Attestations from members that are rotated out stop counting.
+    pub async fn rotate_committee(
+        &self,
+        members: &[String],
+        threshold: u32,
+    ) -> Result<BridgeCommittee, String> {
+        let epoch = self
+            .current_committee()
+            .await?
+            .map(|c| c.epoch + 1)
+            .unwrap_or(1);
+        let committee = committee::new_committee(epoch, members, threshold)?;
+        self.db
+            .save_bridge_committee(&committee)
+            .await
+            .map_err(|e| format!("DB error: {}", e))?;
+        Ok(committee)
+    }
+
+    /// Record an attestation signed by a committee member outside this process. Returns
+    /// false if the member had already attested the transfer.
+    pub async fn submit_attestation(
+        &self,
+        tx_id: &str,
+        signer: &str,
+        signature: &str,
+    ) -> Result<bool, String> {
+        let rec = self
+            .db
+            .get_bridge_tx(tx_id)
+            .await
+            .map_err(|e| format!("DB error: {}", e))?;
+        let committee = self
+            .current_committee()
+            .await?
+            .ok_or_else(|| "No relayer committee configured".to_string())?;
+        let lock_id = rec
+            .lock_id
+            .clone()
+            .ok_or_else(|| format!("Bridge tx {} has no lock_id", tx_id))?;
+        let attestation = BridgeAttestation {
+            lock_id,
+            bridge_tx_id: rec.id.clone(),
+            signer: signer.to_string(),
+            signature: signature.to_string(),
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        let attested = std::slice::from_ref(&attestation);
+        if committee::valid_signers(&committee, &rec, attested).is_empty() {
+            return Err(format!(
+                "Invalid attestation for bridge tx {} from {}",
+                tx_id, signer
+            ));
+        }
+        self.db
+            .add_bridge_attestation(&attestation)
+            .await
+            .map_err(|e| format!("DB error: {}", e))
+    }
+
     /// Get bridge transaction status
     pub async fn get_bridge_status(&self, tx_id: &str) -> Result<BridgeStatus, String> {
         match self.db.get_bridge_tx(tx_id).await {
             Ok(rec) => Ok(BridgeStatus {
                 tx_id: rec.id,
-                status: match rec.status {
-                    p_project_core::models::BridgeTxStatus::Pending => "Pending".to_string(),
-                    p_project_core::models::BridgeTxStatus::Locked => "Locked".to_string(),
-                    p_project_core::models::BridgeTxStatus::Minted => "Minted".to_string(),
-                    p_project_core::models::BridgeTxStatus::Failed => "Failed".to_string(),
-                },
+                status: rec.status.as_str().to_string(),
                 from_chain: rec.from_chain,
                 to_chain: rec.to_chain,
                 amount: rec.amount,
@@ -1,12 +1,24 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
+use chrono::{NaiveDateTime, Utc};
+use p_project_core::models::{BridgeAttestation, BridgeCommittee, BridgeTx, BridgeTxStatus};
+
 use crate::adapter::ChainAdapter;
-use crate::store::BridgeStore;
+use crate::committee::{valid_signers, CommitteeSigner, MintApproval, Release};
+use crate::error::BridgeError;
+use crate::finality::FinalityPolicy;
+use crate::risk::{check_route_totals, RiskLimits};
+use crate::state_machine::{retry_due, transition, RetryPolicy};
+use crate::store::{BoxedBridgeError, BridgeStore};
 
 pub struct Relayer<'a> {
     adapters: &'a HashMap<String, Box<dyn ChainAdapter + Send + Sync>>,
     db: &'a (dyn BridgeStore + Send + Sync),
+    retry: RetryPolicy,
+    finality: HashMap<String, FinalityPolicy>,
+    signer: Option<&'a CommitteeSigner>,
+    risk: RiskLimits,
 }
 
 impl<'a> Relayer<'a> {
@@ -14,85 +26,594 @@ impl<'a> Relayer<'a> {
         adapters: &'a HashMap<String, Box<dyn ChainAdapter + Send + Sync>>,
         db: &'a (dyn BridgeStore + Send + Sync),
     ) -> Self {
-        Self { adapters, db }
+        Self {
+            adapters,
+            db,
+            retry: RetryPolicy::default(),
+            finality: HashMap::new(),
+            signer: None,
+            risk: RiskLimits::default(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_finality_policies(mut self, finality: HashMap<String, FinalityPolicy>) -> Self {
+        self.finality = finality;
+        self
+    }
+
+    /// Attest confirmed transfers with this committee key before minting
+    pub fn with_signer(mut self, signer: &'a CommitteeSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn with_risk_limits(mut self, risk: RiskLimits) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    fn finality_for(&self, chain_name: &str) -> FinalityPolicy {
+        self.finality.get(chain_name).copied().unwrap_or_default()
     }
 
     pub async fn run_once(&self) {
-        // Process locked txs awaiting destination mint for every registered source chain
+        self.run_once_at(Utc::now().naive_utc()).await;
+    }
+
+    /// One relayer pass: trip the breaker on routes whose adapters report more minted
+    /// than locked, then for every registered source chain roll back locks lost to a
+    /// reorg, confirm final locks, settle mints stuck in Minting, mint the confirmed ones
+    /// whose retry is due and route is open, and work through requested refunds
+    pub async fn run_once_at(&self, now: NaiveDateTime) {
+        check_route_totals(
+            self.db,
+            self.adapters,
+            crate::BRIDGED_TOKEN,
+            self.risk.mint_tolerance,
+        )
+        .await;
         for (chain_name, src_adapter) in self.adapters.iter() {
-            if let Ok(items) = self.db.list_bridge_locked_without_dst(chain_name).await {
-                if !items.is_empty() {
+            self.revert_reorged(chain_name, src_adapter.as_ref(), now)
+                .await;
+            self.confirm_locks(chain_name, src_adapter.as_ref()).await;
+            self.recover_stale_mints(chain_name, now).await;
+            self.mint_confirmed(chain_name, now).await;
+            self.refund_requested(chain_name, src_adapter.as_ref(), now)
+                .await;
+        }
+    }
+
+    async fn confirm_locks(
+        &self,
+        chain_name: &str,
+        src_adapter: &(dyn ChainAdapter + Send + Sync),
+    ) {
+        let Ok(items) = self
+            .db
+            .list_bridge_txs_by_status(chain_name, BridgeTxStatus::Locked.as_str())
+            .await
+        else {
+            return;
+        };
+        let policy = self.finality_for(chain_name);
+        if !items.is_empty() {
+            println!(
+                "[Relayer] [{}] found {} locked bridge tx(s)",
+                chain_name,
+                items.len()
+            );
+        }
+        for rec in items {
+            println!(
+                "[Relayer] [{}] checking bridge tx {} (src {})",
+                chain_name,
+                rec.id,
+                rec.src_tx_hash.as_deref().unwrap_or("<missing>")
+            );
+            // Check finality on source chain
+            let Ok(status) = src_adapter
+                .get_tx_status(rec.src_tx_hash.as_deref().unwrap_or(""))
+                .await
+            else {
+                continue;
+            };
+            if policy.is_final(&status) {
+                let _ = transition(
+                    self.db,
+                    &rec.id,
+                    BridgeTxStatus::Locked,
+                    BridgeTxStatus::Confirmed,
+                    None,
+                )
+                .await;
+            } else if status.status == "Failed" {
+                // The lock reverted, so nothing left the user's wallet
+                let _ = transition(
+                    self.db,
+                    &rec.id,
+                    BridgeTxStatus::Locked,
+                    BridgeTxStatus::Failed,
+                    Some("source lock transaction failed"),
+                )
+                .await;
+            } else {
+                println!(
+                    "[Relayer] [{}] waiting for finality: status={} confirmations={}/{} finality={:?}/{:?}",
+                    chain_name,
+                    status.status,
+                    status.confirmations,
+                    policy.confirmations,
+                    status.finality,
+                    policy.tag
+                );
+            }
+        }
+    }
+
+    async fn mint_confirmed(&self, chain_name: &str, now: NaiveDateTime) {
+        let Ok(items) = self
+            .db
+            .list_bridge_txs_by_status(chain_name, BridgeTxStatus::Confirmed.as_str())
+            .await
+        else {
+            return;
+        };
+        // Mints are only sent with committee approval, so none go out until one exists
+        let Ok(Some(committee)) = self.db.latest_bridge_committee().await else {
+            if !items.is_empty() {
+                println!(
+                    "[Relayer] [{}] no relayer committee configured, holding {} confirmed tx(s)",
+                    chain_name,
+                    items.len()
+                );
+            }
+            return;
+        };
+        let Ok(pauses) = self.db.list_bridge_route_pauses().await else {
+            return;
+        };
+        for rec in items.into_iter().filter(|rec| retry_due(rec, now)) {
+            // Held, not failed: the mint goes ahead once the route is resumed
+            if pauses
+                .iter()
+                .any(|p| p.from_chain == rec.from_chain && p.to_chain == rec.to_chain)
+            {
+                continue;
+            }
+            // Without a lock_id a repeat mint of the same lock can't be told apart
+            let Some(lock_id) = rec.lock_id.as_deref() else {
+                let _ = transition(
+                    self.db,
+                    &rec.id,
+                    BridgeTxStatus::Confirmed,
+                    BridgeTxStatus::DeadLetter,
+                    Some("no lock_id recorded for the source lock"),
+                )
+                .await;
+                println!(
+                    "[Relayer] [{}] bridge tx {} has no lock_id, not minting",
+                    chain_name, rec.id
+                );
+                continue;
+            };
+            // Skip the tx if the other records for its lock can't be checked
+            let Ok(minted_by) = self.minted_elsewhere(&rec.id, lock_id).await else {
+                continue;
+            };
+            if let Some(other) = minted_by {
+                let _ = transition(
+                    self.db,
+                    &rec.id,
+                    BridgeTxStatus::Confirmed,
+                    BridgeTxStatus::DeadLetter,
+                    Some(&format!("lock already minted by bridge tx {}", other)),
+                )
+                .await;
+                continue;
+            }
+            let Some(attestations) = self.attested(chain_name, &committee, &rec).await else {
+                continue;
+            };
+            // Claim the mint; a relayer that loses this race leaves the tx alone
+            if !matches!(
+                transition(
+                    self.db,
+                    &rec.id,
+                    BridgeTxStatus::Confirmed,
+                    BridgeTxStatus::Minting,
+                    None,
+                )
+                .await,
+                Ok(true)
+            ) {
+                continue;
+            }
+
+            // Minted before its lock was reorged out and the lock is final again
+            if let Some(dst_tx) = rec.dst_tx_hash.as_deref() {
+                let _ = transition(
+                    self.db,
+                    &rec.id,
+                    BridgeTxStatus::Minting,
+                    BridgeTxStatus::Minted,
+                    None,
+                )
+                .await;
+                println!(
+                    "[Relayer] [{}] bridge tx {} already minted (dst tx {})",
+                    chain_name, rec.id, dst_tx
+                );
+                continue;
+            }
+
+            let Some(dst) = self.adapters.get(&rec.to_chain) else {
+                let msg = format!("no adapter for {}", rec.to_chain);
+                self.retry_or_dead_letter(
+                    &rec,
+                    BridgeTxStatus::Minting,
+                    BridgeTxStatus::Confirmed,
+                    &msg,
+                    now,
+                )
+                .await;
+                continue;
+            };
+            match dst
+                .mint_or_release(
+                    &rec.user_id,
+                    &rec.token,
+                    rec.amount,
+                    &rec.from_chain,
+                    rec.src_tx_hash.as_deref().unwrap_or(""),
+                    &Release::Mint(MintApproval {
+                        tx: &rec,
+                        committee: &committee,
+                        attestations: &attestations,
+                    }),
+                )
+                .await
+            {
+                Ok(dst_tx) => {
+                    // Left in Minting, the tx is settled by the stale mint sweep
+                    if let Err(e) = self.db.set_bridge_dst_tx(&rec.id, &dst_tx).await {
+                        println!(
+                            "[Relayer] [{}] minted bridge tx {} (dst tx {}) but could not record it: {}",
+                            chain_name, rec.id, dst_tx, e
+                        );
+                        continue;
+                    }
+                    let _ = transition(
+                        self.db,
+                        &rec.id,
+                        BridgeTxStatus::Minting,
+                        BridgeTxStatus::Minted,
+                        None,
+                    )
+                    .await;
                     println!(
-                        "[Relayer] [{}] found {} locked bridge tx(s)",
-                        chain_name,
-                        items.len()
+                        "[Relayer] [{}] minted bridge tx {} -> {} (dst tx {})",
+                        chain_name, rec.id, rec.to_chain, dst_tx
                     );
                 }
-                for rec in items {
+                Err(e) => {
                     println!(
-                        "[Relayer] [{}] checking bridge tx {} (src {})",
-                        chain_name,
-                        rec.id,
-                        rec.src_tx_hash.as_deref().unwrap_or("<missing>")
+                        "[Relayer] [{}] mint error for tx {}: {:?}",
+                        chain_name, rec.id, e
                     );
-                    // Check confirmations on source chain
-                    if let Ok(status) = src_adapter
-                        .get_tx_status(rec.src_tx_hash.as_deref().unwrap_or(""))
-                        .await
-                    {
-                        if status.status == "Success" && status.confirmations >= 1 {
-                            // Mint on destination
-                            if let Some(dst) = self.adapters.get(&rec.to_chain) {
-                                match dst
-                                    .mint_or_release(
-                                        &rec.user_id,
-                                        &rec.token,
-                                        rec.amount,
-                                        &rec.from_chain,
-                                        rec.src_tx_hash.as_deref().unwrap_or(""),
-                                        rec.lock_id.as_deref(),
-                                    )
-                                    .await
-                                {
-                                    Ok(dst_tx) => {
-                                        let _ = self.db.set_bridge_dst_tx(&rec.id, &dst_tx).await;
-                                        let _ = self
-                                            .db
-                                            .update_bridge_status(&rec.id, "Minted", None)
-                                            .await;
-                                        println!(
-                                            "[Relayer] [{}] minted bridge tx {} -> {} (dst tx {})",
-                                            chain_name, rec.id, rec.to_chain, dst_tx
-                                        );
-                                    }
-                                    Err(e) => {
-                                        println!(
-                                            "[Relayer] [{}] mint error for tx {}: {:?}",
-                                            chain_name, rec.id, e
-                                        );
-                                        let _ = self
-                                            .db
-                                            .update_bridge_status(
-                                                &rec.id,
-                                                "Failed",
-                                                Some(&format!("{:?}", e)),
-                                            )
-                                            .await;
-                                    }
-                                }
-                            }
-                        } else {
+                    self.retry_or_dead_letter(
+                        &rec,
+                        BridgeTxStatus::Minting,
+                        BridgeTxStatus::Confirmed,
+                        &format!("{:?}", e),
+                        now,
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    /// Settle txs stuck in Minting past the retry policy's timeout, left there by a
+    /// relayer that crashed mid-mint or couldn't record the dst tx. The destination is
+    /// asked for a mint of the tx's lock: one that landed is recorded as Minted, a lock
+    /// never minted goes back to Confirmed to be retried, and a tx whose mint can't be
+    /// looked up is dead-lettered for an operator.
+    async fn recover_stale_mints(&self, chain_name: &str, now: NaiveDateTime) {
+        let Ok(items) = self
+            .db
+            .list_bridge_txs_by_status(chain_name, BridgeTxStatus::Minting.as_str())
+            .await
+        else {
+            return;
+        };
+        for rec in items
+            .into_iter()
+            .filter(|rec| self.retry.minting_timed_out(rec, now))
+        {
+            let found = match (&rec.dst_tx_hash, rec.lock_id.as_deref()) {
+                (Some(dst_tx), _) => Ok(Some(dst_tx.clone())),
+                (None, Some(lock_id)) => match self.adapters.get(&rec.to_chain) {
+                    Some(dst) => dst.find_mint(lock_id).await,
+                    None => Err(BridgeError::UnsupportedChain(rec.to_chain.clone())),
+                },
+                (None, None) => Err(BridgeError::Other("no lock_id recorded".into())),
+            };
+            match found {
+                Ok(Some(dst_tx)) => {
+                    if rec.dst_tx_hash.is_none() {
+                        if let Err(e) = self.db.set_bridge_dst_tx(&rec.id, &dst_tx).await {
                             println!(
-                                "[Relayer] [{}] waiting for confirmations: status={} confirmations={}",
-                                chain_name, status.status, status.confirmations
+                                "[Relayer] [{}] could not record dst tx {} of bridge tx {}: {}",
+                                chain_name, dst_tx, rec.id, e
                             );
+                            continue;
                         }
                     }
+                    let _ = transition(
+                        self.db,
+                        &rec.id,
+                        BridgeTxStatus::Minting,
+                        BridgeTxStatus::Minted,
+                        None,
+                    )
+                    .await;
+                    println!(
+                        "[Relayer] [{}] stale bridge tx {} was minted (dst tx {})",
+                        chain_name, rec.id, dst_tx
+                    );
+                }
+                Ok(None) => {
+                    let msg = format!("mint never landed on {}", rec.to_chain);
+                    self.retry_or_dead_letter(
+                        &rec,
+                        BridgeTxStatus::Minting,
+                        BridgeTxStatus::Confirmed,
+                        &msg,
+                        now,
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    let msg = format!("could not check {} for the mint: {}", rec.to_chain, e);
+                    let _ = transition(
+                        self.db,
+                        &rec.id,
+                        BridgeTxStatus::Minting,
+                        BridgeTxStatus::DeadLetter,
+                        Some(&msg),
+                    )
+                    .await;
+                    println!(
+                        "[Relayer] [{}] stale bridge tx {} dead-lettered: {}",
+                        chain_name, rec.id, msg
+                    );
+                }
+            }
+        }
+    }
+
+    /// Send confirmed and recently minted txs whose source lock has disappeared back to
+    /// Locked, so they are only minted (again) once the lock is final
+    async fn revert_reorged(
+        &self,
+        chain_name: &str,
+        src_adapter: &(dyn ChainAdapter + Send + Sync),
+        now: NaiveDateTime,
+    ) {
+        let policy = self.finality_for(chain_name);
+        for from in [BridgeTxStatus::Confirmed, BridgeTxStatus::Minted] {
+            let Ok(items) = self
+                .db
+                .list_bridge_txs_by_status(chain_name, from.as_str())
+                .await
+            else {
+                continue;
+            };
+            let watched = items.into_iter().filter(|rec| {
+                from == BridgeTxStatus::Confirmed || policy.within_reorg_window(rec.updated_at, now)
+            });
+            for rec in watched {
+                let src_tx = rec.src_tx_hash.as_deref().unwrap_or("");
+                let Ok(status) = src_adapter.get_tx_status(src_tx).await else {
+                    continue;
+                };
+                if status.status != "NotFound" {
+                    continue;
+                }
+                let msg = format!("source lock {} no longer found on {}", src_tx, chain_name);
+                let _ =
+                    transition(self.db, &rec.id, from, BridgeTxStatus::Locked, Some(&msg)).await;
+                println!(
+                    "[Relayer] [{}] bridge tx {} reverted from {:?} to Locked: {}",
+                    chain_name, rec.id, from, msg
+                );
+            }
+        }
+    }
+
+    /// Release the locked funds back to the user on the source chain
+    async fn refund_requested(
+        &self,
+        chain_name: &str,
+        src_adapter: &(dyn ChainAdapter + Send + Sync),
+        now: NaiveDateTime,
+    ) {
+        let Ok(items) = self
+            .db
+            .list_bridge_txs_by_status(chain_name, BridgeTxStatus::Refunding.as_str())
+            .await
+        else {
+            return;
+        };
+        for rec in items.into_iter().filter(|rec| retry_due(rec, now)) {
+            match src_adapter
+                .mint_or_release(
+                    &rec.user_id,
+                    &rec.token,
+                    rec.amount,
+                    &rec.to_chain,
+                    rec.src_tx_hash.as_deref().unwrap_or(""),
+                    &Release::Refund {
+                        lock_id: rec.lock_id.as_deref(),
+                    },
+                )
+                .await
+            {
+                Ok(refund_tx) => {
+                    // Left in Refunding the release would be sent again, so the tx is
+                    // dead-lettered with the refund tx for an operator instead
+                    if let Err(e) = self.db.set_bridge_refund_tx(&rec.id, &refund_tx).await {
+                        let msg =
+                            format!("refunded (refund tx {}) but could not record it", refund_tx);
+                        let _ = transition(
+                            self.db,
+                            &rec.id,
+                            BridgeTxStatus::Refunding,
+                            BridgeTxStatus::DeadLetter,
+                            Some(&msg),
+                        )
+                        .await;
+                        println!(
+                            "[Relayer] [{}] bridge tx {} {}: {}",
+                            chain_name, rec.id, msg, e
+                        );
+                        continue;
+                    }
+                    let _ = transition(
+                        self.db,
+                        &rec.id,
+                        BridgeTxStatus::Refunding,
+                        BridgeTxStatus::Refunded,
+                        None,
+                    )
+                    .await;
+                    println!(
+                        "[Relayer] [{}] refunded bridge tx {} (refund tx {})",
+                        chain_name, rec.id, refund_tx
+                    );
+                }
+                Err(e) => {
+                    println!(
+                        "[Relayer] [{}] refund error for tx {}: {:?}",
+                        chain_name, rec.id, e
+                    );
+                    self.retry_or_dead_letter(
+                        &rec,
+                        BridgeTxStatus::Refunding,
+                        BridgeTxStatus::Refunding,
+                        &format!("{:?}", e),
+                        now,
+                    )
+                    .await;
                 }
             }
         }
     }
 
+    /// Add this relayer's attestation if it sits on the committee, then return the
+    /// transfer's attestations once the committee threshold of valid ones is reached
+    async fn attested(
+        &self,
+        chain_name: &str,
+        committee: &BridgeCommittee,
+        rec: &BridgeTx,
+    ) -> Option<Vec<BridgeAttestation>> {
+        let lock_id = rec.lock_id.as_deref()?;
+        if let Some(signer) = self
+            .signer
+            .filter(|s| committee.members.contains(&s.address()))
+        {
+            match signer.attest(rec) {
+                Ok(attestation) => {
+                    let _ = self.db.add_bridge_attestation(&attestation).await;
+                }
+                Err(e) => println!(
+                    "[Relayer] [{}] could not attest bridge tx {}: {}",
+                    chain_name, rec.id, e
+                ),
+            }
+        }
+        let attestations = self.db.list_bridge_attestations(lock_id).await.ok()?;
+        let signers = valid_signers(committee, rec, &attestations);
+        if signers.len() < committee.threshold as usize {
+            println!(
+                "[Relayer] [{}] bridge tx {} has {}/{} committee attestations",
+                chain_name,
+                rec.id,
+                signers.len(),
+                committee.threshold
+            );
+            return None;
+        }
+        Some(attestations)
+    }
+
+    /// Another bridge tx for the same lock that is minting or already minted
+    async fn minted_elsewhere(
+        &self,
+        id: &str,
+        lock_id: &str,
+    ) -> Result<Option<String>, BoxedBridgeError> {
+        let others = self.db.list_bridge_txs_by_lock_id(lock_id).await?;
+        Ok(others
+            .into_iter()
+            .find(|other| {
+                other.id != id
+                    && matches!(
+                        other.status,
+                        BridgeTxStatus::Minting | BridgeTxStatus::Minted
+                    )
+            })
+            .map(|other| other.id))
+    }
+
+    /// Record a failed attempt: back off and return to `retry_status`, or dead-letter
+    /// the tx once the retry policy runs out
+    async fn retry_or_dead_letter(
+        &self,
+        rec: &BridgeTx,
+        from: BridgeTxStatus,
+        retry_status: BridgeTxStatus,
+        error: &str,
+        now: NaiveDateTime,
+    ) {
+        let attempts = rec.attempts + 1;
+        if self.retry.exhausted(attempts) {
+            let _ = self.db.set_bridge_retry(&rec.id, attempts, None).await;
+            let _ = transition(
+                self.db,
+                &rec.id,
+                from,
+                BridgeTxStatus::DeadLetter,
+                Some(error),
+            )
+            .await;
+            println!(
+                "[Relayer] bridge tx {} dead-lettered after {} attempts",
+                rec.id, attempts
+            );
+            return;
+        }
+        let next_attempt_at = now + self.retry.delay(attempts);
+        let _ = self
+            .db
+            .set_bridge_retry(&rec.id, attempts, Some(next_attempt_at))
+            .await;
+        if from == retry_status {
+            let _ = self
+                .db
+                .update_bridge_status(&rec.id, from.as_str(), Some(error))
+                .await;
+        } else {
+            let _ = transition(self.db, &rec.id, from, retry_status, Some(error)).await;
+        }
+    }
+
     pub async fn run_loop(&self) {
         loop {
             self.run_once().await;
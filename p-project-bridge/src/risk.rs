@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+use p_project_core::models::{BridgeRoutePause, BridgeTx, BridgeVolumeLimit};
+
+use crate::adapter::ChainAdapter;
+use crate::error::BridgeError;
+use crate::store::BridgeStore;
+
+// Volume allowed within a rolling window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VolumeCap {
+    pub limit: f64,
+    pub window_secs: i64,
+}
+
+impl VolumeCap {
+    pub fn new(limit: f64, window_secs: i64) -> Self {
+        Self {
+            limit,
+            window_secs: window_secs.max(1),
+        }
+    }
+
+    fn since(&self, now: NaiveDateTime) -> NaiveDateTime {
+        now - Duration::seconds(self.window_secs)
+    }
+}
+
+// Bridge risk controls. Unset limits don't apply, so the default allows any transfer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RiskLimits {
+    pub max_transfer: Option<f64>,
+    pub per_user: Option<VolumeCap>,
+    pub per_route: Option<VolumeCap>,
+    pub global: Option<VolumeCap>,
+    pub breaker_outflow: Option<VolumeCap>, // Route outflow that trips the circuit breaker
+    pub mint_tolerance: f64, // Minted-over-locked excess tolerated before the breaker trips
+}
+
+/// The pause on `from_chain -> to_chain`, if there is one
+pub(crate) async fn route_pause(
+    db: &(dyn BridgeStore + Send + Sync),
+    from_chain: &str,
+    to_chain: &str,
+) -> Result<Option<BridgeRoutePause>, String> {
+    let pauses = db
+        .list_bridge_route_pauses()
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+    Ok(pauses
+        .into_iter()
+        .find(|p| p.from_chain == from_chain && p.to_chain == to_chain))
+}
+
+/// Check a transfer's amount against the per-transfer maximum and its route against
+/// the pauses. The rolling caps and the circuit breaker are applied when the transfer
+/// is recorded.
+pub(crate) async fn check_transfer(
+    db: &(dyn BridgeStore + Send + Sync),
+    limits: &RiskLimits,
+    from_chain: &str,
+    to_chain: &str,
+    amount: f64,
+) -> Result<(), String> {
+    if !(amount.is_finite() && amount > 0.0) {
+        return Err(format!("Invalid transfer amount: {}", amount));
+    }
+    if let Some(pause) = route_pause(db, from_chain, to_chain).await? {
+        return Err(paused_error(
+            &pause.from_chain,
+            &pause.to_chain,
+            &pause.reason,
+        ));
+    }
+    if let Some(max) = limits.max_transfer {
+        if amount > max {
+            return Err(format!(
+                "Amount {} exceeds the per-transfer maximum of {}",
+                amount, max
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Record `tx` unless it would exceed a rolling cap, checking the caps and inserting
+/// in one step so concurrent transfers can't both squeeze under a cap. A transfer that
+/// would push the route's outflow past the breaker threshold pauses the route and is
+/// rejected.
+pub(crate) async fn record_transfer(
+    db: &(dyn BridgeStore + Send + Sync),
+    limits: &RiskLimits,
+    tx: &BridgeTx,
+    now: NaiveDateTime,
+) -> Result<(), String> {
+    let route = (tx.from_chain.as_str(), tx.to_chain.as_str());
+    let caps: Vec<_> = [
+        ("user", limits.per_user, Some(tx.user_id.as_str()), None),
+        ("route", limits.per_route, None, Some(route)),
+        ("global", limits.global, None, None),
+        ("breaker", limits.breaker_outflow, None, Some(route)),
+    ]
+    .into_iter()
+    .filter_map(|(label, cap, user, route)| cap.map(|cap| (label, cap, user, route)))
+    .collect();
+    let volume_limits: Vec<BridgeVolumeLimit> = caps
+        .iter()
+        .map(|(_, cap, user, route)| BridgeVolumeLimit {
+            user_id: user.map(str::to_string),
+            route: route.map(|(from, to)| (from.to_string(), to.to_string())),
+            since: cap.since(now),
+            limit: cap.limit,
+        })
+        .collect();
+
+    let exceeded = db
+        .create_bridge_tx_within_limits(tx, &volume_limits)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+    let Some((index, used)) = exceeded else {
+        return Ok(());
+    };
+    let (label, cap, _, _) = caps[index];
+    if label == "breaker" {
+        let reason = format!(
+            "circuit breaker: outflow of {} in {}s exceeds {}",
+            used + tx.amount,
+            cap.window_secs,
+            cap.limit
+        );
+        trip(db, &tx.from_chain, &tx.to_chain, &reason).await;
+        return Err(paused_error(&tx.from_chain, &tx.to_chain, &reason));
+    }
+    Err(format!(
+        "Transfer exceeds the {} bridge cap: {} of {} used in the last {}s",
+        label, used, cap.limit, cap.window_secs
+    ))
+}
+
+/// Trip the breaker on every route whose destination reports more of `token` minted
+/// than its source reports locked. Routes where either adapter can't report its total
+/// are logged as unchecked and left open. Adapters that only report chain-wide totals
+/// are checked against the other chains' route totals summed over every route they
+/// share, and a breach trips all of those routes.
+pub(crate) async fn check_route_totals(
+    db: &(dyn BridgeStore + Send + Sync),
+    adapters: &HashMap<String, Box<dyn ChainAdapter + Send + Sync>>,
+    token: &str,
+    tolerance: f64,
+) {
+    let Ok(pauses) = db.list_bridge_route_pauses().await else {
+        return;
+    };
+    let paused = |from_chain: &str, to_chain: &str| {
+        pauses
+            .iter()
+            .any(|p| p.from_chain == from_chain && p.to_chain == to_chain)
+    };
+    for (from_chain, src) in adapters.iter() {
+        for (to_chain, dst) in adapters.iter().filter(|(name, _)| *name != from_chain) {
+            if paused(from_chain, to_chain) {
+                continue;
+            }
+            let (locked, minted) = (
+                src.locked_total(token, to_chain).await,
+                dst.minted_total(token, from_chain).await,
+            );
+            let (&Ok(Some(locked)), &Ok(Some(minted))) = (&locked, &minted) else {
+                println!(
+                    "[Bridge] route {} -> {} unchecked for {}: locked {}, minted {}",
+                    from_chain,
+                    to_chain,
+                    token,
+                    describe_total(&locked),
+                    describe_total(&minted)
+                );
+                continue;
+            };
+            if minted > locked + tolerance {
+                let reason = format!(
+                    "circuit breaker: {} reports {} {} minted against {} locked on {}",
+                    to_chain, minted, token, locked, from_chain
+                );
+                trip(db, from_chain, to_chain, &reason).await;
+            }
+        }
+    }
+
+    for (chain, adapter) in adapters.iter() {
+        let others: Vec<_> = adapters.iter().filter(|(name, _)| *name != chain).collect();
+        if let Ok(Some(locked)) = adapter.chain_locked_total(token).await {
+            let mut minted = Some(0.0);
+            for (_, dst) in &others {
+                let total = dst.minted_total(token, chain).await.ok().flatten();
+                minted = minted.zip(total).map(|(sum, total)| sum + total);
+            }
+            match minted {
+                Some(minted) if minted > locked + tolerance => {
+                    let reason = format!(
+                        "circuit breaker: {} {} minted elsewhere against {} locked on {}",
+                        minted, token, locked, chain
+                    );
+                    for (to_chain, _) in others.iter().filter(|(to, _)| !paused(chain, to)) {
+                        trip(db, chain, to_chain, &reason).await;
+                    }
+                }
+                Some(_) => {}
+                None => println!(
+                    "[Bridge] {} locked total unchecked for {}: a destination total is missing",
+                    chain, token
+                ),
+            }
+        }
+        if let Ok(Some(minted)) = adapter.chain_minted_total(token).await {
+            let mut locked = Some(0.0);
+            for (_, src) in &others {
+                let total = src.locked_total(token, chain).await.ok().flatten();
+                locked = locked.zip(total).map(|(sum, total)| sum + total);
+            }
+            match locked {
+                Some(locked) if minted > locked + tolerance => {
+                    let reason = format!(
+                        "circuit breaker: {} reports {} {} minted against {} locked elsewhere",
+                        chain, minted, token, locked
+                    );
+                    for (from_chain, _) in others.iter().filter(|(from, _)| !paused(from, chain)) {
+                        trip(db, from_chain, chain, &reason).await;
+                    }
+                }
+                Some(_) => {}
+                None => println!(
+                    "[Bridge] {} minted total unchecked for {}: a source total is missing",
+                    chain, token
+                ),
+            }
+        }
+    }
+}
+
+async fn trip(
+    db: &(dyn BridgeStore + Send + Sync),
+    from_chain: &str,
+    to_chain: &str,
+    reason: &str,
+) {
+    println!(
+        "[Bridge] pausing route {} -> {}: {}",
+        from_chain, to_chain, reason
+    );
+    let _ = db.pause_bridge_route(from_chain, to_chain, reason).await;
+}
+
+fn describe_total(total: &Result<Option<f64>, BridgeError>) -> String {
+    match total {
+        Ok(Some(amount)) => amount.to_string(),
+        Ok(None) => "not reported".to_string(),
+        Err(e) => format!("unavailable ({})", e),
+    }
+}
+
+fn paused_error(from_chain: &str, to_chain: &str, reason: &str) -> String {
+    format!(
+        "Bridge route {} -> {} is paused: {}",
+        from_chain, to_chain, reason
+    )
+}
@@ -1,6 +1,8 @@
 use crate::adapter::{AdapterTxStatus, ChainAdapter};
+use crate::committee::Release;
 use crate::config::SolanaConfig;
 use crate::error::BridgeError;
+use crate::finality::FinalityTag;
 use async_trait::async_trait;
 use p_project_core::utils::generate_id;
 
@@ -51,13 +53,14 @@ impl ChainAdapter for SolanaAdapter {
 
     async fn mint_or_release(
         &self,
-        _user: &str,
-        _token: &str,
-        _amount: f64,
-        _from_chain: &str,
+        user: &str,
+        token: &str,
+        amount: f64,
+        from_chain: &str,
         _source_tx: &str,
-        _lock_id: Option<&str>,
+        release: &Release<'_>,
     ) -> Result<String, BridgeError> {
+        release.verify(user, token, amount, from_chain)?;
         if self.rpc_url.is_none() {
             return Err(BridgeError::ConfigMissing("SOLANA_RPC_URL"));
         }
@@ -72,6 +75,7 @@ impl ChainAdapter for SolanaAdapter {
             tx_id: tx_id.to_string(),
             status: "Unknown".to_string(),
             confirmations: self.confirmations,
+            finality: FinalityTag::Latest,
         })
     }
 }
@@ -0,0 +1,91 @@
+use chrono::{Duration, NaiveDateTime};
+use p_project_core::models::{BridgeTx, BridgeTxStatus};
+
+use crate::store::{BoxedBridgeError, BridgeStore};
+
+/// Whether a bridge tx may move from `from` to `to`. Every status change made by the
+/// service and the relayer goes through this check. Confirmed and Minted txs fall back
+/// to Locked when their source lock is reorged away.
+pub fn can_transition(from: BridgeTxStatus, to: BridgeTxStatus) -> bool {
+    use BridgeTxStatus::*;
+    matches!(
+        (from, to),
+        (Pending, Locked)
+            | (Pending, Failed)
+            | (Locked, Confirmed)
+            | (Locked, Failed)
+            | (Confirmed, Locked)
+            | (Confirmed, Minting)
+            | (Confirmed, DeadLetter)
+            | (Minting, Minted)
+            | (Minting, Confirmed)
+            | (Minting, DeadLetter)
+            | (Minted, Locked)
+            | (DeadLetter, Confirmed)
+            | (DeadLetter, Refunding)
+            | (Refunding, Refunded)
+            | (Refunding, DeadLetter)
+    )
+}
+
+/// Exponential backoff for failed mints and refunds
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub base_delay_secs: i64,
+    pub max_delay_secs: i64,
+    pub max_attempts: u32, // Failures allowed before a tx is dead-lettered
+    pub minting_timeout_secs: i64, // Minting longer than this is checked on the destination
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay_secs: 15,
+            max_delay_secs: 3_600,
+            max_attempts: 8,
+            minting_timeout_secs: 900,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the next try after `attempts` failures: base * 2^(attempts - 1), capped
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(30);
+        let secs = self
+            .base_delay_secs
+            .saturating_mul(1i64 << exponent)
+            .min(self.max_delay_secs);
+        Duration::seconds(secs)
+    }
+
+    pub fn exhausted(&self, attempts: u32) -> bool {
+        attempts >= self.max_attempts
+    }
+
+    /// Whether a tx in Minting has gone without an outcome for too long at `now`
+    pub fn minting_timed_out(&self, tx: &BridgeTx, now: NaiveDateTime) -> bool {
+        tx.updated_at + Duration::seconds(self.minting_timeout_secs) <= now
+    }
+}
+
+/// Whether a tx waiting on a retry may be tried again at `now`
+pub fn retry_due(tx: &BridgeTx, now: NaiveDateTime) -> bool {
+    tx.next_attempt_at.is_none_or(|at| at <= now)
+}
+
+/// Move a tx from `from` to `to` if the state machine allows it and the tx is still in
+/// `from`. Returns whether this call made the change.
+pub(crate) async fn transition(
+    db: &(dyn BridgeStore + Send + Sync),
+    id: &str,
+    from: BridgeTxStatus,
+    to: BridgeTxStatus,
+    error_msg: Option<&str>,
+) -> Result<bool, BoxedBridgeError> {
+    if !can_transition(from, to) {
+        return Ok(false);
+    }
+    db.transition_bridge_status(id, from.as_str(), to.as_str(), error_msg)
+        .await
+}
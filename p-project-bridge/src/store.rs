@@ -1,8 +1,11 @@
 use async_trait::async_trait;
+use chrono::NaiveDateTime;
 use std::error::Error;
 
 use p_project_core::database::MySqlDatabase;
-use p_project_core::models::BridgeTx;
+use p_project_core::models::{
+    BridgeAttestation, BridgeCommittee, BridgeRoutePause, BridgeTx, BridgeVolumeLimit,
+};
 
 pub type BoxedBridgeError = Box<dyn Error + Send + Sync>;
 
@@ -33,6 +36,83 @@ pub trait BridgeStore: Send + Sync {
         &self,
         from_chain: &str,
     ) -> Result<Vec<BridgeTx>, BoxedBridgeError>;
+    async fn list_bridge_txs_by_status(
+        &self,
+        from_chain: &str,
+        status: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError>;
+    async fn list_bridge_txs_by_lock_id(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError>;
+    /// Every bridge tx for `token`, oldest first
+    async fn list_bridge_txs_by_token(
+        &self,
+        token: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError>;
+    /// Atomically move a tx from `from_status` to `to_status`. Returns false, changing
+    /// nothing, if the tx is no longer in `from_status`.
+    async fn transition_bridge_status(
+        &self,
+        id: &str,
+        from_status: &str,
+        to_status: &str,
+        error_msg: Option<&str>,
+    ) -> Result<bool, BoxedBridgeError>;
+    async fn set_bridge_retry(
+        &self,
+        id: &str,
+        attempts: u32,
+        next_attempt_at: Option<NaiveDateTime>,
+    ) -> Result<(), BoxedBridgeError>;
+    async fn set_bridge_refund_tx(
+        &self,
+        id: &str,
+        refund_tx_hash: &str,
+    ) -> Result<(), BoxedBridgeError>;
+    /// Record an attestation. Returns false if the signer already attested the lock.
+    async fn add_bridge_attestation(
+        &self,
+        attestation: &BridgeAttestation,
+    ) -> Result<bool, BoxedBridgeError>;
+    async fn list_bridge_attestations(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeAttestation>, BoxedBridgeError>;
+    async fn save_bridge_committee(
+        &self,
+        committee: &BridgeCommittee,
+    ) -> Result<(), BoxedBridgeError>;
+    async fn latest_bridge_committee(&self) -> Result<Option<BridgeCommittee>, BoxedBridgeError>;
+    /// Amount bridged since `since`, optionally narrowed to a user and/or a
+    /// (from_chain, to_chain) route. Failed and refunded transfers are excluded.
+    async fn sum_bridge_volume(
+        &self,
+        user_id: Option<&str>,
+        route: Option<(&str, &str)>,
+        since: NaiveDateTime,
+    ) -> Result<f64, BoxedBridgeError>;
+    /// Record `tx` unless it would exceed one of `limits`, checking and inserting
+    /// atomically. Returns the index of the first limit exceeded and the volume it had
+    /// already counted, inserting nothing.
+    async fn create_bridge_tx_within_limits(
+        &self,
+        tx: &BridgeTx,
+        limits: &[BridgeVolumeLimit],
+    ) -> Result<Option<(usize, f64)>, BoxedBridgeError>;
+    async fn pause_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+        reason: &str,
+    ) -> Result<(), BoxedBridgeError>;
+    /// Returns false if the route was not paused
+    async fn resume_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+    ) -> Result<bool, BoxedBridgeError>;
+    async fn list_bridge_route_pauses(&self) -> Result<Vec<BridgeRoutePause>, BoxedBridgeError>;
 }
 
 #[async_trait]
@@ -95,4 +175,146 @@ impl BridgeStore for MySqlDatabase {
             .await
             .map_err(|e| Box::new(e) as BoxedBridgeError)
     }
+
+    async fn list_bridge_txs_by_status(
+        &self,
+        from_chain: &str,
+        status: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        self.list_bridge_txs_with_status(from_chain, status)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn list_bridge_txs_by_lock_id(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        self.list_bridge_txs_with_lock_id(lock_id)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn list_bridge_txs_by_token(
+        &self,
+        token: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        self.list_bridge_txs_for_token(token)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn transition_bridge_status(
+        &self,
+        id: &str,
+        from_status: &str,
+        to_status: &str,
+        error_msg: Option<&str>,
+    ) -> Result<bool, BoxedBridgeError> {
+        self.compare_and_set_bridge_status(id, from_status, to_status, error_msg)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn set_bridge_retry(
+        &self,
+        id: &str,
+        attempts: u32,
+        next_attempt_at: Option<NaiveDateTime>,
+    ) -> Result<(), BoxedBridgeError> {
+        self.update_bridge_retry(id, attempts, next_attempt_at)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn set_bridge_refund_tx(
+        &self,
+        id: &str,
+        refund_tx_hash: &str,
+    ) -> Result<(), BoxedBridgeError> {
+        self.update_bridge_refund_tx(id, refund_tx_hash)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn add_bridge_attestation(
+        &self,
+        attestation: &BridgeAttestation,
+    ) -> Result<bool, BoxedBridgeError> {
+        self.insert_bridge_attestation(attestation)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn list_bridge_attestations(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeAttestation>, BoxedBridgeError> {
+        self.list_bridge_attestations_for_lock(lock_id)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn save_bridge_committee(
+        &self,
+        committee: &BridgeCommittee,
+    ) -> Result<(), BoxedBridgeError> {
+        self.insert_bridge_committee(committee)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn latest_bridge_committee(&self) -> Result<Option<BridgeCommittee>, BoxedBridgeError> {
+        self.fetch_latest_bridge_committee()
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn sum_bridge_volume(
+        &self,
+        user_id: Option<&str>,
+        route: Option<(&str, &str)>,
+        since: NaiveDateTime,
+    ) -> Result<f64, BoxedBridgeError> {
+        self.sum_bridge_volume_since(user_id, route, since)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn create_bridge_tx_within_limits(
+        &self,
+        tx: &BridgeTx,
+        limits: &[BridgeVolumeLimit],
+    ) -> Result<Option<(usize, f64)>, BoxedBridgeError> {
+        self.insert_bridge_tx_within_limits(tx, limits)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn pause_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+        reason: &str,
+    ) -> Result<(), BoxedBridgeError> {
+        self.upsert_bridge_route_pause(from_chain, to_chain, reason)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn resume_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+    ) -> Result<bool, BoxedBridgeError> {
+        self.delete_bridge_route_pause(from_chain, to_chain)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
+
+    async fn list_bridge_route_pauses(&self) -> Result<Vec<BridgeRoutePause>, BoxedBridgeError> {
+        MySqlDatabase::list_bridge_route_pauses(self)
+            .await
+            .map_err(|e| Box::new(e) as BoxedBridgeError)
+    }
 }
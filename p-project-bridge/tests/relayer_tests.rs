@@ -1,11 +1,18 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use p_project_bridge::{AdapterTxStatus, BoxedBridgeError, BridgeService, BridgeStore, ChainAdapter};
-use p_project_bridge::BridgeError;
-use p_project_core::models::{BridgeTx, BridgeTxStatus};
+use p_project_bridge::{
+    can_transition, verify_attestation, BridgeError, CommitteeSigner, FinalityPolicy, FinalityTag,
+    MintApproval, OrphanKind, Release, RetryPolicy, RiskLimits, VolumeCap,
+};
+use p_project_core::models::{
+    BridgeAttestation, BridgeCommittee, BridgeRoutePause, BridgeTx, BridgeTxStatus,
+    BridgeVolumeLimit,
+};
 
 // ------------------------------
 // Test helpers (mocks)
@@ -14,6 +21,15 @@ use p_project_core::models::{BridgeTx, BridgeTxStatus};
 #[derive(Default)]
 struct MockStore {
     inner: Mutex<HashMap<String, BridgeTx>>,
+    attestations: Mutex<Vec<BridgeAttestation>>,
+    committees: Mutex<Vec<BridgeCommittee>>,
+    pauses: Mutex<Vec<BridgeRoutePause>>,
+    // Fail set_bridge_lock_id like a unique key violation would
+    reject_lock_ids: bool,
+    // Fail set_bridge_dst_tx while set
+    reject_dst_txs: AtomicBool,
+    // Fail set_bridge_refund_tx while set
+    reject_refund_txs: AtomicBool,
 }
 
 impl MockStore {
@@ -21,14 +37,20 @@ impl MockStore {
         Utc::now().naive_utc()
     }
 
-    fn map_status(status: &str) -> BridgeTxStatus {
-        match status {
-            "Locked" => BridgeTxStatus::Locked,
-            "Minted" => BridgeTxStatus::Minted,
-            "Failed" => BridgeTxStatus::Failed,
-            _ => BridgeTxStatus::Pending,
+    // A store whose committee is the test relayer alone, so one relayer can mint
+    fn seated() -> Self {
+        let committee = BridgeCommittee {
+            epoch: 1,
+            members: vec![relayer_key().address()],
+            threshold: 1,
+            created_at: Self::now(),
+        };
+        Self {
+            committees: Mutex::new(vec![committee]),
+            ..Self::default()
         }
     }
+
 }
 
 #[async_trait]
@@ -56,8 +78,11 @@ impl BridgeStore for MockStore {
                 lock_id: None,
                 src_tx_hash: None,
                 dst_tx_hash: None,
-                status: MockStore::map_status(status),
+                status: BridgeTxStatus::from(status),
                 error_msg: None,
+                attempts: 0,
+                next_attempt_at: None,
+                refund_tx_hash: None,
                 created_at: MockStore::now(),
                 updated_at: MockStore::now(),
             },
@@ -74,6 +99,9 @@ impl BridgeStore for MockStore {
     }
 
     async fn set_bridge_dst_tx(&self, id: &str, dst_tx_hash: &str) -> Result<(), BoxedBridgeError> {
+        if self.reject_dst_txs.load(Ordering::SeqCst) {
+            return Err("connection lost".into());
+        }
         if let Some(tx) = self.inner.lock().unwrap().get_mut(id) {
             tx.dst_tx_hash = Some(dst_tx_hash.to_string());
             tx.updated_at = MockStore::now();
@@ -82,6 +110,9 @@ impl BridgeStore for MockStore {
     }
 
     async fn set_bridge_lock_id(&self, id: &str, lock_id: &str) -> Result<(), BoxedBridgeError> {
+        if self.reject_lock_ids {
+            return Err(format!("duplicate entry '{lock_id}' for key 'uniq_bridge_lock_id'").into());
+        }
         if let Some(tx) = self.inner.lock().unwrap().get_mut(id) {
             tx.lock_id = Some(lock_id.to_string());
             tx.updated_at = MockStore::now();
@@ -96,7 +127,7 @@ impl BridgeStore for MockStore {
         error_msg: Option<&str>,
     ) -> Result<(), BoxedBridgeError> {
         if let Some(tx) = self.inner.lock().unwrap().get_mut(id) {
-            tx.status = MockStore::map_status(status);
+            tx.status = BridgeTxStatus::from(status);
             tx.error_msg = error_msg.map(|s| s.to_string());
             tx.updated_at = MockStore::now();
         }
@@ -125,6 +156,208 @@ impl BridgeStore for MockStore {
             .cloned()
             .collect::<Vec<_>>())
     }
+    async fn list_bridge_txs_by_status(
+        &self,
+        from_chain: &str,
+        status: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        let map = self.inner.lock().unwrap();
+        Ok(map
+            .values()
+            .filter(|tx| tx.from_chain == from_chain && tx.status.as_str() == status)
+            .cloned()
+            .collect::<Vec<_>>())
+    }
+
+    async fn list_bridge_txs_by_lock_id(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        let map = self.inner.lock().unwrap();
+        Ok(map
+            .values()
+            .filter(|tx| tx.lock_id.as_deref() == Some(lock_id))
+            .cloned()
+            .collect::<Vec<_>>())
+    }
+
+    async fn list_bridge_txs_by_token(
+        &self,
+        token: &str,
+    ) -> Result<Vec<BridgeTx>, BoxedBridgeError> {
+        let map = self.inner.lock().unwrap();
+        let mut txs = map
+            .values()
+            .filter(|tx| tx.token == token)
+            .cloned()
+            .collect::<Vec<_>>();
+        txs.sort_by_key(|tx| tx.created_at);
+        Ok(txs)
+    }
+
+    async fn transition_bridge_status(
+        &self,
+        id: &str,
+        from_status: &str,
+        to_status: &str,
+        error_msg: Option<&str>,
+    ) -> Result<bool, BoxedBridgeError> {
+        match self.inner.lock().unwrap().get_mut(id) {
+            Some(tx) if tx.status.as_str() == from_status => {
+                tx.status = BridgeTxStatus::from(to_status);
+                tx.error_msg = error_msg.map(|s| s.to_string());
+                tx.updated_at = MockStore::now();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn set_bridge_retry(
+        &self,
+        id: &str,
+        attempts: u32,
+        next_attempt_at: Option<chrono::NaiveDateTime>,
+    ) -> Result<(), BoxedBridgeError> {
+        if let Some(tx) = self.inner.lock().unwrap().get_mut(id) {
+            tx.attempts = attempts;
+            tx.next_attempt_at = next_attempt_at;
+            tx.updated_at = MockStore::now();
+        }
+        Ok(())
+    }
+
+    async fn set_bridge_refund_tx(
+        &self,
+        id: &str,
+        refund_tx_hash: &str,
+    ) -> Result<(), BoxedBridgeError> {
+        if self.reject_refund_txs.load(Ordering::SeqCst) {
+            return Err("connection lost".into());
+        }
+        if let Some(tx) = self.inner.lock().unwrap().get_mut(id) {
+            tx.refund_tx_hash = Some(refund_tx_hash.to_string());
+            tx.updated_at = MockStore::now();
+        }
+        Ok(())
+    }
+
+    async fn add_bridge_attestation(
+        &self,
+        attestation: &BridgeAttestation,
+    ) -> Result<bool, BoxedBridgeError> {
+        let mut attestations = self.attestations.lock().unwrap();
+        if attestations
+            .iter()
+            .any(|a| a.lock_id == attestation.lock_id && a.signer == attestation.signer)
+        {
+            return Ok(false);
+        }
+        attestations.push(attestation.clone());
+        Ok(true)
+    }
+
+    async fn list_bridge_attestations(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<BridgeAttestation>, BoxedBridgeError> {
+        let attestations = self.attestations.lock().unwrap();
+        Ok(attestations
+            .iter()
+            .filter(|a| a.lock_id == lock_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn save_bridge_committee(
+        &self,
+        committee: &BridgeCommittee,
+    ) -> Result<(), BoxedBridgeError> {
+        self.committees.lock().unwrap().push(committee.clone());
+        Ok(())
+    }
+
+    async fn latest_bridge_committee(&self) -> Result<Option<BridgeCommittee>, BoxedBridgeError> {
+        let committees = self.committees.lock().unwrap();
+        Ok(committees.iter().max_by_key(|c| c.epoch).cloned())
+    }
+
+    async fn sum_bridge_volume(
+        &self,
+        user_id: Option<&str>,
+        route: Option<(&str, &str)>,
+        since: chrono::NaiveDateTime,
+    ) -> Result<f64, BoxedBridgeError> {
+        let map = self.inner.lock().unwrap();
+        Ok(volume(&map, user_id, route, since))
+    }
+
+    async fn create_bridge_tx_within_limits(
+        &self,
+        tx: &BridgeTx,
+        limits: &[BridgeVolumeLimit],
+    ) -> Result<Option<(usize, f64)>, BoxedBridgeError> {
+        let mut map = self.inner.lock().unwrap();
+        for (index, limit) in limits.iter().enumerate() {
+            let route = limit.route.as_ref().map(|(from, to)| (from.as_str(), to.as_str()));
+            let used = volume(&map, limit.user_id.as_deref(), route, limit.since);
+            if used + tx.amount > limit.limit {
+                return Ok(Some((index, used)));
+            }
+        }
+        map.insert(tx.id.clone(), tx.clone());
+        Ok(None)
+    }
+
+    async fn pause_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+        reason: &str,
+    ) -> Result<(), BoxedBridgeError> {
+        let mut pauses = self.pauses.lock().unwrap();
+        pauses.retain(|p| !(p.from_chain == from_chain && p.to_chain == to_chain));
+        pauses.push(BridgeRoutePause {
+            from_chain: from_chain.to_string(),
+            to_chain: to_chain.to_string(),
+            reason: reason.to_string(),
+            paused_at: MockStore::now(),
+        });
+        Ok(())
+    }
+
+    async fn resume_bridge_route(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+    ) -> Result<bool, BoxedBridgeError> {
+        let mut pauses = self.pauses.lock().unwrap();
+        let before = pauses.len();
+        pauses.retain(|p| !(p.from_chain == from_chain && p.to_chain == to_chain));
+        Ok(pauses.len() < before)
+    }
+
+    async fn list_bridge_route_pauses(&self) -> Result<Vec<BridgeRoutePause>, BoxedBridgeError> {
+        Ok(self.pauses.lock().unwrap().clone())
+    }
+}
+
+// Amount bridged since `since`, leaving out failed and refunded transfers
+fn volume(
+    map: &HashMap<String, BridgeTx>,
+    user_id: Option<&str>,
+    route: Option<(&str, &str)>,
+    since: chrono::NaiveDateTime,
+) -> f64 {
+    map.values()
+        .filter(|tx| {
+            tx.created_at >= since
+                && !matches!(tx.status, BridgeTxStatus::Failed | BridgeTxStatus::Refunded)
+                && user_id.is_none_or(|u| tx.user_id == u)
+                && route.is_none_or(|(from, to)| tx.from_chain == from && tx.to_chain == to)
+        })
+        .map(|tx| tx.amount)
+        .sum()
 }
 
 struct MockAdapter {
@@ -132,8 +365,18 @@ struct MockAdapter {
     // Controls get_tx_status output
     status: &'static str,
     confirmations: u32,
+    finality: FinalityTag,
     // Controls mint_or_release
     mint_result: Result<String, BridgeError>,
+    // Controls locked_total / minted_total, per counterpart chain or for any route
+    route_totals: HashMap<&'static str, (Option<f64>, Option<f64>)>,
+    locked: Option<f64>,
+    minted: Option<f64>,
+    // Controls chain_locked_total / chain_minted_total
+    chain_locked: Option<f64>,
+    chain_minted: Option<f64>,
+    // Controls find_mint
+    found_mint: Result<Option<String>, BridgeError>,
 }
 
 impl MockAdapter {
@@ -142,7 +385,14 @@ impl MockAdapter {
             name,
             status: "Success",
             confirmations: 5,
+            finality: FinalityTag::Latest,
             mint_result: Ok(format!("mock-{name}-mint")),
+            route_totals: HashMap::new(),
+            locked: None,
+            minted: None,
+            chain_locked: None,
+            chain_minted: None,
+            found_mint: Err(BridgeError::Unimplemented("mint lookup by lock_id")),
         }
     }
 
@@ -152,10 +402,42 @@ impl MockAdapter {
         self
     }
 
+    fn with_finality(mut self, finality: FinalityTag) -> Self {
+        self.finality = finality;
+        self
+    }
+
+    fn with_totals(mut self, locked: Option<f64>, minted: Option<f64>) -> Self {
+        self.locked = locked;
+        self.minted = minted;
+        self
+    }
+
+    fn with_route_totals(
+        mut self,
+        counterpart: &'static str,
+        locked: Option<f64>,
+        minted: Option<f64>,
+    ) -> Self {
+        self.route_totals.insert(counterpart, (locked, minted));
+        self
+    }
+
+    fn with_chain_totals(mut self, locked: Option<f64>, minted: Option<f64>) -> Self {
+        self.chain_locked = locked;
+        self.chain_minted = minted;
+        self
+    }
+
     fn with_mint_error(mut self, msg: &'static str) -> Self {
         self.mint_result = Err(BridgeError::TxFailed(msg.to_string()));
         self
     }
+
+    fn with_found_mint(mut self, dst_tx: Option<&'static str>) -> Self {
+        self.found_mint = Ok(dst_tx.map(|tx| tx.to_string()));
+        self
+    }
 }
 
 #[async_trait]
@@ -166,23 +448,24 @@ impl ChainAdapter for MockAdapter {
 
     async fn lock(
         &self,
-        _user: &str,
+        user: &str,
         _token: &str,
         _amount: f64,
         _to_chain: &str,
     ) -> Result<String, BridgeError> {
-        Ok(format!("mock-{}-lock", self.name))
+        Ok(format!("mock-{}-lock-{}", self.name, user))
     }
 
     async fn mint_or_release(
         &self,
-        _user: &str,
-        _token: &str,
-        _amount: f64,
-        _from_chain: &str,
+        user: &str,
+        token: &str,
+        amount: f64,
+        from_chain: &str,
         _source_tx: &str,
-        _lock_id: Option<&str>,
+        release: &Release<'_>,
     ) -> Result<String, BridgeError> {
+        release.verify(user, token, amount, from_chain)?;
         self.mint_result.clone()
     }
 
@@ -191,12 +474,47 @@ impl ChainAdapter for MockAdapter {
             tx_id: tx_id.to_string(),
             status: self.status.to_string(),
             confirmations: self.confirmations,
+            finality: self.finality,
         })
     }
 
     async fn extract_lock_id(&self, tx_hash: &str) -> Result<Option<String>, BridgeError> {
         Ok(Some(format!("lockid-{tx_hash}")))
     }
+
+    async fn find_mint(&self, _lock_id: &str) -> Result<Option<String>, BridgeError> {
+        self.found_mint.clone()
+    }
+
+    async fn locked_total(
+        &self,
+        _token: &str,
+        to_chain: &str,
+    ) -> Result<Option<f64>, BridgeError> {
+        Ok(self
+            .route_totals
+            .get(to_chain)
+            .map_or(self.locked, |(locked, _)| *locked))
+    }
+
+    async fn minted_total(
+        &self,
+        _token: &str,
+        from_chain: &str,
+    ) -> Result<Option<f64>, BridgeError> {
+        Ok(self
+            .route_totals
+            .get(from_chain)
+            .map_or(self.minted, |(_, minted)| *minted))
+    }
+
+    async fn chain_locked_total(&self, _token: &str) -> Result<Option<f64>, BridgeError> {
+        Ok(self.chain_locked)
+    }
+
+    async fn chain_minted_total(&self, _token: &str) -> Result<Option<f64>, BridgeError> {
+        Ok(self.chain_minted)
+    }
 }
 
 // ------------------------------
@@ -205,7 +523,7 @@ impl ChainAdapter for MockAdapter {
 
 #[tokio::test]
 async fn relayer_mints_on_successful_confirmation() {
-    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::default());
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
 
     // Seed a locked tx with src tx hash and lock id
     let id = "tx-1";
@@ -226,7 +544,7 @@ async fn relayer_mints_on_successful_confirmation() {
         Box::new(MockAdapter::new("Solana")),
     );
 
-    let service = BridgeService::with_adapters(store.clone(), adapters);
+    let service = relayer_service(store.clone(), adapters);
     let relayer = service.relayer();
     relayer.run_once().await;
 
@@ -237,7 +555,7 @@ async fn relayer_mints_on_successful_confirmation() {
 
 #[tokio::test]
 async fn relayer_waits_for_confirmations() {
-    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::default());
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
     let id = "tx-2";
     store
         .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 5.0, "Locked")
@@ -256,7 +574,7 @@ async fn relayer_waits_for_confirmations() {
         Box::new(MockAdapter::new("Solana")),
     );
 
-    let service = BridgeService::with_adapters(store.clone(), adapters);
+    let service = relayer_service(store.clone(), adapters);
     let relayer = service.relayer();
     relayer.run_once().await;
 
@@ -267,7 +585,7 @@ async fn relayer_waits_for_confirmations() {
 
 #[tokio::test]
 async fn relayer_handles_mint_error() {
-    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::default());
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
     let id = "tx-3";
     store
         .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 42.0, "Locked")
@@ -286,36 +604,1261 @@ async fn relayer_handles_mint_error() {
         Box::new(MockAdapter::new("Solana").with_mint_error("boom")),
     );
 
-    let service = BridgeService::with_adapters(store.clone(), adapters);
+    let service = relayer_service(store.clone(), adapters);
     let relayer = service.relayer();
     relayer.run_once().await;
 
     let rec = store.get_bridge_tx(id).await.unwrap();
-    assert!(matches!(rec.status, BridgeTxStatus::Failed));
+    // A failed mint goes back to Confirmed and waits out the backoff
+    assert!(matches!(rec.status, BridgeTxStatus::Confirmed));
+    assert_eq!(rec.attempts, 1);
+    assert!(rec.next_attempt_at.is_some());
     assert!(rec.error_msg.as_deref().unwrap_or_default().contains("boom"));
 }
 
 #[tokio::test]
 async fn bridge_service_sets_lock_id_and_mints() {
     // Build adapters for a full bridge call path
-    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::default());
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
     let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
     adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
     adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
 
-    let service = BridgeService::with_adapters(store.clone(), adapters);
+    let service = relayer_service(store.clone(), adapters);
     let tx_id = service
         .bridge_tokens("user-42", "Ethereum", "Solana", 7.5)
         .await
         .expect("bridge_tokens should succeed");
 
+    // bridge_tokens only locks; minting is left to the relayer
     let rec = store.get_bridge_tx(&tx_id).await.unwrap();
-    assert!(matches!(rec.status, BridgeTxStatus::Minted));
+    assert!(matches!(rec.status, BridgeTxStatus::Locked));
     assert!(rec.lock_id.is_some());
     assert!(rec.src_tx_hash.is_some());
+    assert!(rec.dst_tx_hash.is_none());
+
+    service.relayer().run_once().await;
+    let rec = store.get_bridge_tx(&tx_id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Minted));
     assert!(rec.dst_tx_hash.is_some());
 }
 
+#[tokio::test]
+async fn relayer_backs_off_then_dead_letters() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let id = "tx-4";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 3.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0xbbb").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock4").await.unwrap();
+
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert(
+        "Solana".to_string(),
+        Box::new(MockAdapter::new("Solana").with_mint_error("rpc down")),
+    );
+
+    let mut service = relayer_service(store.clone(), adapters);
+    service.set_retry_policy(RetryPolicy {
+        base_delay_secs: 10,
+        max_delay_secs: 60,
+        max_attempts: 3,
+        ..RetryPolicy::default()
+    });
+    let relayer = service.relayer();
+    let start = Utc::now().naive_utc();
+
+    relayer.run_once_at(start).await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert_eq!(rec.attempts, 1);
+    assert_eq!(rec.next_attempt_at, Some(start + Duration::seconds(10)));
+
+    // Not due yet: nothing changes
+    relayer.run_once_at(start + Duration::seconds(5)).await;
+    assert_eq!(store.get_bridge_tx(id).await.unwrap().attempts, 1);
+
+    relayer.run_once_at(start + Duration::seconds(10)).await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert_eq!(rec.attempts, 2);
+    assert_eq!(rec.next_attempt_at, Some(start + Duration::seconds(30)));
+
+    relayer.run_once_at(start + Duration::seconds(30)).await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::DeadLetter));
+    assert_eq!(rec.attempts, 3);
+    assert!(rec.error_msg.as_deref().unwrap_or_default().contains("rpc down"));
+
+    // Dead-lettered txs are left alone until an operator steps in
+    relayer.run_once_at(start + Duration::hours(1)).await;
+    assert_eq!(store.get_bridge_tx(id).await.unwrap().attempts, 3);
+}
+
+#[tokio::test]
+async fn relayer_never_mints_a_lock_twice() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    for (id, src) in [("tx-5a", "0xccc"), ("tx-5b", "0xddd")] {
+        store
+            .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 9.0, "Locked")
+            .await
+            .unwrap();
+        store.set_bridge_src_tx(id, src).await.unwrap();
+        store.set_bridge_lock_id(id, "0xshared").await.unwrap();
+    }
+
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+
+    let service = relayer_service(store.clone(), adapters);
+    let relayer = service.relayer();
+    relayer.run_once().await;
+    relayer.run_once().await;
+
+    let a = store.get_bridge_tx("tx-5a").await.unwrap();
+    let b = store.get_bridge_tx("tx-5b").await.unwrap();
+    let minted = [&a, &b]
+        .iter()
+        .filter(|rec| matches!(rec.status, BridgeTxStatus::Minted))
+        .count();
+    assert_eq!(minted, 1);
+    let duplicate = if matches!(a.status, BridgeTxStatus::Minted) { &b } else { &a };
+    assert!(matches!(duplicate.status, BridgeTxStatus::DeadLetter));
+    assert!(duplicate.dst_tx_hash.is_none());
+}
+
+#[tokio::test]
+async fn bridge_service_rejects_already_bridged_lock() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+
+    let service = relayer_service(store.clone(), adapters);
+    service
+        .bridge_tokens("user-1", "Ethereum", "Solana", 1.0)
+        .await
+        .unwrap();
+    // The mock adapter hands a user the same lock tx, so the second call reuses the lock_id
+    let err = service
+        .bridge_tokens("user-1", "Ethereum", "Solana", 1.0)
+        .await
+        .unwrap_err();
+    assert!(err.contains("already bridged"));
+}
+
+#[tokio::test]
+async fn bridge_service_fails_tx_when_lock_id_cannot_be_recorded() {
+    let store = Arc::new(MockStore {
+        reject_lock_ids: true,
+        ..MockStore::default()
+    });
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+
+    let service = relayer_service(store.clone(), adapters);
+    let err = service
+        .bridge_tokens("user-1", "Ethereum", "Solana", 1.0)
+        .await
+        .unwrap_err();
+    assert!(err.contains("uniq_bridge_lock_id"));
+
+    let txs = store.list_bridge_txs_by_token("P").await.unwrap();
+    assert_eq!(txs.len(), 1);
+    assert!(matches!(txs[0].status, BridgeTxStatus::Failed));
+    assert!(txs[0].lock_id.is_none());
+}
+
+#[tokio::test]
+async fn relayer_refuses_to_mint_without_lock_id() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let id = "tx-nolock";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 2.0, "Confirmed")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0xnolock").await.unwrap();
+
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+
+    let service = relayer_service(store.clone(), adapters);
+    service.relayer().run_once().await;
+
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::DeadLetter));
+    assert!(rec.dst_tx_hash.is_none());
+    assert!(rec.error_msg.as_deref().unwrap_or_default().contains("lock_id"));
+}
+
+#[tokio::test]
+async fn dead_letter_can_be_refunded() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let id = "tx-6";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 4.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0xeee").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock6").await.unwrap();
+
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert(
+        "Solana".to_string(),
+        Box::new(
+            MockAdapter::new("Solana")
+                .with_mint_error("paused")
+                .with_found_mint(None),
+        ),
+    );
+
+    let mut service = relayer_service(store.clone(), adapters);
+    service.set_retry_policy(RetryPolicy {
+        max_attempts: 1,
+        ..RetryPolicy::default()
+    });
+    service.relayer().run_once().await;
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::DeadLetter
+    ));
+    // Only dead-lettered txs can be reopened
+    assert!(service.refund_dead_letter("missing").await.is_err());
+
+    service.refund_dead_letter(id).await.unwrap();
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Refunding));
+    assert_eq!(rec.attempts, 0);
+    assert!(service.retry_dead_letter(id).await.is_err());
+
+    service.relayer().run_once().await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Refunded));
+    assert!(rec.refund_tx_hash.is_some());
+    assert!(rec.dst_tx_hash.is_none());
+}
+
+// A dead-lettered tx must not be refunded unless its lock provably never minted
+async fn assert_refund_refused(
+    service: &BridgeService,
+    store: &Arc<dyn BridgeStore + Send + Sync>,
+    id: &str,
+) {
+    assert!(service.refund_dead_letter(id).await.is_err());
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::DeadLetter));
+}
+
+#[tokio::test]
+async fn dead_letter_after_mint_errors_is_refunded_only_without_a_mint() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let id = "tx-6b";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 4.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0xeeb").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock6b").await.unwrap();
+
+    let build = |solana: MockAdapter| {
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+        adapters.insert("Solana".to_string(), Box::new(solana));
+        let mut service = relayer_service(store.clone(), adapters);
+        service.set_retry_policy(RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        });
+        service
+    };
+
+    // The mint errors out after it may have been broadcast
+    build(MockAdapter::new("Solana").with_mint_error("timeout"))
+        .relayer()
+        .run_once()
+        .await;
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::DeadLetter
+    ));
+
+    // A destination that can't look the mint up, or found it, blocks the refund
+    assert_refund_refused(&build(MockAdapter::new("Solana")), &store, id).await;
+    let landed = build(MockAdapter::new("Solana").with_found_mint(Some("0xlanded")));
+    assert_refund_refused(&landed, &store, id).await;
+
+    build(MockAdapter::new("Solana").with_found_mint(None))
+        .refund_dead_letter(id)
+        .await
+        .unwrap();
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::Refunding
+    ));
+}
+
+#[tokio::test]
+async fn dead_letter_from_unchecked_stale_mint_is_not_refunded() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let id = "tx-11c";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 1.0, "Minting")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0x445").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock11c").await.unwrap();
+
+    let build = |solana: MockAdapter| {
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+        adapters.insert("Solana".to_string(), Box::new(solana));
+        relayer_service(store.clone(), adapters)
+    };
+
+    // Solana and Sui adapters can't look mints up, so the stale mint is dead-lettered
+    let service = build(MockAdapter::new("Solana"));
+    service
+        .relayer()
+        .run_once_at(Utc::now().naive_utc() + Duration::hours(1))
+        .await;
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::DeadLetter
+    ));
+    assert_refund_refused(&service, &store, id).await;
+
+    // Nor once the dst tx has been recorded after all
+    store.set_bridge_dst_tx(id, "0xlate").await.unwrap();
+    let service = build(MockAdapter::new("Solana").with_found_mint(None));
+    assert_refund_refused(&service, &store, id).await;
+}
+
+#[tokio::test]
+async fn dead_letter_for_a_lock_minted_elsewhere_is_not_refunded() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    for (id, src) in [("tx-5c", "0xcc1"), ("tx-5d", "0xdd1")] {
+        store
+            .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 9.0, "Locked")
+            .await
+            .unwrap();
+        store.set_bridge_src_tx(id, src).await.unwrap();
+        store.set_bridge_lock_id(id, "0xshared2").await.unwrap();
+    }
+
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert(
+        "Solana".to_string(),
+        Box::new(MockAdapter::new("Solana").with_found_mint(None)),
+    );
+    let service = relayer_service(store.clone(), adapters);
+    service.relayer().run_once().await;
+    service.relayer().run_once().await;
+
+    let mut duplicate = None;
+    for id in ["tx-5c", "tx-5d"] {
+        let rec = store.get_bridge_tx(id).await.unwrap();
+        if matches!(rec.status, BridgeTxStatus::DeadLetter) {
+            duplicate = Some(rec);
+        }
+    }
+    let duplicate = duplicate.unwrap();
+    assert!(duplicate
+        .error_msg
+        .as_deref()
+        .unwrap_or_default()
+        .contains("already minted"));
+
+    // Even a destination reporting no mint doesn't outweigh the minted record
+    assert_refund_refused(&service, &store, &duplicate.id).await;
+}
+
+#[tokio::test]
+async fn unrecorded_refund_is_dead_lettered_not_resent() {
+    let store = Arc::new(MockStore::seated());
+    let id = "tx-6c";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 4.0, "Refunding")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0xeec").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock6c").await.unwrap();
+    store.reject_refund_txs.store(true, Ordering::SeqCst);
+
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+    let service = relayer_service(store.clone(), adapters);
+    service.relayer().run_once().await;
+
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::DeadLetter));
+    assert!(rec.refund_tx_hash.is_none());
+    assert!(rec.error_msg.as_deref().unwrap_or_default().contains("mock-Ethereum-mint"));
+}
+
+#[tokio::test]
+async fn relayer_applies_per_chain_confirmation_count() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let id = "tx-7";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 2.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0xfff").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock7").await.unwrap();
+
+    let build = |confirmations| {
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert(
+            "Ethereum".to_string(),
+            Box::new(MockAdapter::new("Ethereum").with_status("Success", confirmations)),
+        );
+        adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+        let mut service = relayer_service(store.clone(), adapters);
+        service.set_finality_policy("Ethereum", FinalityPolicy::new(12, FinalityTag::Latest));
+        service
+    };
+
+    // Plenty for the old one-confirmation rule, short of Ethereum's policy
+    build(5).relayer().run_once().await;
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::Locked
+    ));
+
+    build(12).relayer().run_once().await;
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::Minted
+    ));
+}
+
+#[tokio::test]
+async fn relayer_waits_for_finalized_tag() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let id = "tx-8";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 2.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0x111").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock8").await.unwrap();
+
+    let build = |finality| {
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert(
+            "Ethereum".to_string(),
+            Box::new(MockAdapter::new("Ethereum").with_finality(finality)),
+        );
+        adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+        let mut service = relayer_service(store.clone(), adapters);
+        service.set_finality_policy("Ethereum", FinalityPolicy::new(1, FinalityTag::Finalized));
+        service
+    };
+
+    build(FinalityTag::Safe).relayer().run_once().await;
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::Locked
+    ));
+
+    build(FinalityTag::Finalized).relayer().run_once().await;
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::Minted
+    ));
+}
+
+#[tokio::test]
+async fn relayer_reverts_minted_tx_when_lock_is_reorged_out() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let id = "tx-9";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 6.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0x222").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock9").await.unwrap();
+
+    let build = |status, mint_error: Option<&'static str>| {
+        let mut solana = MockAdapter::new("Solana");
+        if let Some(msg) = mint_error {
+            solana = solana.with_mint_error(msg);
+        }
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert(
+            "Ethereum".to_string(),
+            Box::new(MockAdapter::new("Ethereum").with_status(status, 5)),
+        );
+        adapters.insert("Solana".to_string(), Box::new(solana));
+        relayer_service(store.clone(), adapters)
+    };
+
+    build("Success", None).relayer().run_once().await;
+    let minted = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(minted.status, BridgeTxStatus::Minted));
+
+    // The lock vanishes from the source chain
+    build("NotFound", None).relayer().run_once().await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Locked));
+    assert!(rec.error_msg.as_deref().unwrap_or_default().contains("no longer found"));
+    assert_eq!(rec.dst_tx_hash, minted.dst_tx_hash);
+
+    // Once the lock is back and final the record returns to Minted without a second mint
+    build("Success", Some("must not mint twice"))
+        .relayer()
+        .run_once()
+        .await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Minted));
+    assert_eq!(rec.attempts, 0);
+    assert_eq!(rec.dst_tx_hash, minted.dst_tx_hash);
+}
+
+#[tokio::test]
+async fn unrecorded_mint_is_settled_from_the_destination() {
+    let store = Arc::new(MockStore::seated());
+    let id = "tx-10";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 8.0, "Confirmed")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0x333").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock10").await.unwrap();
+
+    let build = |found_mint| {
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+        adapters.insert(
+            "Solana".to_string(),
+            Box::new(MockAdapter::new("Solana").with_found_mint(found_mint)),
+        );
+        relayer_service(store.clone(), adapters)
+    };
+    let start = Utc::now().naive_utc();
+
+    // The mint goes out but its dst tx can't be recorded, so the tx stays in Minting
+    store.reject_dst_txs.store(true, Ordering::SeqCst);
+    build(None).relayer().run_once_at(start).await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Minting));
+    assert!(rec.dst_tx_hash.is_none());
+    store.reject_dst_txs.store(false, Ordering::SeqCst);
+
+    // Nothing happens until the tx has been Minting for the whole timeout
+    let timeout = Duration::seconds(RetryPolicy::default().minting_timeout_secs);
+    build(Some("0xlanded")).relayer().run_once_at(start).await;
+    assert!(matches!(
+        store.get_bridge_tx(id).await.unwrap().status,
+        BridgeTxStatus::Minting
+    ));
+
+    build(Some("0xlanded"))
+        .relayer()
+        .run_once_at(start + timeout + Duration::seconds(1))
+        .await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Minted));
+    assert_eq!(rec.dst_tx_hash.as_deref(), Some("0xlanded"));
+    assert_eq!(rec.attempts, 0);
+}
+
+#[tokio::test]
+async fn stale_mint_that_never_landed_is_retried_or_dead_lettered() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    for (id, lock_id) in [("tx-11a", "0xlock11a"), ("tx-11b", "0xlock11b")] {
+        store
+            .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 1.0, "Minting")
+            .await
+            .unwrap();
+        store.set_bridge_src_tx(id, "0x444").await.unwrap();
+        store.set_bridge_lock_id(id, lock_id).await.unwrap();
+    }
+    let later = Utc::now().naive_utc() + Duration::hours(1);
+
+    let build = |solana: MockAdapter| {
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+        adapters.insert("Solana".to_string(), Box::new(solana));
+        relayer_service(store.clone(), adapters)
+    };
+
+    // The destination has no mint for the lock: back to Confirmed behind a backoff
+    build(MockAdapter::new("Solana").with_found_mint(None))
+        .relayer()
+        .run_once_at(later)
+        .await;
+    for id in ["tx-11a", "tx-11b"] {
+        let rec = store.get_bridge_tx(id).await.unwrap();
+        assert!(matches!(rec.status, BridgeTxStatus::Confirmed));
+        assert_eq!(rec.attempts, 1);
+        assert!(rec.error_msg.as_deref().unwrap_or_default().contains("never landed"));
+    }
+
+    // A destination that can't look mints up leaves the call to an operator
+    store
+        .transition_bridge_status("tx-11b", "Confirmed", "Minting", None)
+        .await
+        .unwrap();
+    build(MockAdapter::new("Solana"))
+        .relayer()
+        .run_once_at(later + Duration::hours(1))
+        .await;
+    let rec = store.get_bridge_tx("tx-11b").await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::DeadLetter));
+    assert!(rec.dst_tx_hash.is_none());
+}
+
+// Service whose relayer attests with `relayer_key`
+fn relayer_service(
+    store: Arc<dyn BridgeStore + Send + Sync>,
+    adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>>,
+) -> BridgeService {
+    let mut service = BridgeService::with_adapters(store, adapters);
+    service.set_committee_signer(relayer_key());
+    service
+}
+
+fn relayer_key() -> CommitteeSigner {
+    committee_key(99)
+}
+
+fn committee_key(byte: u8) -> CommitteeSigner {
+    CommitteeSigner::new(&hex_key(byte)).unwrap()
+}
+
+fn hex_key(byte: u8) -> String {
+    format!("0x{}", format!("{:02x}", byte).repeat(32))
+}
+
+#[tokio::test]
+async fn committee_mint_waits_for_threshold_attestations() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::default());
+    let id = "tx-10";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 8.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0x333").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock10").await.unwrap();
+
+    let keys = [committee_key(1), committee_key(2), committee_key(3)];
+    let build = |signer: &CommitteeSigner| {
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+        adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+        let mut service = BridgeService::with_adapters(store.clone(), adapters);
+        service.set_committee_signer(signer.clone());
+        service
+    };
+
+    let first = build(&keys[0]);
+    let members: Vec<String> = keys.iter().map(|k| k.address()).collect();
+    let committee = first.rotate_committee(&members, 2).await.unwrap();
+    assert_eq!(committee.epoch, 1);
+
+    // One signature out of two: confirmed but not minted, and no retry is burned
+    first.relayer().run_once().await;
+    first.relayer().run_once().await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Confirmed));
+    assert_eq!(rec.attempts, 0);
+    assert_eq!(store.list_bridge_attestations("0xlock10").await.unwrap().len(), 1);
+
+    build(&keys[1]).relayer().run_once().await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Minted));
+    assert_eq!(store.list_bridge_attestations("0xlock10").await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn committee_rotation_and_external_attestations() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::default());
+    let id = "tx-11";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 8.0, "Confirmed")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0x444").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock11").await.unwrap();
+
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+    let service = BridgeService::with_adapters(store.clone(), adapters);
+
+    let (a, b, c) = (committee_key(4), committee_key(5), committee_key(6));
+    assert!(service
+        .rotate_committee(&[a.address(), a.address()], 1)
+        .await
+        .is_err());
+    assert!(service.rotate_committee(&[a.address()], 2).await.is_err());
+    assert!(service.rotate_committee(&["not-an-address".to_string()], 1).await.is_err());
+    service
+        .rotate_committee(&[a.address(), b.address()], 2)
+        .await
+        .unwrap();
+
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    let from_a = a.attest(&rec).unwrap();
+    let from_c = c.attest(&rec).unwrap();
+    assert!(service
+        .submit_attestation(id, &from_a.signer, &from_a.signature)
+        .await
+        .unwrap());
+    // Resubmitting is a no-op, non-members and forged signatures are rejected
+    assert!(!service
+        .submit_attestation(id, &from_a.signer, &from_a.signature)
+        .await
+        .unwrap());
+    assert!(service
+        .submit_attestation(id, &from_c.signer, &from_c.signature)
+        .await
+        .is_err());
+    assert!(service
+        .submit_attestation(id, &b.address(), &from_a.signature)
+        .await
+        .is_err());
+
+    // Rotate b out for c; c's signature now completes the quorum alongside a's
+    let rotated = service
+        .rotate_committee(&[a.address(), c.address()], 2)
+        .await
+        .unwrap();
+    assert_eq!(rotated.epoch, 2);
+    service
+        .submit_attestation(id, &from_c.signer, &from_c.signature)
+        .await
+        .unwrap();
+    service.relayer().run_once().await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Minted));
+}
+
+#[test]
+fn attestation_covers_every_transfer_field() {
+    let signer = committee_key(7);
+    let tx = BridgeTx {
+        id: "tx-12".to_string(),
+        user_id: "user-1".to_string(),
+        token: "P".to_string(),
+        from_chain: "Ethereum".to_string(),
+        to_chain: "Solana".to_string(),
+        amount: 1.5,
+        lock_id: Some("0xlock12".to_string()),
+        src_tx_hash: None,
+        dst_tx_hash: None,
+        status: BridgeTxStatus::Confirmed,
+        error_msg: None,
+        attempts: 0,
+        next_attempt_at: None,
+        refund_tx_hash: None,
+        created_at: Utc::now().naive_utc(),
+        updated_at: Utc::now().naive_utc(),
+    };
+    let attestation = signer.attest(&tx).unwrap();
+    assert!(verify_attestation(&tx, &attestation));
+
+    let mut tampered = tx.clone();
+    tampered.amount = 15.0;
+    assert!(!verify_attestation(&tampered, &attestation));
+    let mut tampered = tx.clone();
+    tampered.user_id = "user-2".to_string();
+    assert!(!verify_attestation(&tampered, &attestation));
+    let mut tampered = tx.clone();
+    tampered.to_chain = "Sui".to_string();
+    assert!(!verify_attestation(&tampered, &attestation));
+
+    let mut unlocked = tx.clone();
+    unlocked.lock_id = None;
+    assert!(signer.attest(&unlocked).is_err());
+    assert!(CommitteeSigner::new("not-a-key").is_err());
+}
+
+#[test]
+fn mint_release_needs_threshold_attestations_for_the_exact_transfer() {
+    let (a, b) = (committee_key(8), committee_key(9));
+    let tx = BridgeTx {
+        id: "tx-13".to_string(),
+        user_id: "user-1".to_string(),
+        token: "P".to_string(),
+        from_chain: "Ethereum".to_string(),
+        to_chain: "Solana".to_string(),
+        amount: 2.5,
+        lock_id: Some("0xlock13".to_string()),
+        src_tx_hash: None,
+        dst_tx_hash: None,
+        status: BridgeTxStatus::Confirmed,
+        error_msg: None,
+        attempts: 0,
+        next_attempt_at: None,
+        refund_tx_hash: None,
+        created_at: Utc::now().naive_utc(),
+        updated_at: Utc::now().naive_utc(),
+    };
+    let committee = BridgeCommittee {
+        epoch: 1,
+        members: vec![a.address(), b.address()],
+        threshold: 2,
+        created_at: Utc::now().naive_utc(),
+    };
+    let attestations = [a.attest(&tx).unwrap(), b.attest(&tx).unwrap()];
+    let release = |attestations| {
+        Release::Mint(MintApproval {
+            tx: &tx,
+            committee: &committee,
+            attestations,
+        })
+    };
+
+    assert!(release(&attestations[..]).verify("user-1", "P", 2.5, "Ethereum").is_ok());
+    assert!(release(&attestations[..1]).verify("user-1", "P", 2.5, "Ethereum").is_err());
+    assert!(release(&attestations[..]).verify("user-2", "P", 2.5, "Ethereum").is_err());
+    assert!(release(&attestations[..]).verify("user-1", "P", 25.0, "Ethereum").is_err());
+    assert!(release(&attestations[..]).verify("user-1", "P", 2.5, "Sui").is_err());
+    assert_eq!(release(&attestations[..]).lock_id(), Some("0xlock13"));
+}
+
+#[tokio::test]
+async fn relayer_holds_mints_until_a_committee_exists() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::default());
+    let id = "tx-14";
+    store
+        .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", 3.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(id, "0x555").await.unwrap();
+    store.set_bridge_lock_id(id, "0xlock14").await.unwrap();
+
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+    let service = relayer_service(store.clone(), adapters);
+
+    service.relayer().run_once().await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Confirmed));
+    assert!(rec.dst_tx_hash.is_none());
+    assert_eq!(rec.attempts, 0);
+
+    service
+        .rotate_committee(&[relayer_key().address()], 1)
+        .await
+        .unwrap();
+    service.relayer().run_once().await;
+    let rec = store.get_bridge_tx(id).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Minted));
+}
+
+fn two_chain_service(
+    store: &Arc<dyn BridgeStore + Send + Sync>,
+    limits: RiskLimits,
+) -> BridgeService {
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert("Ethereum".to_string(), Box::new(MockAdapter::new("Ethereum")));
+    adapters.insert("Solana".to_string(), Box::new(MockAdapter::new("Solana")));
+    let mut service = relayer_service(store.clone(), adapters);
+    service.set_risk_limits(limits);
+    service
+}
+
+#[tokio::test]
+async fn risk_limits_enforce_transfer_max_and_rolling_caps() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    for (id, user, from, to, amount, status) in [
+        ("v-1", "alice", "Ethereum", "Solana", 100.0, "Minted"),
+        ("v-2", "bob", "Ethereum", "Solana", 100.0, "Locked"),
+        ("v-3", "carol", "Solana", "Ethereum", 150.0, "Confirmed"),
+        // Failed transfers never moved funds and don't count
+        ("v-4", "alice", "Ethereum", "Solana", 1_000.0, "Failed"),
+    ] {
+        store
+            .create_bridge_tx(id, user, "P", from, to, amount, status)
+            .await
+            .unwrap();
+    }
+    let service = two_chain_service(
+        &store,
+        RiskLimits {
+            max_transfer: Some(100.0),
+            per_user: Some(VolumeCap::new(150.0, 3_600)),
+            per_route: Some(VolumeCap::new(250.0, 3_600)),
+            global: Some(VolumeCap::new(400.0, 3_600)),
+            ..RiskLimits::default()
+        },
+    );
+
+    let err = service
+        .bridge_tokens("alice", "Ethereum", "Solana", 101.0)
+        .await
+        .unwrap_err();
+    assert!(err.contains("per-transfer maximum"));
+    let err = service
+        .bridge_tokens("alice", "Ethereum", "Solana", 60.0)
+        .await
+        .unwrap_err();
+    assert!(err.contains("user bridge cap"));
+    let err = service
+        .bridge_tokens("erin", "Ethereum", "Solana", 60.0)
+        .await
+        .unwrap_err();
+    assert!(err.contains("route bridge cap"));
+    let err = service
+        .bridge_tokens("erin", "Solana", "Ethereum", 60.0)
+        .await
+        .unwrap_err();
+    assert!(err.contains("global bridge cap"));
+    // Rejected transfers leave no record behind
+    assert_eq!(
+        store
+            .sum_bridge_volume(None, None, Utc::now().naive_utc() - Duration::hours(1))
+            .await
+            .unwrap(),
+        350.0
+    );
+
+    service
+        .bridge_tokens("erin", "Solana", "Ethereum", 40.0)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn risk_limits_reject_bad_amounts_and_hold_under_concurrent_transfers() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let service = two_chain_service(
+        &store,
+        RiskLimits {
+            global: Some(VolumeCap::new(100.0, 3_600)),
+            ..RiskLimits::default()
+        },
+    );
+
+    for amount in [f64::NAN, f64::INFINITY, 0.0, -5.0] {
+        let err = service
+            .bridge_tokens("alice", "Ethereum", "Solana", amount)
+            .await
+            .unwrap_err();
+        assert!(err.contains("Invalid transfer amount"));
+    }
+
+    // Only as many transfers as fit under the cap go through, however they interleave
+    let send = |user| service.bridge_tokens(user, "Ethereum", "Solana", 40.0);
+    let results = tokio::join!(send("alice"), send("bob"), send("carol"), send("dave"));
+    let accepted = [results.0, results.1, results.2, results.3]
+        .iter()
+        .filter(|r| r.is_ok())
+        .count();
+    assert_eq!(accepted, 2);
+    assert_eq!(
+        store
+            .sum_bridge_volume(None, None, Utc::now().naive_utc() - Duration::hours(1))
+            .await
+            .unwrap(),
+        80.0
+    );
+}
+
+#[tokio::test]
+async fn circuit_breaker_pauses_route_on_outflow_until_resumed() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    store
+        .create_bridge_tx("o-1", "alice", "P", "Ethereum", "Solana", 80.0, "Minted")
+        .await
+        .unwrap();
+    let held = "o-2";
+    store
+        .create_bridge_tx(held, "bob", "P", "Ethereum", "Solana", 10.0, "Locked")
+        .await
+        .unwrap();
+    store.set_bridge_src_tx(held, "0x555").await.unwrap();
+    store.set_bridge_lock_id(held, "0xlock-o2").await.unwrap();
+    let service = two_chain_service(
+        &store,
+        RiskLimits {
+            breaker_outflow: Some(VolumeCap::new(100.0, 3_600)),
+            ..RiskLimits::default()
+        },
+    );
+
+    let err = service
+        .bridge_tokens("carol", "Ethereum", "Solana", 15.0)
+        .await
+        .unwrap_err();
+    assert!(err.contains("is paused"));
+    let pauses = service.paused_routes().await.unwrap();
+    assert_eq!(pauses.len(), 1);
+    assert_eq!(pauses[0].from_chain, "Ethereum");
+    assert_eq!(pauses[0].to_chain, "Solana");
+    assert!(pauses[0].reason.contains("circuit breaker"));
+    // Small transfers are refused too until an operator resumes the route
+    assert!(service
+        .bridge_tokens("carol", "Ethereum", "Solana", 1.0)
+        .await
+        .is_err());
+
+    // Mints on the paused route are held rather than failed
+    service.relayer().run_once().await;
+    let rec = store.get_bridge_tx(held).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Confirmed));
+    assert_eq!(rec.attempts, 0);
+
+    // The reverse route is unaffected
+    service
+        .bridge_tokens("carol", "Solana", "Ethereum", 5.0)
+        .await
+        .unwrap();
+
+    assert!(service.resume_route("Ethereum", "Solana").await.unwrap());
+    assert!(!service.resume_route("Ethereum", "Solana").await.unwrap());
+    service.relayer().run_once().await;
+    let rec = store.get_bridge_tx(held).await.unwrap();
+    assert!(matches!(rec.status, BridgeTxStatus::Minted));
+}
+
+#[tokio::test]
+async fn circuit_breaker_trips_on_mismatched_adapter_totals() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+    adapters.insert(
+        "Ethereum".to_string(),
+        Box::new(MockAdapter::new("Ethereum").with_totals(Some(100.0), None)),
+    );
+    adapters.insert(
+        "Solana".to_string(),
+        Box::new(MockAdapter::new("Solana").with_totals(None, Some(150.0))),
+    );
+    let service = relayer_service(store.clone(), adapters);
+
+    assert!(service.pause_route("Ethereum", "Bogus", "typo").await.is_err());
+    service
+        .pause_route("Solana", "Ethereum", "maintenance")
+        .await
+        .unwrap();
+    let err = service
+        .bridge_tokens("alice", "Solana", "Ethereum", 1.0)
+        .await
+        .unwrap_err();
+    assert!(err.contains("maintenance"));
+
+    // Solana reports more minted from Ethereum than Ethereum holds locked
+    service.relayer().run_once().await;
+    let pauses = service.paused_routes().await.unwrap();
+    let tripped = pauses
+        .iter()
+        .find(|p| p.from_chain == "Ethereum" && p.to_chain == "Solana")
+        .expect("route should be paused");
+    assert!(tripped.reason.contains("150"));
+    // The manual pause keeps its own reason
+    assert!(pauses
+        .iter()
+        .any(|p| p.from_chain == "Solana" && p.reason == "maintenance"));
+}
+
+#[tokio::test]
+async fn supply_audit_reports_route_drift_and_orphans() {
+    let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+    for (id, amount, lock_id, dst, status) in [
+        ("a-1", 10.0, "0xlock1", Some("0xmint1"), "Minted"),
+        ("a-2", 10.0, "0xlock1", Some("0xmint2"), "Minted"), // Second mint of one lock
+        ("a-3", 20.0, "0xlock3", None, "Confirmed"),         // Never minted
+        ("a-4", 5.0, "0xlock4", Some("0xmint4"), "Locked"),  // Minted, then reorged
+        ("a-5", 7.0, "0xlock5", None, "Refunded"),
+    ] {
+        store
+            .create_bridge_tx(id, "user-1", "P", "Ethereum", "Solana", amount, status)
+            .await
+            .unwrap();
+        store.set_bridge_src_tx(id, &format!("0xsrc-{id}")).await.unwrap();
+        store.set_bridge_lock_id(id, lock_id).await.unwrap();
+        if let Some(dst) = dst {
+            store.set_bridge_dst_tx(id, dst).await.unwrap();
+        }
+    }
+    let build = |src_status, locked, minted| {
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert(
+            "Ethereum".to_string(),
+            Box::new(
+                MockAdapter::new("Ethereum")
+                    .with_status(src_status, 5)
+                    .with_totals(Some(locked), None),
+            ),
+        );
+        adapters.insert(
+            "Solana".to_string(),
+            Box::new(MockAdapter::new("Solana").with_totals(None, Some(minted))),
+        );
+        relayer_service(store.clone(), adapters)
+    };
+    let route = |report: &p_project_bridge::SupplyReport, from: &str| {
+        report
+            .routes
+            .iter()
+            .find(|r| r.from_chain == from)
+            .cloned()
+            .unwrap()
+    };
+
+    // The chains agree with the ledger: 45 held on Ethereum, 25 minted on Solana
+    let report = build("Success", 45.0, 25.0)
+        .audit_supply(Duration::zero())
+        .await
+        .unwrap();
+    let eth_sol = route(&report, "Ethereum");
+    assert_eq!((eth_sol.ledger_locked, eth_sol.ledger_minted), (45.0, 25.0));
+    assert_eq!(eth_sol.locked_drift(), Some(0.0));
+    assert_eq!(eth_sol.minted_drift(), Some(0.0));
+    assert!(report.drifted(1e-6).is_empty());
+    let sol_eth = route(&report, "Solana");
+    assert_eq!((sol_eth.chain_locked, sol_eth.chain_minted), (None, None));
+    // A route the chains can't report on is never clean
+    let unreported = report.unreported();
+    assert_eq!(unreported.len(), 1);
+    assert_eq!(unreported[0].from_chain, "Solana");
+
+    let locks = report.orphans_of(OrphanKind::Lock);
+    assert_eq!(locks.len(), 1);
+    assert_eq!(locks[0].tx.id, "a-3");
+    let mints = report.orphans_of(OrphanKind::Mint);
+    assert_eq!(mints.len(), 2);
+    assert!(mints
+        .iter()
+        .any(|o| o.tx.id == "a-4" && o.reason.contains("Locked")));
+    assert!(mints.iter().any(|o| o.reason.contains("already minted")));
+    assert!(!report.is_clean(1e-6));
+
+    // Young locks are still in flight, not orphaned
+    let service = build("Success", 45.0, 25.0);
+    let report = service.audit_supply(Duration::hours(1)).await.unwrap();
+    assert!(report.orphans_of(OrphanKind::Lock).is_empty());
+
+    // Solana has minted more than the ledger knows of and Ethereum lost the locks
+    let report = build("NotFound", 45.0, 40.0)
+        .audit_supply(Duration::hours(1))
+        .await
+        .unwrap();
+    let drifted = report.drifted(1e-6);
+    assert_eq!(drifted.len(), 1);
+    assert_eq!(drifted[0].minted_drift(), Some(15.0));
+    let mints = report.orphans_of(OrphanKind::Mint);
+    assert_eq!(mints.len(), 3);
+    assert!(mints
+        .iter()
+        .any(|o| o.reason.contains("not found on Ethereum")));
+}
+
+#[tokio::test]
+async fn supply_audit_reconciles_chain_wide_totals_across_three_chains() {
+    // Ethereum's contract can only report totals over every route, Solana and Sui per route
+    let service = |eth_minted: f64, sol_from_eth: f64| {
+        let store: Arc<dyn BridgeStore + Send + Sync> = Arc::new(MockStore::seated());
+        let mut adapters: HashMap<String, Box<dyn ChainAdapter + Send + Sync>> = HashMap::new();
+        adapters.insert(
+            "Ethereum".to_string(),
+            Box::new(MockAdapter::new("Ethereum").with_chain_totals(Some(8.0), Some(eth_minted))),
+        );
+        adapters.insert(
+            "Solana".to_string(),
+            Box::new(
+                MockAdapter::new("Solana")
+                    .with_route_totals("Ethereum", Some(2.0), Some(sol_from_eth))
+                    .with_route_totals("Sui", Some(0.0), Some(1.0)),
+            ),
+        );
+        adapters.insert(
+            "Sui".to_string(),
+            Box::new(
+                MockAdapter::new("Sui")
+                    .with_route_totals("Ethereum", Some(0.0), Some(3.0))
+                    .with_route_totals("Solana", Some(1.0), Some(0.0)),
+            ),
+        );
+        (store.clone(), relayer_service(store, adapters))
+    };
+    let seed = |store: Arc<dyn BridgeStore + Send + Sync>| async move {
+        for (id, amount, from, to) in [
+            ("c-1", 5.0, "Ethereum", "Solana"),
+            ("c-2", 3.0, "Ethereum", "Sui"),
+            ("c-3", 2.0, "Solana", "Ethereum"),
+            ("c-4", 1.0, "Sui", "Solana"),
+        ] {
+            store
+                .create_bridge_tx(id, "user-1", "P", from, to, amount, "Minted")
+                .await
+                .unwrap();
+            store.set_bridge_src_tx(id, &format!("0xsrc-{id}")).await.unwrap();
+            store.set_bridge_lock_id(id, &format!("0xlock-{id}")).await.unwrap();
+            store.set_bridge_dst_tx(id, &format!("0xmint-{id}")).await.unwrap();
+        }
+    };
+    let paused = |pauses: &[BridgeRoutePause], from: &str, to: &str| {
+        pauses.iter().any(|p| p.from_chain == from && p.to_chain == to)
+    };
+
+    // Ethereum's 8 locked back 5 on Solana and 3 on Sui, its 2 minted are Solana's lock
+    let (store, healthy) = service(2.0, 5.0);
+    seed(store).await;
+    let report = healthy.audit_supply(Duration::zero()).await.unwrap();
+    assert!(report.is_clean(1e-6));
+    let eth_sol = report
+        .routes
+        .iter()
+        .find(|r| r.from_chain == "Ethereum" && r.to_chain == "Solana")
+        .unwrap();
+    assert_eq!(eth_sol.chain_locked, None);
+    assert_eq!(report.chains.len(), 1);
+    let eth = &report.chains[0];
+    assert_eq!((eth.ledger_locked, eth.ledger_minted), (8.0, 2.0));
+    assert_eq!((eth.counterpart_minted, eth.counterpart_locked), (Some(8.0), Some(2.0)));
+    assert_eq!(eth.unbacked(), Some(0.0));
+    healthy.relayer().run_once().await;
+    assert!(healthy.paused_routes().await.unwrap().is_empty());
+
+    // Ethereum mints 2 more than the ledger or the other chains' locks account for
+    let (store, inbound) = service(4.0, 5.0);
+    seed(store).await;
+    let report = inbound.audit_supply(Duration::zero()).await.unwrap();
+    assert!(report.drifted(1e-6).is_empty());
+    let drifted = report.drifted_chains(1e-6);
+    assert_eq!(drifted.len(), 1);
+    assert_eq!(drifted[0].minted_drift(), Some(2.0));
+    assert_eq!(drifted[0].unbacked(), Some(2.0));
+    assert!(!report.is_clean(1e-6));
+    inbound.relayer().run_once().await;
+    let pauses = inbound.paused_routes().await.unwrap();
+    assert!(paused(&pauses, "Solana", "Ethereum") && paused(&pauses, "Sui", "Ethereum"));
+    assert!(!paused(&pauses, "Ethereum", "Solana"));
+
+    // Solana mints 4 more than Ethereum's locks back
+    let (store, outbound) = service(2.0, 9.0);
+    seed(store).await;
+    let report = outbound.audit_supply(Duration::zero()).await.unwrap();
+    assert_eq!(report.drifted(1e-6).len(), 1);
+    assert_eq!(report.chains[0].unbacked(), Some(4.0));
+    outbound.relayer().run_once().await;
+    let pauses = outbound.paused_routes().await.unwrap();
+    assert!(paused(&pauses, "Ethereum", "Solana") && paused(&pauses, "Ethereum", "Sui"));
+    assert!(!paused(&pauses, "Solana", "Ethereum"));
+}
+
+#[test]
+fn state_machine_rejects_skipped_steps() {
+    use BridgeTxStatus::*;
+    assert!(can_transition(Locked, Confirmed));
+    assert!(can_transition(DeadLetter, Refunding));
+    assert!(!can_transition(Locked, Minted));
+    assert!(!can_transition(Minted, Minting));
+    assert!(!can_transition(Refunded, Confirmed));
+}
+
 // ------------------------------
 // Small unit checks for relayer components
 // ------------------------------
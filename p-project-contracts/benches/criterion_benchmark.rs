@@ -4,7 +4,8 @@ use p_project_contracts::advanced_cryptography::post_quantum;
 use p_project_contracts::advanced_cryptography::threshold_signatures;
 use p_project_contracts::advanced_cryptography::zero_knowledge;
 use p_project_contracts::l2_cross_chain::L2CrossChainProtocol;
-use p_project_contracts::l2_rollup::{L2Rollup, L2Transaction, RollupConfig};
+use p_project_contracts::l2_rollup::{L2Rollup, RollupConfig};
+use p_project_contracts::l2_signing::L2Keypair;
 use p_project_contracts::liquidity_pool::LiquidityPool;
 
 // Benchmark post-quantum cryptography operations
@@ -142,18 +143,21 @@ fn bench_l2_transaction_processing(b: &mut Bencher) {
     let mut rollup = L2Rollup::new(config);
 
     // Initialize accounts
-    rollup.initialize_account("user1".to_string(), 1000.0);
-    rollup.initialize_account("user2".to_string(), 1000.0);
+    let user1 = L2Keypair::from_seed("user1");
+    let user2 = L2Keypair::from_seed("user2");
+    rollup.initialize_account(user1.address().to_string(), 1000.0);
+    rollup.initialize_account(user2.address().to_string(), 1000.0);
 
     b.iter(|| {
-        let tx = L2Transaction {
-            from: "user1".to_string(),
-            to: "user2".to_string(),
-            amount: 100.0,
-            nonce: 1,
-            signature: "signature".to_string(),
-            timestamp: chrono::Utc::now().naive_utc(),
-        };
+        let tx = user1
+            .signed_transfer(
+                "test_chain",
+                user2.address(),
+                100.0,
+                rollup.next_nonce(user1.address()),
+                chrono::Utc::now().naive_utc(),
+            )
+            .unwrap();
         let _result = rollup.add_transaction(tx);
         black_box(_result);
     });
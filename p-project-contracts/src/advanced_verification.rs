@@ -5,6 +5,7 @@
 mod advanced_verification {
     use super::l2_cross_chain::{CrossChainMessage, L2CrossChainProtocol};
     use super::l2_rollup::{L2Rollup, L2Transaction, RollupConfig, RollupError};
+    use super::l2_signing::L2Keypair;
     use super::l2_state_management::{L2StateManager, StateCheckpointManager};
     use super::liquidity_pool::{LiquidityPool, LiquidityPoolConfig, LiquidityPoolError};
     use chrono::Utc;
@@ -69,6 +70,7 @@ mod advanced_verification {
             from: "user".to_string(),
             to: "recipient".to_string(),
             amount: -100.0, // Invalid negative amount
+            fee: 0.0,
             nonce: 0,
             signature: "sig".to_string(),
             timestamp: Utc::now().naive_utc(),
@@ -204,33 +206,35 @@ mod advanced_verification {
         let mut rollup = L2Rollup::new(config);
 
         // Initialize user with balance
-        rollup.initialize_account("user".to_string(), 10000.0);
+        let user = L2Keypair::from_seed("user");
+        rollup.initialize_account(user.address().to_string(), 10000.0);
 
         // Add more transactions than batch size
         for i in 0..10 {
             let amount: f64 = kani::any();
             kani::assume(amount > 0.0 && amount < 100.0);
 
-            let transaction = L2Transaction {
-                from: "user".to_string(),
-                to: format!("recipient_{}", i),
-                amount,
-                nonce: i as u64,
-                signature: format!("sig_{}", i),
-                timestamp: Utc::now().naive_utc(),
-            };
+            let transaction = user
+                .signed_transfer(
+                    "test-chain",
+                    &format!("recipient_{}", i),
+                    amount,
+                    i as u64,
+                    Utc::now().naive_utc(),
+                )
+                .unwrap();
 
             let _ = rollup.add_transaction(transaction);
         }
 
         // Verify transactions were added
-        assert_eq!(rollup.pending_transactions.len(), 10);
+        assert_eq!(rollup.pending_transaction_count(), 10);
 
         // Submit batch (should process up to max_batch_size)
         let result = rollup.submit_batch();
         assert!(result.is_ok());
 
         // Verify some transactions remain
-        assert!(rollup.pending_transactions.len() > 0);
+        assert!(rollup.pending_transaction_count() > 0);
     }
 }
@@ -0,0 +1,145 @@
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+// Bisection steps used to find the uniform clearing price
+const CLEARING_ITERATIONS: usize = 200;
+
+// A swap waiting for the end of its epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchOrder {
+    pub order_id: u64,
+    pub user_id: String,
+    pub input_token: String,
+    pub input_amount: f64,
+    pub min_output: f64,
+}
+
+// Collects a pool's swaps over fixed epochs so they all settle at one price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchAuction {
+    pub epoch_secs: i64,
+    pub epoch_start: NaiveDateTime,
+    pub orders: Vec<BatchOrder>, // Pending orders in submission order
+    pub next_order_id: u64,
+}
+
+impl BatchAuction {
+    pub fn new(epoch_secs: i64, now: NaiveDateTime) -> Self {
+        Self {
+            epoch_secs: epoch_secs.max(1),
+            epoch_start: now,
+            orders: Vec::new(),
+            next_order_id: 1,
+        }
+    }
+
+    /// When the current epoch stops accepting orders and can be settled
+    pub fn epoch_end(&self) -> NaiveDateTime {
+        self.epoch_start + Duration::seconds(self.epoch_secs)
+    }
+
+    /// Start of the epoch containing `now`, keeping epochs aligned to the first one
+    pub(crate) fn next_epoch_start(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let elapsed = (now - self.epoch_start).num_seconds().max(0);
+        self.epoch_start + Duration::seconds(elapsed - elapsed % self.epoch_secs)
+    }
+}
+
+// Outcome of one order in a settled batch. Unfilled orders keep their input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchFill {
+    pub order_id: u64,
+    pub user_id: String,
+    pub input_token: String,
+    pub input_amount: f64,
+    pub output_token: String,
+    pub output_amount: f64,
+    pub filled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSettlement {
+    pub epoch_start: NaiveDateTime,
+    pub epoch_end: NaiveDateTime,
+    pub clearing_price: Option<f64>, // token_b per token_a, None if nothing traded
+    pub matched_token_a: f64,        // token_a that changed hands without the pool
+    pub pool_input_token: Option<String>,
+    pub pool_input_amount: f64,
+    pub pool_output_amount: f64,
+    pub fills: Vec<BatchFill>,
+}
+
+// Uniform price for a batch and the net imbalance sent through the pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Clearing {
+    pub price: f64,        // token_b per token_a
+    pub pool_input_a: f64, // token_a sold to the pool (0 if none)
+    pub pool_input_b: f64, // token_b sold to the pool (0 if none)
+    pub pool_output: f64,  // What the pool pays for that input
+}
+
+/// Uniform clearing price for `sell_a` of token_a and `sell_b` of token_b offered against
+/// each other. Opposite flows are matched directly; the side left over trades with the
+/// pool at that same price, so the price is where the pool's average price for the net
+/// imbalance `Δ` makes both sides add up: `out(Δ) / Δ = p` with `Δ = sell_a − sell_b / p`
+/// (mirrored for a token_b imbalance). When the two sides balance inside the pool's fee
+/// band the batch clears at `sell_b / sell_a` without touching the pool.
+/// `quote_a` and `quote_b` give the pool's output for selling token_a and token_b, and
+/// `marginal_a` and `marginal_b` what it pays per unit of each at the margin, net of fees.
+pub(crate) fn clear(
+    sell_a: f64,
+    sell_b: f64,
+    marginal_a: f64,
+    marginal_b: f64,
+    quote_a: impl Fn(f64) -> Option<f64>,
+    quote_b: impl Fn(f64) -> Option<f64>,
+) -> Option<Clearing> {
+    if sell_a <= 0.0 && sell_b <= 0.0 {
+        return None;
+    }
+    if sell_a * marginal_a > sell_b {
+        let (input, output) = net_imbalance(sell_a, sell_b, quote_a)?;
+        return Some(Clearing {
+            price: output / input,
+            pool_input_a: input,
+            pool_input_b: 0.0,
+            pool_output: output,
+        });
+    }
+    if sell_b * marginal_b > sell_a {
+        let (input, output) = net_imbalance(sell_b, sell_a, quote_b)?;
+        return Some(Clearing {
+            price: input / output,
+            pool_input_a: 0.0,
+            pool_input_b: input,
+            pool_output: output,
+        });
+    }
+    Some(Clearing {
+        price: sell_b / sell_a,
+        pool_input_a: 0.0,
+        pool_input_b: 0.0,
+        pool_output: 0.0,
+    })
+}
+
+/// Amount of the heavy side sold to the pool, and the pool's output for it, solving
+/// `Δ − heavy + light · Δ / out(Δ) = 0` on `(0, heavy]` by bisection. The left side
+/// grows with `Δ` because the pool's average price falls as the trade gets larger.
+fn net_imbalance(heavy: f64, light: f64, quote: impl Fn(f64) -> Option<f64>) -> Option<(f64, f64)> {
+    let excess =
+        |delta: f64| -> Option<f64> { Some(delta - heavy + light * delta / quote(delta)?) };
+    let (mut lo, mut hi) = (0.0, heavy);
+    for _ in 0..CLEARING_ITERATIONS {
+        let mid = (lo + hi) / 2.0;
+        if mid <= lo || mid >= hi {
+            break;
+        }
+        if excess(mid)? < 0.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some((hi, quote(hi)?))
+}
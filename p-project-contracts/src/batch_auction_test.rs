@@ -0,0 +1,186 @@
+#[cfg(test)]
+mod tests {
+    use super::super::liquidity_pool::{LiquidityPool, LiquidityPoolError};
+    use chrono::{Duration, NaiveDateTime};
+
+    fn at(secs: i64) -> NaiveDateTime {
+        NaiveDateTime::UNIX_EPOCH + Duration::seconds(1_700_000_000 + secs)
+    }
+
+    fn batch_pool() -> LiquidityPool {
+        let mut pool = LiquidityPool::new(
+            "p_usdc".to_string(),
+            "P".to_string(),
+            "USDC".to_string(),
+            0.003,
+            "REWARD".to_string(),
+            0.0,
+            0.0,
+        );
+        pool.add_liquidity("lp".to_string(), 1_000_000.0, 1_000_000.0, 30)
+            .unwrap();
+        pool.enable_batch_auction(60, at(0));
+        pool
+    }
+
+    #[test]
+    fn test_opposite_orders_match_without_the_pool() {
+        let mut pool = batch_pool();
+        assert_eq!(
+            pool.swap("P", 1_000.0),
+            Err(LiquidityPoolError::BatchAuctionActive)
+        );
+        pool.submit_batch_swap("alice", "P", 1_000.0, 990.0, at(1))
+            .unwrap();
+        pool.submit_batch_swap("bob", "USDC", 1_000.0, 990.0, at(2))
+            .unwrap();
+        let before = pool.get_reserves();
+
+        let settlement = pool.settle_batch(at(60)).unwrap();
+        assert_eq!(settlement.clearing_price, Some(1.0));
+        assert_eq!(settlement.matched_token_a, 1_000.0);
+        assert_eq!(settlement.pool_input_token, None);
+        assert_eq!(pool.get_reserves(), before);
+        // Matched flow pays no pool fee
+        for fill in &settlement.fills {
+            assert!(fill.filled);
+            assert_eq!(fill.output_amount, 1_000.0);
+        }
+        assert_eq!(settlement.fills[0].output_token, "USDC");
+        assert_eq!(settlement.fills[1].output_token, "P");
+    }
+
+    #[test]
+    fn test_net_imbalance_routed_through_pool_at_uniform_price() {
+        let mut pool = batch_pool();
+        let mut direct = pool.clone();
+        direct.disable_batch_auction();
+        let direct_out = direct.swap("P", 10_000.0).unwrap();
+
+        pool.submit_batch_swap("alice", "P", 6_000.0, 0.0, at(1))
+            .unwrap();
+        pool.submit_batch_swap("carol", "P", 4_000.0, 0.0, at(2))
+            .unwrap();
+        pool.submit_batch_swap("bob", "USDC", 4_000.0, 0.0, at(3))
+            .unwrap();
+        let settlement = pool.settle_batch(at(61)).unwrap();
+        let price = settlement.clearing_price.unwrap();
+        assert_eq!(settlement.pool_input_token.as_deref(), Some("P"));
+
+        // Every order trades at the same price, and the pool trade at it too
+        let fills = &settlement.fills;
+        assert!((fills[0].output_amount / 6_000.0 - price).abs() < 1e-9);
+        assert!((fills[1].output_amount / 4_000.0 - price).abs() < 1e-9);
+        assert!((4_000.0 / fills[2].output_amount - price).abs() < 1e-9);
+        assert!(
+            (settlement.pool_output_amount / settlement.pool_input_amount - price).abs() < 1e-9
+        );
+        // Only the imbalance hit the pool: P sold by both sides adds up
+        assert!((settlement.pool_input_amount + fills[2].output_amount - 10_000.0).abs() < 1e-6);
+        assert!((settlement.matched_token_a - fills[2].output_amount).abs() < 1e-6);
+        let (reserve_p, reserve_usdc) = pool.get_reserves();
+        assert!((reserve_p - 1_000_000.0 - settlement.pool_input_amount).abs() < 1e-6);
+        assert!((1_000_000.0 - reserve_usdc - settlement.pool_output_amount).abs() < 1e-6);
+        // Sellers of P do better than swapping the same total directly
+        assert!(fills[0].output_amount + fills[1].output_amount > direct_out);
+    }
+
+    #[test]
+    fn test_settlement_ignores_submission_order() {
+        let orders = [
+            ("victim", "USDC", 20_000.0),
+            ("attacker", "USDC", 50_000.0),
+            ("seller", "P", 15_000.0),
+            ("attacker", "P", 50_000.0),
+        ];
+        let settle = |order: &[usize]| {
+            let mut pool = batch_pool();
+            for (k, i) in order.iter().enumerate() {
+                let (user, token, amount) = orders[*i];
+                pool.submit_batch_swap(user, token, amount, 0.0, at(k as i64))
+                    .unwrap();
+            }
+            let settlement = pool.settle_batch(at(60)).unwrap();
+            let mut outputs: Vec<(String, String, f64)> = settlement
+                .fills
+                .iter()
+                .map(|f| (f.user_id.clone(), f.input_token.clone(), f.output_amount))
+                .collect();
+            outputs.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+            (
+                settlement.clearing_price.unwrap(),
+                outputs,
+                pool.get_reserves(),
+            )
+        };
+
+        let (price, outputs, reserves) = settle(&[0, 1, 2, 3]);
+        for permutation in [[1, 0, 3, 2], [3, 2, 1, 0], [1, 3, 0, 2]] {
+            let (p, o, r) = settle(&permutation);
+            assert!((p - price).abs() < 1e-12);
+            assert!((r.0 - reserves.0).abs() < 1e-6 && (r.1 - reserves.1).abs() < 1e-6);
+            for (x, y) in o.iter().zip(&outputs) {
+                assert_eq!((&x.0, &x.1), (&y.0, &y.1));
+                assert!((x.2 - y.2).abs() < 1e-6);
+            }
+        }
+        // Buying before the victim and selling after gains nothing: both legs clear
+        // at the same price, so the round trip returns at most what was put in
+        let attacker_usdc_in = 50_000.0;
+        let attacker_usdc_out = outputs
+            .iter()
+            .find(|o| o.0 == "attacker" && o.1 == "P")
+            .unwrap()
+            .2;
+        let attacker_p_out = outputs
+            .iter()
+            .find(|o| o.0 == "attacker" && o.1 == "USDC")
+            .unwrap()
+            .2;
+        assert!((attacker_p_out * price - attacker_usdc_in).abs() < 1e-6);
+        assert!((attacker_usdc_out - 50_000.0 * price).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_epochs_and_limit_prices() {
+        let mut pool = batch_pool();
+        assert_eq!(
+            pool.settle_batch(at(59)).map(|_| ()),
+            Err(LiquidityPoolError::EpochNotEnded)
+        );
+        // Asks for more than the pool can pay for a large sell
+        let greedy = pool
+            .submit_batch_swap("greedy", "P", 100_000.0, 99_000.0, at(10))
+            .unwrap();
+        let modest = pool
+            .submit_batch_swap("modest", "P", 1_000.0, 990.0, at(20))
+            .unwrap();
+        assert_eq!(
+            pool.submit_batch_swap("late", "P", 1_000.0, 0.0, at(60)),
+            Err(LiquidityPoolError::EpochEnded)
+        );
+
+        let settlement = pool.settle_batch(at(150)).unwrap();
+        let greedy_fill = &settlement.fills[0];
+        assert_eq!(greedy_fill.order_id, greedy);
+        assert!(!greedy_fill.filled);
+        assert_eq!(greedy_fill.output_amount, 0.0);
+        let modest_fill = &settlement.fills[1];
+        assert_eq!(modest_fill.order_id, modest);
+        assert!(modest_fill.filled && modest_fill.output_amount >= 990.0);
+        assert_eq!(settlement.pool_input_amount, 1_000.0);
+
+        // The next epoch is aligned to the first one
+        let auction = pool.batch_auction.as_ref().unwrap();
+        assert_eq!(auction.epoch_start, at(120));
+        assert!(auction.orders.is_empty());
+        let empty = pool.settle_batch(at(180)).unwrap();
+        assert!(empty.fills.is_empty() && empty.clearing_price.is_none());
+
+        pool.submit_batch_swap("alice", "USDC", 5.0, 0.0, at(181))
+            .unwrap();
+        let pending = pool.disable_batch_auction();
+        assert_eq!(pending.len(), 1);
+        assert!(pool.swap("P", 10.0).is_ok());
+    }
+}
@@ -3,7 +3,8 @@
 
 #[cfg(kani)]
 mod comprehensive_verification {
-    use super::l2_rollup::{L2Rollup, L2Transaction, RollupConfig};
+    use super::l2_rollup::{L2Rollup, RollupConfig};
+    use super::l2_signing::L2Keypair;
     use super::l2_state_management::L2StateManager;
     use super::liquidity_pool::{LiquidityPool, LiquidityPoolConfig};
     use chrono::Utc;
@@ -99,7 +100,8 @@ mod comprehensive_verification {
         let mut rollup = L2Rollup::new(config);
 
         // Initialize user with large balance
-        rollup.initialize_account("user".to_string(), 1000000.0);
+        let user = L2Keypair::from_seed("user");
+        rollup.initialize_account(user.address().to_string(), 1000000.0);
 
         // Add many small transactions
         let num_transactions: usize = kani::any();
@@ -109,20 +111,21 @@ mod comprehensive_verification {
             let amount: f64 = kani::any();
             kani::assume(amount > 0.0 && amount < 100.0);
 
-            let transaction = L2Transaction {
-                from: "user".to_string(),
-                to: format!("recipient_{}", i),
-                amount,
-                nonce: i as u64,
-                signature: format!("sig_{}", i),
-                timestamp: Utc::now().naive_utc(),
-            };
+            let transaction = user
+                .signed_transfer(
+                    "test-chain",
+                    &format!("recipient_{}", i),
+                    amount,
+                    i as u64,
+                    Utc::now().naive_utc(),
+                )
+                .unwrap();
 
             let _ = rollup.add_transaction(transaction);
         }
 
         // Verify all transactions were added
-        assert_eq!(rollup.pending_transactions.len(), num_transactions);
+        assert_eq!(rollup.pending_transaction_count(), num_transactions);
 
         // Create block
         let result = rollup.create_block();
@@ -130,7 +133,7 @@ mod comprehensive_verification {
 
         // Verify block was created
         assert_eq!(rollup.blocks.len(), 1);
-        assert_eq!(rollup.pending_transactions.len(), 0);
+        assert_eq!(rollup.pending_transaction_count(), 0);
     }
 
     // Verification harness for fee calculation accuracy
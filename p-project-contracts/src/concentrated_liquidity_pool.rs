@@ -0,0 +1,685 @@
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+// Price ratio between two adjacent ticks: price(i) = TICK_BASE^i
+pub const TICK_BASE: f64 = 1.0001;
+
+// Tick bounds, as in Uniswap v3 (prices from ~2.9e-39 to ~3.4e38)
+pub const MIN_TICK: i32 = -887_272;
+pub const MAX_TICK: i32 = 887_272;
+
+// Swap input left over from floating point rounding that is treated as consumed
+const DUST: f64 = 1e-12;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConcentratedPoolError {
+    InvalidAmount,
+    InvalidPrice,
+    InvalidTickRange,
+    InsufficientLiquidity,
+    PositionNotFound,
+    NotPositionOwner,
+}
+
+impl fmt::Display for ConcentratedPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConcentratedPoolError::InvalidAmount => write!(f, "Amount must be positive"),
+            ConcentratedPoolError::InvalidPrice => write!(f, "Price is out of the tick range"),
+            ConcentratedPoolError::InvalidTickRange => {
+                write!(
+                    f,
+                    "Ticks must be ordered, in bounds and on the tick spacing"
+                )
+            }
+            ConcentratedPoolError::InsufficientLiquidity => {
+                write!(f, "Insufficient liquidity in pool")
+            }
+            ConcentratedPoolError::PositionNotFound => write!(f, "Position not found"),
+            ConcentratedPoolError::NotPositionOwner => {
+                write!(f, "Position belongs to another user")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConcentratedPoolError {}
+
+/// Square root of the price at `tick`
+pub fn sqrt_price_at_tick(tick: i32) -> f64 {
+    TICK_BASE.powf(tick as f64 / 2.0)
+}
+
+/// Greatest tick whose price is at or below the price with square root `sqrt_price`
+pub fn tick_at_sqrt_price(sqrt_price: f64) -> i32 {
+    let estimate = (2.0 * sqrt_price.ln() / TICK_BASE.ln()).floor() as i32;
+    // Correct for rounding in the logarithm
+    let tick = if sqrt_price_at_tick(estimate + 1) <= sqrt_price {
+        estimate + 1
+    } else if sqrt_price_at_tick(estimate) > sqrt_price {
+        estimate - 1
+    } else {
+        estimate
+    };
+    tick.clamp(MIN_TICK, MAX_TICK)
+}
+
+/// Greatest tick whose price is at or below `price`
+pub fn tick_at_price(price: f64) -> i32 {
+    tick_at_sqrt_price(price.sqrt())
+}
+
+/// Token amounts backing `liquidity` over [sqrt_lower, sqrt_upper] at the current price:
+/// all token_a below the range, all token_b above it, and a mix inside
+fn amounts_for_liquidity(
+    sqrt_price: f64,
+    sqrt_lower: f64,
+    sqrt_upper: f64,
+    liquidity: f64,
+) -> (f64, f64) {
+    if sqrt_price <= sqrt_lower {
+        (
+            liquidity * (sqrt_upper - sqrt_lower) / (sqrt_lower * sqrt_upper),
+            0.0,
+        )
+    } else if sqrt_price < sqrt_upper {
+        (
+            liquidity * (sqrt_upper - sqrt_price) / (sqrt_price * sqrt_upper),
+            liquidity * (sqrt_price - sqrt_lower),
+        )
+    } else {
+        (0.0, liquidity * (sqrt_upper - sqrt_lower))
+    }
+}
+
+/// Largest liquidity over [sqrt_lower, sqrt_upper] that the given amounts can back
+fn liquidity_for_amounts(
+    sqrt_price: f64,
+    sqrt_lower: f64,
+    sqrt_upper: f64,
+    amount_a: f64,
+    amount_b: f64,
+) -> f64 {
+    let from_a = |from: f64| amount_a * from * sqrt_upper / (sqrt_upper - from);
+    let from_b = |to: f64| amount_b / (to - sqrt_lower);
+    if sqrt_price <= sqrt_lower {
+        from_a(sqrt_lower)
+    } else if sqrt_price < sqrt_upper {
+        from_a(sqrt_price).min(from_b(sqrt_price))
+    } else {
+        from_b(sqrt_upper)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedPoolConfig {
+    pub pool_id: String,
+    pub token_a: String, // Price is quoted in token_b per token_a
+    pub token_b: String,
+    pub fee_tier: f64,
+    pub tick_spacing: i32, // Positions may only start and end on multiples of this
+    pub start_date: NaiveDateTime,
+}
+
+// State kept for each tick that bounds at least one position
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TickInfo {
+    pub liquidity_gross: f64, // Liquidity of all positions using this tick
+    pub liquidity_net: f64,   // Liquidity added when the price crosses upwards
+    // Fee growth per unit of liquidity on the other side of the tick from the
+    // current price; flipped every time the price crosses it
+    pub fee_growth_outside_a: f64,
+    pub fee_growth_outside_b: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedPosition {
+    pub owner: String,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: f64,
+    pub fee_growth_inside_last_a: f64,
+    pub fee_growth_inside_last_b: f64,
+    pub fees_owed_a: f64,
+    pub fees_owed_b: f64,
+    pub created_at: NaiveDateTime,
+}
+
+// Result of running a swap against the pool without applying it
+struct SwapOutcome {
+    output_amount: f64,
+    sqrt_price: f64,
+    tick: i32,
+    liquidity: f64,
+    fee_growth_global: f64, // Of the input token
+    fee_amount: f64,
+    crossed: Vec<(i32, f64)>, // Tick with the input token's fee growth when it was crossed
+}
+
+// Uniswap v3-style pool: liquidity is provided over tick ranges and only earns fees
+// (and is only traded against) while the price is inside its range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedLiquidityPool {
+    pub config: ConcentratedPoolConfig,
+    pub sqrt_price: f64,
+    pub tick: i32,      // Tick of the current price
+    pub liquidity: f64, // Liquidity of the positions in range
+    pub fee_growth_global_a: f64,
+    pub fee_growth_global_b: f64,
+    pub ticks: BTreeMap<i32, TickInfo>,
+    pub positions: HashMap<u64, ConcentratedPosition>,
+    pub next_position_id: u64,
+    pub total_token_a: f64, // Held by the pool, including uncollected fees
+    pub total_token_b: f64,
+    pub total_volume: f64,
+    pub total_fees: f64,
+}
+
+impl ConcentratedLiquidityPool {
+    /// Create a pool starting at `initial_price` (token_b per token_a)
+    pub fn new(
+        pool_id: String,
+        token_a: String,
+        token_b: String,
+        fee_tier: f64,
+        tick_spacing: i32,
+        initial_price: f64,
+    ) -> Result<Self, ConcentratedPoolError> {
+        if !(0.0..1.0).contains(&fee_tier) || tick_spacing <= 0 {
+            return Err(ConcentratedPoolError::InvalidAmount);
+        }
+        let sqrt_price = initial_price.sqrt();
+        if !(sqrt_price_at_tick(MIN_TICK)..sqrt_price_at_tick(MAX_TICK)).contains(&sqrt_price) {
+            return Err(ConcentratedPoolError::InvalidPrice);
+        }
+
+        let config = ConcentratedPoolConfig {
+            pool_id,
+            token_a,
+            token_b,
+            fee_tier,
+            tick_spacing,
+            start_date: Utc::now().naive_utc(),
+        };
+        Ok(Self {
+            config,
+            sqrt_price,
+            tick: tick_at_sqrt_price(sqrt_price),
+            liquidity: 0.0,
+            fee_growth_global_a: 0.0,
+            fee_growth_global_b: 0.0,
+            ticks: BTreeMap::new(),
+            positions: HashMap::new(),
+            next_position_id: 1,
+            total_token_a: 0.0,
+            total_token_b: 0.0,
+            total_volume: 0.0,
+            total_fees: 0.0,
+        })
+    }
+
+    /// Current price in token_b per token_a
+    pub fn price(&self) -> f64 {
+        self.sqrt_price * self.sqrt_price
+    }
+
+    pub fn get_reserves(&self) -> (f64, f64) {
+        (self.total_token_a, self.total_token_b)
+    }
+
+    pub fn get_position(&self, position_id: u64) -> Option<&ConcentratedPosition> {
+        self.positions.get(&position_id)
+    }
+
+    /// Widest usable tick range covering [price_lower, price_upper]
+    pub fn tick_range_for_prices(
+        &self,
+        price_lower: f64,
+        price_upper: f64,
+    ) -> Result<(i32, i32), ConcentratedPoolError> {
+        if !(price_lower > 0.0 && price_lower < price_upper && price_upper.is_finite()) {
+            return Err(ConcentratedPoolError::InvalidPrice);
+        }
+        let spacing = self.config.tick_spacing;
+        let lower = tick_at_price(price_lower).div_euclid(spacing) * spacing;
+        let upper_tick = tick_at_price(price_upper);
+        let upper = if upper_tick.rem_euclid(spacing) == 0 {
+            upper_tick
+        } else {
+            (upper_tick.div_euclid(spacing) + 1) * spacing
+        };
+        self.validate_range(lower, upper)?;
+        Ok((lower, upper))
+    }
+
+    fn validate_range(
+        &self,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<(), ConcentratedPoolError> {
+        let spacing = self.config.tick_spacing;
+        if tick_lower >= tick_upper
+            || tick_lower < MIN_TICK
+            || tick_upper > MAX_TICK
+            || tick_lower % spacing != 0
+            || tick_upper % spacing != 0
+        {
+            return Err(ConcentratedPoolError::InvalidTickRange);
+        }
+        Ok(())
+    }
+
+    /// Fee growth per unit of liquidity inside [tick_lower, tick_upper], for both tokens
+    fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (f64, f64) {
+        let lower = self.ticks.get(&tick_lower).cloned().unwrap_or_default();
+        let upper = self.ticks.get(&tick_upper).cloned().unwrap_or_default();
+        let (global_a, global_b) = (self.fee_growth_global_a, self.fee_growth_global_b);
+        let (below_a, below_b) = if self.tick >= tick_lower {
+            (lower.fee_growth_outside_a, lower.fee_growth_outside_b)
+        } else {
+            (
+                global_a - lower.fee_growth_outside_a,
+                global_b - lower.fee_growth_outside_b,
+            )
+        };
+        let (above_a, above_b) = if self.tick < tick_upper {
+            (upper.fee_growth_outside_a, upper.fee_growth_outside_b)
+        } else {
+            (
+                global_a - upper.fee_growth_outside_a,
+                global_b - upper.fee_growth_outside_b,
+            )
+        };
+        (global_a - below_a - above_a, global_b - below_b - above_b)
+    }
+
+    /// Apply a liquidity change to a tick; returns true once no position uses it, in
+    /// which case the caller removes it after reading its fee growth
+    fn update_tick(&mut self, tick: i32, liquidity_delta: f64, is_upper: bool) -> bool {
+        // Fee growth below a new tick is assumed to have all happened below it
+        let (global_a, global_b) = (self.fee_growth_global_a, self.fee_growth_global_b);
+        let at_or_below = tick <= self.tick;
+        let info = self.ticks.entry(tick).or_insert_with(|| TickInfo {
+            fee_growth_outside_a: if at_or_below { global_a } else { 0.0 },
+            fee_growth_outside_b: if at_or_below { global_b } else { 0.0 },
+            ..TickInfo::default()
+        });
+        info.liquidity_gross += liquidity_delta;
+        info.liquidity_net += if is_upper {
+            -liquidity_delta
+        } else {
+            liquidity_delta
+        };
+        info.liquidity_gross <= liquidity_delta.abs() * DUST
+    }
+
+    /// Change a position's liquidity, crediting the fees it earned so far. Returns the
+    /// token amounts the change corresponds to at the current price.
+    fn modify_position(&mut self, position_id: u64, liquidity_delta: f64) -> (f64, f64) {
+        let Some(position) = self.positions.get(&position_id) else {
+            return (0.0, 0.0);
+        };
+        let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+
+        let clear_lower = self.update_tick(tick_lower, liquidity_delta, false);
+        let clear_upper = self.update_tick(tick_upper, liquidity_delta, true);
+        let (inside_a, inside_b) = self.fee_growth_inside(tick_lower, tick_upper);
+        if clear_lower {
+            self.ticks.remove(&tick_lower);
+        }
+        if clear_upper {
+            self.ticks.remove(&tick_upper);
+        }
+        if let Some(position) = self.positions.get_mut(&position_id) {
+            position.fees_owed_a +=
+                position.liquidity * (inside_a - position.fee_growth_inside_last_a);
+            position.fees_owed_b +=
+                position.liquidity * (inside_b - position.fee_growth_inside_last_b);
+            position.fee_growth_inside_last_a = inside_a;
+            position.fee_growth_inside_last_b = inside_b;
+            position.liquidity += liquidity_delta;
+        }
+        if tick_lower <= self.tick && self.tick < tick_upper {
+            self.liquidity = (self.liquidity + liquidity_delta).max(0.0);
+        }
+
+        amounts_for_liquidity(
+            self.sqrt_price,
+            sqrt_price_at_tick(tick_lower),
+            sqrt_price_at_tick(tick_upper),
+            liquidity_delta.abs(),
+        )
+    }
+
+    fn owned_position(
+        &self,
+        owner: &str,
+        position_id: u64,
+    ) -> Result<&ConcentratedPosition, ConcentratedPoolError> {
+        let position = self
+            .positions
+            .get(&position_id)
+            .ok_or(ConcentratedPoolError::PositionNotFound)?;
+        if position.owner != owner {
+            return Err(ConcentratedPoolError::NotPositionOwner);
+        }
+        Ok(position)
+    }
+
+    fn deposit(
+        &mut self,
+        position_id: u64,
+        amount_a: f64,
+        amount_b: f64,
+    ) -> Result<(f64, f64), ConcentratedPoolError> {
+        if !(amount_a >= 0.0 && amount_b >= 0.0) || amount_a + amount_b <= 0.0 {
+            return Err(ConcentratedPoolError::InvalidAmount);
+        }
+        let position = self
+            .positions
+            .get(&position_id)
+            .ok_or(ConcentratedPoolError::PositionNotFound)?;
+        let liquidity = liquidity_for_amounts(
+            self.sqrt_price,
+            sqrt_price_at_tick(position.tick_lower),
+            sqrt_price_at_tick(position.tick_upper),
+            amount_a,
+            amount_b,
+        );
+        if !liquidity.is_finite() || liquidity <= 0.0 {
+            return Err(ConcentratedPoolError::InvalidAmount);
+        }
+
+        let (used_a, used_b) = self.modify_position(position_id, liquidity);
+        self.total_token_a += used_a;
+        self.total_token_b += used_b;
+        Ok((used_a, used_b))
+    }
+
+    /// Open a position over [tick_lower, tick_upper] with as much liquidity as the
+    /// amounts allow at the current price. Returns the position id and the amounts
+    /// actually deposited; any excess of one token is not taken.
+    pub fn add_liquidity(
+        &mut self,
+        owner: String,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount_a: f64,
+        amount_b: f64,
+    ) -> Result<(u64, f64, f64), ConcentratedPoolError> {
+        self.validate_range(tick_lower, tick_upper)?;
+        let position_id = self.next_position_id;
+        let (inside_a, inside_b) = self.fee_growth_inside(tick_lower, tick_upper);
+        self.positions.insert(
+            position_id,
+            ConcentratedPosition {
+                owner,
+                tick_lower,
+                tick_upper,
+                liquidity: 0.0,
+                fee_growth_inside_last_a: inside_a,
+                fee_growth_inside_last_b: inside_b,
+                fees_owed_a: 0.0,
+                fees_owed_b: 0.0,
+                created_at: Utc::now().naive_utc(),
+            },
+        );
+
+        match self.deposit(position_id, amount_a, amount_b) {
+            Ok((used_a, used_b)) => {
+                self.next_position_id += 1;
+                Ok((position_id, used_a, used_b))
+            }
+            Err(e) => {
+                self.positions.remove(&position_id);
+                Err(e)
+            }
+        }
+    }
+
+    /// Add liquidity to an existing position; returns the amounts deposited
+    pub fn increase_liquidity(
+        &mut self,
+        owner: &str,
+        position_id: u64,
+        amount_a: f64,
+        amount_b: f64,
+    ) -> Result<(f64, f64), ConcentratedPoolError> {
+        self.owned_position(owner, position_id)?;
+        self.deposit(position_id, amount_a, amount_b)
+    }
+
+    /// Withdraw `liquidity` from a position and return the underlying tokens. Earned
+    /// fees stay in the position until collected.
+    pub fn decrease_liquidity(
+        &mut self,
+        owner: &str,
+        position_id: u64,
+        liquidity: f64,
+    ) -> Result<(f64, f64), ConcentratedPoolError> {
+        let position = self.owned_position(owner, position_id)?;
+        if liquidity.is_nan() || liquidity <= 0.0 || liquidity > position.liquidity {
+            return Err(ConcentratedPoolError::InvalidAmount);
+        }
+
+        let (amount_a, amount_b) = self.modify_position(position_id, -liquidity);
+        let amount_a = amount_a.min(self.total_token_a);
+        let amount_b = amount_b.min(self.total_token_b);
+        self.total_token_a -= amount_a;
+        self.total_token_b -= amount_b;
+        Ok((amount_a, amount_b))
+    }
+
+    /// Fees a position has earned and not collected yet, as (token_a, token_b)
+    pub fn uncollected_fees(&self, position_id: u64) -> Result<(f64, f64), ConcentratedPoolError> {
+        let position = self
+            .positions
+            .get(&position_id)
+            .ok_or(ConcentratedPoolError::PositionNotFound)?;
+        let (inside_a, inside_b) = self.fee_growth_inside(position.tick_lower, position.tick_upper);
+        Ok((
+            position.fees_owed_a
+                + position.liquidity * (inside_a - position.fee_growth_inside_last_a),
+            position.fees_owed_b
+                + position.liquidity * (inside_b - position.fee_growth_inside_last_b),
+        ))
+    }
+
+    /// Pay out a position's fees; a position without liquidity is closed afterwards
+    pub fn collect_fees(
+        &mut self,
+        owner: &str,
+        position_id: u64,
+    ) -> Result<(f64, f64), ConcentratedPoolError> {
+        self.owned_position(owner, position_id)?;
+        let (fees_a, fees_b) = self.uncollected_fees(position_id)?;
+        let (inside_a, inside_b) = {
+            let position = &self.positions[&position_id];
+            self.fee_growth_inside(position.tick_lower, position.tick_upper)
+        };
+        let fees_a = fees_a.min(self.total_token_a);
+        let fees_b = fees_b.min(self.total_token_b);
+        self.total_token_a -= fees_a;
+        self.total_token_b -= fees_b;
+
+        let closed = match self.positions.get_mut(&position_id) {
+            Some(position) => {
+                position.fees_owed_a = 0.0;
+                position.fees_owed_b = 0.0;
+                position.fee_growth_inside_last_a = inside_a;
+                position.fee_growth_inside_last_b = inside_b;
+                position.liquidity <= 0.0
+            }
+            None => false,
+        };
+        if closed {
+            self.positions.remove(&position_id);
+        }
+        Ok((fees_a, fees_b))
+    }
+
+    /// Walk the price through initialized ticks until `input_amount` is used up
+    fn run_swap(
+        &self,
+        input_token: &str,
+        input_amount: f64,
+    ) -> Result<(bool, SwapOutcome), ConcentratedPoolError> {
+        if !input_amount.is_finite() || input_amount <= 0.0 {
+            return Err(ConcentratedPoolError::InvalidAmount);
+        }
+        // Selling token_a pushes the price down
+        let a_to_b = if input_token == self.config.token_a {
+            true
+        } else if input_token == self.config.token_b {
+            false
+        } else {
+            return Err(ConcentratedPoolError::InvalidAmount);
+        };
+
+        let fee = self.config.fee_tier;
+        let mut state = SwapOutcome {
+            output_amount: 0.0,
+            sqrt_price: self.sqrt_price,
+            tick: self.tick,
+            liquidity: self.liquidity,
+            fee_growth_global: if a_to_b {
+                self.fee_growth_global_a
+            } else {
+                self.fee_growth_global_b
+            },
+            fee_amount: 0.0,
+            crossed: Vec::new(),
+        };
+        let mut remaining = input_amount;
+
+        while remaining > input_amount * DUST {
+            let next = if a_to_b {
+                self.ticks.range(..=state.tick).next_back()
+            } else {
+                self.ticks.range(state.tick + 1..).next()
+            };
+            let target_tick =
+                next.map(|(t, _)| *t)
+                    .unwrap_or(if a_to_b { MIN_TICK } else { MAX_TICK });
+            let target = sqrt_price_at_tick(target_tick);
+            let (s, l) = (state.sqrt_price, state.liquidity);
+
+            // Input needed (after fee) to move the price all the way to the target
+            let needed = if a_to_b {
+                l * (s - target) / (s * target)
+            } else {
+                l * (target - s)
+            };
+            let available = remaining * (1.0 - fee);
+            let reached = l <= 0.0 || available >= needed;
+            let new_sqrt_price = if reached {
+                target
+            } else if a_to_b {
+                l * s / (l + available * s)
+            } else {
+                s + available / l
+            };
+            if l > 0.0 {
+                let (used, step_fee) = if reached {
+                    (needed, needed * fee / (1.0 - fee))
+                } else {
+                    (available, remaining - available)
+                };
+                state.output_amount += if a_to_b {
+                    l * (s - new_sqrt_price)
+                } else {
+                    l * (new_sqrt_price - s) / (s * new_sqrt_price)
+                };
+                state.fee_amount += step_fee;
+                state.fee_growth_global += step_fee / l;
+                remaining = if reached {
+                    remaining - used - step_fee
+                } else {
+                    0.0
+                };
+            }
+            state.sqrt_price = new_sqrt_price;
+
+            if !reached {
+                state.tick = tick_at_sqrt_price(new_sqrt_price);
+                break;
+            }
+            let Some((_, info)) = next else {
+                // Ran past the last range with input left over
+                if remaining > input_amount * DUST {
+                    return Err(ConcentratedPoolError::InsufficientLiquidity);
+                }
+                state.tick = if a_to_b { MIN_TICK } else { MAX_TICK - 1 };
+                break;
+            };
+            state.crossed.push((target_tick, state.fee_growth_global));
+            if a_to_b {
+                state.liquidity = (state.liquidity - info.liquidity_net).max(0.0);
+                state.tick = target_tick - 1;
+            } else {
+                state.liquidity = (state.liquidity + info.liquidity_net).max(0.0);
+                state.tick = target_tick;
+            }
+        }
+
+        let reserve_out = if a_to_b {
+            self.total_token_b
+        } else {
+            self.total_token_a
+        };
+        if state.output_amount <= 0.0 || state.output_amount > reserve_out {
+            return Err(ConcentratedPoolError::InsufficientLiquidity);
+        }
+        Ok((a_to_b, state))
+    }
+
+    /// Output of swapping `input_amount` of one token for the other
+    pub fn calculate_swap_output(
+        &self,
+        input_token: &str,
+        input_amount: f64,
+    ) -> Result<f64, ConcentratedPoolError> {
+        self.run_swap(input_token, input_amount)
+            .map(|(_, outcome)| outcome.output_amount)
+    }
+
+    /// Execute a swap, crossing as many ticks as the input requires
+    pub fn swap(
+        &mut self,
+        input_token: &str,
+        input_amount: f64,
+    ) -> Result<f64, ConcentratedPoolError> {
+        let (a_to_b, outcome) = self.run_swap(input_token, input_amount)?;
+
+        for (tick, input_growth) in &outcome.crossed {
+            // Fee growth of the output token did not change during the swap
+            let (growth_a, growth_b) = if a_to_b {
+                (*input_growth, self.fee_growth_global_b)
+            } else {
+                (self.fee_growth_global_a, *input_growth)
+            };
+            if let Some(info) = self.ticks.get_mut(tick) {
+                info.fee_growth_outside_a = growth_a - info.fee_growth_outside_a;
+                info.fee_growth_outside_b = growth_b - info.fee_growth_outside_b;
+            }
+        }
+
+        if a_to_b {
+            self.fee_growth_global_a = outcome.fee_growth_global;
+            self.total_token_a += input_amount;
+            self.total_token_b -= outcome.output_amount;
+        } else {
+            self.fee_growth_global_b = outcome.fee_growth_global;
+            self.total_token_b += input_amount;
+            self.total_token_a -= outcome.output_amount;
+        }
+        self.sqrt_price = outcome.sqrt_price;
+        self.tick = outcome.tick;
+        self.liquidity = outcome.liquidity;
+        self.total_volume += input_amount;
+        self.total_fees += outcome.fee_amount;
+        Ok(outcome.output_amount)
+    }
+}
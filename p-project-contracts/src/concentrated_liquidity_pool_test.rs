@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+    use super::super::concentrated_liquidity_pool::{
+        sqrt_price_at_tick, tick_at_price, ConcentratedLiquidityPool, ConcentratedPoolError,
+    };
+    use super::super::liquidity_pool::LiquidityPool;
+
+    fn pool_at(price: f64) -> ConcentratedLiquidityPool {
+        ConcentratedLiquidityPool::new(
+            "p_usdt_cl".to_string(),
+            "P".to_string(),
+            "USDT".to_string(),
+            0.003,
+            60,
+            price,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_tick_math_round_trips() {
+        for tick in [-887_272, -46_055, -1, 0, 1, 60, 887_271] {
+            assert_eq!(
+                tick_at_price(sqrt_price_at_tick(tick).powi(2) * (1.0 + 1e-9)),
+                tick
+            );
+        }
+        assert_eq!(tick_at_price(1.0), 0);
+        assert_eq!(tick_at_price(0.9999), -2);
+        let pool = pool_at(0.01);
+        assert_eq!(pool.tick, -46_055);
+        let (lower, upper) = pool.tick_range_for_prices(0.005, 0.02).unwrap();
+        assert!(sqrt_price_at_tick(lower).powi(2) <= 0.005);
+        assert!(sqrt_price_at_tick(upper).powi(2) >= 0.02);
+        assert_eq!((lower % 60, upper % 60), (0, 0));
+    }
+
+    #[test]
+    fn test_position_amounts_depend_on_range() {
+        let mut pool = pool_at(1.0);
+        assert_eq!(
+            pool.add_liquidity("lp".into(), 60, 30, 1.0, 1.0),
+            Err(ConcentratedPoolError::InvalidTickRange)
+        );
+        assert_eq!(
+            pool.add_liquidity("lp".into(), -61, 60, 1.0, 1.0),
+            Err(ConcentratedPoolError::InvalidTickRange)
+        );
+
+        // In range: both tokens, limited by the scarcer one
+        let (id, a, b) = pool
+            .add_liquidity("lp".into(), -600, 600, 1_000.0, 5_000.0)
+            .unwrap();
+        assert!((a - 1_000.0).abs() < 1e-6);
+        assert!((b - 1_000.0).abs() < 1e-6);
+        assert!(pool.liquidity > 0.0);
+        assert_eq!(pool.get_position(id).unwrap().liquidity, pool.liquidity);
+
+        // Above the price only token_a is needed, below it only token_b
+        let (_, a, b) = pool
+            .add_liquidity("lp".into(), 600, 1_200, 1_000.0, 1_000.0)
+            .unwrap();
+        assert!((a - 1_000.0).abs() < 1e-6);
+        assert_eq!(b, 0.0);
+        let (_, a, b) = pool
+            .add_liquidity("lp".into(), -1_200, -600, 1_000.0, 1_000.0)
+            .unwrap();
+        assert_eq!(a, 0.0);
+        assert!((b - 1_000.0).abs() < 1e-6);
+        assert_eq!(
+            pool.add_liquidity("lp".into(), 600, 1_200, 0.0, 1_000.0),
+            Err(ConcentratedPoolError::InvalidAmount)
+        );
+        let (ra, rb) = pool.get_reserves();
+        assert!((ra - 2_000.0).abs() < 1e-6 && (rb - 2_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_concentrated_range_beats_full_range_depth() {
+        // Same capital at the launch price, full range vs +/- ~20% around it
+        let mut full = LiquidityPool::new(
+            "p_usdt".to_string(),
+            "P".to_string(),
+            "USDT".to_string(),
+            0.003,
+            "REWARD".to_string(),
+            0.0,
+            0.0,
+        );
+        full.add_liquidity("lp".into(), 1_000_000.0, 10_000.0, 30)
+            .unwrap();
+        let mut pool = pool_at(0.01);
+        let (lower, upper) = pool.tick_range_for_prices(0.008, 0.012).unwrap();
+        pool.add_liquidity("lp".into(), lower, upper, 1_000_000.0, 10_000.0)
+            .unwrap();
+
+        let full_out = full.calculate_swap_output("USDT", 500.0).unwrap();
+        let cl_out = pool.calculate_swap_output("USDT", 500.0).unwrap();
+        assert!(cl_out > full_out);
+        // Close to the spot price less the fee
+        assert!(cl_out > 500.0 / 0.01 * 0.997 * 0.99);
+    }
+
+    #[test]
+    fn test_swap_crosses_multiple_ticks() {
+        let mut pool = pool_at(1.0);
+        let (inner, _, _) = pool
+            .add_liquidity("lp1".into(), -600, 600, 10_000.0, 10_000.0)
+            .unwrap();
+        pool.add_liquidity("lp2".into(), 600, 1_200, 5_000.0, 0.0)
+            .unwrap();
+        pool.add_liquidity("lp3".into(), 1_200, 2_400, 5_000.0, 0.0)
+            .unwrap();
+        let inner_liquidity = pool.get_position(inner).unwrap().liquidity;
+        assert_eq!(pool.liquidity, inner_liquidity);
+
+        // Buying most of the token_a in the pool walks the price through every range
+        let quoted = pool.calculate_swap_output("USDT", 20_000.0).unwrap();
+        let out = pool.swap("USDT", 20_000.0).unwrap();
+        assert_eq!(out, quoted);
+        assert!(pool.tick >= 1_200 && pool.tick < 2_400);
+        assert!(pool.price() > sqrt_price_at_tick(1_200).powi(2));
+        assert_ne!(pool.liquidity, inner_liquidity);
+        assert!(pool.total_token_a > 0.0);
+
+        // Selling back crosses down again and restores the inner range's liquidity
+        let back = pool.swap("P", out).unwrap();
+        assert!(pool.tick >= -600 && pool.tick < 600);
+        assert!((pool.liquidity - inner_liquidity).abs() < 1e-6 * inner_liquidity);
+        // The fees of the round trip stay in the pool
+        assert!(back < 20_000.0 * 0.997);
+        assert!(pool.price() > 1.0);
+
+        // More than all the token_a in the pool cannot be bought
+        let before = pool.clone();
+        assert_eq!(
+            pool.swap("USDT", 1_000_000.0),
+            Err(ConcentratedPoolError::InsufficientLiquidity)
+        );
+        assert_eq!(pool.sqrt_price, before.sqrt_price);
+        assert_eq!(pool.get_reserves(), before.get_reserves());
+    }
+
+    #[test]
+    fn test_fees_accrue_only_while_in_range() {
+        let mut pool = pool_at(1.0);
+        let (wide, _, _) = pool
+            .add_liquidity("alice".into(), -1_200, 1_200, 10_000.0, 10_000.0)
+            .unwrap();
+        let (narrow, _, _) = pool
+            .add_liquidity("bob".into(), -60, 60, 1_000.0, 1_000.0)
+            .unwrap();
+        let (idle, _, _) = pool
+            .add_liquidity("carol".into(), 2_400, 3_000, 1_000.0, 0.0)
+            .unwrap();
+
+        pool.swap("P", 10.0).unwrap();
+        let (wide_a, _) = pool.uncollected_fees(wide).unwrap();
+        let (narrow_a, _) = pool.uncollected_fees(narrow).unwrap();
+        let wide_l = pool.get_position(wide).unwrap().liquidity;
+        let narrow_l = pool.get_position(narrow).unwrap().liquidity;
+        // Both ranges were active for the whole swap: fees split by liquidity
+        assert!((wide_a + narrow_a - 10.0 * 0.003).abs() < 1e-9);
+        assert!((wide_a / narrow_a - wide_l / narrow_l).abs() < 1e-6);
+        assert_eq!(pool.uncollected_fees(idle).unwrap(), (0.0, 0.0));
+
+        // Push the price out of bob's range; only alice earns from then on
+        pool.swap("P", 2_000.0).unwrap();
+        assert!(pool.tick < -60);
+        let (_, narrow_before) = pool.uncollected_fees(narrow).unwrap();
+        pool.swap("P", 100.0).unwrap();
+        let (_, narrow_after) = pool.uncollected_fees(narrow).unwrap();
+        assert_eq!(narrow_before, narrow_after);
+        let (narrow_total_a, _) = pool.uncollected_fees(narrow).unwrap();
+        assert!(narrow_total_a > narrow_a);
+        let (wide_total_a, _) = pool.uncollected_fees(wide).unwrap();
+        assert!((wide_total_a + narrow_total_a - pool.total_fees).abs() < 1e-9);
+
+        // Withdrawals keep the fees owed; collecting an empty position closes it
+        assert_eq!(
+            pool.collect_fees("alice", narrow),
+            Err(ConcentratedPoolError::NotPositionOwner)
+        );
+        let (a, b) = pool.decrease_liquidity("bob", narrow, narrow_l).unwrap();
+        assert!(a > 1_000.0 && b < 1_000.0);
+        assert_eq!(pool.uncollected_fees(narrow).unwrap().0, narrow_total_a);
+        let fees = pool.collect_fees("bob", narrow).unwrap();
+        assert_eq!(fees.0, narrow_total_a);
+        assert!(pool.get_position(narrow).is_none());
+        assert!(!pool.ticks.contains_key(&-60) && !pool.ticks.contains_key(&60));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     liquidity_pool::{LiquidityPool, LiquidityPoolError},
+    multi_asset_stable_pool::MultiAssetStablePool,
     stable_liquidity_pool::{StableLiquidityPool, StablePoolError},
 };
 use chrono::{Duration, NaiveDateTime, Utc};
@@ -53,13 +54,15 @@ impl std::error::Error for CrossChainLiquidityError {}
 pub enum AnyPool {
     Constant(LiquidityPool),
     Stable(StableLiquidityPool),
+    MultiStable(MultiAssetStablePool),
 }
 
 impl AnyPool {
-    fn tokens(&self) -> (String, String) {
+    fn tokens(&self) -> Vec<String> {
         match self {
-            AnyPool::Constant(p) => (p.config.token_a.clone(), p.config.token_b.clone()),
-            AnyPool::Stable(p) => (p.config.token_a.clone(), p.config.token_b.clone()),
+            AnyPool::Constant(p) => vec![p.config.token_a.clone(), p.config.token_b.clone()],
+            AnyPool::Stable(p) => vec![p.config.token_a.clone(), p.config.token_b.clone()],
+            AnyPool::MultiStable(p) => p.tokens().to_vec(),
         }
     }
 
@@ -77,13 +80,39 @@ impl AnyPool {
             AnyPool::Stable(p) => p
                 .add_liquidity(user_id, token_a_amount, token_b_amount, duration_days)
                 .map_err(map_sp_err),
+            // Deposit into the first two tokens only
+            AnyPool::MultiStable(p) => {
+                let mut amounts = vec![0.0; p.tokens().len()];
+                amounts[0] = token_a_amount;
+                amounts[1] = token_b_amount;
+                p.add_liquidity(user_id, &amounts, duration_days)
+                    .map_err(map_sp_err)
+            }
+        }
+    }
+
+    fn add_liquidity_amounts(
+        &mut self,
+        user_id: String,
+        amounts: &[f64],
+        duration_days: i64,
+    ) -> Result<f64, CrossChainLiquidityError> {
+        match self {
+            AnyPool::MultiStable(p) => p
+                .add_liquidity(user_id, amounts, duration_days)
+                .map_err(map_sp_err),
+            _ => match amounts {
+                [a, b] => self.add_liquidity(user_id, *a, *b, duration_days),
+                _ => Err(CrossChainLiquidityError::InvalidAmount),
+            },
         }
     }
 
-    fn reserves(&self) -> (f64, f64) {
+    fn reserves(&self) -> Vec<f64> {
         match self {
-            AnyPool::Constant(p) => (p.total_token_a, p.total_token_b),
-            AnyPool::Stable(p) => (p.total_token_a, p.total_token_b),
+            AnyPool::Constant(p) => vec![p.total_token_a, p.total_token_b],
+            AnyPool::Stable(p) => vec![p.total_token_a, p.total_token_b],
+            AnyPool::MultiStable(p) => p.get_reserves().to_vec(),
         }
     }
 }
@@ -118,6 +147,12 @@ pub struct PoolDeployment {
     pub total_reward_allocation: f64,
     pub apr_rate: f64,
     pub amplification: Option<f64>,
+    // Tokens after token_a and token_b; a non-empty list deploys a multi-asset stable pool
+    #[serde(default)]
+    pub extra_tokens: Vec<String>,
+    // Decimals of token_a, token_b and the extra tokens, in that order (0 for all if empty)
+    #[serde(default)]
+    pub token_decimals: Vec<u32>,
 }
 
 pub struct CrossChainLiquidityManager {
@@ -147,7 +182,34 @@ impl CrossChainLiquidityManager {
             return Err(CrossChainLiquidityError::PoolAlreadyExists);
         }
 
-        let pool = if let Some(a) = cfg.amplification {
+        let pool = if !cfg.extra_tokens.is_empty() {
+            let a = cfg
+                .amplification
+                .ok_or(CrossChainLiquidityError::UnsupportedOperation)?;
+            let names: Vec<String> = [cfg.token_a, cfg.token_b]
+                .into_iter()
+                .chain(cfg.extra_tokens)
+                .collect();
+            let decimals = if cfg.token_decimals.is_empty() {
+                vec![0; names.len()]
+            } else if cfg.token_decimals.len() == names.len() {
+                cfg.token_decimals
+            } else {
+                return Err(CrossChainLiquidityError::UnsupportedOperation);
+            };
+            AnyPool::MultiStable(
+                MultiAssetStablePool::new(
+                    cfg.pool_id,
+                    names.into_iter().zip(decimals).collect(),
+                    cfg.fee_tier,
+                    a,
+                    cfg.reward_token,
+                    cfg.total_reward_allocation,
+                    cfg.apr_rate,
+                )
+                .map_err(map_sp_err)?,
+            )
+        } else if let Some(a) = cfg.amplification {
             AnyPool::Stable(
                 StableLiquidityPool::new(
                     cfg.pool_id,
@@ -199,6 +261,8 @@ impl CrossChainLiquidityManager {
                 total_reward_allocation,
                 apr_rate,
                 amplification: None,
+                extra_tokens: Vec::new(),
+                token_decimals: Vec::new(),
             },
         )
     }
@@ -226,6 +290,45 @@ impl CrossChainLiquidityManager {
                 total_reward_allocation,
                 apr_rate,
                 amplification: Some(amplification),
+                extra_tokens: Vec::new(),
+                token_decimals: Vec::new(),
+            },
+        )
+    }
+
+    /// Deploy a stable pool over three or more `(token, decimals)` pairs; pairs go
+    /// through `deploy_stable_pool`
+    pub fn deploy_multi_stable_pool(
+        &mut self,
+        chain: Chain,
+        pool_id: String,
+        tokens: Vec<(String, u32)>,
+        fee_tier: f64,
+        amplification: f64,
+        reward_token: String,
+        total_reward_allocation: f64,
+        apr_rate: f64,
+    ) -> Result<(), CrossChainLiquidityError> {
+        if tokens.len() < 3 {
+            return Err(CrossChainLiquidityError::UnsupportedOperation);
+        }
+        let (mut names, token_decimals): (Vec<String>, Vec<u32>) = tokens.into_iter().unzip();
+        let extra_tokens = names.split_off(2);
+        let token_b = names.pop().unwrap_or_default();
+        let token_a = names.pop().unwrap_or_default();
+        self.deploy_pool(
+            chain,
+            PoolDeployment {
+                pool_id,
+                token_a,
+                token_b,
+                fee_tier,
+                reward_token,
+                total_reward_allocation,
+                apr_rate,
+                amplification: Some(amplification),
+                extra_tokens,
+                token_decimals,
             },
         )
     }
@@ -247,11 +350,40 @@ impl CrossChainLiquidityManager {
         pool.add_liquidity(user_id, token_a_amount, token_b_amount, duration_days)
     }
 
+    /// Deposit one amount per pool token, in the pool's token order. Multi-asset pools
+    /// accept any mix; pair pools take exactly two amounts.
+    pub fn add_multi_liquidity(
+        &mut self,
+        chain: Chain,
+        pool_id: &str,
+        user_id: String,
+        amounts: &[f64],
+        duration_days: i64,
+    ) -> Result<f64, CrossChainLiquidityError> {
+        let key = (chain, pool_id.to_string());
+        let pool = self
+            .pools
+            .get_mut(&key)
+            .ok_or(CrossChainLiquidityError::PoolNotFound)?;
+        pool.add_liquidity_amounts(user_id, amounts, duration_days)
+    }
+
+    /// Reserves of the pool's first two tokens
     pub fn get_reserves(
         &self,
         chain: Chain,
         pool_id: &str,
     ) -> Result<(f64, f64), CrossChainLiquidityError> {
+        let reserves = self.get_all_reserves(chain, pool_id)?;
+        Ok((reserves[0], reserves[1]))
+    }
+
+    /// Reserves of every pool token, in the pool's token order
+    pub fn get_all_reserves(
+        &self,
+        chain: Chain,
+        pool_id: &str,
+    ) -> Result<Vec<f64>, CrossChainLiquidityError> {
         let key = (chain, pool_id.to_string());
         let pool = self
             .pools
@@ -263,10 +395,14 @@ impl CrossChainLiquidityManager {
     pub fn total_liquidity_for_pair(&self, token_a: &str, token_b: &str) -> f64 {
         let mut total = 0.0;
         for (_key, pool) in self.pools.iter() {
-            let (a, b) = pool.tokens();
-            if (a == token_a && b == token_b) || (a == token_b && b == token_a) {
-                let (ra, rb) = pool.reserves();
-                total += (ra * rb).sqrt();
+            let tokens = pool.tokens();
+            let reserves = pool.reserves();
+            let a = tokens.iter().position(|t| t == token_a);
+            let b = tokens.iter().position(|t| t == token_b);
+            if let (Some(a), Some(b)) = (a, b) {
+                if a != b {
+                    total += (reserves[a] * reserves[b]).sqrt();
+                }
             }
         }
         total
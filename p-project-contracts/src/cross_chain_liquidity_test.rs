@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod tests {
     use super::super::community_liquidity::CommunityLiquidityProgram;
-    use super::super::cross_chain_liquidity::{Chain, CrossChainLiquidityManager};
+    use super::super::cross_chain_liquidity::{
+        Chain, CrossChainLiquidityError, CrossChainLiquidityManager, PoolDeployment,
+    };
 
     fn approx_eq(a: f64, b: f64, eps: f64) -> bool {
         (a - b).abs() <= eps
@@ -79,6 +81,75 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_deploy_multi_asset_stable_pool() {
+        let mut mgr = CrossChainLiquidityManager::new();
+        mgr.deploy_multi_stable_pool(
+            Chain::Ethereum,
+            "3pool_eth".into(),
+            vec![("USDC".into(), 6), ("USDT".into(), 6), ("DAI".into(), 18)],
+            0.0004,
+            200.0,
+            "REWARD".into(),
+            100_000.0,
+            0.10,
+        )
+        .unwrap();
+        // Pair-style deposits only fund the first two tokens, which a fresh pool rejects
+        assert!(matches!(
+            mgr.add_liquidity(Chain::Ethereum, "3pool_eth", "lp".into(), 1e6, 1e6, 30),
+            Err(CrossChainLiquidityError::InvalidAmount)
+        ));
+        mgr.add_multi_liquidity(
+            Chain::Ethereum,
+            "3pool_eth",
+            "lp".into(),
+            &[50_000.0e6, 40_000.0e6, 30_000.0e18],
+            30,
+        )
+        .unwrap();
+        assert_eq!(
+            mgr.get_all_reserves(Chain::Ethereum, "3pool_eth").unwrap(),
+            vec![50_000.0e6, 40_000.0e6, 30_000.0e18]
+        );
+        assert_eq!(
+            mgr.get_reserves(Chain::Ethereum, "3pool_eth").unwrap(),
+            (50_000.0e6, 40_000.0e6)
+        );
+        assert!(mgr.total_liquidity_for_pair("DAI", "USDC") > 0.0);
+        assert_eq!(mgr.total_liquidity_for_pair("DAI", "FRAX"), 0.0);
+
+        // Multi-asset deployments need an amplification coefficient
+        let cfg = PoolDeployment {
+            pool_id: "3pool_bsc".into(),
+            token_a: "USDC".into(),
+            token_b: "USDT".into(),
+            fee_tier: 0.0004,
+            reward_token: "REWARD".into(),
+            total_reward_allocation: 0.0,
+            apr_rate: 0.0,
+            amplification: None,
+            extra_tokens: vec!["DAI".into()],
+            token_decimals: Vec::new(),
+        };
+        assert!(matches!(
+            mgr.deploy_pool(Chain::BSC, cfg.clone()),
+            Err(CrossChainLiquidityError::UnsupportedOperation)
+        ));
+        mgr.deploy_pool(
+            Chain::BSC,
+            PoolDeployment {
+                amplification: Some(100.0),
+                ..cfg
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            mgr.get_all_reserves(Chain::BSC, "3pool_bsc").unwrap(),
+            vec![0.0; 3]
+        );
+    }
+
     #[test]
     fn test_community_liquidity_incentives_and_leaderboard() {
         // Create program with generous early window to ensure multiplier applies
@@ -1,3 +1,4 @@
+use crate::concentrated_liquidity_pool::{ConcentratedLiquidityPool, ConcentratedPoolError};
 use crate::liquidity_pool::{LiquidityPool, LiquidityPoolError};
 use crate::stable_liquidity_pool::{StableLiquidityPool, StablePoolError};
 use std::collections::HashMap;
@@ -7,6 +8,7 @@ use std::collections::HashMap;
 pub struct DexListingManager {
     pools: HashMap<String, LiquidityPool>,
     stable_pools: HashMap<String, StableLiquidityPool>,
+    concentrated_pools: HashMap<String, ConcentratedLiquidityPool>,
 }
 
 impl DexListingManager {
@@ -61,6 +63,27 @@ impl DexListingManager {
         Ok(())
     }
 
+    pub fn create_concentrated_pool(
+        &mut self,
+        pool_id: &str,
+        token_a: &str,
+        token_b: &str,
+        fee_tier: f64,
+        tick_spacing: i32,
+        launch_price: f64,
+    ) -> Result<(), ConcentratedPoolError> {
+        let p = ConcentratedLiquidityPool::new(
+            pool_id.to_string(),
+            token_a.to_string(),
+            token_b.to_string(),
+            fee_tier,
+            tick_spacing,
+            launch_price,
+        )?;
+        self.concentrated_pools.insert(pool_id.to_string(), p);
+        Ok(())
+    }
+
     pub fn seed_constant_liquidity(
         &mut self,
         pool_id: &str,
@@ -101,6 +124,32 @@ impl DexListingManager {
         )
     }
 
+    /// Seed liquidity over the price range [price_lower, price_upper] (token_b per
+    /// token_a), widened to the nearest usable ticks. Returns the position id and the
+    /// amounts deposited.
+    pub fn seed_concentrated_liquidity(
+        &mut self,
+        pool_id: &str,
+        lp_user: &str,
+        price_lower: f64,
+        price_upper: f64,
+        token_a_amount: f64,
+        token_b_amount: f64,
+    ) -> Result<(u64, f64, f64), ConcentratedPoolError> {
+        let p = self
+            .concentrated_pools
+            .get_mut(pool_id)
+            .expect("concentrated pool must be created before seeding");
+        let (tick_lower, tick_upper) = p.tick_range_for_prices(price_lower, price_upper)?;
+        p.add_liquidity(
+            lp_user.to_string(),
+            tick_lower,
+            tick_upper,
+            token_a_amount,
+            token_b_amount,
+        )
+    }
+
     pub fn constant_pool(&self, pool_id: &str) -> Option<&LiquidityPool> {
         self.pools.get(pool_id)
     }
@@ -108,4 +157,8 @@ impl DexListingManager {
     pub fn stable_pool(&self, pool_id: &str) -> Option<&StableLiquidityPool> {
         self.stable_pools.get(pool_id)
     }
+
+    pub fn concentrated_pool(&self, pool_id: &str) -> Option<&ConcentratedLiquidityPool> {
+        self.concentrated_pools.get(pool_id)
+    }
 }
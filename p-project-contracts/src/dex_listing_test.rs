@@ -72,4 +72,31 @@ mod tests {
         let out = sp.calculate_swap_output("P-COIN", 10_000.0).unwrap();
         assert!(out > 0.0);
     }
+
+    #[test]
+    fn concentrates_launch_liquidity_around_launch_price() {
+        let mut mgr = DexListingManager::new();
+        mgr.create_constant_pool("p_usdt", "P", "USDT", 0.003, "REWARD", 0.0, 0.0);
+        mgr.seed_constant_liquidity("p_usdt", "lp", 1_000_000.0, 10_000.0, 30)
+            .unwrap();
+        mgr.create_concentrated_pool("p_usdt_cl", "P", "USDT", 0.003, 60, 0.01)
+            .unwrap();
+        let (id, p_used, usdt_used) = mgr
+            .seed_concentrated_liquidity("p_usdt_cl", "lp", 0.008, 0.0125, 1_000_000.0, 10_000.0)
+            .unwrap();
+        assert!(p_used <= 1_000_000.0 && usdt_used <= 10_000.0);
+
+        let cl = mgr.concentrated_pool("p_usdt_cl").unwrap();
+        assert!(cl.get_position(id).unwrap().liquidity > 0.0);
+        let out_full = mgr
+            .constant_pool("p_usdt")
+            .unwrap()
+            .calculate_swap_output("USDT", 1_000.0)
+            .unwrap();
+        let out_cl = cl.calculate_swap_output("USDT", 1_000.0).unwrap();
+        assert!(
+            out_cl > out_full,
+            "concentrated liquidity must reduce slippage"
+        );
+    }
 }
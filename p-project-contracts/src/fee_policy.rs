@@ -0,0 +1,139 @@
+use crate::price_oracle::PriceOracle;
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// What a fee policy can look at when pricing a swap
+pub struct FeeContext<'a> {
+    pub base_fee: f64, // The pool's configured fee_tier
+    pub input_amount: f64,
+    pub input_reserve: f64,
+    pub oracle: &'a PriceOracle, // The pool's price history
+    pub now: NaiveDateTime,
+}
+
+impl FeeContext<'_> {
+    /// Trade size as a fraction of the input-side reserve
+    pub fn size_ratio(&self) -> f64 {
+        if self.input_reserve <= 0.0 {
+            return 0.0;
+        }
+        self.input_amount / self.input_reserve
+    }
+}
+
+/// Swap fee model a pool can use in place of its fixed `fee_tier`
+pub trait FeePolicy: fmt::Debug + Send + Sync {
+    /// Fee charged on a swap, as a fraction of the input
+    fn fee_rate(&self, ctx: &FeeContext) -> f64;
+
+    fn clone_box(&self) -> Box<dyn FeePolicy>;
+
+    /// This policy as one of the built-in policies pools can save and reload. Custom
+    /// policies return None and make their pool fail to serialize.
+    fn builtin(&self) -> Option<BuiltinFeePolicy> {
+        None
+    }
+}
+
+impl Clone for Box<dyn FeePolicy> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// The fee policies shipped with the crate, in the form a pool saves them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BuiltinFeePolicy {
+    Volatility(VolatilityFeePolicy),
+}
+
+impl BuiltinFeePolicy {
+    pub fn into_policy(self) -> Box<dyn FeePolicy> {
+        match self {
+            BuiltinFeePolicy::Volatility(policy) => Box::new(policy),
+        }
+    }
+}
+
+// Serde for a pool's `fee_policy`: built-in policies round-trip, and saving a pool with a
+// custom policy fails instead of silently falling back to the fixed fee tier on reload
+pub(crate) mod serde_policy {
+    use super::{BuiltinFeePolicy, FeePolicy};
+    use serde::{ser::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        policy: &Option<Box<dyn FeePolicy>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let builtin = match policy {
+            Some(policy) => Some(policy.builtin().ok_or_else(|| {
+                S::Error::custom(format!(
+                    "custom fee policy {:?} can't be saved; clear it and re-attach it after loading",
+                    policy
+                ))
+            })?),
+            None => None,
+        };
+        builtin.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Box<dyn FeePolicy>>, D::Error> {
+        Ok(Option::<BuiltinFeePolicy>::deserialize(deserializer)?
+            .map(BuiltinFeePolicy::into_policy))
+    }
+}
+
+// Fee that rises with recent realized volatility and with trade size relative to the
+// reserves: `base + volatility_factor · σ + size_factor · amount / reserve`, clamped to
+// `[min_fee, max_fee]`. σ is read from the pool's oracle over the volatility window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolatilityFeePolicy {
+    pub min_fee: f64,
+    pub max_fee: f64,
+    pub volatility_window_secs: i64,
+    pub volatility_factor: f64,
+    pub size_factor: f64,
+}
+
+impl VolatilityFeePolicy {
+    /// Bounds are ordered and kept within `[0, 1)`; factors are floored at zero
+    pub fn new(
+        min_fee: f64,
+        max_fee: f64,
+        volatility_window_secs: i64,
+        volatility_factor: f64,
+        size_factor: f64,
+    ) -> Self {
+        let min_fee = min_fee.clamp(0.0, 0.99);
+        Self {
+            min_fee,
+            max_fee: max_fee.clamp(min_fee, 0.99),
+            volatility_window_secs: volatility_window_secs.max(1),
+            volatility_factor: volatility_factor.max(0.0),
+            size_factor: size_factor.max(0.0),
+        }
+    }
+}
+
+impl FeePolicy for VolatilityFeePolicy {
+    fn fee_rate(&self, ctx: &FeeContext) -> f64 {
+        let volatility = ctx
+            .oracle
+            .realized_volatility(Duration::seconds(self.volatility_window_secs), ctx.now);
+        let fee = ctx.base_fee
+            + self.volatility_factor * volatility
+            + self.size_factor * ctx.size_ratio();
+        fee.clamp(self.min_fee, self.max_fee)
+    }
+
+    fn clone_box(&self) -> Box<dyn FeePolicy> {
+        Box::new(self.clone())
+    }
+
+    fn builtin(&self) -> Option<BuiltinFeePolicy> {
+        Some(BuiltinFeePolicy::Volatility(self.clone()))
+    }
+}
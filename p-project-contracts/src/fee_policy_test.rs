@@ -0,0 +1,204 @@
+#[cfg(test)]
+mod tests {
+    use super::super::fee_policy::{BuiltinFeePolicy, FeeContext, FeePolicy, VolatilityFeePolicy};
+    use super::super::liquidity_pool::LiquidityPool;
+    use super::super::price_oracle::PriceOracle;
+    use super::super::stable_liquidity_pool::StableLiquidityPool;
+    use chrono::{Duration, NaiveDateTime};
+
+    fn at(secs: i64) -> NaiveDateTime {
+        NaiveDateTime::UNIX_EPOCH + Duration::seconds(1_700_000_000 + secs)
+    }
+
+    fn context(oracle: &PriceOracle, input_amount: f64) -> FeeContext<'_> {
+        FeeContext {
+            base_fee: 0.003,
+            input_amount,
+            input_reserve: 1_000_000.0,
+            oracle,
+            now: at(200),
+        }
+    }
+
+    #[test]
+    fn test_realized_volatility_from_observations() {
+        let mut oracle = PriceOracle::default();
+        assert_eq!(oracle.realized_volatility(Duration::hours(1), at(0)), 0.0);
+        // Doubles at 100s and halves back at 150s
+        oracle.record(at(0), 1.0).unwrap();
+        oracle.record(at(100), 2.0).unwrap();
+        oracle.record(at(150), 1.0).unwrap();
+        let ln2 = 2f64.ln();
+        let both = oracle.realized_volatility(Duration::seconds(200), at(200));
+        assert!((both - ln2 * 2f64.sqrt()).abs() < 1e-12);
+        let last = oracle.realized_volatility(Duration::seconds(75), at(200));
+        assert!((last - ln2).abs() < 1e-12);
+        assert_eq!(
+            oracle.realized_volatility(Duration::seconds(10), at(200)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_volatility_policy_scales_and_clamps() {
+        let policy = VolatilityFeePolicy::new(0.001, 0.05, 3_600, 0.05, 0.5);
+        let mut calm = PriceOracle::default();
+        calm.record(at(0), 1.0).unwrap();
+        calm.record(at(100), 1.0).unwrap();
+        let mut volatile = PriceOracle::default();
+        volatile.record(at(0), 1.0).unwrap();
+        volatile.record(at(100), 1.2).unwrap();
+        volatile.record(at(150), 0.9).unwrap();
+
+        // No movement and a small trade: just the base fee plus a sliver for size
+        let calm_fee = policy.fee_rate(&context(&calm, 1_000.0));
+        assert!((calm_fee - (0.003 + 0.5 * 0.001)).abs() < 1e-12);
+        let volatile_fee = policy.fee_rate(&context(&volatile, 1_000.0));
+        assert!(volatile_fee > calm_fee + 0.01);
+        // Larger trades pay more, up to the cap
+        let large = policy.fee_rate(&context(&calm, 50_000.0));
+        assert!((large - (0.003 + 0.5 * 0.05)).abs() < 1e-12);
+        assert_eq!(policy.fee_rate(&context(&volatile, 500_000.0)), 0.05);
+
+        // Floor applies when the base fee is below it; bounds are ordered on construction
+        let floored = VolatilityFeePolicy::new(0.004, 0.002, 3_600, 0.0, 0.0);
+        assert_eq!(floored.max_fee, 0.004);
+        assert_eq!(floored.fee_rate(&context(&calm, 1.0)), 0.004);
+    }
+
+    #[test]
+    fn test_constant_pool_charges_policy_fee() {
+        let mut pool = LiquidityPool::new(
+            "p_usdc".to_string(),
+            "P".to_string(),
+            "USDC".to_string(),
+            0.003,
+            "RWD".to_string(),
+            0.0,
+            0.0,
+        );
+        pool.add_liquidity("lp".to_string(), 1_000_000.0, 1_000_000.0, 30)
+            .unwrap();
+        let fixed = pool.calculate_swap_output("P", 10_000.0).unwrap();
+        pool.set_fee_policy(Box::new(VolatilityFeePolicy::new(
+            0.001, 0.03, 3_600, 0.1, 0.2,
+        )));
+        // A clone keeps the policy
+        let mut calm = pool.clone();
+        let calm_fee = calm.swap_fee("P", 10_000.0);
+        assert!((calm_fee - (0.003 + 0.2 * 0.01)).abs() < 1e-9);
+        assert!(calm.calculate_swap_output("P", 10_000.0).unwrap() < fixed);
+
+        // Whipsaw the price, then the same trade costs more
+        pool.swap("P", 100_000.0).unwrap();
+        let back = pool.swap("USDC", 100_000.0).unwrap();
+        pool.swap("P", back).unwrap();
+        let fee = pool.swap_fee("P", 10_000.0);
+        assert!(fee > calm_fee);
+        let quoted = pool.calculate_swap_output("P", 10_000.0).unwrap();
+        let fees_before = pool.total_fees;
+        assert_eq!(pool.swap("P", 10_000.0).unwrap(), quoted);
+        assert!((pool.total_fees - fees_before - 10_000.0 * fee).abs() < 1e-6);
+
+        assert!(pool.clear_fee_policy().is_some());
+        assert_eq!(pool.swap_fee("P", 10_000.0), 0.003);
+    }
+
+    #[test]
+    fn test_stable_pool_charges_policy_fee() {
+        let mut pool = StableLiquidityPool::new(
+            "usdc_usdt".to_string(),
+            "USDC".to_string(),
+            "USDT".to_string(),
+            0.0005,
+            100.0,
+            "RWD".to_string(),
+            0.0,
+            0.0,
+        )
+        .unwrap();
+        pool.add_liquidity("lp".to_string(), 1_000_000.0, 1_000_000.0, 30)
+            .unwrap();
+        let fixed = pool.calculate_swap_output("USDC", 200_000.0).unwrap();
+        pool.set_fee_policy(Box::new(VolatilityFeePolicy::new(
+            0.0001, 0.01, 3_600, 0.0, 0.02,
+        )));
+
+        // Small trades stay near the base fee, large ones pay for their size
+        assert!((pool.swap_fee("USDC", 1_000.0) - 0.00052).abs() < 1e-12);
+        let fee = pool.swap_fee("USDC", 200_000.0);
+        assert!((fee - 0.0045).abs() < 1e-12);
+        let output = pool.swap("USDC", 200_000.0).unwrap();
+        assert!(output < fixed);
+        assert!((pool.total_fees - 200_000.0 * fee).abs() < 1e-6);
+    }
+
+    #[derive(Debug, Clone)]
+    struct FlatFee(f64);
+
+    impl FeePolicy for FlatFee {
+        fn fee_rate(&self, _ctx: &FeeContext) -> f64 {
+            self.0
+        }
+
+        fn clone_box(&self) -> Box<dyn FeePolicy> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_pools_save_builtin_fee_policy() {
+        let policy = VolatilityFeePolicy::new(0.001, 0.03, 3_600, 0.1, 0.2);
+        let mut pool = LiquidityPool::new(
+            "p_usdc".to_string(),
+            "P".to_string(),
+            "USDC".to_string(),
+            0.003,
+            "RWD".to_string(),
+            0.0,
+            0.0,
+        );
+        pool.add_liquidity("lp".to_string(), 1_000_000.0, 1_000_000.0, 30)
+            .unwrap();
+        pool.set_fee_policy(Box::new(policy.clone()));
+        let reloaded: LiquidityPool =
+            serde_json::from_str(&serde_json::to_string(&pool).unwrap()).unwrap();
+        assert_eq!(
+            reloaded.fee_policy.as_ref().and_then(|p| p.builtin()),
+            Some(BuiltinFeePolicy::Volatility(policy.clone()))
+        );
+        assert_eq!(
+            reloaded.swap_fee("P", 10_000.0),
+            pool.swap_fee("P", 10_000.0)
+        );
+
+        // Pools saved before fee policies existed load with the fixed fee tier
+        let mut saved = serde_json::to_value(&pool).unwrap();
+        saved.as_object_mut().unwrap().remove("fee_policy");
+        let legacy: LiquidityPool = serde_json::from_value(saved).unwrap();
+        assert!(legacy.fee_policy.is_none());
+
+        // A custom policy can't be saved, so it isn't silently dropped either
+        let mut stable = StableLiquidityPool::new(
+            "usdc_usdt".to_string(),
+            "USDC".to_string(),
+            "USDT".to_string(),
+            0.0005,
+            100.0,
+            "RWD".to_string(),
+            0.0,
+            0.0,
+        )
+        .unwrap();
+        stable.set_fee_policy(Box::new(FlatFee(0.002)));
+        let err = serde_json::to_string(&stable).unwrap_err();
+        assert!(err.to_string().contains("custom fee policy"));
+        stable.set_fee_policy(Box::new(policy));
+        let reloaded: StableLiquidityPool =
+            serde_json::from_str(&serde_json::to_string(&stable).unwrap()).unwrap();
+        assert_eq!(
+            reloaded.swap_fee("USDC", 1_000.0),
+            stable.swap_fee("USDC", 1_000.0)
+        );
+    }
+}
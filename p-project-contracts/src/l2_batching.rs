@@ -1,3 +1,5 @@
+use crate::l2_compression;
+use crate::l2_mempool::{Mempool, MempoolConfig};
 use crate::l2_rollup::{L2Block, L2Transaction, RollupError};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
@@ -83,6 +85,11 @@ impl BatchValidator {
             return Err(RollupError::InvalidTransaction);
         }
 
+        // Check fee is a valid non-negative number
+        if !transaction.fee.is_finite() || transaction.fee < 0.0 {
+            return Err(RollupError::FeeTooLow);
+        }
+
         // Check addresses are not empty
         if transaction.from.is_empty() || transaction.to.is_empty() {
             return Err(RollupError::InvalidTransaction);
@@ -99,27 +106,46 @@ impl BatchValidator {
 
 // Batch aggregator
 pub struct BatchAggregator {
-    pub pending_transactions: Vec<L2Transaction>,
+    pub mempool: Mempool,
+    pub account_nonces: HashMap<String, u64>, // Next executable nonce per sender
     pub batch_config: BatchConfig,
     pub batch_counter: u64,
 }
 
 impl BatchAggregator {
     pub fn new(batch_config: BatchConfig) -> Self {
+        Self::with_mempool_config(batch_config, MempoolConfig::default())
+    }
+
+    pub fn with_mempool_config(batch_config: BatchConfig, mempool_config: MempoolConfig) -> Self {
         Self {
-            pending_transactions: Vec::new(),
+            mempool: Mempool::new(mempool_config),
+            account_nonces: HashMap::new(),
             batch_config,
             batch_counter: 0,
         }
     }
 
-    /// Add a transaction to the pending queue
+    /// Record the on-chain nonce of a sender so admission can check nonce ordering
+    pub fn set_account_nonce(&mut self, address: &str, nonce: u64) {
+        self.account_nonces.insert(address.to_string(), nonce);
+        self.mempool
+            .prune(|sender| self.account_nonces.get(sender).copied().unwrap_or(0));
+    }
+
+    /// Add a transaction to the mempool
     pub fn add_transaction(&mut self, transaction: L2Transaction) -> Result<(), RollupError> {
         // Validate transaction first
         let validator = BatchValidator::new(self.batch_config.clone());
         validator.validate_transaction(&transaction)?;
 
-        self.pending_transactions.push(transaction);
+        let account_nonce = self
+            .account_nonces
+            .get(&transaction.from)
+            .copied()
+            .unwrap_or(0);
+        self.mempool
+            .insert(transaction, account_nonce, current_datetime())?;
         Ok(())
     }
 
@@ -130,18 +156,18 @@ impl BatchAggregator {
         state_root_after: String,
         submitter: String,
     ) -> Result<TransactionBatch, RollupError> {
-        if self.pending_transactions.is_empty() {
+        self.mempool.expire(current_datetime());
+        if self.mempool.is_empty() {
             return Err(RollupError::BatchSubmissionFailed);
         }
 
-        // Limit batch size
-        let batch_size = std::cmp::min(
-            self.pending_transactions.len(),
-            self.batch_config.max_transactions_per_batch,
-        );
-
-        let transactions: Vec<L2Transaction> =
-            self.pending_transactions.drain(0..batch_size).collect();
+        // Highest fees first, each sender in nonce order, limited to the batch size
+        let transactions = self
+            .mempool
+            .drain_best(self.batch_config.max_transactions_per_batch);
+        for tx in &transactions {
+            self.account_nonces.insert(tx.from.clone(), tx.nonce + 1);
+        }
 
         self.batch_counter += 1;
         let batch_id = format!("batch_{}", self.batch_counter);
@@ -169,8 +195,11 @@ impl BatchAggregator {
 
     /// Check if a batch should be created based on size or timeout
     pub fn should_create_batch(&self, last_batch_time: u64) -> bool {
+        // Expired transactions will be dropped, so they do not count towards a batch
+        let live = self.mempool.live_count(current_datetime());
+
         // Check if we have enough transactions
-        if self.pending_transactions.len() >= self.batch_config.max_transactions_per_batch {
+        if live >= self.batch_config.max_transactions_per_batch {
             return true;
         }
 
@@ -180,8 +209,8 @@ impl BatchAggregator {
             .unwrap()
             .as_secs();
 
-        if current_time - last_batch_time >= self.batch_config.batch_timeout {
-            return !self.pending_transactions.is_empty();
+        if current_time.saturating_sub(last_batch_time) >= self.batch_config.batch_timeout {
+            return live > 0;
         }
 
         false
@@ -189,15 +218,19 @@ impl BatchAggregator {
 
     /// Get number of pending transactions
     pub fn pending_transaction_count(&self) -> usize {
-        self.pending_transactions.len()
+        self.mempool.len()
     }
 
     /// Clear pending transactions
     pub fn clear_pending(&mut self) {
-        self.pending_transactions.clear();
+        self.mempool.clear();
     }
 }
 
+fn current_datetime() -> chrono::NaiveDateTime {
+    chrono::Utc::now().naive_utc()
+}
+
 // Batch submitter
 pub struct BatchSubmitter {
     pub validator: BatchValidator,
@@ -270,26 +303,16 @@ impl BatchSubmitter {
 }
 
 // Batch compression for efficient L1 submission
-pub struct BatchCompressor {
-    pub compression_ratio: f64,
-}
+pub struct BatchCompressor;
 
 impl BatchCompressor {
     pub fn new() -> Self {
-        Self {
-            compression_ratio: 0.5, // 50% compression
-        }
+        Self
     }
 
-    /// Compress batch data
+    /// Compress batch data into the versioned binary wire format
     pub fn compress_batch(&self, batch: &TransactionBatch) -> Result<Vec<u8>, RollupError> {
-        // Serialize batch to JSON
-        let json_data = serde_json::to_string(batch)
-            .map_err(|e| RollupError::SerializationError(e.to_string()))?;
-
-        // In a real implementation, we would use actual compression algorithms
-        // For now, we'll just convert to bytes
-        Ok(json_data.into_bytes())
+        Ok(l2_compression::compress(batch))
     }
 
     /// Decompress batch data
@@ -297,19 +320,26 @@ impl BatchCompressor {
         &self,
         compressed_data: Vec<u8>,
     ) -> Result<TransactionBatch, RollupError> {
-        // Convert bytes back to string
-        let json_data = String::from_utf8(compressed_data)
-            .map_err(|e| RollupError::SerializationError(e.to_string()))?;
-
-        // Deserialize JSON to batch
-        serde_json::from_str(&json_data).map_err(|e| RollupError::SerializationError(e.to_string()))
+        l2_compression::decompress(&compressed_data)
     }
 
-    /// Estimate compressed size
+    /// Compressed size in bytes, measured by actually encoding the batch
     pub fn estimate_compressed_size(&self, batch: &TransactionBatch) -> usize {
-        // Estimate size based on number of transactions and compression ratio
-        let uncompressed_size = batch.transactions.len() * 200; // Rough estimate per transaction
-        (uncompressed_size as f64 * self.compression_ratio) as usize
+        l2_compression::compress(batch).len()
+    }
+
+    /// Ratio of compressed size to the JSON encoding of the same batch
+    pub fn compression_ratio(&self, batch: &TransactionBatch) -> Result<f64, RollupError> {
+        let json_size = serde_json::to_vec(batch)
+            .map_err(|e| RollupError::SerializationError(e.to_string()))?
+            .len();
+        Ok(self.estimate_compressed_size(batch) as f64 / json_size as f64)
+    }
+}
+
+impl Default for BatchCompressor {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -336,6 +366,31 @@ impl BatchFeeCalculator {
         self.base_fee_per_batch + transaction_fees + gas_fees
     }
 
+    /// Calculate fee for a batch including the L1 calldata cost of its compressed bytes
+    pub fn calculate_batch_fee_for_data(
+        &self,
+        transaction_count: usize,
+        gas_used: u64,
+        compressed_data: &[u8],
+    ) -> f64 {
+        let calldata_fees = l2_compression::calldata_gas(compressed_data) as f64 * self.gas_price;
+        self.calculate_batch_fee(transaction_count, gas_used) + calldata_fees
+    }
+
+    /// Calculate fee for a batch priced on its measured compressed encoding
+    pub fn calculate_compressed_batch_fee(
+        &self,
+        batch: &TransactionBatch,
+        compressor: &BatchCompressor,
+    ) -> Result<f64, RollupError> {
+        let compressed = compressor.compress_batch(batch)?;
+        Ok(self.calculate_batch_fee_for_data(
+            batch.transactions.len(),
+            batch.gas_used,
+            &compressed,
+        ))
+    }
+
     /// Calculate fee for a single transaction
     pub fn calculate_transaction_fee(&self) -> f64 {
         self.fee_per_transaction + (21000.0 * self.gas_price)
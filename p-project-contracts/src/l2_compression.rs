@@ -0,0 +1,523 @@
+use crate::l2_batching::TransactionBatch;
+use crate::l2_rollup::{L2Transaction, RollupError};
+use chrono::{DateTime, NaiveDateTime};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+// Wire format identifiers for compressed batches
+pub const CODEC_MAGIC: [u8; 2] = *b"PB";
+pub const CODEC_VERSION: u8 = 1;
+
+// Header flags
+const FLAG_LZ: u8 = 0b0000_0001;
+
+// Header layout: magic (2) + version (1) + flags (1) + checksum (4), followed by the
+// varint-encoded raw payload length and the payload itself
+const HEADER_LEN: usize = 8;
+
+// Tags used for strings that are frequently hex encoded (roots, signatures)
+const TAG_UTF8: u8 = 0;
+const TAG_HEX: u8 = 1;
+const TAG_PREFIXED_HEX: u8 = 2;
+
+// LZSS parameters
+const LZ_MIN_MATCH: usize = 4;
+const LZ_MAX_MATCH: usize = LZ_MIN_MATCH + u8::MAX as usize;
+const LZ_WINDOW: usize = u16::MAX as usize;
+const LZ_HASH_BITS: u32 = 14;
+
+fn codec_error(msg: &str) -> RollupError {
+    RollupError::SerializationError(msg.to_string())
+}
+
+// Byte writer for the field-packed batch encoding
+#[derive(Default)]
+struct ByteWriter {
+    buf: Vec<u8>,
+}
+
+impl ByteWriter {
+    fn put_u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn put_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn put_zigzag(&mut self, value: i64) {
+        self.put_varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn put_bytes(&mut self, bytes: &[u8]) {
+        self.put_varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn put_str(&mut self, value: &str) {
+        self.put_bytes(value.as_bytes());
+    }
+
+    /// Store lowercase hex strings as raw bytes, everything else as UTF-8
+    fn put_hexish(&mut self, value: &str) {
+        let (tag, body) = match value.strip_prefix("0x") {
+            Some(rest) if is_packable_hex(rest) => (TAG_PREFIXED_HEX, rest),
+            _ if is_packable_hex(value) => (TAG_HEX, value),
+            _ => (TAG_UTF8, value),
+        };
+        self.put_u8(tag);
+        if tag == TAG_UTF8 {
+            self.put_str(body);
+        } else {
+            // is_packable_hex guarantees the decode succeeds
+            self.put_bytes(&hex::decode(body).unwrap_or_default());
+        }
+    }
+
+    fn put_f64(&mut self, value: f64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_datetime(&mut self, value: &NaiveDateTime, previous_secs: i64) -> i64 {
+        let utc = value.and_utc();
+        let secs = utc.timestamp();
+        self.put_zigzag(secs.wrapping_sub(previous_secs));
+        self.put_varint(utc.timestamp_subsec_nanos() as u64);
+        secs
+    }
+}
+
+// Non-empty, even-length, lowercase hex round-trips exactly through hex::encode
+fn is_packable_hex(value: &str) -> bool {
+    !value.is_empty()
+        && value.len().is_multiple_of(2)
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+// Byte reader counterpart of ByteWriter
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RollupError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| codec_error("unexpected end of batch data"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn get_u8(&mut self) -> Result<u8, RollupError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn get_varint(&mut self) -> Result<u64, RollupError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.get_u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(codec_error("varint overflow"))
+    }
+
+    fn get_len(&mut self) -> Result<usize, RollupError> {
+        let len = self.get_varint()?;
+        usize::try_from(len).map_err(|_| codec_error("length overflow"))
+    }
+
+    fn get_zigzag(&mut self) -> Result<i64, RollupError> {
+        let raw = self.get_varint()?;
+        Ok(((raw >> 1) as i64) ^ -((raw & 1) as i64))
+    }
+
+    fn get_bytes(&mut self) -> Result<&'a [u8], RollupError> {
+        let len = self.get_len()?;
+        self.take(len)
+    }
+
+    fn get_str(&mut self) -> Result<String, RollupError> {
+        let bytes = self.get_bytes()?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|e| RollupError::SerializationError(e.to_string()))
+    }
+
+    fn get_hexish(&mut self) -> Result<String, RollupError> {
+        match self.get_u8()? {
+            TAG_UTF8 => self.get_str(),
+            TAG_HEX => Ok(hex::encode(self.get_bytes()?)),
+            TAG_PREFIXED_HEX => Ok(format!("0x{}", hex::encode(self.get_bytes()?))),
+            _ => Err(codec_error("unknown string tag")),
+        }
+    }
+
+    fn get_f64(&mut self) -> Result<f64, RollupError> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(f64::from_le_bytes(bytes))
+    }
+
+    fn get_datetime(&mut self, previous_secs: i64) -> Result<(NaiveDateTime, i64), RollupError> {
+        let secs = previous_secs.wrapping_add(self.get_zigzag()?);
+        let nanos =
+            u32::try_from(self.get_varint()?).map_err(|_| codec_error("invalid timestamp"))?;
+        let datetime = DateTime::from_timestamp(secs, nanos)
+            .ok_or_else(|| codec_error("invalid timestamp"))?
+            .naive_utc();
+        Ok((datetime, secs))
+    }
+}
+
+// Address dictionary shared by all transactions in a batch
+#[derive(Debug, Default)]
+pub struct AddressDictionary {
+    pub addresses: Vec<String>,
+    index: HashMap<String, u64>,
+}
+
+impl AddressDictionary {
+    /// Build a dictionary of unique addresses in first-seen order
+    pub fn from_transactions(transactions: &[L2Transaction]) -> Self {
+        let mut dictionary = Self::default();
+        for tx in transactions {
+            dictionary.intern(&tx.from);
+            dictionary.intern(&tx.to);
+        }
+        dictionary
+    }
+
+    /// Return the index of an address, inserting it if unseen
+    pub fn intern(&mut self, address: &str) -> u64 {
+        if let Some(index) = self.index.get(address) {
+            return *index;
+        }
+        let index = self.addresses.len() as u64;
+        self.addresses.push(address.to_string());
+        self.index.insert(address.to_string(), index);
+        index
+    }
+
+    /// Resolve an index back to its address
+    pub fn resolve(&self, index: u64) -> Option<&str> {
+        self.addresses.get(index as usize).map(|a| a.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+/// Encode a batch into the field-packed binary layout (no header, no compression)
+pub fn encode_batch(batch: &TransactionBatch) -> Vec<u8> {
+    let mut w = ByteWriter::default();
+
+    w.put_str(&batch.batch_id);
+    w.put_str(&batch.submitter);
+    w.put_hexish(&batch.signature);
+    w.put_hexish(&batch.state_root_before);
+    w.put_hexish(&batch.state_root_after);
+    w.put_varint(batch.gas_used);
+    w.put_varint(batch.timestamp);
+
+    // Block numbers are usually consecutive, so store deltas
+    w.put_varint(batch.block_numbers.len() as u64);
+    let mut previous_block = 0u64;
+    for block_number in &batch.block_numbers {
+        w.put_zigzag(block_number.wrapping_sub(previous_block) as i64);
+        previous_block = *block_number;
+    }
+
+    let dictionary = AddressDictionary::from_transactions(&batch.transactions);
+    w.put_varint(dictionary.len() as u64);
+    for address in &dictionary.addresses {
+        w.put_hexish(address);
+    }
+
+    w.put_varint(batch.transactions.len() as u64);
+    let mut previous_secs = batch.timestamp as i64;
+    for tx in &batch.transactions {
+        w.put_varint(dictionary.index[&tx.from]);
+        w.put_varint(dictionary.index[&tx.to]);
+        w.put_f64(tx.amount);
+        w.put_f64(tx.fee);
+        w.put_varint(tx.nonce);
+        w.put_hexish(&tx.signature);
+        previous_secs = w.put_datetime(&tx.timestamp, previous_secs);
+    }
+
+    w.buf
+}
+
+/// Decode a batch from the field-packed binary layout
+pub fn decode_batch(data: &[u8]) -> Result<TransactionBatch, RollupError> {
+    let mut r = ByteReader::new(data);
+
+    let batch_id = r.get_str()?;
+    let submitter = r.get_str()?;
+    let signature = r.get_hexish()?;
+    let state_root_before = r.get_hexish()?;
+    let state_root_after = r.get_hexish()?;
+    let gas_used = r.get_varint()?;
+    let timestamp = r.get_varint()?;
+
+    let block_count = r.get_len()?;
+    let mut block_numbers = Vec::with_capacity(block_count.min(data.len()));
+    let mut previous_block = 0u64;
+    for _ in 0..block_count {
+        previous_block = previous_block.wrapping_add(r.get_zigzag()? as u64);
+        block_numbers.push(previous_block);
+    }
+
+    let address_count = r.get_len()?;
+    let mut dictionary = AddressDictionary::default();
+    for _ in 0..address_count {
+        let address = r.get_hexish()?;
+        dictionary.intern(&address);
+    }
+    if dictionary.len() != address_count {
+        return Err(codec_error("duplicate address in dictionary"));
+    }
+
+    let tx_count = r.get_len()?;
+    let mut transactions = Vec::with_capacity(tx_count.min(data.len()));
+    let mut previous_secs = timestamp as i64;
+    for _ in 0..tx_count {
+        let from = dictionary
+            .resolve(r.get_varint()?)
+            .ok_or_else(|| codec_error("address index out of range"))?
+            .to_string();
+        let to = dictionary
+            .resolve(r.get_varint()?)
+            .ok_or_else(|| codec_error("address index out of range"))?
+            .to_string();
+        let amount = r.get_f64()?;
+        let fee = r.get_f64()?;
+        let nonce = r.get_varint()?;
+        let tx_signature = r.get_hexish()?;
+        let (tx_timestamp, secs) = r.get_datetime(previous_secs)?;
+        previous_secs = secs;
+        transactions.push(L2Transaction {
+            from,
+            to,
+            amount,
+            fee,
+            nonce,
+            signature: tx_signature,
+            timestamp: tx_timestamp,
+        });
+    }
+
+    if !r.is_empty() {
+        return Err(codec_error("trailing bytes after batch"));
+    }
+
+    Ok(TransactionBatch {
+        batch_id,
+        transactions,
+        block_numbers,
+        gas_used,
+        timestamp,
+        submitter,
+        signature,
+        state_root_before,
+        state_root_after,
+    })
+}
+
+fn lz_hash(window: &[u8]) -> usize {
+    let word = u32::from_le_bytes([window[0], window[1], window[2], window[3]]);
+    (word.wrapping_mul(2_654_435_761) >> (32 - LZ_HASH_BITS)) as usize
+}
+
+/// LZSS compression: groups of eight items prefixed by a flag byte, where a set bit
+/// marks a back-reference (u16 offset + u8 length) and a clear bit a literal byte
+pub fn lz_compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2 + 16);
+    let mut table = vec![usize::MAX; 1 << LZ_HASH_BITS];
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let flag_index = out.len();
+        out.push(0u8);
+
+        for bit in 0..8 {
+            if pos >= input.len() {
+                break;
+            }
+
+            let mut match_len = 0;
+            let mut match_offset = 0;
+            if pos + LZ_MIN_MATCH <= input.len() {
+                let slot = lz_hash(&input[pos..]);
+                let candidate = table[slot];
+                table[slot] = pos;
+                if candidate != usize::MAX && pos - candidate <= LZ_WINDOW {
+                    let max_len = (input.len() - pos).min(LZ_MAX_MATCH);
+                    while match_len < max_len
+                        && input[candidate + match_len] == input[pos + match_len]
+                    {
+                        match_len += 1;
+                    }
+                    match_offset = pos - candidate;
+                }
+            }
+
+            if match_len >= LZ_MIN_MATCH {
+                out[flag_index] |= 1 << bit;
+                out.extend_from_slice(&(match_offset as u16).to_le_bytes());
+                out.push((match_len - LZ_MIN_MATCH) as u8);
+                // Index the skipped positions so later matches can find them
+                for p in pos + 1..pos + match_len {
+                    if p + LZ_MIN_MATCH <= input.len() {
+                        table[lz_hash(&input[p..])] = p;
+                    }
+                }
+                pos += match_len;
+            } else {
+                out.push(input[pos]);
+                pos += 1;
+            }
+        }
+    }
+
+    out
+}
+
+/// Inverse of `lz_compress`; `expected_len` bounds the output size
+pub fn lz_decompress(input: &[u8], expected_len: usize) -> Result<Vec<u8>, RollupError> {
+    // `expected_len` comes from an untrusted header, so preallocate no more than the
+    // input can expand to: a 3-byte back-reference yields at most LZ_MAX_MATCH bytes
+    let max_len = input.len().saturating_mul(LZ_MAX_MATCH / 3 + 1);
+    let mut out: Vec<u8> = Vec::with_capacity(expected_len.min(max_len));
+    let mut r = ByteReader::new(input);
+
+    while !r.is_empty() {
+        let flags = r.get_u8()?;
+        for bit in 0..8 {
+            if r.is_empty() {
+                break;
+            }
+            if flags & (1 << bit) != 0 {
+                let offset_bytes = r.take(2)?;
+                let offset = u16::from_le_bytes([offset_bytes[0], offset_bytes[1]]) as usize;
+                let len = r.get_u8()? as usize + LZ_MIN_MATCH;
+                if offset == 0 || offset > out.len() || out.len() + len > expected_len {
+                    return Err(codec_error("invalid back-reference"));
+                }
+                let start = out.len() - offset;
+                // Copy byte by byte: the reference may overlap the bytes being written
+                for i in 0..len {
+                    let byte = out[start + i];
+                    out.push(byte);
+                }
+            } else {
+                if out.len() >= expected_len {
+                    return Err(codec_error("decompressed data exceeds declared length"));
+                }
+                out.push(r.get_u8()?);
+            }
+        }
+    }
+
+    if out.len() != expected_len {
+        return Err(codec_error("decompressed length mismatch"));
+    }
+    Ok(out)
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let digest = Keccak256::digest(payload);
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Produce the versioned wire format: header + (optionally LZSS-compressed) packed batch
+pub fn compress(batch: &TransactionBatch) -> Vec<u8> {
+    let raw = encode_batch(batch);
+    let compressed = lz_compress(&raw);
+
+    let (flags, body) = if compressed.len() < raw.len() {
+        (FLAG_LZ, compressed)
+    } else {
+        (0, raw.clone())
+    };
+
+    let mut w = ByteWriter::default();
+    w.buf.extend_from_slice(&CODEC_MAGIC);
+    w.put_u8(CODEC_VERSION);
+    w.put_u8(flags);
+    w.buf.extend_from_slice(&checksum(&raw));
+    w.put_varint(raw.len() as u64);
+    w.buf.extend_from_slice(&body);
+    w.buf
+}
+
+/// Parse the versioned wire format back into a batch
+pub fn decompress(data: &[u8]) -> Result<TransactionBatch, RollupError> {
+    if data.len() < HEADER_LEN {
+        return Err(codec_error("batch data shorter than header"));
+    }
+    if data[0..2] != CODEC_MAGIC {
+        return Err(codec_error("bad batch magic"));
+    }
+    if data[2] != CODEC_VERSION {
+        return Err(RollupError::SerializationError(format!(
+            "unsupported batch codec version {}",
+            data[2]
+        )));
+    }
+    let flags = data[3];
+    if flags & !FLAG_LZ != 0 {
+        return Err(codec_error("unknown batch flags"));
+    }
+    let expected_checksum = &data[4..8];
+
+    let mut r = ByteReader::new(&data[HEADER_LEN..]);
+    let raw_len = r.get_len()?;
+    let body = &data[HEADER_LEN + r.pos..];
+
+    let raw = if flags & FLAG_LZ != 0 {
+        lz_decompress(body, raw_len)?
+    } else if body.len() == raw_len {
+        body.to_vec()
+    } else {
+        return Err(codec_error("stored length mismatch"));
+    };
+
+    if checksum(&raw) != expected_checksum {
+        return Err(codec_error("batch checksum mismatch"));
+    }
+
+    decode_batch(&raw)
+}
+
+/// L1 calldata gas for a payload (EIP-2028: 4 per zero byte, 16 per non-zero byte)
+pub fn calldata_gas(data: &[u8]) -> u64 {
+    data.iter()
+        .map(|b| if *b == 0 { 4u64 } else { 16u64 })
+        .sum()
+}
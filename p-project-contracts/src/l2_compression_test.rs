@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod tests {
+    use super::super::l2_batching::{BatchCompressor, BatchFeeCalculator, TransactionBatch};
+    use super::super::l2_compression::{self, AddressDictionary, CODEC_VERSION};
+    use super::super::l2_rollup::{L2Transaction, RollupError};
+    use chrono::{DateTime, NaiveDateTime};
+
+    fn ts(secs: i64, nanos: u32) -> NaiveDateTime {
+        DateTime::from_timestamp(secs, nanos).unwrap().naive_utc()
+    }
+
+    fn sample_batch(tx_count: usize) -> TransactionBatch {
+        let users = [
+            "alice",
+            "bob",
+            "0xabcdef0123456789abcdef0123456789abcdef01",
+            "carol",
+        ];
+        let transactions = (0..tx_count)
+            .map(|i| L2Transaction {
+                from: users[i % users.len()].to_string(),
+                to: users[(i + 1) % users.len()].to_string(),
+                amount: 10.5 + i as f64 * 0.1,
+                fee: (i % 3) as f64 * 0.01,
+                nonce: i as u64,
+                signature: format!("{:064x}", i * 7919),
+                timestamp: ts(1_700_000_000 + i as i64, (i as u32) * 1_000),
+            })
+            .collect();
+
+        TransactionBatch {
+            batch_id: "batch_42".to_string(),
+            transactions,
+            block_numbers: vec![100, 101, 102, 104],
+            gas_used: tx_count as u64 * 21000,
+            timestamp: 1_700_000_500,
+            submitter: "operator1".to_string(),
+            signature: String::new(),
+            state_root_before: "ab".repeat(32),
+            state_root_after: format!("0x{}", "cd".repeat(32)),
+        }
+    }
+
+    fn assert_same_batch(a: &TransactionBatch, b: &TransactionBatch) {
+        assert_eq!(
+            serde_json::to_string(a).unwrap(),
+            serde_json::to_string(b).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip_is_exact() {
+        let compressor = BatchCompressor::new();
+        let batch = sample_batch(50);
+
+        let data = compressor.compress_batch(&batch).unwrap();
+        assert_eq!(&data[0..2], b"PB");
+        assert_eq!(data[2], CODEC_VERSION);
+
+        let decoded = compressor.decompress_batch(data).unwrap();
+        assert_same_batch(&batch, &decoded);
+    }
+
+    #[test]
+    fn test_round_trip_empty_and_odd_values() {
+        let compressor = BatchCompressor::new();
+        let mut batch = sample_batch(0);
+        batch.block_numbers = vec![u64::MAX, 0, 7];
+        batch.signature = "not hex at all".to_string();
+        batch.state_root_before = "ABCD".to_string(); // uppercase hex stays UTF-8
+        let decoded = compressor
+            .decompress_batch(compressor.compress_batch(&batch).unwrap())
+            .unwrap();
+        assert_same_batch(&batch, &decoded);
+
+        let mut batch = sample_batch(3);
+        batch.transactions[0].amount = -0.0;
+        batch.transactions[1].amount = f64::MAX;
+        batch.transactions[2].timestamp = ts(-5, 999_999_999);
+        batch.transactions[2].signature = String::new();
+        let decoded = compressor
+            .decompress_batch(compressor.compress_batch(&batch).unwrap())
+            .unwrap();
+        assert_eq!(
+            decoded.transactions[0].amount.to_bits(),
+            (-0.0f64).to_bits()
+        );
+        assert_same_batch(&batch, &decoded);
+    }
+
+    #[test]
+    fn test_compressed_is_much_smaller_than_json() {
+        let compressor = BatchCompressor::new();
+        let batch = sample_batch(200);
+
+        let json_size = serde_json::to_vec(&batch).unwrap().len();
+        let measured = compressor.estimate_compressed_size(&batch);
+        assert_eq!(measured, compressor.compress_batch(&batch).unwrap().len());
+        assert!(measured * 3 < json_size, "{} vs {}", measured, json_size);
+        assert!(compressor.compression_ratio(&batch).unwrap() < 0.34);
+    }
+
+    #[test]
+    fn test_address_dictionary_deduplicates() {
+        let batch = sample_batch(40);
+        let dictionary = AddressDictionary::from_transactions(&batch.transactions);
+        assert_eq!(dictionary.len(), 4);
+        assert_eq!(dictionary.resolve(0), Some("alice"));
+        assert_eq!(dictionary.resolve(4), None);
+    }
+
+    #[test]
+    fn test_lz_round_trip() {
+        let mut input = Vec::new();
+        for i in 0..5000u32 {
+            input.extend_from_slice(&(i % 97).to_le_bytes());
+        }
+        input.extend_from_slice(&[0u8; 1000]);
+        let compressed = l2_compression::lz_compress(&input);
+        assert!(compressed.len() < input.len() / 4);
+        let restored = l2_compression::lz_decompress(&compressed, input.len()).unwrap();
+        assert_eq!(restored, input);
+
+        assert!(l2_compression::lz_compress(&[]).is_empty());
+        assert_eq!(
+            l2_compression::lz_decompress(&[], 0).unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn test_rejects_corrupted_data() {
+        let compressor = BatchCompressor::new();
+        let data = compressor.compress_batch(&sample_batch(10)).unwrap();
+
+        let mut bad_version = data.clone();
+        bad_version[2] = CODEC_VERSION + 1;
+        assert!(matches!(
+            compressor.decompress_batch(bad_version),
+            Err(RollupError::SerializationError(_))
+        ));
+
+        let mut bad_magic = data.clone();
+        bad_magic[0] = b'X';
+        assert!(compressor.decompress_batch(bad_magic).is_err());
+
+        let mut flipped = data.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 0xff;
+        assert!(compressor.decompress_batch(flipped).is_err());
+
+        let truncated = data[..data.len() / 2].to_vec();
+        assert!(compressor.decompress_batch(truncated).is_err());
+
+        assert!(compressor.decompress_batch(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_oversized_length_header() {
+        let compressor = BatchCompressor::new();
+        let data = compressor.compress_batch(&sample_batch(10)).unwrap();
+        assert_eq!(data[3], 1, "sample batch should be LZ-compressed");
+
+        // Declared raw length of u64::MAX (a ten-byte varint) in front of the real body
+        let len_bytes = data[8..].iter().position(|b| b & 0x80 == 0).unwrap() + 1;
+        let mut forged = data[..8].to_vec();
+        forged.extend_from_slice(&[0xff; 9]);
+        forged.push(0x01);
+        forged.extend_from_slice(&data[8 + len_bytes..]);
+        assert!(matches!(
+            compressor.decompress_batch(forged),
+            Err(RollupError::SerializationError(_))
+        ));
+
+        assert!(l2_compression::lz_decompress(&[0x00, 1, 2, 3], usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_fee_priced_on_measured_bytes() {
+        let compressor = BatchCompressor::new();
+        let calculator = BatchFeeCalculator::new(1.0, 0.01, 0.000001);
+        let small = sample_batch(5);
+        let large = sample_batch(100);
+
+        let small_fee = calculator
+            .calculate_compressed_batch_fee(&small, &compressor)
+            .unwrap();
+        let data = compressor.compress_batch(&small).unwrap();
+        let expected = calculator.calculate_batch_fee(5, small.gas_used)
+            + l2_compression::calldata_gas(&data) as f64 * 0.000001;
+        assert!((small_fee - expected).abs() < 1e-12);
+
+        let large_fee = calculator
+            .calculate_compressed_batch_fee(&large, &compressor)
+            .unwrap();
+        assert!(large_fee > small_fee);
+
+        assert_eq!(l2_compression::calldata_gas(&[0, 1, 0, 255]), 40);
+    }
+}
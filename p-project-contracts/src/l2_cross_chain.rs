@@ -1,8 +1,13 @@
 use crate::l2_rollup::{L2Rollup, L2Transaction, RollupError};
+use crate::l2_withdrawal::WithdrawalProof;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
 
+// Rollup account holding locked tokens until they are released or bridged out. No key
+// controls it; only the bridge moves funds out, through system transfers.
+pub const BRIDGE_ESCROW_ADDRESS: &str = "0x0000000000000000000000000000000000000001";
+
 // Message structure for cross-chain communication
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrossChainMessage {
@@ -22,8 +27,14 @@ pub struct CrossChainMessage {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrossChainBridgeState {
     pub locked_tokens: HashMap<String, f64>, // token_address -> amount
+    #[serde(default)]
+    pub user_locks: HashMap<String, HashMap<String, f64>>, // user -> token_address -> amount
     pub processed_messages: HashMap<String, bool>, // message_id -> processed
     pub pending_messages: Vec<CrossChainMessage>,
+    #[serde(default)]
+    pub releases: u64, // Releases made so far; numbers their rollup transfer ids
+    #[serde(default)]
+    pub locks: u64, // Locks made so far; keeps lock ids unique
 }
 
 // Cross-chain communication protocol
@@ -40,8 +51,11 @@ impl L2CrossChainProtocol {
             rollup,
             bridge_state: CrossChainBridgeState {
                 locked_tokens: HashMap::new(),
+                user_locks: HashMap::new(),
                 processed_messages: HashMap::new(),
                 pending_messages: Vec::new(),
+                releases: 0,
+                locks: 0,
             },
             chain_id,
             connected_chains: Vec::new(),
@@ -82,37 +96,57 @@ impl L2CrossChainProtocol {
         }
     }
 
-    /// Lock tokens for cross-chain transfer
+    /// Lock tokens for cross-chain transfer: move them from the user into the bridge
+    /// escrow on the rollup. The debit is committed by the next batch; the destination
+    /// chain should only act on the lock once that batch is final.
     pub fn lock_tokens(
         &mut self,
         user: String,
         token: String,
         amount: f64,
     ) -> Result<String, RollupError> {
-        // Check user balance
-        let user_balance = self.rollup.get_balance(&user);
-        if user_balance < amount {
-            return Err(RollupError::InsufficientBalance);
-        }
-
-        // Deduct tokens from user
         let mut hasher = Keccak256::new();
+        hasher.update(self.chain_id.as_bytes());
         hasher.update(user.as_bytes());
         hasher.update(token.as_bytes());
-        hasher.update(&amount.to_le_bytes());
+        hasher.update(amount.to_le_bytes());
+        hasher.update(self.bridge_state.locks.to_le_bytes());
         let lock_id = format!("{:x}", hasher.finalize());
 
-        // Update user balance
-        // In a real implementation, we would update the rollup state
-        // For now, we'll just track the locked tokens
-        let current_locked = self.bridge_state.locked_tokens.get(&token).unwrap_or(&0.0);
-        self.bridge_state
+        // Fails without touching the bridge state if the user can't cover the amount
+        self.rollup.system_transfer(
+            &format!("lock-{}", lock_id),
+            Some(&user),
+            BRIDGE_ESCROW_ADDRESS,
+            amount,
+        )?;
+        self.bridge_state.locks += 1;
+        *self
+            .bridge_state
             .locked_tokens
-            .insert(token.clone(), current_locked + amount);
+            .entry(token.clone())
+            .or_insert(0.0) += amount;
+        *self
+            .bridge_state
+            .user_locks
+            .entry(user)
+            .or_default()
+            .entry(token)
+            .or_insert(0.0) += amount;
 
         Ok(lock_id)
     }
 
+    /// Amount of `token` that `user` has locked and not yet had released
+    pub fn locked_by(&self, user: &str, token: &str) -> f64 {
+        self.bridge_state
+            .user_locks
+            .get(user)
+            .and_then(|tokens| tokens.get(token))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
     /// Create cross-chain message
     pub fn create_cross_chain_message(
         &mut self,
@@ -179,25 +213,17 @@ impl L2CrossChainProtocol {
             return Err(RollupError::InvalidTransaction);
         }
 
-        // Verify message signature
-        // In a real implementation, we would verify the cryptographic signature
-        // For now, we'll assume it's valid
+        // Only intact messages sent to this chain by a connected one are minted
+        if !self.verify_message(&message)
+            || message.destination_chain != self.chain_id
+            || !self.connected_chains.contains(&message.source_chain)
+        {
+            return Err(RollupError::InvalidTransaction);
+        }
 
         // Mint tokens to recipient
-        let recipient_balance = self.rollup.get_balance(&message.recipient);
-
-        // Create a transaction to mint tokens
-        let transaction = L2Transaction {
-            from: "bridge".to_string(), // Special bridge address
-            to: message.recipient.clone(),
-            amount: message.amount,
-            nonce: 0, // Bridge transactions don't use nonce
-            signature: message.signature.clone(),
-            timestamp: chrono::Utc::now().naive_utc(),
-        };
-
-        // Add transaction to rollup
-        self.rollup.add_transaction(transaction)?;
+        self.rollup
+            .deposit(&message.message_id, &message.recipient, message.amount)?;
 
         // Mark message as processed
         self.bridge_state
@@ -207,34 +233,50 @@ impl L2CrossChainProtocol {
         Ok(())
     }
 
-    /// Release locked tokens (in case of failed transfer)
+    /// Release locked tokens (in case of failed transfer): pay them out of the bridge
+    /// escrow back to the user who locked them
     pub fn release_tokens(
         &mut self,
         user: String,
         token: String,
         amount: f64,
     ) -> Result<(), RollupError> {
-        let current_locked = self.bridge_state.locked_tokens.get(&token).unwrap_or(&0.0);
-        if *current_locked < amount {
+        if self.locked_by(&user, &token) < amount {
             return Err(RollupError::InsufficientBalance);
         }
 
-        self.bridge_state
+        // Return tokens to the user, then take them off the locked totals
+        let release_id = format!("release-{}-{}", token, self.bridge_state.releases);
+        self.rollup
+            .system_transfer(&release_id, Some(BRIDGE_ESCROW_ADDRESS), &user, amount)?;
+        self.bridge_state.releases += 1;
+        *self
+            .bridge_state
             .locked_tokens
-            .insert(token.clone(), current_locked - amount);
+            .entry(token.clone())
+            .or_insert(0.0) -= amount;
+        *self
+            .bridge_state
+            .user_locks
+            .entry(user)
+            .or_default()
+            .entry(token)
+            .or_insert(0.0) -= amount;
+        Ok(())
+    }
 
-        // Add tokens back to user balance
-        let transaction = L2Transaction {
-            from: "bridge".to_string(), // Special bridge address
-            to: user,
-            amount,
-            nonce: 0,
-            signature: String::new(),
-            timestamp: chrono::Utc::now().naive_utc(),
-        };
+    /// Start a user exit to L1: the signed withdrawal burns the funds on L2 and is
+    /// committed in the withdrawal root of the next batch. Returns the withdrawal id.
+    pub fn initiate_withdrawal(
+        &mut self,
+        transaction: L2Transaction,
+    ) -> Result<String, RollupError> {
+        self.rollup.request_withdrawal(transaction)
+    }
 
-        self.rollup.add_transaction(transaction)?;
-        Ok(())
+    /// Proof to present to the L1 verifier once the withdrawal's batch is final
+    pub fn withdrawal_proof(&self, withdrawal_id: &str) -> Result<WithdrawalProof, RollupError> {
+        self.rollup.generate_withdrawal_proof(withdrawal_id)
     }
 
     /// Get bridge status
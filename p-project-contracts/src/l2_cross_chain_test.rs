@@ -1,7 +1,36 @@
 #[cfg(test)]
 mod tests {
     use super::super::l2_rollup::{L2Rollup, RollupConfig};
-    use super::super::l2_cross_chain::{L2CrossChainProtocol, CrossChainMessage};
+    use super::super::l2_cross_chain::{
+        CrossChainMessage, L2CrossChainProtocol, BRIDGE_ESCROW_ADDRESS,
+    };
+    use super::super::l2_rollup::RollupError;
+
+    /// Message signed by an "ethereum" protocol that locked `amount` for `recipient`
+    fn incoming_message(recipient: &str, amount: f64) -> CrossChainMessage {
+        let config = RollupConfig {
+            chain_id: "ethereum".to_string(),
+            operator_address: "operator1".to_string(),
+            batch_submission_interval: 300,
+            max_batch_size: 100,
+            gas_price: 0.001,
+        };
+        let mut rollup = L2Rollup::new(config);
+        rollup.initialize_account("user1".to_string(), 1000.0);
+        let mut source = L2CrossChainProtocol::new(rollup, "ethereum".to_string());
+        source.add_connected_chain("test-chain".to_string());
+        source
+            .create_cross_chain_message(
+                "ethereum".to_string(),
+                "test-chain".to_string(),
+                "user1".to_string(),
+                recipient.to_string(),
+                amount,
+                "P".to_string(),
+                vec![1, 2, 3, 4],
+            )
+            .unwrap()
+    }
 
     #[test]
     fn test_create_cross_chain_protocol() {
@@ -68,6 +97,16 @@ mod tests {
 
         let bridge_status = protocol.get_bridge_status();
         assert_eq!(*bridge_status.locked_tokens.get("P").unwrap(), 100.0);
+        assert_eq!(protocol.locked_by("user1", "P"), 100.0);
+
+        // The locked amount leaves the user for the escrow
+        assert_eq!(protocol.rollup.get_balance("user1"), 900.0);
+        assert_eq!(protocol.rollup.get_balance(BRIDGE_ESCROW_ADDRESS), 100.0);
+
+        // Locking the same amount again is a separate lock
+        let second = protocol.lock_tokens("user1".to_string(), "P".to_string(), 100.0).unwrap();
+        assert_ne!(second, lock_id);
+        assert_eq!(protocol.rollup.get_balance("user1"), 800.0);
     }
 
     #[test]
@@ -86,7 +125,9 @@ mod tests {
         let mut protocol = L2CrossChainProtocol::new(rollup, "test-chain".to_string());
 
         let result = protocol.lock_tokens("user1".to_string(), "P".to_string(), 100.0);
-        assert_eq!(result, Err(super::super::l2_rollup::RollupError::InsufficientBalance));
+        assert_eq!(result, Err(RollupError::InsufficientBalance));
+        assert!(protocol.bridge_state.locked_tokens.is_empty());
+        assert_eq!(protocol.rollup.get_balance("user1"), 50.0);
     }
 
     #[test]
@@ -153,7 +194,7 @@ mod tests {
             vec![1, 2, 3, 4],
         );
 
-        assert_eq!(result, Err(super::super::l2_rollup::RollupError::InvalidTransaction));
+        assert_eq!(result.err(), Some(RollupError::InvalidTransaction));
     }
 
     #[test]
@@ -166,30 +207,62 @@ mod tests {
             gas_price: 0.001,
         };
 
-        let mut rollup = L2Rollup::new(config);
-        rollup.initialize_account("bridge".to_string(), 10000.0); // Initialize bridge with funds
-
+        let rollup = L2Rollup::new(config);
         let mut protocol = L2CrossChainProtocol::new(rollup, "test-chain".to_string());
+        protocol.add_connected_chain("ethereum".to_string());
 
-        let message = CrossChainMessage {
-            message_id: "msg1".to_string(),
-            source_chain: "ethereum".to_string(),
-            destination_chain: "test-chain".to_string(),
-            sender: "user1".to_string(),
-            recipient: "user2".to_string(),
-            amount: 100.0,
-            token: "P".to_string(),
-            payload: vec![1, 2, 3, 4],
-            timestamp: 1234567890,
-            signature: "sig1".to_string(),
-        };
+        let message = incoming_message("user2", 100.0);
+        let message_id = message.message_id.clone();
 
         let result = protocol.process_incoming_message(message);
         assert!(result.is_ok());
 
         let bridge_status = protocol.get_bridge_status();
-        assert!(bridge_status.processed_messages.contains_key("msg1"));
-        assert_eq!(*bridge_status.processed_messages.get("msg1").unwrap(), true);
+        assert!(bridge_status.processed_messages.contains_key(&message_id));
+        assert!(bridge_status.processed_messages[&message_id]);
+        assert_eq!(protocol.rollup.get_balance("user2"), 100.0);
+    }
+
+    #[test]
+    fn test_forged_message_is_not_minted() {
+        let config = RollupConfig {
+            chain_id: "test-chain".to_string(),
+            operator_address: "operator1".to_string(),
+            batch_submission_interval: 300,
+            max_batch_size: 100,
+            gas_price: 0.001,
+        };
+
+        let rollup = L2Rollup::new(config);
+        let mut protocol = L2CrossChainProtocol::new(rollup, "test-chain".to_string());
+        let message = incoming_message("user2", 100.0);
+
+        // Messages from a chain that isn't connected are refused
+        assert_eq!(
+            protocol.process_incoming_message(message.clone()),
+            Err(RollupError::InvalidTransaction)
+        );
+        protocol.add_connected_chain("ethereum".to_string());
+
+        let mut inflated = message.clone();
+        inflated.amount = 1_000_000.0;
+        let mut fabricated = message.clone();
+        fabricated.message_id = "msg1".to_string();
+        fabricated.signature = "sig1".to_string();
+        let mut misrouted = message.clone();
+        misrouted.destination_chain = "polygon".to_string();
+        for forged in [inflated, fabricated, misrouted] {
+            assert_eq!(
+                protocol.process_incoming_message(forged),
+                Err(RollupError::InvalidTransaction)
+            );
+        }
+        assert_eq!(protocol.rollup.get_balance("user2"), 0.0);
+        assert!(protocol.bridge_state.processed_messages.is_empty());
+
+        // The untouched message still goes through
+        protocol.process_incoming_message(message).unwrap();
+        assert_eq!(protocol.rollup.get_balance("user2"), 100.0);
     }
 
     #[test]
@@ -204,7 +277,6 @@ mod tests {
 
         let mut rollup = L2Rollup::new(config);
         rollup.initialize_account("user1".to_string(), 1000.0);
-        rollup.initialize_account("bridge".to_string(), 10000.0);
 
         let mut protocol = L2CrossChainProtocol::new(rollup, "test-chain".to_string());
 
@@ -216,6 +288,75 @@ mod tests {
 
         let bridge_status = protocol.get_bridge_status();
         assert_eq!(*bridge_status.locked_tokens.get("P").unwrap(), 50.0);
+        assert_eq!(protocol.rollup.get_balance("user1"), 950.0);
+
+        // Each release is paid out of the escrow under its own transfer id
+        protocol.release_tokens("user1".to_string(), "P".to_string(), 50.0).unwrap();
+        assert_eq!(protocol.rollup.get_balance("user1"), 1000.0);
+        assert_eq!(protocol.rollup.get_balance(BRIDGE_ESCROW_ADDRESS), 0.0);
+        assert_eq!(protocol.bridge_state.locked_tokens.get("P"), Some(&0.0));
+
+        // Nothing is left to release
+        let result = protocol.release_tokens("user1".to_string(), "P".to_string(), 50.0);
+        assert_eq!(result, Err(RollupError::InsufficientBalance));
+        assert_eq!(protocol.rollup.get_balance("user1"), 1000.0);
+    }
+
+    #[test]
+    fn test_release_only_to_the_locking_user() {
+        let config = RollupConfig {
+            chain_id: "test-chain".to_string(),
+            operator_address: "operator1".to_string(),
+            batch_submission_interval: 300,
+            max_batch_size: 100,
+            gas_price: 0.001,
+        };
+
+        let mut rollup = L2Rollup::new(config);
+        rollup.initialize_account("user1".to_string(), 1000.0);
+        rollup.initialize_account("user2".to_string(), 1000.0);
+
+        let mut protocol = L2CrossChainProtocol::new(rollup, "test-chain".to_string());
+        protocol.lock_tokens("user1".to_string(), "P".to_string(), 100.0).unwrap();
+        protocol.lock_tokens("user2".to_string(), "P".to_string(), 10.0).unwrap();
+
+        // user2 can't claim user1's lock, only its own
+        let result = protocol.release_tokens("user2".to_string(), "P".to_string(), 100.0);
+        assert_eq!(result, Err(RollupError::InsufficientBalance));
+        let result = protocol.release_tokens("user3".to_string(), "P".to_string(), 1.0);
+        assert_eq!(result, Err(RollupError::InsufficientBalance));
+        protocol.release_tokens("user2".to_string(), "P".to_string(), 10.0).unwrap();
+
+        assert_eq!(protocol.rollup.get_balance("user2"), 1000.0);
+        assert_eq!(protocol.rollup.get_balance(BRIDGE_ESCROW_ADDRESS), 100.0);
+        assert_eq!(protocol.locked_by("user1", "P"), 100.0);
+        assert_eq!(protocol.bridge_state.locked_tokens.get("P"), Some(&100.0));
+    }
+
+    #[test]
+    fn test_failed_release_keeps_locked_total() {
+        let config = RollupConfig {
+            chain_id: "test-chain".to_string(),
+            operator_address: "operator1".to_string(),
+            batch_submission_interval: 300,
+            max_batch_size: 100,
+            gas_price: 0.001,
+        };
+
+        let mut rollup = L2Rollup::new(config);
+        rollup.initialize_account("user1".to_string(), 1000.0);
+
+        let mut protocol = L2CrossChainProtocol::new(rollup, "test-chain".to_string());
+        protocol.lock_tokens("user1".to_string(), "P".to_string(), 100.0).unwrap();
+
+        let result = protocol.release_tokens("user1".to_string(), "P".to_string(), -5.0);
+        assert_eq!(result, Err(RollupError::InvalidTransaction));
+        let result = protocol.release_tokens("user1".to_string(), "P".to_string(), f64::NAN);
+        assert_eq!(result, Err(RollupError::InvalidTransaction));
+
+        assert_eq!(protocol.bridge_state.locked_tokens.get("P"), Some(&100.0));
+        assert_eq!(protocol.bridge_state.releases, 0);
+        assert_eq!(protocol.rollup.get_balance("user1"), 900.0);
     }
 
     #[test]
@@ -302,26 +443,17 @@ mod tests {
 
         let rollup = L2Rollup::new(config);
         let mut protocol = L2CrossChainProtocol::new(rollup, "test-chain".to_string());
+        protocol.add_connected_chain("ethereum".to_string());
 
-        let message = CrossChainMessage {
-            message_id: "replay-test".to_string(),
-            source_chain: "ethereum".to_string(),
-            destination_chain: "test-chain".to_string(),
-            sender: "user1".to_string(),
-            recipient: "user2".to_string(),
-            amount: 10.0,
-            token: "P".to_string(),
-            payload: vec![],
-            timestamp: 1,
-            signature: "sig".to_string(),
-        };
+        let message = incoming_message("user2", 10.0);
 
         // First time should pass
         assert!(protocol.process_incoming_message(message.clone()).is_ok());
         // Second time should be rejected
         assert_eq!(
             protocol.process_incoming_message(message),
-            Err(super::super::l2_rollup::RollupError::InvalidTransaction)
+            Err(RollupError::InvalidTransaction)
         );
+        assert_eq!(protocol.rollup.get_balance("user2"), 10.0);
     }
 }
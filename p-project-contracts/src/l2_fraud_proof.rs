@@ -0,0 +1,378 @@
+use crate::l2_rollup::{apply_transfer, BatchStatus, L2Account, L2Batch, L2Rollup, RollupError};
+use crate::l2_signing::verify_transaction_signature;
+use crate::l2_state_management::{L2StateManager, SmtProof};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+
+// Dispute game configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisputeConfig {
+    pub min_operator_bond: f64,
+    pub challenger_reward_ratio: f64, // Share of the slashed bond paid to the challenger
+}
+
+impl Default for DisputeConfig {
+    fn default() -> Self {
+        Self {
+            min_operator_bond: 10_000.0,
+            challenger_reward_ratio: 0.5,
+        }
+    }
+}
+
+// Account value plus its Merkle proof against a claimed state root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountWitness {
+    pub address: String,
+    pub account: Option<L2Account>, // None when the account does not exist yet
+    pub proof: SmtProof, // Compressed; proves the empty slot when the account is absent
+}
+
+impl AccountWitness {
+    fn verify(&self, root: &str) -> bool {
+        if let Some(account) = &self.account {
+            if account.address != self.address {
+                return false;
+            }
+        }
+        L2StateManager::verify_compressed_proof_against_root(
+            root,
+            &self.address,
+            self.account.as_ref(),
+            &self.proof,
+        )
+    }
+}
+
+// Proof that a single transaction in a batch was executed incorrectly.
+// The sender witness is proven against the claimed root before the transaction; the
+// receiver witness against the root obtained after the sender update is applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FraudProof {
+    pub batch_id: String,
+    pub tx_index: usize,
+    pub sender: AccountWitness,
+    pub receiver: AccountWitness,
+}
+
+// Outcome of re-executing a disputed transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DisputeVerdict {
+    ValidTransition,
+    InvalidTransaction(String), // The operator included a transaction that cannot execute
+    StateRootMismatch { claimed: String, computed: String },
+}
+
+impl DisputeVerdict {
+    pub fn is_fraud(&self) -> bool {
+        !matches!(self, DisputeVerdict::ValidTransition)
+    }
+}
+
+// Record of a resolved challenge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeRecord {
+    pub challenge_id: String,
+    pub batch_id: String,
+    pub tx_index: usize,
+    pub challenger: String,
+    pub operator: String,
+    pub verdict: DisputeVerdict,
+    pub slashed_amount: f64,
+    pub challenger_reward: f64,
+    pub reverted_batches: Vec<String>,
+    pub timestamp: NaiveDateTime,
+}
+
+// Optimistic rollup dispute game: operator bonds, fraud proofs and slashing
+pub struct DisputeGame {
+    pub config: DisputeConfig,
+    pub bonds: HashMap<String, f64>,
+    pub challenges: Vec<ChallengeRecord>,
+    pub burned: f64, // Slashed funds not paid out to challengers
+}
+
+impl DisputeGame {
+    pub fn new(config: DisputeConfig) -> Self {
+        Self {
+            config,
+            bonds: HashMap::new(),
+            challenges: Vec::new(),
+            burned: 0.0,
+        }
+    }
+
+    /// Deposit an operator bond
+    pub fn post_bond(&mut self, operator: &str, amount: f64) -> Result<f64, RollupError> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(RollupError::InvalidTransaction);
+        }
+        let bond = self.bonds.entry(operator.to_string()).or_insert(0.0);
+        *bond += amount;
+        Ok(*bond)
+    }
+
+    /// Current bond of an operator
+    pub fn bond_of(&self, operator: &str) -> f64 {
+        self.bonds.get(operator).copied().unwrap_or(0.0)
+    }
+
+    /// Whether the operator is bonded enough to submit batches
+    pub fn is_bonded(&self, operator: &str) -> bool {
+        self.bond_of(operator) >= self.config.min_operator_bond
+    }
+
+    /// Withdraw bond; only allowed once none of the operator's batches can be challenged
+    pub fn withdraw_bond(
+        &mut self,
+        rollup: &L2Rollup,
+        operator: &str,
+        amount: f64,
+    ) -> Result<f64, RollupError> {
+        let has_open_batches = rollup
+            .batches
+            .iter()
+            .any(|b| b.operator == operator && b.status == BatchStatus::Pending);
+        if has_open_batches {
+            return Err(RollupError::InvalidTransaction);
+        }
+        let bond = self
+            .bonds
+            .get_mut(operator)
+            .ok_or(RollupError::InsufficientBond)?;
+        if amount > *bond {
+            return Err(RollupError::InsufficientBond);
+        }
+        *bond -= amount;
+        Ok(*bond)
+    }
+
+    /// Rebuild the pre-state of a transaction from the checkpoint preceding its batch
+    fn state_before_tx(
+        rollup: &L2Rollup,
+        batch: &L2Batch,
+        tx_index: usize,
+    ) -> Result<L2StateManager, RollupError> {
+        let snapshot = rollup
+            .checkpoints
+            .get_snapshot_before_batch(batch.batch_number)
+            .ok_or(RollupError::SnapshotUnavailable)?;
+        if snapshot.state_root != batch.state_root_before {
+            return Err(RollupError::StateRootMismatch);
+        }
+
+        let mut state = L2StateManager::from_accounts(&snapshot.accounts);
+        for tx in &batch.transactions[..tx_index] {
+            let (sender, receiver) =
+                apply_transfer(state.get_account(&tx.from), state.get_account(&tx.to), tx)?;
+            state.update_account(sender)?;
+            if let Some(receiver) = receiver {
+                state.update_account(receiver)?;
+            }
+        }
+        Ok(state)
+    }
+
+    /// Build a fraud proof for the transaction at `tx_index` of a batch by replaying
+    /// the batch from the checkpoint taken before it
+    pub fn build_fraud_proof(
+        rollup: &L2Rollup,
+        batch_id: &str,
+        tx_index: usize,
+    ) -> Result<FraudProof, RollupError> {
+        let batch = rollup
+            .get_batch(batch_id)
+            .ok_or(RollupError::BatchNotFound)?;
+        let tx = batch
+            .transactions
+            .get(tx_index)
+            .ok_or(RollupError::InvalidFraudProof)?;
+
+        let mut state = Self::state_before_tx(rollup, batch, tx_index)?;
+        let sender = AccountWitness {
+            address: tx.from.clone(),
+            account: state.get_account(&tx.from).cloned(),
+            proof: state
+                .generate_compressed_account_proof(&tx.from)
+                .ok_or(RollupError::MerkleTreeError)?,
+        };
+
+        // The receiver is proven against the state after the sender update
+        if let Ok((updated_sender, _)) =
+            apply_transfer(state.get_account(&tx.from), state.get_account(&tx.to), tx)
+        {
+            state.update_account(updated_sender)?;
+        }
+        let receiver = AccountWitness {
+            address: tx.to.clone(),
+            account: state.get_account(&tx.to).cloned(),
+            proof: state
+                .generate_compressed_account_proof(&tx.to)
+                .ok_or(RollupError::MerkleTreeError)?,
+        };
+
+        Ok(FraudProof {
+            batch_id: batch_id.to_string(),
+            tx_index,
+            sender,
+            receiver,
+        })
+    }
+
+    /// Find the first transaction whose claimed post-state root is wrong, if any
+    pub fn find_fraudulent_transaction(
+        rollup: &L2Rollup,
+        batch_id: &str,
+    ) -> Result<Option<usize>, RollupError> {
+        let batch = rollup
+            .get_batch(batch_id)
+            .ok_or(RollupError::BatchNotFound)?;
+        for tx_index in 0..batch.transactions.len() {
+            let proof = Self::build_fraud_proof(rollup, batch_id, tx_index)?;
+            if Self::verify_fraud_proof(batch, &proof)?.is_fraud() {
+                return Ok(Some(tx_index));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Re-execute the disputed transaction against the witnesses and compare the
+    /// resulting state root with the one claimed by the operator
+    pub fn verify_fraud_proof(
+        batch: &L2Batch,
+        proof: &FraudProof,
+    ) -> Result<DisputeVerdict, RollupError> {
+        if proof.batch_id != batch.batch_id {
+            return Err(RollupError::InvalidFraudProof);
+        }
+        let tx = batch
+            .transactions
+            .get(proof.tx_index)
+            .ok_or(RollupError::InvalidFraudProof)?;
+        let root_before = batch
+            .claimed_root_before(proof.tx_index)
+            .ok_or(RollupError::InvalidFraudProof)?;
+        let claimed_after = batch
+            .claimed_root_after(proof.tx_index)
+            .ok_or(RollupError::InvalidFraudProof)?;
+
+        if proof.sender.address != tx.from || proof.receiver.address != tx.to {
+            return Err(RollupError::InvalidFraudProof);
+        }
+        // A transaction the sender never signed is fraud regardless of the state
+        if let Err(e) = verify_transaction_signature(&batch.chain_id, tx) {
+            return Ok(DisputeVerdict::InvalidTransaction(e.to_string()));
+        }
+        if !proof.sender.verify(root_before) {
+            return Err(RollupError::InvalidFraudProof);
+        }
+
+        // Whether the transaction can execute depends on the sender alone, so it is
+        // first run without the receiver witness, which is not yet proven
+        let (new_sender, sole_receiver) =
+            match apply_transfer(proof.sender.account.as_ref(), None, tx) {
+                Ok(accounts) => accounts,
+                Err(e) => return Ok(DisputeVerdict::InvalidTransaction(e.to_string())),
+            };
+
+        let mid_root =
+            L2StateManager::root_with_account(&tx.from, &new_sender, &proof.sender.proof)
+                .ok_or(RollupError::InvalidFraudProof)?;
+
+        let computed = match sole_receiver {
+            // Withdrawals burn the amount and only touch the sender leaf
+            None => mid_root,
+            // Self-transfers touch a single leaf
+            Some(new_receiver) if tx.from == tx.to => {
+                L2StateManager::root_with_account(&tx.to, &new_receiver, &proof.sender.proof)
+                    .ok_or(RollupError::InvalidFraudProof)?
+            }
+            Some(_) => {
+                if !proof.receiver.verify(&mid_root) {
+                    return Err(RollupError::InvalidFraudProof);
+                }
+                let new_receiver = apply_transfer(
+                    proof.sender.account.as_ref(),
+                    proof.receiver.account.as_ref(),
+                    tx,
+                )
+                .ok()
+                .and_then(|(_, receiver)| receiver)
+                .ok_or(RollupError::InvalidFraudProof)?;
+                L2StateManager::root_with_account(&tx.to, &new_receiver, &proof.receiver.proof)
+                    .ok_or(RollupError::InvalidFraudProof)?
+            }
+        };
+
+        if computed == claimed_after {
+            Ok(DisputeVerdict::ValidTransition)
+        } else {
+            Ok(DisputeVerdict::StateRootMismatch {
+                claimed: claimed_after.to_string(),
+                computed,
+            })
+        }
+    }
+
+    /// Challenge a batch. A successful fraud proof slashes the operator bond, pays the
+    /// challenger and rolls the rollup back to the checkpoint before the batch.
+    pub fn challenge(
+        &mut self,
+        rollup: &mut L2Rollup,
+        challenger: &str,
+        proof: FraudProof,
+        now: NaiveDateTime,
+    ) -> Result<ChallengeRecord, RollupError> {
+        let batch = rollup
+            .get_batch(&proof.batch_id)
+            .cloned()
+            .ok_or(RollupError::BatchNotFound)?;
+        if !batch.is_challengeable(now) {
+            return Err(RollupError::ChallengeWindowClosed);
+        }
+
+        let verdict = Self::verify_fraud_proof(&batch, &proof)?;
+        if !verdict.is_fraud() {
+            return Err(RollupError::InvalidFraudProof);
+        }
+
+        let reverted_batches = rollup.rollback_to_checkpoint(batch.batch_number)?;
+
+        let slashed_amount = self
+            .bonds
+            .insert(batch.operator.clone(), 0.0)
+            .unwrap_or(0.0);
+        let challenger_reward = slashed_amount * self.config.challenger_reward_ratio;
+        self.burned += slashed_amount - challenger_reward;
+
+        let mut hasher = Keccak256::new();
+        hasher.update(batch.batch_id.as_bytes());
+        hasher.update(proof.tx_index.to_le_bytes());
+        hasher.update(challenger.as_bytes());
+        hasher.update(self.challenges.len().to_le_bytes());
+        let challenge_id = format!("{:x}", hasher.finalize());
+
+        let record = ChallengeRecord {
+            challenge_id,
+            batch_id: batch.batch_id.clone(),
+            tx_index: proof.tx_index,
+            challenger: challenger.to_string(),
+            operator: batch.operator.clone(),
+            verdict,
+            slashed_amount,
+            challenger_reward,
+            reverted_batches,
+            timestamp: now,
+        };
+        self.challenges.push(record.clone());
+        Ok(record)
+    }
+}
+
+impl Default for DisputeGame {
+    fn default() -> Self {
+        Self::new(DisputeConfig::default())
+    }
+}
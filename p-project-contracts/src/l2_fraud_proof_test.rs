@@ -0,0 +1,294 @@
+#[cfg(test)]
+mod tests {
+    use super::super::l2_fraud_proof::{DisputeConfig, DisputeGame, DisputeVerdict};
+    use super::super::l2_rollup::{BatchStatus, L2Rollup, RollupError};
+    use super::super::l2_test_support::{addr, rollup, signed_transfer};
+    use chrono::{Duration, Utc};
+
+    fn game() -> DisputeGame {
+        let mut game = DisputeGame::new(DisputeConfig {
+            min_operator_bond: 1000.0,
+            challenger_reward_ratio: 0.5,
+        });
+        game.post_bond("operator1", 2000.0).unwrap();
+        game
+    }
+
+    fn submit_sample_batch(rollup: &mut L2Rollup) -> String {
+        rollup
+            .add_transaction(signed_transfer("alice", "bob", 100.0, 0))
+            .unwrap();
+        rollup
+            .add_transaction(signed_transfer("bob", "carol", 50.0, 0))
+            .unwrap();
+        rollup
+            .add_transaction(signed_transfer("alice", "alice", 10.0, 1))
+            .unwrap();
+        rollup.submit_batch().unwrap().batch_id
+    }
+
+    #[test]
+    fn test_batch_records_window_and_intermediate_roots() {
+        let mut rollup = rollup();
+        let batch_id = submit_sample_batch(&mut rollup);
+        let batch = rollup.get_batch(&batch_id).unwrap();
+
+        assert_eq!(batch.status, BatchStatus::Pending);
+        assert_eq!(batch.intermediate_state_roots.len(), 3);
+        assert_eq!(batch.intermediate_state_roots[2], batch.state_root_after);
+        assert!(!batch.operator_signature.is_empty());
+        assert!(L2Rollup::verify_batch_signature(batch));
+        assert!(batch.challenge_deadline > batch.timestamp);
+    }
+
+    #[test]
+    fn test_honest_batch_cannot_be_challenged() {
+        let mut rollup = rollup();
+        let batch_id = submit_sample_batch(&mut rollup);
+        let mut game = game();
+
+        assert_eq!(
+            DisputeGame::find_fraudulent_transaction(&rollup, &batch_id).unwrap(),
+            None
+        );
+        for tx_index in 0..3 {
+            let proof = DisputeGame::build_fraud_proof(&rollup, &batch_id, tx_index).unwrap();
+            let batch = rollup.get_batch(&batch_id).unwrap();
+            assert_eq!(
+                DisputeGame::verify_fraud_proof(batch, &proof).unwrap(),
+                DisputeVerdict::ValidTransition
+            );
+            let result = game.challenge(&mut rollup, "watcher", proof, Utc::now().naive_utc());
+            assert_eq!(result.unwrap_err(), RollupError::InvalidFraudProof);
+        }
+        assert_eq!(game.bond_of("operator1"), 2000.0);
+    }
+
+    #[test]
+    fn test_fraudulent_root_is_slashed_and_rolled_back() {
+        let mut rollup = rollup();
+        let first_batch = submit_sample_batch(&mut rollup);
+        let balances_after_first = (
+            rollup.get_balance(&addr("alice")),
+            rollup.get_balance(&addr("bob")),
+        );
+
+        rollup
+            .add_transaction(signed_transfer("alice", "dave", 200.0, 2))
+            .unwrap();
+        rollup
+            .add_transaction(signed_transfer("bob", "dave", 20.0, 1))
+            .unwrap();
+        let batch_id = rollup.submit_batch().unwrap().batch_id;
+
+        // A malicious operator claims dave received far more on the second transfer
+        let forged = "11".repeat(32);
+        {
+            let batch = rollup
+                .batches
+                .iter_mut()
+                .find(|b| b.batch_id == batch_id)
+                .unwrap();
+            batch.intermediate_state_roots[1] = forged.clone();
+            batch.state_root_after = forged.clone();
+        }
+
+        let mut game = game();
+        let tx_index = DisputeGame::find_fraudulent_transaction(&rollup, &batch_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx_index, 1);
+
+        let proof = DisputeGame::build_fraud_proof(&rollup, &batch_id, tx_index).unwrap();
+        let record = game
+            .challenge(&mut rollup, "watcher", proof, Utc::now().naive_utc())
+            .unwrap();
+
+        assert!(matches!(
+            record.verdict,
+            DisputeVerdict::StateRootMismatch { ref claimed, .. } if *claimed == forged
+        ));
+        assert_eq!(record.slashed_amount, 2000.0);
+        assert_eq!(record.challenger_reward, 1000.0);
+        assert_eq!(record.reverted_batches, vec![batch_id.clone()]);
+        assert_eq!(game.bond_of("operator1"), 0.0);
+        assert_eq!(game.burned, 1000.0);
+
+        // State is back at the checkpoint taken before the disputed batch
+        assert_eq!(rollup.get_balance(&addr("alice")), balances_after_first.0);
+        assert_eq!(rollup.get_balance(&addr("bob")), balances_after_first.1);
+        assert_eq!(rollup.get_balance(&addr("dave")), 0.0);
+        assert_eq!(
+            rollup.get_state_root(),
+            rollup.get_batch(&first_batch).unwrap().state_root_after
+        );
+        assert_eq!(
+            rollup.get_batch(&batch_id).unwrap().status,
+            BatchStatus::Reverted
+        );
+        assert_eq!(
+            rollup.get_batch(&first_batch).unwrap().status,
+            BatchStatus::Pending
+        );
+    }
+
+    #[test]
+    fn test_invalid_transaction_in_batch_is_fraud() {
+        let mut rollup = rollup();
+        let batch_id = submit_sample_batch(&mut rollup);
+
+        // Operator sneaks in an overdraft the state could never have executed
+        {
+            let batch = rollup
+                .batches
+                .iter_mut()
+                .find(|b| b.batch_id == batch_id)
+                .unwrap();
+            batch.transactions[1].amount = 10_000.0;
+        }
+
+        let proof = DisputeGame::build_fraud_proof(&rollup, &batch_id, 1).unwrap();
+        let batch = rollup.get_batch(&batch_id).unwrap();
+        assert!(matches!(
+            DisputeGame::verify_fraud_proof(batch, &proof).unwrap(),
+            DisputeVerdict::InvalidTransaction(_)
+        ));
+        assert!(!L2Rollup::verify_batch_signature(batch));
+
+        let mut game = game();
+        let record = game
+            .challenge(&mut rollup, "watcher", proof, Utc::now().naive_utc())
+            .unwrap();
+        assert!(record.verdict.is_fraud());
+        assert_eq!(rollup.get_balance(&addr("alice")), 1000.0);
+        assert_eq!(rollup.get_balance(&addr("carol")), 0.0);
+    }
+
+    #[test]
+    fn test_tampered_witness_is_rejected() {
+        let mut rollup = rollup();
+        let batch_id = submit_sample_batch(&mut rollup);
+
+        let mut proof = DisputeGame::build_fraud_proof(&rollup, &batch_id, 0).unwrap();
+        proof.sender.account.as_mut().unwrap().balance = 1.0;
+
+        let mut game = game();
+        let result = game.challenge(&mut rollup, "watcher", proof, Utc::now().naive_utc());
+        assert_eq!(result.unwrap_err(), RollupError::InvalidFraudProof);
+    }
+
+    #[test]
+    fn test_tampered_receiver_witness_is_not_fraud() {
+        let mut rollup = rollup();
+        let batch_id = submit_sample_batch(&mut rollup);
+        let mut game = game();
+
+        let honest = DisputeGame::build_fraud_proof(&rollup, &batch_id, 0).unwrap();
+        let mut wrong_address = honest.clone();
+        wrong_address.receiver.account.as_mut().unwrap().address = addr("mallory");
+        let mut wrong_balance = honest;
+        wrong_balance.receiver.account.as_mut().unwrap().balance = 1.0;
+
+        for proof in [wrong_address, wrong_balance] {
+            let batch = rollup.get_batch(&batch_id).unwrap();
+            assert_eq!(
+                DisputeGame::verify_fraud_proof(batch, &proof).unwrap_err(),
+                RollupError::InvalidFraudProof
+            );
+            let result = game.challenge(&mut rollup, "watcher", proof, Utc::now().naive_utc());
+            assert_eq!(result.unwrap_err(), RollupError::InvalidFraudProof);
+        }
+        assert_eq!(game.bond_of("operator1"), 2000.0);
+        assert_eq!(rollup.get_balance(&addr("bob")), 550.0);
+    }
+
+    #[test]
+    fn test_challenge_window_and_finalization() {
+        let mut rollup = rollup();
+        rollup.set_challenge_period(60);
+        let batch_id = submit_sample_batch(&mut rollup);
+        {
+            let batch = rollup
+                .batches
+                .iter_mut()
+                .find(|b| b.batch_id == batch_id)
+                .unwrap();
+            batch.intermediate_state_roots[0] = "22".repeat(32);
+        }
+
+        let mut game = game();
+        assert_eq!(
+            game.withdraw_bond(&rollup, "operator1", 100.0).unwrap_err(),
+            RollupError::InvalidTransaction
+        );
+
+        let later = Utc::now().naive_utc() + Duration::seconds(120);
+        let proof = DisputeGame::build_fraud_proof(&rollup, &batch_id, 0).unwrap();
+        let result = game.challenge(&mut rollup, "watcher", proof, later);
+        assert_eq!(result.unwrap_err(), RollupError::ChallengeWindowClosed);
+
+        assert_eq!(rollup.finalize_batches(later), vec![batch_id.clone()]);
+        assert_eq!(
+            rollup.get_batch(&batch_id).unwrap().status,
+            BatchStatus::Finalized
+        );
+        assert_eq!(
+            game.withdraw_bond(&rollup, "operator1", 500.0).unwrap(),
+            1500.0
+        );
+        assert!(game.is_bonded("operator1"));
+    }
+    #[test]
+    fn test_every_pending_batch_keeps_its_checkpoint() {
+        let mut rollup = rollup();
+        // Far more pending batches than a fixed-size checkpoint ring would hold
+        for nonce in 0..70 {
+            rollup
+                .add_transaction(signed_transfer("alice", "bob", 1.0, nonce))
+                .unwrap();
+            rollup.submit_batch().unwrap();
+        }
+        let first = rollup.batches[0].batch_id.clone();
+        rollup.batches[0].intermediate_state_roots[0] = "33".repeat(32);
+
+        let proof = DisputeGame::build_fraud_proof(&rollup, &first, 0).unwrap();
+        let record = game()
+            .challenge(&mut rollup, "watcher", proof, Utc::now().naive_utc())
+            .unwrap();
+        assert_eq!(record.reverted_batches.len(), 70);
+        assert_eq!(rollup.get_balance(&addr("alice")), 1000.0);
+    }
+
+    #[test]
+    fn test_finalization_prunes_unneeded_checkpoints() {
+        let mut rollup = rollup();
+        rollup.set_challenge_period(60);
+        for nonce in 0..3 {
+            rollup
+                .add_transaction(signed_transfer("alice", "bob", 1.0, nonce))
+                .unwrap();
+            rollup.submit_batch().unwrap();
+        }
+        rollup.set_challenge_period(7 * 24 * 60 * 60);
+        rollup
+            .add_transaction(signed_transfer("alice", "bob", 1.0, 3))
+            .unwrap();
+        let pending = rollup.submit_batch().unwrap().batch_id;
+
+        let later = Utc::now().naive_utc() + Duration::seconds(120);
+        assert_eq!(rollup.finalize_batches(later).len(), 3);
+        assert!(rollup
+            .checkpoints
+            .snapshots
+            .iter()
+            .all(|s| s.batch_number >= 3));
+
+        // The pending batch can still be disputed and the last final state proven
+        assert!(DisputeGame::build_fraud_proof(&rollup, &pending, 0).is_ok());
+        assert!(rollup.account_proof_at_batch(3, &addr("bob")).is_ok());
+        assert_eq!(
+            rollup.account_proof_at_batch(2, &addr("bob")).unwrap_err(),
+            RollupError::SnapshotUnavailable
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use crate::l2_rollup::{check_nonce, L2Transaction, RollupError};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+// Mempool configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolConfig {
+    pub max_size: usize,           // Total transactions held across all senders
+    pub max_per_account: usize,    // Queued transactions per sender
+    pub tx_ttl_secs: i64,          // Transactions older than this are dropped
+    pub min_fee: f64,              // Fee floor for admission
+    pub replacement_fee_bump: f64, // Relative fee increase required to replace a transaction
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10_000,
+            max_per_account: 64,
+            tx_ttl_secs: 3 * 60 * 60,
+            min_fee: 0.0,
+            replacement_fee_bump: 0.1,
+        }
+    }
+}
+
+// Transaction held in the mempool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PooledTransaction {
+    pub transaction: L2Transaction,
+    pub hash: String,
+    pub received_at: NaiveDateTime,
+    pub sequence: u64, // Arrival order, used to break fee ties
+}
+
+// Result of a successful insert
+#[derive(Debug, Clone, PartialEq)]
+pub enum MempoolInsert {
+    Added,
+    Replaced(L2Transaction), // Same sender and nonce, paying a higher fee
+    AddedWithEviction(L2Transaction), // A cheaper transaction was evicted to make room
+}
+
+// Head of a sender queue, ordered by fee then by arrival
+struct Candidate {
+    fee: f64,
+    sequence: u64,
+    sender: String,
+    nonce: u64,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.fee
+            .total_cmp(&other.fee)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+// Sequencer mempool: per-sender nonce-ordered queues, selected by fee priority
+pub struct Mempool {
+    pub config: MempoolConfig,
+    queues: HashMap<String, BTreeMap<u64, PooledTransaction>>,
+    hashes: HashMap<String, (String, u64)>, // tx hash -> (sender, nonce)
+    next_sequence: u64,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            queues: HashMap::new(),
+            hashes: HashMap::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// Number of queued transactions
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Whether a transaction with this hash is queued
+    pub fn contains(&self, tx_hash: &str) -> bool {
+        self.hashes.contains_key(tx_hash)
+    }
+
+    /// Number of transactions queued for a sender
+    pub fn pending_count(&self, sender: &str) -> usize {
+        self.queues.get(sender).map(|q| q.len()).unwrap_or(0)
+    }
+
+    /// Next nonce for a sender given its account nonce, counting queued transactions
+    pub fn next_nonce(&self, sender: &str, account_nonce: u64) -> u64 {
+        account_nonce + self.pending_count(sender) as u64
+    }
+
+    /// Queued transactions of a sender in nonce order
+    pub fn pending_for(&self, sender: &str) -> Vec<&L2Transaction> {
+        self.queues
+            .get(sender)
+            .map(|q| q.values().map(|p| &p.transaction).collect())
+            .unwrap_or_default()
+    }
+
+    /// Admit a transaction. `account_nonce` is the sender's current on-chain nonce;
+    /// the transaction must either continue the sender's queue without a gap or
+    /// replace a queued transaction with the same nonce at a sufficiently higher fee.
+    pub fn insert(
+        &mut self,
+        transaction: L2Transaction,
+        account_nonce: u64,
+        now: NaiveDateTime,
+    ) -> Result<MempoolInsert, RollupError> {
+        if !transaction.fee.is_finite() || transaction.fee < self.config.min_fee {
+            return Err(RollupError::FeeTooLow);
+        }
+        let hash = transaction.hash();
+        if self.hashes.contains_key(&hash) {
+            return Err(RollupError::DuplicateTransaction);
+        }
+
+        let sender = transaction.from.clone();
+        let nonce = transaction.nonce;
+
+        // Replacement of a queued transaction
+        let existing_fee = self
+            .queues
+            .get(&sender)
+            .and_then(|q| q.get(&nonce))
+            .map(|p| p.transaction.fee);
+        if let Some(existing_fee) = existing_fee {
+            let required = existing_fee * (1.0 + self.config.replacement_fee_bump);
+            if transaction.fee <= existing_fee || transaction.fee < required {
+                return Err(RollupError::ReplacementUnderpriced);
+            }
+            if let Some(replaced) = self.remove_entry(&sender, nonce) {
+                self.push(transaction, hash, now);
+                return Ok(MempoolInsert::Replaced(replaced.transaction));
+            }
+        }
+
+        check_nonce(self.next_nonce(&sender, account_nonce), nonce)?;
+        if self.pending_count(&sender) >= self.config.max_per_account {
+            return Err(RollupError::AccountLimitReached);
+        }
+
+        let mut evicted = None;
+        if self.len() >= self.config.max_size {
+            // Only the last transaction of another sender can go without opening a nonce gap
+            let (victim_sender, victim_nonce, victim_fee) = self
+                .queues
+                .iter()
+                .filter(|(s, _)| **s != sender)
+                .filter_map(|(s, q)| {
+                    q.iter()
+                        .next_back()
+                        .map(|(n, p)| (s.clone(), *n, p.transaction.fee, p.sequence))
+                })
+                .min_by(|a, b| a.2.total_cmp(&b.2).then_with(|| b.3.cmp(&a.3)))
+                .map(|(s, n, fee, _)| (s, n, fee))
+                .ok_or(RollupError::MempoolFull)?;
+            if transaction.fee <= victim_fee {
+                return Err(RollupError::MempoolFull);
+            }
+            evicted = self
+                .remove_entry(&victim_sender, victim_nonce)
+                .map(|p| p.transaction);
+        }
+
+        self.push(transaction, hash, now);
+        Ok(match evicted {
+            Some(tx) => MempoolInsert::AddedWithEviction(tx),
+            None => MempoolInsert::Added,
+        })
+    }
+
+    fn push(&mut self, transaction: L2Transaction, hash: String, now: NaiveDateTime) {
+        let sender = transaction.from.clone();
+        let nonce = transaction.nonce;
+        self.hashes.insert(hash.clone(), (sender.clone(), nonce));
+        self.queues.entry(sender).or_default().insert(
+            nonce,
+            PooledTransaction {
+                transaction,
+                hash,
+                received_at: now,
+                sequence: self.next_sequence,
+            },
+        );
+        self.next_sequence += 1;
+    }
+
+    fn remove_entry(&mut self, sender: &str, nonce: u64) -> Option<PooledTransaction> {
+        let queue = self.queues.get_mut(sender)?;
+        let pooled = queue.remove(&nonce)?;
+        if queue.is_empty() {
+            self.queues.remove(sender);
+        }
+        self.hashes.remove(&pooled.hash);
+        Some(pooled)
+    }
+
+    /// Drop transactions from `nonce` onwards for a sender
+    fn truncate_sender(&mut self, sender: &str, nonce: u64) -> Vec<L2Transaction> {
+        let nonces: Vec<u64> = self
+            .queues
+            .get(sender)
+            .map(|q| q.range(nonce..).map(|(n, _)| *n).collect())
+            .unwrap_or_default();
+        nonces
+            .into_iter()
+            .filter_map(|n| self.remove_entry(sender, n).map(|p| p.transaction))
+            .collect()
+    }
+
+    /// Remove a transaction by hash. Later transactions of the same sender are
+    /// removed too, since they could no longer execute.
+    pub fn remove(&mut self, tx_hash: &str) -> Vec<L2Transaction> {
+        match self.hashes.get(tx_hash).cloned() {
+            Some((sender, nonce)) => self.truncate_sender(&sender, nonce),
+            None => Vec::new(),
+        }
+    }
+
+    /// Drop transactions older than the configured TTL (and the ones queued behind them)
+    pub fn expire(&mut self, now: NaiveDateTime) -> Vec<L2Transaction> {
+        let cutoff = now - Duration::seconds(self.config.tx_ttl_secs);
+        let mut stale: Vec<(String, u64)> = self
+            .queues
+            .iter()
+            .filter_map(|(sender, queue)| {
+                queue
+                    .iter()
+                    .find(|(_, p)| p.received_at < cutoff)
+                    .map(|(nonce, _)| (sender.clone(), *nonce))
+            })
+            .collect();
+        stale.sort();
+        stale
+            .into_iter()
+            .flat_map(|(sender, nonce)| self.truncate_sender(&sender, nonce))
+            .collect()
+    }
+
+    /// Transactions that have not expired at `now`
+    pub fn live_count(&self, now: NaiveDateTime) -> usize {
+        let cutoff = now - Duration::seconds(self.config.tx_ttl_secs);
+        self.queues
+            .values()
+            .flat_map(|q| q.values())
+            .filter(|p| p.received_at >= cutoff)
+            .count()
+    }
+
+    /// Re-align queues with account nonces after execution: already used nonces are
+    /// dropped, and a queue that no longer starts at the account nonce is discarded.
+    pub fn prune<F: Fn(&str) -> u64>(&mut self, account_nonce: F) -> Vec<L2Transaction> {
+        let mut senders: Vec<String> = self.queues.keys().cloned().collect();
+        senders.sort();
+        let mut dropped = Vec::new();
+        for sender in senders {
+            let nonce = account_nonce(&sender);
+            let used: Vec<u64> = self.queues[&sender]
+                .range(..nonce)
+                .map(|(n, _)| *n)
+                .collect();
+            for n in used {
+                dropped.extend(self.remove_entry(&sender, n).map(|p| p.transaction));
+            }
+            let first = self
+                .queues
+                .get(&sender)
+                .and_then(|q| q.keys().next().copied());
+            if first.is_some_and(|first| first != nonce) {
+                dropped.extend(self.truncate_sender(&sender, 0));
+            }
+        }
+        dropped
+    }
+
+    /// Best transactions in execution order without removing them: repeatedly take the
+    /// highest fee among the sender queue heads, so each sender stays in nonce order.
+    /// Ties go to the transaction that arrived first, which keeps selection deterministic.
+    pub fn select(&self, max: usize) -> Vec<&L2Transaction> {
+        let mut heap: BinaryHeap<Candidate> = self
+            .queues
+            .iter()
+            .filter_map(|(sender, queue)| {
+                queue.iter().next().map(|(nonce, p)| Candidate {
+                    fee: p.transaction.fee,
+                    sequence: p.sequence,
+                    sender: sender.clone(),
+                    nonce: *nonce,
+                })
+            })
+            .collect();
+
+        let mut selected = Vec::with_capacity(max.min(self.len()));
+        while selected.len() < max {
+            let Some(best) = heap.pop() else {
+                break;
+            };
+            let queue = &self.queues[&best.sender];
+            selected.push(&queue[&best.nonce].transaction);
+            if let Some((nonce, p)) = queue.range(best.nonce + 1..).next() {
+                heap.push(Candidate {
+                    fee: p.transaction.fee,
+                    sequence: p.sequence,
+                    sender: best.sender,
+                    nonce: *nonce,
+                });
+            }
+        }
+        selected
+    }
+
+    /// Remove and return the best `max` transactions in execution order
+    pub fn drain_best(&mut self, max: usize) -> Vec<L2Transaction> {
+        let picked: Vec<(String, u64)> = self
+            .select(max)
+            .into_iter()
+            .map(|tx| (tx.from.clone(), tx.nonce))
+            .collect();
+        picked
+            .into_iter()
+            .filter_map(|(sender, nonce)| self.remove_entry(&sender, nonce).map(|p| p.transaction))
+            .collect()
+    }
+
+    /// Remove every queued transaction
+    pub fn clear(&mut self) {
+        self.queues.clear();
+        self.hashes.clear();
+    }
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self::new(MempoolConfig::default())
+    }
+}
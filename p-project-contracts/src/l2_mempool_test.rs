@@ -0,0 +1,279 @@
+#[cfg(test)]
+mod tests {
+    use super::super::l2_batching::{BatchAggregator, BatchConfig};
+    use super::super::l2_mempool::{Mempool, MempoolConfig, MempoolInsert};
+    use super::super::l2_rollup::{L2Rollup, L2Transaction, RollupConfig, RollupError};
+    use super::super::l2_signing::L2Keypair;
+    use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+    fn t0() -> NaiveDateTime {
+        DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc()
+    }
+
+    fn tx(from: &str, nonce: u64, fee: f64) -> L2Transaction {
+        L2Transaction {
+            from: from.to_string(),
+            to: "sink".to_string(),
+            amount: 1.0,
+            fee,
+            nonce,
+            signature: format!("sig_{}_{}_{}", from, nonce, fee),
+            timestamp: t0(),
+        }
+    }
+
+    fn config(max_size: usize, max_per_account: usize) -> MempoolConfig {
+        MempoolConfig {
+            max_size,
+            max_per_account,
+            tx_ttl_secs: 60,
+            min_fee: 0.0,
+            replacement_fee_bump: 0.1,
+        }
+    }
+
+    fn order(selected: &[&L2Transaction]) -> Vec<(String, u64)> {
+        selected.iter().map(|t| (t.from.clone(), t.nonce)).collect()
+    }
+
+    #[test]
+    fn test_fee_priority_respects_sender_nonce_order() {
+        let mut pool = Mempool::new(config(100, 10));
+        pool.insert(tx("alice", 0, 1.0), 0, t0()).unwrap();
+        pool.insert(tx("alice", 1, 9.0), 0, t0()).unwrap();
+        pool.insert(tx("bob", 0, 5.0), 0, t0()).unwrap();
+        pool.insert(tx("carol", 0, 5.0), 0, t0()).unwrap();
+
+        // alice/1 pays the most but must wait for alice/0; equal fees keep arrival order
+        assert_eq!(
+            order(&pool.select(10)),
+            vec![
+                ("bob".to_string(), 0),
+                ("carol".to_string(), 0),
+                ("alice".to_string(), 0),
+                ("alice".to_string(), 1),
+            ]
+        );
+        assert_eq!(pool.select(2).len(), 2);
+
+        let drained = pool.drain_best(3);
+        assert_eq!(drained.len(), 3);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.pending_for("alice")[0].nonce, 1);
+    }
+
+    #[test]
+    fn test_nonce_rules_and_duplicates() {
+        let mut pool = Mempool::new(config(100, 10));
+        assert_eq!(
+            pool.insert(tx("alice", 4, 1.0), 3, t0()),
+            Err(RollupError::NonceGap {
+                expected: 3,
+                actual: 4
+            })
+        );
+        assert_eq!(
+            pool.insert(tx("alice", 2, 1.0), 3, t0()),
+            Err(RollupError::NonceTooLow {
+                expected: 3,
+                actual: 2
+            })
+        );
+        pool.insert(tx("alice", 3, 1.0), 3, t0()).unwrap();
+        assert_eq!(
+            pool.insert(tx("alice", 3, 1.0), 3, t0()),
+            Err(RollupError::DuplicateTransaction)
+        );
+        assert_eq!(pool.next_nonce("alice", 3), 4);
+        assert_eq!(
+            pool.insert(tx("alice", 0, -1.0), 0, t0()),
+            Err(RollupError::FeeTooLow)
+        );
+    }
+
+    #[test]
+    fn test_replacement_requires_fee_bump() {
+        let mut pool = Mempool::new(config(100, 10));
+        pool.insert(tx("alice", 0, 1.0), 0, t0()).unwrap();
+        pool.insert(tx("alice", 1, 1.0), 0, t0()).unwrap();
+
+        assert_eq!(
+            pool.insert(tx("alice", 0, 1.05), 0, t0()),
+            Err(RollupError::ReplacementUnderpriced)
+        );
+        let replacement = tx("alice", 0, 1.5);
+        assert_eq!(
+            pool.insert(replacement.clone(), 0, t0()),
+            Ok(MempoolInsert::Replaced(tx("alice", 0, 1.0)))
+        );
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.pending_for("alice")[0], &replacement);
+    }
+
+    #[test]
+    fn test_per_account_cap_and_eviction() {
+        let mut pool = Mempool::new(config(3, 2));
+        pool.insert(tx("alice", 0, 1.0), 0, t0()).unwrap();
+        pool.insert(tx("alice", 1, 2.0), 0, t0()).unwrap();
+        assert_eq!(
+            pool.insert(tx("alice", 2, 3.0), 0, t0()),
+            Err(RollupError::AccountLimitReached)
+        );
+
+        pool.insert(tx("bob", 0, 0.5), 0, t0()).unwrap();
+        // Full: a cheaper transaction is rejected
+        assert_eq!(
+            pool.insert(tx("carol", 0, 0.5), 0, t0()),
+            Err(RollupError::MempoolFull)
+        );
+        // A better paying one evicts the cheapest queue tail
+        assert_eq!(
+            pool.insert(tx("carol", 0, 4.0), 0, t0()),
+            Ok(MempoolInsert::AddedWithEviction(tx("bob", 0, 0.5)))
+        );
+        // alice/0 is the cheapest, but evicting it would strand alice/1
+        assert_eq!(
+            pool.insert(tx("dave", 0, 1.5), 0, t0()),
+            Err(RollupError::MempoolFull)
+        );
+        assert_eq!(
+            pool.insert(tx("dave", 0, 2.5), 0, t0()),
+            Ok(MempoolInsert::AddedWithEviction(tx("alice", 1, 2.0)))
+        );
+        assert_eq!(pool.len(), 3);
+    }
+
+    #[test]
+    fn test_expiry_drops_dependent_transactions() {
+        let mut pool = Mempool::new(config(100, 10));
+        pool.insert(tx("alice", 0, 1.0), 0, t0()).unwrap();
+        pool.insert(tx("bob", 0, 1.0), 0, t0() + Duration::seconds(50))
+            .unwrap();
+        pool.insert(tx("alice", 1, 1.0), 0, t0() + Duration::seconds(50))
+            .unwrap();
+
+        let now = t0() + Duration::seconds(90);
+        assert_eq!(pool.live_count(now), 2);
+        let expired = pool.expire(now);
+        assert_eq!(expired.len(), 2);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.pending_count("bob"), 1);
+    }
+
+    #[test]
+    fn test_prune_realigns_with_account_nonces() {
+        let mut pool = Mempool::new(config(100, 10));
+        for nonce in 0..3 {
+            pool.insert(tx("alice", nonce, 1.0), 0, t0()).unwrap();
+        }
+        pool.insert(tx("bob", 5, 1.0), 5, t0()).unwrap();
+
+        // alice/0 executed elsewhere; bob's account moved past a gap
+        let dropped = pool.prune(|sender| if sender == "alice" { 1 } else { 4 });
+        assert_eq!(dropped.len(), 2);
+        assert_eq!(pool.pending_for("alice").len(), 2);
+        assert_eq!(pool.pending_count("bob"), 0);
+
+        let first_hash = pool.pending_for("alice")[0].hash();
+        let removed = pool.remove(&first_hash);
+        assert_eq!(removed.len(), 2);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_rollup_builds_blocks_by_fee() {
+        let config = RollupConfig {
+            chain_id: "test-chain".to_string(),
+            operator_address: "operator1".to_string(),
+            batch_submission_interval: 300,
+            max_batch_size: 2,
+            gas_price: 0.001,
+        };
+        let mut rollup = L2Rollup::new(config);
+        let alice = L2Keypair::from_seed("alice");
+        let bob = L2Keypair::from_seed("bob");
+        rollup.initialize_account(alice.address().to_string(), 100.0);
+        rollup.initialize_account(bob.address().to_string(), 100.0);
+
+        let now = Utc::now().naive_utc();
+        let cheap = alice
+            .signed_transfer_with_fee("test-chain", "sink", 10.0, 0.1, 0, now)
+            .unwrap();
+        let rich = bob
+            .signed_transfer_with_fee("test-chain", "sink", 10.0, 2.0, 0, now)
+            .unwrap();
+        let follow_up = bob
+            .signed_transfer_with_fee("test-chain", "sink", 10.0, 1.0, 1, now)
+            .unwrap();
+        rollup.add_transaction(cheap.clone()).unwrap();
+        rollup.add_transaction(rich.clone()).unwrap();
+        rollup.add_transaction(follow_up.clone()).unwrap();
+
+        // The fee must be covered on top of the amount
+        let too_expensive = alice
+            .signed_transfer_with_fee("test-chain", "sink", 95.0, 10.0, 1, now)
+            .unwrap();
+        assert_eq!(
+            rollup.add_transaction(too_expensive),
+            Err(RollupError::InsufficientBalance)
+        );
+
+        let block = rollup.create_block().unwrap();
+        assert_eq!(block.transactions, vec![rich, follow_up]);
+        assert_eq!(rollup.pending_transaction_count(), 1);
+        assert_eq!(rollup.get_balance(bob.address()), 77.0);
+        assert_eq!(rollup.get_balance("sink"), 20.0);
+
+        let block = rollup.create_block().unwrap();
+        assert_eq!(block.transactions, vec![cheap]);
+        assert_eq!(rollup.get_balance(alice.address()), 89.9);
+    }
+
+    #[test]
+    fn test_aggregator_pulls_from_mempool() {
+        let batch_config = BatchConfig {
+            max_batch_size: 10,
+            batch_timeout: 300,
+            gas_limit_per_batch: 1_000_000,
+            max_transactions_per_batch: 2,
+        };
+        let mut aggregator = BatchAggregator::new(batch_config);
+        let now_secs = Utc::now().timestamp() as u64;
+
+        aggregator.add_transaction(tx("alice", 0, 1.0)).unwrap();
+        assert!(!aggregator.should_create_batch(now_secs));
+        assert!(aggregator.should_create_batch(now_secs - 301));
+
+        aggregator.add_transaction(tx("bob", 0, 3.0)).unwrap();
+        aggregator.add_transaction(tx("alice", 1, 5.0)).unwrap();
+        assert_eq!(
+            aggregator.add_transaction(tx("bob", 2, 1.0)),
+            Err(RollupError::NonceGap {
+                expected: 1,
+                actual: 2
+            })
+        );
+        assert!(aggregator.should_create_batch(now_secs));
+
+        let batch = aggregator
+            .create_batch("before".to_string(), "after".to_string(), "op".to_string())
+            .unwrap();
+        let picked: Vec<(String, u64)> = batch
+            .transactions
+            .iter()
+            .map(|t| (t.from.clone(), t.nonce))
+            .collect();
+        assert_eq!(
+            picked,
+            vec![("bob".to_string(), 0), ("alice".to_string(), 0)]
+        );
+        assert_eq!(aggregator.pending_transaction_count(), 1);
+
+        // Nonces continue from what was batched
+        aggregator.add_transaction(tx("bob", 1, 1.0)).unwrap();
+        aggregator.set_account_nonce("alice", 2);
+        assert_eq!(aggregator.pending_transaction_count(), 1);
+    }
+}
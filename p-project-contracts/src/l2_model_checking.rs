@@ -4,7 +4,8 @@
 #[cfg(kani)]
 mod l2_model_checking {
     use super::l2_cross_chain::L2CrossChainProtocol;
-    use super::l2_rollup::{L2Rollup, L2Transaction, RollupConfig};
+    use super::l2_rollup::{L2Rollup, RollupConfig};
+    use super::l2_signing::L2Keypair;
     use chrono::Utc;
 
     // Model checking harness for rollup state consistency
@@ -58,40 +59,46 @@ mod l2_model_checking {
         // Initialize sender with sufficient balance
         let sender_balance: f64 = kani::any();
         kani::assume(sender_balance > 0.0 && sender_balance <= 1000.0);
-        rollup.initialize_account("sender".to_string(), sender_balance);
+        let sender = L2Keypair::from_seed("sender");
+        let receiver = L2Keypair::from_seed("receiver");
+        rollup.initialize_account(sender.address().to_string(), sender_balance);
 
         // Create nondeterministic transaction amount
         let tx_amount: f64 = kani::any();
         kani::assume(tx_amount > 0.0 && tx_amount <= sender_balance);
 
         // Create transaction
-        let transaction = L2Transaction {
-            from: "sender".to_string(),
-            to: "receiver".to_string(),
-            amount: tx_amount,
-            nonce: 0,
-            signature: "test_signature".to_string(),
-            timestamp: Utc::now().naive_utc(),
-        };
+        let transaction = sender
+            .signed_transfer(
+                "test-chain",
+                receiver.address(),
+                tx_amount,
+                0,
+                Utc::now().naive_utc(),
+            )
+            .unwrap();
 
         // Add transaction to rollup
         let result = rollup.add_transaction(transaction);
 
         // Verify transaction was added successfully
         assert!(result.is_ok());
-        assert_eq!(rollup.pending_transactions.len(), 1);
+        assert_eq!(rollup.pending_transaction_count(), 1);
 
         // Process the transaction by creating a block
         let block_result = rollup.create_block();
 
         // Verify block was created successfully
         assert!(block_result.is_ok());
-        assert_eq!(rollup.pending_transactions.len(), 0);
+        assert_eq!(rollup.pending_transaction_count(), 0);
         assert_eq!(rollup.blocks.len(), 1);
 
         // Verify final state consistency
-        assert_eq!(rollup.get_balance("sender"), sender_balance - tx_amount);
-        assert_eq!(rollup.get_balance("receiver"), tx_amount);
+        assert_eq!(
+            rollup.get_balance(sender.address()),
+            sender_balance - tx_amount
+        );
+        assert_eq!(rollup.get_balance(receiver.address()), tx_amount);
     }
 
     // Model checking harness for cross-chain message processing
@@ -1,7 +1,21 @@
-use chrono::{NaiveDateTime, Utc};
+use crate::l2_mempool::{Mempool, MempoolConfig};
+use crate::l2_signing::verify_transaction_signature;
+use crate::l2_state_management::{
+    account_leaf_hash, ordered_merkle_root, L2StateManager, SmtProof, SparseMerkleTree,
+    StateCheckpointManager, StateSnapshot, ACCOUNT_TREE_DEPTH,
+};
+use crate::l2_storage::{ChainSnapshot, L2Storage, RollupPersistence, StorageRecord};
+use crate::l2_withdrawal::{withdrawal_root, Withdrawal, WithdrawalProof};
+use chrono::{Duration, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+
+// Default optimistic challenge window for submitted batches (7 days)
+pub const DEFAULT_CHALLENGE_PERIOD_SECS: i64 = 7 * 24 * 60 * 60;
+
+// Transfers to this address burn the amount on L2 and exit it to L1
+pub const L2_EXIT_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
 
 // Custom error types for L2 rollup operations
 #[derive(Debug, Clone, PartialEq)]
@@ -13,6 +27,25 @@ pub enum RollupError {
     StateRootMismatch,
     SerializationError(String),
     MerkleTreeError,
+    BatchNotFound,
+    ChallengeWindowClosed,
+    InvalidFraudProof,
+    InsufficientBond,
+    SnapshotUnavailable,
+    InvalidSignature,
+    NonceTooLow { expected: u64, actual: u64 },
+    NonceGap { expected: u64, actual: u64 },
+    FeeTooLow,
+    DuplicateTransaction,
+    ReplacementUnderpriced,
+    AccountLimitReached,
+    MempoolFull,
+    InvalidWithdrawalProof,
+    WithdrawalAlreadyClaimed,
+    BatchNotFinalized,
+    ForcedExitUnavailable,
+    RollupFrozen,
+    StorageError(String),
 }
 
 impl std::fmt::Display for RollupError {
@@ -25,28 +58,125 @@ impl std::fmt::Display for RollupError {
             RollupError::StateRootMismatch => write!(f, "State root mismatch"),
             RollupError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             RollupError::MerkleTreeError => write!(f, "Merkle tree error"),
+            RollupError::BatchNotFound => write!(f, "Batch not found"),
+            RollupError::ChallengeWindowClosed => write!(f, "Challenge window closed"),
+            RollupError::InvalidFraudProof => write!(f, "Invalid fraud proof"),
+            RollupError::InsufficientBond => write!(f, "Insufficient operator bond"),
+            RollupError::SnapshotUnavailable => write!(f, "No state snapshot available"),
+            RollupError::InvalidSignature => write!(f, "Invalid transaction signature"),
+            RollupError::NonceTooLow { expected, actual } => {
+                write!(f, "Nonce too low: expected {}, got {}", expected, actual)
+            }
+            RollupError::NonceGap { expected, actual } => {
+                write!(f, "Nonce gap: expected {}, got {}", expected, actual)
+            }
+            RollupError::FeeTooLow => write!(f, "Transaction fee too low"),
+            RollupError::DuplicateTransaction => write!(f, "Transaction already queued"),
+            RollupError::ReplacementUnderpriced => write!(f, "Replacement transaction underpriced"),
+            RollupError::AccountLimitReached => {
+                write!(f, "Too many queued transactions for account")
+            }
+            RollupError::MempoolFull => write!(f, "Mempool full"),
+            RollupError::InvalidWithdrawalProof => write!(f, "Invalid withdrawal proof"),
+            RollupError::WithdrawalAlreadyClaimed => write!(f, "Withdrawal already claimed"),
+            RollupError::BatchNotFinalized => write!(f, "Batch not finalized"),
+            RollupError::ForcedExitUnavailable => {
+                write!(f, "Forced exit unavailable while the operator is live")
+            }
+            RollupError::RollupFrozen => write!(f, "Rollup frozen after missed forced exit"),
+            RollupError::StorageError(msg) => write!(f, "Storage error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for RollupError {}
 
+/// Check a transaction nonce against the next nonce expected from its sender
+pub fn check_nonce(expected: u64, actual: u64) -> Result<(), RollupError> {
+    match actual.cmp(&expected) {
+        std::cmp::Ordering::Equal => Ok(()),
+        std::cmp::Ordering::Less => Err(RollupError::NonceTooLow { expected, actual }),
+        std::cmp::Ordering::Greater => Err(RollupError::NonceGap { expected, actual }),
+    }
+}
+
 // Transaction structure for L2
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct L2Transaction {
     pub from: String,
     pub to: String,
     pub amount: f64,
+    #[serde(default)]
+    pub fee: f64, // Priority fee paid by the sender on top of the amount
     pub nonce: u64,
     pub signature: String,
     pub timestamp: NaiveDateTime,
 }
 
+impl L2Transaction {
+    /// Unique transaction hash, covering the signature
+    pub fn hash(&self) -> String {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.from.as_bytes());
+        hasher.update(self.to.as_bytes());
+        hasher.update(self.amount.to_le_bytes());
+        hasher.update(self.fee.to_le_bytes());
+        hasher.update(self.nonce.to_le_bytes());
+        hasher.update(self.signature.as_bytes());
+        hasher.update(
+            self.timestamp
+                .and_utc()
+                .timestamp_nanos_opt()
+                .unwrap_or(0)
+                .to_le_bytes(),
+        );
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Whether the transaction burns its amount to exit it to L1
+    pub fn is_withdrawal(&self) -> bool {
+        self.to == L2_EXIT_ADDRESS
+    }
+}
+
+// Result of executing a single transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionReceipt {
+    pub tx_hash: String,
+    pub from: String,
+    pub nonce: u64,
+    pub index: usize, // Position in the execution order of its block or batch
+    pub success: bool,
+    pub error: Option<String>, // Failure reason when the transaction was excluded
+    pub state_root: String,    // State root after the transaction (unchanged on failure)
+}
+
+impl TransactionReceipt {
+    /// Leaf hash of the receipt in the receipts tree
+    pub fn leaf_hash(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.tx_hash.as_bytes());
+        hasher.update((self.index as u64).to_le_bytes());
+        hasher.update([self.success as u8]);
+        hasher.update(self.error.as_deref().unwrap_or("").as_bytes());
+        hasher.update(self.state_root.as_bytes());
+        hasher.finalize().into()
+    }
+}
+
+/// Merkle root over an ordered list of receipts
+pub fn receipts_root(receipts: &[TransactionReceipt]) -> String {
+    let leaves: Vec<[u8; 32]> = receipts.iter().map(|r| r.leaf_hash()).collect();
+    hex::encode(ordered_merkle_root(&leaves))
+}
+
 // Block structure for L2
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct L2Block {
     pub block_number: u64,
-    pub transactions: Vec<L2Transaction>,
+    pub transactions: Vec<L2Transaction>, // Successfully executed transactions
+    pub receipts: Vec<TransactionReceipt>, // One receipt per attempted transaction
+    pub receipts_root: String,
     pub state_root: String,
     pub previous_block_hash: String,
     pub timestamp: NaiveDateTime,
@@ -54,13 +184,39 @@ pub struct L2Block {
 }
 
 // Account structure for L2 state
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct L2Account {
     pub address: String,
     pub balance: f64,
     pub nonce: u64,
 }
 
+// Balance change made by the bridge rather than by a signed transaction: a credit of
+// funds bridged in (no `from`), or a move between two accounts such as into and out of
+// the bridge escrow. It applies at once and is committed by the next batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemTransfer {
+    pub transfer_id: String,
+    pub from: Option<String>,
+    pub to: String,
+    pub amount: f64,
+    pub sequence: u64, // Position among all system transfers applied to the rollup
+}
+
+impl SystemTransfer {
+    /// Hash the transfer's receipt is filed under; unique per transfer id
+    pub fn hash(&self) -> String {
+        system_transfer_hash(&self.transfer_id)
+    }
+}
+
+fn system_transfer_hash(transfer_id: &str) -> String {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"system-transfer");
+    hasher.update(transfer_id.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
 // Rollup configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RollupConfig {
@@ -71,71 +227,311 @@ pub struct RollupConfig {
     pub gas_price: f64,
 }
 
-// Merkle tree node for state commitments
+// Undo record: the value an account had before it was overwritten
 #[derive(Debug, Clone)]
-pub struct MerkleNode {
-    pub hash: String,
-    pub left: Option<Box<MerkleNode>>,
-    pub right: Option<Box<MerkleNode>>,
+struct JournalEntry {
+    address: String,
+    previous: Option<L2Account>, // None if the account did not exist
 }
 
 // Rollup state manager
 pub struct RollupStateManager {
     pub accounts: HashMap<String, L2Account>,
     pub state_root: String,
-    pub merkle_tree: Option<MerkleNode>,
+    pub merkle_tree: SparseMerkleTree,
+    journal: Vec<Vec<JournalEntry>>, // One frame per open begin()
+    dirty: BTreeSet<String>,         // Addresses written since the last take_changes()
 }
 
 impl RollupStateManager {
     pub fn new() -> Self {
+        let merkle_tree = SparseMerkleTree::new(ACCOUNT_TREE_DEPTH);
         Self {
             accounts: HashMap::new(),
-            state_root: String::new(),
-            merkle_tree: None,
+            state_root: merkle_tree.root(),
+            merkle_tree,
+            journal: Vec::new(),
+            dirty: BTreeSet::new(),
+        }
+    }
+
+    /// Open a journal frame; changes made until the matching commit or revert can be undone.
+    /// Frames nest, so a transaction can be reverted inside a block that is still open.
+    pub fn begin(&mut self) {
+        self.journal.push(Vec::new());
+    }
+
+    /// Close the innermost frame and keep its changes
+    pub fn commit(&mut self) {
+        if let Some(frame) = self.journal.pop() {
+            if let Some(parent) = self.journal.last_mut() {
+                parent.extend(frame);
+            }
         }
     }
 
+    /// Close the innermost frame and undo its changes
+    pub fn revert(&mut self) {
+        let Some(frame) = self.journal.pop() else {
+            return;
+        };
+        for entry in frame.into_iter().rev() {
+            self.dirty.insert(entry.address.clone());
+            match entry.previous {
+                Some(account) => {
+                    self.merkle_tree
+                        .insert(&entry.address, account_leaf_hash(&account));
+                    self.accounts.insert(entry.address, account);
+                }
+                None => {
+                    self.merkle_tree.insert(&entry.address, [0u8; 32]);
+                    self.accounts.remove(&entry.address);
+                }
+            }
+        }
+        self.update_state_root();
+    }
+
+    /// Number of open journal frames
+    pub fn journal_depth(&self) -> usize {
+        self.journal.len()
+    }
+
     pub fn get_account(&self, address: &str) -> Option<&L2Account> {
         self.accounts.get(address)
     }
 
     pub fn update_account(&mut self, account: L2Account) {
+        if let Some(frame) = self.journal.last_mut() {
+            frame.push(JournalEntry {
+                address: account.address.clone(),
+                previous: self.accounts.get(&account.address).cloned(),
+            });
+        }
+        self.dirty.insert(account.address.clone());
+        self.merkle_tree
+            .insert(&account.address, account_leaf_hash(&account));
         self.accounts.insert(account.address.clone(), account);
         self.update_state_root();
     }
 
-    pub fn update_state_root(&mut self) {
-        // Create a simple state root based on account data
-        let mut hasher = Keccak256::new();
-
-        // Sort accounts by address for consistent hashing
-        let mut accounts: Vec<(&String, &L2Account)> = self.accounts.iter().collect();
-        accounts.sort_by(|a, b| a.0.cmp(b.0));
+    /// Delete an account from the state
+    pub fn remove_account(&mut self, address: &str) {
+        self.dirty.insert(address.to_string());
+        self.merkle_tree.insert(address, [0u8; 32]);
+        self.accounts.remove(address);
+        self.update_state_root();
+    }
 
-        for (_, account) in accounts {
-            hasher.update(account.address.as_bytes());
-            hasher.update(&account.balance.to_le_bytes());
-            hasher.update(&account.nonce.to_le_bytes());
+    /// Accounts written since the previous call: current values and deleted addresses
+    pub fn take_changes(&mut self) -> (Vec<L2Account>, Vec<String>) {
+        let mut updated = Vec::new();
+        let mut removed = Vec::new();
+        for address in std::mem::take(&mut self.dirty) {
+            match self.accounts.get(&address) {
+                Some(account) => updated.push(account.clone()),
+                None => removed.push(address),
+            }
         }
+        (updated, removed)
+    }
 
-        let result = hasher.finalize();
-        self.state_root = format!("{:x}", result);
+    /// Recompute the state root: the sparse Merkle root over all account leaves. Fees
+    /// are burned, so the balances it commits to add up to what was credited to L2 less
+    /// withdrawals and every fee paid.
+    pub fn update_state_root(&mut self) {
+        self.state_root = self.merkle_tree.root();
     }
 
     pub fn get_state_root(&self) -> &str {
         &self.state_root
     }
+
+    /// Merkle proof for an account against the current state root
+    pub fn generate_account_proof(&self, address: &str) -> Option<Vec<String>> {
+        self.merkle_tree.generate_proof(address)
+    }
+
+    /// Compressed proof of an account, or of its absence, against the current state root
+    pub fn generate_compressed_account_proof(&self, address: &str) -> Option<SmtProof> {
+        self.merkle_tree.generate_compressed_proof(address)
+    }
+
+    /// All accounts sorted by address
+    pub fn sorted_accounts(&self) -> Vec<L2Account> {
+        let mut accounts: Vec<L2Account> = self.accounts.values().cloned().collect();
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+        accounts
+    }
+
+    /// Replace the whole state with the given accounts
+    pub fn restore(&mut self, accounts: &[L2Account]) {
+        self.journal.clear();
+        self.dirty.clear();
+        self.accounts.clear();
+        self.merkle_tree = SparseMerkleTree::new(ACCOUNT_TREE_DEPTH);
+        let leaves: Vec<(&str, [u8; 32])> = accounts
+            .iter()
+            .map(|a| (a.address.as_str(), account_leaf_hash(a)))
+            .collect();
+        self.merkle_tree.update_batch(&leaves);
+        for account in accounts {
+            self.accounts
+                .insert(account.address.clone(), account.clone());
+        }
+        self.update_state_root();
+    }
+}
+
+/// Apply a transfer to the sender and receiver accounts, returning their new values.
+/// This is the single state transition used both by the rollup and by fraud proofs.
+/// Withdrawals burn the amount, so they return no receiver account. The fee is burned
+/// too, which keeps every transition down to the sender and receiver leaves.
+pub fn apply_transfer(
+    sender: Option<&L2Account>,
+    receiver: Option<&L2Account>,
+    transaction: &L2Transaction,
+) -> Result<(L2Account, Option<L2Account>), RollupError> {
+    if !transaction.amount.is_finite() || transaction.amount <= 0.0 {
+        return Err(RollupError::InvalidTransaction);
+    }
+    if !transaction.fee.is_finite() || transaction.fee < 0.0 {
+        return Err(RollupError::FeeTooLow);
+    }
+
+    let mut sender_account = sender.cloned().ok_or(RollupError::InsufficientBalance)?;
+    if sender_account.address != transaction.from {
+        return Err(RollupError::InvalidTransaction);
+    }
+    check_nonce(sender_account.nonce, transaction.nonce)?;
+    // The fee is burned: it leaves the sender and no L2 account is credited with it
+    let total_cost = transaction.amount + transaction.fee;
+    if sender_account.balance < total_cost {
+        return Err(RollupError::InsufficientBalance);
+    }
+    sender_account.balance -= total_cost;
+    sender_account.nonce += 1;
+    if transaction.is_withdrawal() {
+        return Ok((sender_account, None));
+    }
+
+    // Self-transfers credit the already debited sender
+    let receiver_base = if transaction.to == transaction.from {
+        Some(&sender_account)
+    } else {
+        receiver
+    };
+    let receiver_account = match receiver_base {
+        Some(account) if account.address == transaction.to => L2Account {
+            address: account.address.clone(),
+            balance: account.balance + transaction.amount,
+            nonce: account.nonce,
+        },
+        Some(_) => return Err(RollupError::InvalidTransaction),
+        None => L2Account {
+            address: transaction.to.clone(),
+            balance: transaction.amount,
+            nonce: 0,
+        },
+    };
+
+    Ok((sender_account, Some(receiver_account)))
+}
+
+// Lifecycle of a submitted batch under the optimistic dispute game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BatchStatus {
+    Pending,   // Inside the challenge window
+    Finalized, // Challenge window elapsed without a successful challenge
+    Reverted,  // Rolled back after a successful fraud proof
 }
 
 // Batch structure for transaction batching
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct L2Batch {
     pub batch_id: String,
+    pub batch_number: u64,
+    pub chain_id: String,
     pub transactions: Vec<L2Transaction>,
     pub state_root_before: String,
     pub state_root_after: String,
+    pub intermediate_state_roots: Vec<String>, // State root after each transaction
+    pub receipts: Vec<TransactionReceipt>,
+    pub receipts_root: String,
+    #[serde(default)]
+    pub withdrawals: Vec<Withdrawal>, // Burned withdrawals exiting to L1 with this batch
+    #[serde(default)]
+    pub withdrawal_root: String,
+    #[serde(default)]
+    pub system_transfers: Vec<SystemTransfer>, // Applied since the previous batch, before state_root_before
     pub timestamp: NaiveDateTime,
+    pub operator: String,
     pub operator_signature: String,
+    pub challenge_deadline: NaiveDateTime,
+    pub status: BatchStatus,
+}
+
+impl L2Batch {
+    /// Commitment over everything the operator claims about the batch
+    pub fn commitment(&self) -> String {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.batch_id.as_bytes());
+        hasher.update(self.batch_number.to_le_bytes());
+        hasher.update(self.chain_id.as_bytes());
+        hasher.update(self.state_root_before.as_bytes());
+        hasher.update(self.state_root_after.as_bytes());
+        for root in &self.intermediate_state_roots {
+            hasher.update(root.as_bytes());
+        }
+        hasher.update(self.receipts_root.as_bytes());
+        hasher.update(self.withdrawal_root.as_bytes());
+        for transfer in &self.system_transfers {
+            hasher.update(transfer.hash().as_bytes());
+            hasher.update(transfer.from.as_deref().unwrap_or("").as_bytes());
+            hasher.update(transfer.to.as_bytes());
+            hasher.update(transfer.amount.to_le_bytes());
+            hasher.update(transfer.sequence.to_le_bytes());
+        }
+        for tx in &self.transactions {
+            hasher.update(tx.from.as_bytes());
+            hasher.update(tx.to.as_bytes());
+            hasher.update(tx.amount.to_le_bytes());
+            hasher.update(tx.nonce.to_le_bytes());
+            hasher.update(tx.signature.as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// State root the batch claims before the transaction at `tx_index`
+    pub fn claimed_root_before(&self, tx_index: usize) -> Option<&str> {
+        if tx_index == 0 {
+            Some(self.state_root_before.as_str())
+        } else {
+            self.intermediate_state_roots
+                .get(tx_index - 1)
+                .map(|r| r.as_str())
+        }
+    }
+
+    /// State root the batch claims after the transaction at `tx_index`
+    pub fn claimed_root_after(&self, tx_index: usize) -> Option<&str> {
+        self.intermediate_state_roots
+            .get(tx_index)
+            .map(|r| r.as_str())
+    }
+
+    /// Whether the batch can still be challenged at `now`
+    pub fn is_challengeable(&self, now: NaiveDateTime) -> bool {
+        self.status == BatchStatus::Pending && now <= self.challenge_deadline
+    }
+}
+
+// Operator attestation over a batch commitment
+fn operator_signature(operator: &str, commitment: &str) -> String {
+    let mut hasher = Keccak256::new();
+    hasher.update(operator.as_bytes());
+    hasher.update(commitment.as_bytes());
+    format!("{:x}", hasher.finalize())
 }
 
 // Main rollup structure
@@ -143,10 +539,25 @@ pub struct L2Rollup {
     pub config: RollupConfig,
     pub state_manager: RollupStateManager,
     pub blocks: Vec<L2Block>,
-    pub pending_transactions: Vec<L2Transaction>,
+    pub mempool: Mempool,
     pub batches: Vec<L2Batch>,
     pub latest_block_number: u64,
     pub latest_batch_id: u64,
+    pub checkpoints: StateCheckpointManager,
+    pub challenge_period_secs: i64,
+    pub receipts: HashMap<String, TransactionReceipt>, // tx hash -> latest receipt
+    pub withdrawal_queue: Vec<Withdrawal>,             // Burned on L2, waiting for the next batch
+    pub system_transfers: Vec<SystemTransfer>,         // Bridge transfers for the next batch
+    system_sequence: u64,                              // Sequence of the next system transfer
+    persistence: Option<RollupPersistence>,
+}
+
+// Transactions that executed, with their receipts and the state root after each one
+struct ExecutionOutcome {
+    included: Vec<L2Transaction>,
+    intermediate_state_roots: Vec<String>,
+    receipts: Vec<TransactionReceipt>,
+    withdrawals: Vec<Withdrawal>,
 }
 
 impl L2Rollup {
@@ -155,50 +566,233 @@ impl L2Rollup {
             config,
             state_manager: RollupStateManager::new(),
             blocks: Vec::new(),
-            pending_transactions: Vec::new(),
+            mempool: Mempool::default(),
             batches: Vec::new(),
             latest_block_number: 0,
             latest_batch_id: 0,
+            checkpoints: StateCheckpointManager::unbounded(),
+            challenge_period_secs: DEFAULT_CHALLENGE_PERIOD_SECS,
+            receipts: HashMap::new(),
+            withdrawal_queue: Vec::new(),
+            system_transfers: Vec::new(),
+            system_sequence: 0,
+            persistence: None,
         }
     }
 
+    /// Create a rollup with a custom mempool configuration
+    pub fn with_mempool_config(config: RollupConfig, mempool_config: MempoolConfig) -> Self {
+        let mut rollup = Self::new(config);
+        rollup.mempool = Mempool::new(mempool_config);
+        rollup
+    }
+
+    /// Set the challenge window applied to newly submitted batches
+    pub fn set_challenge_period(&mut self, seconds: i64) {
+        self.challenge_period_secs = seconds;
+    }
+
     /// Add a transaction to the pending queue
     pub fn add_transaction(&mut self, transaction: L2Transaction) -> Result<(), RollupError> {
         // Validate transaction
-        if transaction.amount <= 0.0 {
+        if !transaction.amount.is_finite() || transaction.amount <= 0.0 {
             return Err(RollupError::InvalidTransaction);
         }
 
+        // Only the key behind the sender address may move its funds
+        verify_transaction_signature(&self.config.chain_id, &transaction)?;
+
         // Check sender has sufficient balance
-        if let Some(account) = self.state_manager.get_account(&transaction.from) {
-            if account.balance < transaction.amount {
+        let account_nonce = if let Some(account) = self.state_manager.get_account(&transaction.from)
+        {
+            if account.balance < transaction.amount + transaction.fee {
                 return Err(RollupError::InsufficientBalance);
             }
+            account.nonce
         } else {
             // New account, check if it's trying to send tokens
             if transaction.amount > 0.0 {
                 return Err(RollupError::InsufficientBalance);
             }
-        }
+            0
+        };
 
-        self.pending_transactions.push(transaction);
+        // Nonce ordering, replacement and per-account limits are enforced by the mempool
+        self.mempool
+            .insert(transaction, account_nonce, Utc::now().naive_utc())?;
         Ok(())
     }
 
-    /// Create a new block from pending transactions
+    /// Queue a signed withdrawal: a transfer to `L2_EXIT_ADDRESS` that burns the
+    /// amount when executed and is committed in the next batch's withdrawal root.
+    /// Returns the withdrawal id used to claim the funds on L1.
+    pub fn request_withdrawal(
+        &mut self,
+        transaction: L2Transaction,
+    ) -> Result<String, RollupError> {
+        if !transaction.is_withdrawal() {
+            return Err(RollupError::InvalidTransaction);
+        }
+        let withdrawal_id = transaction.hash();
+        self.add_transaction(transaction)?;
+        Ok(withdrawal_id)
+    }
+
+    /// Credit `amount` to `address` from outside the rollup, such as tokens bridged in
+    /// from another chain. This is the privileged counterpart of a withdrawal; see
+    /// `system_transfer`.
+    pub fn deposit(
+        &mut self,
+        deposit_id: &str,
+        address: &str,
+        amount: f64,
+    ) -> Result<TransactionReceipt, RollupError> {
+        self.system_transfer(deposit_id, None, address, amount)
+    }
+
+    /// Move `amount` from `from` to `to`, or credit it to `to` from outside the rollup
+    /// when `from` is None. System transfers skip signature and nonce checks, so only
+    /// the bridge may call this, and `transfer_id` must be unique per transfer. The
+    /// change is journaled and receipted like a transaction, with the zero address as
+    /// the sender of credits, and is committed by the next batch. A rollback applies
+    /// it again on the restored state, since it is not the operator's to undo.
+    pub fn system_transfer(
+        &mut self,
+        transfer_id: &str,
+        from: Option<&str>,
+        to: &str,
+        amount: f64,
+    ) -> Result<TransactionReceipt, RollupError> {
+        if self
+            .receipts
+            .contains_key(&system_transfer_hash(transfer_id))
+        {
+            return Err(RollupError::DuplicateTransaction);
+        }
+        let transfer = SystemTransfer {
+            transfer_id: transfer_id.to_string(),
+            from: from.map(str::to_string),
+            to: to.to_string(),
+            amount,
+            sequence: self.system_sequence,
+        };
+        let receipt = self.apply_system_transfer(&transfer)?;
+        self.system_sequence += 1;
+        self.system_transfers.push(transfer.clone());
+        self.receipts
+            .insert(receipt.tx_hash.clone(), receipt.clone());
+        self.record_account_changes();
+        self.record(StorageRecord::SystemTransfer {
+            transfer,
+            receipt: receipt.clone(),
+        });
+        self.flush_storage()?;
+        Ok(receipt)
+    }
+
+    /// Debit and credit the accounts of a system transfer in one journal frame
+    fn apply_system_transfer(
+        &mut self,
+        transfer: &SystemTransfer,
+    ) -> Result<TransactionReceipt, RollupError> {
+        let amount = transfer.amount;
+        if !amount.is_finite()
+            || amount <= 0.0
+            || transfer.to == L2_EXIT_ADDRESS
+            || transfer.from.as_deref() == Some(L2_EXIT_ADDRESS)
+            || transfer.from.as_deref() == Some(transfer.to.as_str())
+        {
+            return Err(RollupError::InvalidTransaction);
+        }
+
+        let debited = match transfer.from.as_deref() {
+            Some(from) => match self.state_manager.get_account(from) {
+                Some(account) if account.balance >= amount => Some(L2Account {
+                    balance: account.balance - amount,
+                    ..account.clone()
+                }),
+                _ => return Err(RollupError::InsufficientBalance),
+            },
+            None => None,
+        };
+        let credited = match self.state_manager.get_account(&transfer.to) {
+            Some(account) => L2Account {
+                balance: account.balance + amount,
+                ..account.clone()
+            },
+            None => L2Account {
+                address: transfer.to.clone(),
+                balance: amount,
+                nonce: 0,
+            },
+        };
+        self.state_manager.begin();
+        if let Some(account) = debited {
+            self.state_manager.update_account(account);
+        }
+        self.state_manager.update_account(credited);
+        self.state_manager.commit();
+
+        Ok(TransactionReceipt {
+            tx_hash: transfer.hash(),
+            from: transfer
+                .from
+                .clone()
+                .unwrap_or_else(|| L2_EXIT_ADDRESS.to_string()),
+            nonce: 0,
+            index: self.system_transfers.len(),
+            success: true,
+            error: None,
+            state_root: self.state_manager.get_state_root().to_string(),
+        })
+    }
+
+    /// Number of transactions waiting in the mempool
+    pub fn pending_transaction_count(&self) -> usize {
+        self.mempool.len()
+    }
+
+    /// Next nonce the sender must use, including its queued transactions
+    pub fn next_nonce(&self, address: &str) -> u64 {
+        let account_nonce = self
+            .state_manager
+            .get_account(address)
+            .map(|a| a.nonce)
+            .unwrap_or(0);
+        self.mempool.next_nonce(address, account_nonce)
+    }
+
+    /// Take the best transactions for the next block, dropping expired ones first
+    fn take_block_transactions(&mut self) -> Vec<L2Transaction> {
+        self.mempool.expire(Utc::now().naive_utc());
+        self.mempool.drain_best(self.config.max_batch_size.max(1))
+    }
+
+    /// Drop queued transactions invalidated by the nonces of the new state
+    fn prune_mempool(&mut self) {
+        let accounts = &self.state_manager;
+        self.mempool
+            .prune(|sender| accounts.get_account(sender).map(|a| a.nonce).unwrap_or(0));
+    }
+
+    /// Create a new block from pending transactions.
+    /// Transactions that fail are left out of the block and reported in its receipts;
+    /// if none of them execute the block is discarded and the state is left untouched.
     pub fn create_block(&mut self) -> Result<L2Block, RollupError> {
-        if self.pending_transactions.is_empty() {
+        let transactions = self.take_block_transactions();
+        if transactions.is_empty() {
             return Err(RollupError::InvalidBlock);
         }
 
-        let state_root_before = self.state_manager.get_state_root().to_string();
-
-        // Process transactions and update state
-        let transactions = self.pending_transactions.clone();
-        self.pending_transactions.clear();
-        for tx in &transactions {
-            self.process_transaction(tx)?;
+        self.state_manager.begin();
+        let outcome = self.execute_transactions(transactions);
+        self.prune_mempool();
+        if outcome.included.is_empty() {
+            self.state_manager.revert();
+            return Err(RollupError::InvalidBlock);
         }
+        self.state_manager.commit();
+        self.withdrawal_queue.extend(outcome.withdrawals);
 
         let state_root_after = self.state_manager.get_state_root().to_string();
 
@@ -206,97 +800,564 @@ impl L2Rollup {
 
         let block = L2Block {
             block_number: self.latest_block_number,
-            transactions: transactions.clone(),
-            state_root: state_root_after.clone(),
+            transactions: outcome.included,
+            receipts_root: receipts_root(&outcome.receipts),
+            receipts: outcome.receipts,
+            state_root: state_root_after,
             previous_block_hash: self.get_latest_block_hash(),
             timestamp: Utc::now().naive_utc(),
             batch_id: None,
         };
 
         self.blocks.push(block.clone());
-        self.pending_transactions.clear();
+        self.record_account_changes();
+        self.record(StorageRecord::Block(block.clone()));
+        self.flush_storage()?;
 
         Ok(block)
     }
 
-    /// Process a single transaction and update state
-    fn process_transaction(&mut self, transaction: &L2Transaction) -> Result<(), RollupError> {
-        // Deduct from sender
-        if let Some(mut sender_account) = self.state_manager.get_account(&transaction.from).cloned()
-        {
-            if sender_account.balance < transaction.amount {
-                return Err(RollupError::InsufficientBalance);
+    /// Execute transactions in order, each inside its own journal frame so a failing
+    /// transaction is undone without affecting the ones before it
+    fn execute_transactions(&mut self, transactions: Vec<L2Transaction>) -> ExecutionOutcome {
+        let mut outcome = ExecutionOutcome {
+            included: Vec::with_capacity(transactions.len()),
+            intermediate_state_roots: Vec::with_capacity(transactions.len()),
+            receipts: Vec::with_capacity(transactions.len()),
+            withdrawals: Vec::new(),
+        };
+
+        for (index, tx) in transactions.into_iter().enumerate() {
+            self.state_manager.begin();
+            let error = match self.process_transaction(&tx) {
+                Ok(()) => {
+                    self.state_manager.commit();
+                    None
+                }
+                Err(e) => {
+                    self.state_manager.revert();
+                    Some(e.to_string())
+                }
+            };
+
+            let state_root = self.state_manager.get_state_root().to_string();
+            let receipt = TransactionReceipt {
+                tx_hash: tx.hash(),
+                from: tx.from.clone(),
+                nonce: tx.nonce,
+                index,
+                success: error.is_none(),
+                error,
+                state_root: state_root.clone(),
+            };
+            self.receipts
+                .insert(receipt.tx_hash.clone(), receipt.clone());
+            outcome.receipts.push(receipt);
+            if outcome.receipts[index].success {
+                if tx.is_withdrawal() {
+                    outcome.withdrawals.push(Withdrawal::from_transaction(&tx));
+                }
+                outcome.included.push(tx);
+                outcome.intermediate_state_roots.push(state_root);
             }
-            sender_account.balance -= transaction.amount;
-            sender_account.nonce += 1;
-            self.state_manager.update_account(sender_account);
-        } else {
-            return Err(RollupError::InsufficientBalance);
         }
 
-        // Add to receiver
-        let receiver_balance =
-            if let Some(receiver_account) = self.state_manager.get_account(&transaction.to) {
-                receiver_account.balance
-            } else {
-                0.0
-            };
+        outcome
+    }
 
-        let receiver_account = L2Account {
-            address: transaction.to.clone(),
-            balance: receiver_balance + transaction.amount,
-            nonce: if let Some(receiver_account) = self.state_manager.get_account(&transaction.to) {
-                receiver_account.nonce
-            } else {
-                0
-            },
-        };
+    /// Process a single transaction and update state
+    fn process_transaction(&mut self, transaction: &L2Transaction) -> Result<(), RollupError> {
+        let (sender_account, receiver_account) = apply_transfer(
+            self.state_manager.get_account(&transaction.from),
+            self.state_manager.get_account(&transaction.to),
+            transaction,
+        )?;
 
-        self.state_manager.update_account(receiver_account);
+        self.state_manager.update_account(sender_account);
+        if let Some(receiver_account) = receiver_account {
+            self.state_manager.update_account(receiver_account);
+        }
 
         Ok(())
     }
 
+    /// Receipt of an executed (or rejected) transaction
+    pub fn get_receipt(&self, tx_hash: &str) -> Option<&TransactionReceipt> {
+        self.receipts.get(tx_hash)
+    }
+
+    /// Take a full snapshot of the current state unless the latest one already matches
+    fn ensure_checkpoint(&mut self) {
+        let current_root = self.state_manager.get_state_root().to_string();
+        let up_to_date = self
+            .checkpoints
+            .get_latest_snapshot()
+            .map(|s| {
+                s.state_root == current_root
+                    && s.batch_number == self.latest_batch_id
+                    && s.system_sequence == self.system_sequence
+            })
+            .unwrap_or(false);
+        if !up_to_date {
+            let mut snapshot = StateSnapshot::with_accounts(
+                current_root,
+                self.latest_block_number,
+                self.latest_batch_id,
+                self.state_manager.sorted_accounts(),
+            );
+            snapshot.system_sequence = self.system_sequence;
+            self.record(StorageRecord::Checkpoint(snapshot.clone()));
+            self.checkpoints.add_snapshot(snapshot);
+        }
+    }
+
     /// Submit a batch of transactions to L1
     pub fn submit_batch(&mut self) -> Result<L2Batch, RollupError> {
-        if self.pending_transactions.is_empty() {
+        if self.mempool.is_empty() && self.system_transfers.is_empty() {
             return Err(RollupError::BatchSubmissionFailed);
         }
 
+        // Checkpoint the pre-state so a disputed batch can be rolled back
+        self.ensure_checkpoint();
+
         // Process all pending transactions
         let state_root_before = self.state_manager.get_state_root().to_string();
 
-        let transactions = self.pending_transactions.clone();
-        self.pending_transactions.clear();
-        for tx in &transactions {
-            self.process_transaction(tx)?;
+        let transactions = self.take_block_transactions();
+        self.state_manager.begin();
+        let outcome = self.execute_transactions(transactions);
+        self.prune_mempool();
+        if outcome.included.is_empty() && self.system_transfers.is_empty() {
+            self.state_manager.revert();
+            return Err(RollupError::BatchSubmissionFailed);
         }
+        self.state_manager.commit();
 
         let state_root_after = self.state_manager.get_state_root().to_string();
+        let mut withdrawals = std::mem::take(&mut self.withdrawal_queue);
+        withdrawals.extend(outcome.withdrawals);
 
         self.latest_batch_id += 1;
         let batch_id = format!("batch_{}", self.latest_batch_id);
+        let timestamp = Utc::now().naive_utc();
 
-        let batch = L2Batch {
+        let mut batch = L2Batch {
             batch_id: batch_id.clone(),
-            transactions: transactions.clone(),
+            batch_number: self.latest_batch_id,
+            chain_id: self.config.chain_id.clone(),
+            transactions: outcome.included,
             state_root_before,
-            state_root_after: state_root_after.clone(),
-            timestamp: Utc::now().naive_utc(),
-            operator_signature: String::new(), // In a real implementation, this would be a cryptographic signature
+            state_root_after,
+            intermediate_state_roots: outcome.intermediate_state_roots,
+            receipts_root: receipts_root(&outcome.receipts),
+            receipts: outcome.receipts,
+            withdrawal_root: withdrawal_root(&withdrawals),
+            withdrawals,
+            system_transfers: std::mem::take(&mut self.system_transfers),
+            timestamp,
+            operator: self.config.operator_address.clone(),
+            operator_signature: String::new(),
+            challenge_deadline: timestamp + Duration::seconds(self.challenge_period_secs),
+            status: BatchStatus::Pending,
         };
+        batch.operator_signature = operator_signature(&batch.operator, &batch.commitment());
 
         // Update all pending transactions with batch ID
+        self.label_unbatched_blocks(&batch_id);
+
+        self.batches.push(batch.clone());
+        self.record_account_changes();
+        self.record(StorageRecord::Batch(batch.clone()));
+        self.ensure_checkpoint();
+        self.flush_storage()?;
+
+        Ok(batch)
+    }
+
+    /// Look up a submitted batch
+    pub fn get_batch(&self, batch_id: &str) -> Option<&L2Batch> {
+        self.batches.iter().find(|b| b.batch_id == batch_id)
+    }
+
+    /// Check that a batch's operator signature matches its contents
+    pub fn verify_batch_signature(batch: &L2Batch) -> bool {
+        batch.operator_signature == operator_signature(&batch.operator, &batch.commitment())
+    }
+
+    /// Finalize every pending batch whose challenge window has elapsed
+    pub fn finalize_batches(&mut self, now: NaiveDateTime) -> Vec<String> {
+        let mut finalized = Vec::new();
+        for batch in self.batches.iter_mut() {
+            if batch.status == BatchStatus::Pending && now > batch.challenge_deadline {
+                batch.status = BatchStatus::Finalized;
+                finalized.push(batch.batch_id.clone());
+            }
+        }
+        for batch_id in &finalized {
+            self.record(StorageRecord::BatchStatus {
+                batch_id: batch_id.clone(),
+                status: BatchStatus::Finalized,
+            });
+        }
+        if !finalized.is_empty() {
+            self.prune_checkpoints();
+        }
+        finalized
+    }
+
+    /// Drop checkpoints nothing can use any more. Every pending batch keeps the snapshot
+    /// it would be rolled back to, and the latest finalized batch keeps its post-state
+    /// for forced exits; older snapshots go.
+    fn prune_checkpoints(&mut self) {
+        let live = || {
+            self.batches
+                .iter()
+                .filter(|b| b.status != BatchStatus::Reverted)
+        };
+        let oldest_pending = live()
+            .filter(|b| b.status == BatchStatus::Pending)
+            .map(|b| b.batch_number - 1)
+            .min();
+        let latest_finalized = live()
+            .filter(|b| b.status == BatchStatus::Finalized)
+            .map(|b| b.batch_number)
+            .max();
+        let keep_from = match (oldest_pending, latest_finalized) {
+            (Some(pending), Some(finalized)) => pending.min(finalized),
+            (Some(batch_number), None) | (None, Some(batch_number)) => batch_number,
+            (None, None) => return,
+        };
+        self.checkpoints.prune_before_batch(keep_from);
+    }
+
+    /// Inclusion proof of a withdrawal in the withdrawal root of its (non-reverted) batch
+    pub fn generate_withdrawal_proof(
+        &self,
+        withdrawal_id: &str,
+    ) -> Result<WithdrawalProof, RollupError> {
+        self.batches
+            .iter()
+            .filter(|b| b.status != BatchStatus::Reverted)
+            .find_map(|b| WithdrawalProof::build(b, withdrawal_id))
+            .ok_or(RollupError::InvalidWithdrawalProof)
+    }
+
+    /// Account value and proof against the state root committed by a batch, as used
+    /// for forced exits when the operator stops including withdrawals
+    pub fn account_proof_at_batch(
+        &self,
+        batch_number: u64,
+        address: &str,
+    ) -> Result<(Option<L2Account>, SmtProof), RollupError> {
+        let batch = self
+            .batches
+            .iter()
+            .find(|b| b.batch_number == batch_number && b.status != BatchStatus::Reverted)
+            .ok_or(RollupError::BatchNotFound)?;
+        let snapshot = self
+            .checkpoints
+            .snapshots
+            .iter()
+            .rev()
+            .find(|s| s.batch_number == batch_number && s.state_root == batch.state_root_after)
+            .ok_or(RollupError::SnapshotUnavailable)?;
+
+        let state = L2StateManager::from_accounts(&snapshot.accounts);
+        let proof = state
+            .generate_compressed_account_proof(address)
+            .ok_or(RollupError::MerkleTreeError)?;
+        Ok((state.get_account(address).cloned(), proof))
+    }
+
+    /// Roll the state back to the last checkpoint taken before `batch_number` and revert
+    /// that batch and every later one. Returns the ids of the reverted batches.
+    pub fn rollback_to_checkpoint(
+        &mut self,
+        batch_number: u64,
+    ) -> Result<Vec<String>, RollupError> {
+        let snapshot = self
+            .checkpoints
+            .get_snapshot_before_batch(batch_number)
+            .cloned()
+            .ok_or(RollupError::SnapshotUnavailable)?;
+
+        // Changes made before the rollback are logged first; the rollback itself is
+        // replayed from the log rather than recorded account by account
+        self.record_account_changes();
+        self.state_manager.restore(&snapshot.accounts);
+        if self.state_manager.get_state_root() != snapshot.state_root {
+            return Err(RollupError::StateRootMismatch);
+        }
+
+        let mut reverted = Vec::new();
+        let mut replayed = Vec::new();
+        for batch in self.batches.iter_mut() {
+            if batch.batch_number > snapshot.batch_number && batch.status != BatchStatus::Reverted {
+                batch.status = BatchStatus::Reverted;
+                reverted.push(batch.batch_id.clone());
+                replayed.extend(batch.system_transfers.iter().cloned());
+            }
+        }
+        replayed.append(&mut self.system_transfers);
+
+        // Blocks built on top of the restored state are no longer valid
+        self.blocks
+            .retain(|block| block.block_number <= snapshot.block_number);
+        self.latest_block_number = snapshot.block_number;
+        self.mempool.clear();
+        self.withdrawal_queue.clear();
+        self.checkpoints
+            .snapshots
+            .retain(|s| s.batch_number <= snapshot.batch_number);
+
+        // Bridge transfers of the reverted batches wait for the next batch again. Those
+        // made after the snapshot are applied again first; one the restored state can
+        // no longer cover is dropped and its receipt marked failed. Recovery replays
+        // this along with the rollback.
+        for transfer in replayed {
+            if transfer.sequence < snapshot.system_sequence {
+                self.system_transfers.push(transfer);
+                continue;
+            }
+            let receipt = match self.apply_system_transfer(&transfer) {
+                Ok(receipt) => {
+                    self.system_transfers.push(transfer);
+                    receipt
+                }
+                Err(e) => TransactionReceipt {
+                    tx_hash: transfer.hash(),
+                    from: transfer.from.unwrap_or_else(|| L2_EXIT_ADDRESS.to_string()),
+                    nonce: 0,
+                    index: self.system_transfers.len(),
+                    success: false,
+                    error: Some(e.to_string()),
+                    state_root: self.state_manager.get_state_root().to_string(),
+                },
+            };
+            self.receipts.insert(receipt.tx_hash.clone(), receipt);
+        }
+        self.record(StorageRecord::Rollback { batch_number });
+        self.flush_storage()?;
+
+        Ok(reverted)
+    }
+
+    /// Assign every block not yet covered by a batch to `batch_id`
+    fn label_unbatched_blocks(&mut self, batch_id: &str) {
         for block in self.blocks.iter_mut() {
             if block.batch_id.is_none() {
-                block.batch_id = Some(batch_id.clone());
+                block.batch_id = Some(batch_id.to_string());
             }
         }
+    }
 
-        self.batches.push(batch.clone());
-        self.pending_transactions.clear();
+    /// Buffer a record for the attached storage, if any
+    fn record(&mut self, record: StorageRecord) {
+        if let Some(persistence) = self.persistence.as_mut() {
+            persistence.pending.push(record);
+        }
+    }
 
-        Ok(batch)
+    /// Buffer the accounts written since the last record
+    fn record_account_changes(&mut self) {
+        let (updated, removed) = self.state_manager.take_changes();
+        if !updated.is_empty() || !removed.is_empty() {
+            let state_root = self.state_manager.get_state_root().to_string();
+            self.record(StorageRecord::Accounts {
+                updated,
+                removed,
+                state_root,
+            });
+        }
+    }
+
+    /// Persist the rollup to `storage` from now on. The storage must be empty; the
+    /// current state is written as the first snapshot. Use `recover` to resume a chain.
+    pub fn attach_storage(
+        &mut self,
+        mut storage: Box<dyn L2Storage>,
+        snapshot_interval: u64,
+    ) -> Result<(), RollupError> {
+        if storage.last_sequence() > 0 || storage.latest_snapshot()?.is_some() {
+            return Err(RollupError::StorageError(
+                "storage already holds a chain".to_string(),
+            ));
+        }
+        self.state_manager.take_changes();
+        storage.save_snapshot(&self.chain_snapshot(0))?;
+        self.persistence = Some(RollupPersistence::new(storage, snapshot_interval));
+        Ok(())
+    }
+
+    /// Flush pending records and hand the storage back
+    pub fn detach_storage(&mut self) -> Result<Option<Box<dyn L2Storage>>, RollupError> {
+        self.flush_storage()?;
+        Ok(self.persistence.take().map(|p| p.storage))
+    }
+
+    /// Write buffered records to the attached storage, taking a snapshot once enough
+    /// records have accumulated. Blocks, batches and rollbacks flush on their own;
+    /// account initialization and batch finalization are written on the next flush.
+    /// Returns the sequence number of the last persisted record.
+    pub fn flush_storage(&mut self) -> Result<u64, RollupError> {
+        if self.persistence.is_none() {
+            return Ok(0);
+        }
+        self.record_account_changes();
+        let Some(persistence) = self.persistence.as_mut() else {
+            return Ok(0);
+        };
+        if !persistence.pending.is_empty() {
+            persistence.storage.append(&persistence.pending)?;
+            persistence.pending.clear();
+        }
+        let sequence = persistence.storage.last_sequence();
+        if sequence - persistence.last_snapshot >= persistence.snapshot_interval {
+            let snapshot = self.chain_snapshot(sequence);
+            if let Some(persistence) = self.persistence.as_mut() {
+                persistence.storage.save_snapshot(&snapshot)?;
+                persistence.last_snapshot = sequence;
+            }
+        }
+        Ok(sequence)
+    }
+
+    /// Full chain state as of log record `sequence`
+    pub fn chain_snapshot(&self, sequence: u64) -> ChainSnapshot {
+        ChainSnapshot {
+            sequence,
+            state_root: self.get_state_root().to_string(),
+            accounts: self.state_manager.sorted_accounts(),
+            blocks: self.blocks.clone(),
+            batches: self.batches.clone(),
+            checkpoints: self.checkpoints.snapshots.clone(),
+            receipts: self.receipts.values().cloned().collect(),
+            withdrawal_queue: self.withdrawal_queue.clone(),
+            system_transfers: self.system_transfers.clone(),
+            system_sequence: self.system_sequence,
+            latest_block_number: self.latest_block_number,
+            latest_batch_id: self.latest_batch_id,
+        }
+    }
+
+    /// Rebuild a rollup from storage: load the latest snapshot, replay the log
+    /// records written after it and check the state root at every step. Pending
+    /// mempool transactions are not persisted and must be resubmitted.
+    pub fn recover(
+        config: RollupConfig,
+        storage: Box<dyn L2Storage>,
+        snapshot_interval: u64,
+    ) -> Result<Self, RollupError> {
+        let mut rollup = Self::new(config);
+        let snapshot = storage.latest_snapshot()?;
+        let mut sequence = 0;
+        if let Some(snapshot) = snapshot {
+            rollup.state_manager.restore(&snapshot.accounts);
+            if rollup.get_state_root() != snapshot.state_root {
+                return Err(RollupError::StateRootMismatch);
+            }
+            rollup.blocks = snapshot.blocks;
+            rollup.batches = snapshot.batches;
+            rollup.checkpoints.snapshots = snapshot.checkpoints;
+            rollup.receipts = snapshot
+                .receipts
+                .into_iter()
+                .map(|r| (r.tx_hash.clone(), r))
+                .collect();
+            rollup.withdrawal_queue = snapshot.withdrawal_queue;
+            rollup.system_transfers = snapshot.system_transfers;
+            rollup.system_sequence = snapshot.system_sequence;
+            rollup.latest_block_number = snapshot.latest_block_number;
+            rollup.latest_batch_id = snapshot.latest_batch_id;
+            sequence = snapshot.sequence;
+        }
+
+        for (_, record) in storage.read_after(sequence)? {
+            rollup.apply_record(record)?;
+        }
+        rollup.state_manager.take_changes();
+
+        let mut persistence = RollupPersistence::new(storage, snapshot_interval);
+        persistence.last_snapshot = sequence;
+        rollup.persistence = Some(persistence);
+        Ok(rollup)
+    }
+
+    /// Re-apply one log record during recovery
+    fn apply_record(&mut self, record: StorageRecord) -> Result<(), RollupError> {
+        match record {
+            StorageRecord::Accounts {
+                updated,
+                removed,
+                state_root,
+            } => {
+                for account in updated {
+                    self.state_manager.update_account(account);
+                }
+                for address in removed {
+                    self.state_manager.remove_account(&address);
+                }
+                if self.get_state_root() != state_root {
+                    return Err(RollupError::StateRootMismatch);
+                }
+            }
+            StorageRecord::Block(block) => {
+                if self.get_state_root() != block.state_root {
+                    return Err(RollupError::StateRootMismatch);
+                }
+                for receipt in &block.receipts {
+                    self.receipts
+                        .insert(receipt.tx_hash.clone(), receipt.clone());
+                }
+                self.withdrawal_queue.extend(
+                    block
+                        .transactions
+                        .iter()
+                        .filter(|tx| tx.is_withdrawal())
+                        .map(Withdrawal::from_transaction),
+                );
+                self.latest_block_number = block.block_number;
+                self.blocks.push(block);
+            }
+            StorageRecord::Batch(batch) => {
+                if self.get_state_root() != batch.state_root_after {
+                    return Err(RollupError::StateRootMismatch);
+                }
+                for receipt in &batch.receipts {
+                    self.receipts
+                        .insert(receipt.tx_hash.clone(), receipt.clone());
+                }
+                self.label_unbatched_blocks(&batch.batch_id);
+                self.withdrawal_queue.clear();
+                self.system_transfers.clear();
+                self.latest_batch_id = batch.batch_number;
+                self.batches.push(batch);
+            }
+            StorageRecord::Checkpoint(snapshot) => self.checkpoints.add_snapshot(snapshot),
+            StorageRecord::BatchStatus { batch_id, status } => {
+                let batch = self
+                    .batches
+                    .iter_mut()
+                    .find(|b| b.batch_id == batch_id)
+                    .ok_or(RollupError::BatchNotFound)?;
+                batch.status = status;
+                if status == BatchStatus::Finalized {
+                    self.prune_checkpoints();
+                }
+            }
+            StorageRecord::Rollback { batch_number } => {
+                self.rollback_to_checkpoint(batch_number)?;
+            }
+            StorageRecord::SystemTransfer { transfer, receipt } => {
+                if self.get_state_root() != receipt.state_root {
+                    return Err(RollupError::StateRootMismatch);
+                }
+                self.receipts.insert(receipt.tx_hash.clone(), receipt);
+                self.system_sequence = transfer.sequence + 1;
+                self.system_transfers.push(transfer);
+            }
+        }
+        Ok(())
     }
 
     /// Get the hash of the latest block
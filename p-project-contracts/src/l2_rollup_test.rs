@@ -1,8 +1,27 @@
 #[cfg(test)]
 mod tests {
-    use super::super::l2_rollup::{L2Rollup, L2Transaction, RollupConfig, RollupError};
+    use super::super::l2_rollup::{
+        receipts_root, L2Account, L2Rollup, L2Transaction, RollupConfig, RollupError,
+    };
+    use super::super::l2_signing::L2Keypair;
     use chrono::Utc;
 
+    fn user(name: &str) -> L2Keypair {
+        L2Keypair::from_seed(name)
+    }
+
+    fn signed(from: &str, to: &str, amount: f64, nonce: u64) -> L2Transaction {
+        user(from)
+            .signed_transfer(
+                "test-chain",
+                user(to).address(),
+                amount,
+                nonce,
+                Utc::now().naive_utc(),
+            )
+            .unwrap()
+    }
+
     #[test]
     fn test_create_rollup() {
         let config = RollupConfig {
@@ -22,7 +41,7 @@ mod tests {
         assert_eq!(rollup.config.gas_price, 0.001);
         assert_eq!(rollup.latest_block_number, 0);
         assert_eq!(rollup.latest_batch_id, 0);
-        assert_eq!(rollup.pending_transactions.len(), 0);
+        assert_eq!(rollup.pending_transaction_count(), 0);
         assert_eq!(rollup.blocks.len(), 0);
         assert_eq!(rollup.batches.len(), 0);
     }
@@ -38,10 +57,10 @@ mod tests {
         };
 
         let mut rollup = L2Rollup::new(config);
-        rollup.initialize_account("user1".to_string(), 1000.0);
+        rollup.initialize_account(user("user1").address().to_string(), 1000.0);
 
-        assert_eq!(rollup.get_balance("user1"), 1000.0);
-        assert_eq!(rollup.get_balance("user2"), 0.0);
+        assert_eq!(rollup.get_balance(user("user1").address()), 1000.0);
+        assert_eq!(rollup.get_balance(user("user2").address()), 0.0);
     }
 
     #[test]
@@ -55,20 +74,13 @@ mod tests {
         };
 
         let mut rollup = L2Rollup::new(config);
-        rollup.initialize_account("user1".to_string(), 1000.0);
+        rollup.initialize_account(user("user1").address().to_string(), 1000.0);
 
-        let transaction = L2Transaction {
-            from: "user1".to_string(),
-            to: "user2".to_string(),
-            amount: 100.0,
-            nonce: 0,
-            signature: "sig1".to_string(),
-            timestamp: Utc::now().naive_utc(),
-        };
+        let transaction = signed("user1", "user2", 100.0, 0);
 
         let result = rollup.add_transaction(transaction);
         assert!(result.is_ok());
-        assert_eq!(rollup.pending_transactions.len(), 1);
+        assert_eq!(rollup.pending_transaction_count(), 1);
     }
 
     #[test]
@@ -84,27 +96,13 @@ mod tests {
         let mut rollup = L2Rollup::new(config);
 
         // Test negative amount
-        let transaction = L2Transaction {
-            from: "user1".to_string(),
-            to: "user2".to_string(),
-            amount: -100.0,
-            nonce: 0,
-            signature: "sig1".to_string(),
-            timestamp: Utc::now().naive_utc(),
-        };
+        let transaction = signed("user1", "user2", -100.0, 0);
 
         let result = rollup.add_transaction(transaction);
         assert_eq!(result, Err(RollupError::InvalidTransaction));
 
         // Test insufficient balance
-        let transaction = L2Transaction {
-            from: "user1".to_string(),
-            to: "user2".to_string(),
-            amount: 100.0,
-            nonce: 0,
-            signature: "sig1".to_string(),
-            timestamp: Utc::now().naive_utc(),
-        };
+        let transaction = signed("user1", "user2", 100.0, 0);
 
         let result = rollup.add_transaction(transaction);
         assert_eq!(result, Err(RollupError::InsufficientBalance));
@@ -121,23 +119,16 @@ mod tests {
         };
 
         let mut rollup = L2Rollup::new(config);
-        rollup.initialize_account("user1".to_string(), 1000.0);
+        rollup.initialize_account(user("user1").address().to_string(), 1000.0);
 
-        let transaction = L2Transaction {
-            from: "user1".to_string(),
-            to: "user2".to_string(),
-            amount: 100.0,
-            nonce: 0,
-            signature: "sig1".to_string(),
-            timestamp: Utc::now().naive_utc(),
-        };
+        let transaction = signed("user1", "user2", 100.0, 0);
 
         rollup.add_transaction(transaction).unwrap();
-        assert_eq!(rollup.pending_transactions.len(), 1);
+        assert_eq!(rollup.pending_transaction_count(), 1);
 
         let result = rollup.create_block();
         assert!(result.is_ok());
-        assert_eq!(rollup.pending_transactions.len(), 0);
+        assert_eq!(rollup.pending_transaction_count(), 0);
         assert_eq!(rollup.blocks.len(), 1);
         assert_eq!(rollup.latest_block_number, 1);
     }
@@ -168,24 +159,17 @@ mod tests {
         };
 
         let mut rollup = L2Rollup::new(config);
-        rollup.initialize_account("user1".to_string(), 1000.0);
+        rollup.initialize_account(user("user1").address().to_string(), 1000.0);
 
-        let transaction = L2Transaction {
-            from: "user1".to_string(),
-            to: "user2".to_string(),
-            amount: 100.0,
-            nonce: 0,
-            signature: "sig1".to_string(),
-            timestamp: Utc::now().naive_utc(),
-        };
+        let transaction = signed("user1", "user2", 100.0, 0);
 
         rollup.add_transaction(transaction).unwrap();
-        assert_eq!(rollup.pending_transactions.len(), 1);
+        assert_eq!(rollup.pending_transaction_count(), 1);
 
         let state_root_before = rollup.get_state_root().to_string();
         let result = rollup.submit_batch();
         assert!(result.is_ok());
-        assert_eq!(rollup.pending_transactions.len(), 0);
+        assert_eq!(rollup.pending_transaction_count(), 0);
         assert_eq!(rollup.batches.len(), 1);
         assert_eq!(rollup.latest_batch_id, 1);
 
@@ -206,23 +190,327 @@ mod tests {
         };
 
         let mut rollup = L2Rollup::new(config);
-        rollup.initialize_account("user1".to_string(), 1000.0);
-        rollup.initialize_account("user2".to_string(), 0.0);
+        rollup.initialize_account(user("user1").address().to_string(), 1000.0);
+        rollup.initialize_account(user("user2").address().to_string(), 0.0);
 
-        let transaction = L2Transaction {
-            from: "user1".to_string(),
-            to: "user2".to_string(),
-            amount: 100.0,
-            nonce: 0,
-            signature: "sig1".to_string(),
-            timestamp: Utc::now().naive_utc(),
-        };
+        let transaction = signed("user1", "user2", 100.0, 0);
 
         rollup.add_transaction(transaction).unwrap();
         let block = rollup.create_block().unwrap();
 
-        assert_eq!(rollup.get_balance("user1"), 900.0);
-        assert_eq!(rollup.get_balance("user2"), 100.0);
+        assert_eq!(rollup.get_balance(user("user1").address()), 900.0);
+        assert_eq!(rollup.get_balance(user("user2").address()), 100.0);
         assert_eq!(block.transactions.len(), 1);
     }
-}
\ No newline at end of file
+
+    fn funded_rollup() -> L2Rollup {
+        let config = RollupConfig {
+            chain_id: "test-chain".to_string(),
+            operator_address: "operator1".to_string(),
+            batch_submission_interval: 300,
+            max_batch_size: 100,
+            gas_price: 0.001,
+        };
+
+        let mut rollup = L2Rollup::new(config);
+        rollup.initialize_account(user("user1").address().to_string(), 1000.0);
+        rollup
+    }
+
+    #[test]
+    fn test_rejects_forged_signature() {
+        let mut rollup = funded_rollup();
+
+        // Signed by a different key than the one behind `from`
+        let mut forged = signed("mallory", "user2", 100.0, 0);
+        forged.from = user("user1").address().to_string();
+        assert_eq!(
+            rollup.add_transaction(forged),
+            Err(RollupError::InvalidSignature)
+        );
+
+        // Amount changed after signing
+        let mut tampered = signed("user1", "user2", 100.0, 0);
+        tampered.amount = 900.0;
+        assert_eq!(
+            rollup.add_transaction(tampered),
+            Err(RollupError::InvalidSignature)
+        );
+
+        // Not a signature at all
+        let mut garbage = signed("user1", "user2", 100.0, 0);
+        garbage.signature = "sig1".to_string();
+        assert_eq!(
+            rollup.add_transaction(garbage),
+            Err(RollupError::InvalidSignature)
+        );
+
+        // Signed for another chain
+        let other_chain = user("user1")
+            .signed_transfer(
+                "other-chain",
+                user("user2").address(),
+                100.0,
+                0,
+                Utc::now().naive_utc(),
+            )
+            .unwrap();
+        assert_eq!(
+            rollup.add_transaction(other_chain),
+            Err(RollupError::InvalidSignature)
+        );
+
+        assert_eq!(rollup.pending_transaction_count(), 0);
+    }
+
+    #[test]
+    fn test_nonces_are_strictly_sequential() {
+        let mut rollup = funded_rollup();
+        let sender = user("user1").address().to_string();
+
+        assert_eq!(
+            rollup.add_transaction(signed("user1", "user2", 10.0, 1)),
+            Err(RollupError::NonceGap {
+                expected: 0,
+                actual: 1
+            })
+        );
+
+        rollup
+            .add_transaction(signed("user1", "user2", 10.0, 0))
+            .unwrap();
+        rollup
+            .add_transaction(signed("user1", "user2", 10.0, 1))
+            .unwrap();
+        assert_eq!(rollup.next_nonce(&sender), 2);
+
+        rollup.create_block().unwrap();
+        assert_eq!(rollup.state_manager.get_account(&sender).unwrap().nonce, 2);
+
+        // Replaying an executed transaction is rejected
+        assert_eq!(
+            rollup.add_transaction(signed("user1", "user2", 10.0, 1)),
+            Err(RollupError::NonceTooLow {
+                expected: 2,
+                actual: 1
+            })
+        );
+        rollup
+            .add_transaction(signed("user1", "user2", 10.0, 2))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_failed_transaction_is_excluded_with_receipt() {
+        let mut rollup = funded_rollup();
+        let sender = user("user1").address().to_string();
+        let receiver = user("user2").address().to_string();
+
+        // Each transfer passes admission, but the second overdraws once the first executed
+        let first = signed("user1", "user2", 600.0, 0);
+        let second = signed("user1", "user2", 600.0, 1);
+        let third = signed("user1", "user2", 50.0, 2);
+        rollup.add_transaction(first.clone()).unwrap();
+        rollup.add_transaction(second.clone()).unwrap();
+        rollup.add_transaction(third.clone()).unwrap();
+
+        let block = rollup.create_block().unwrap();
+        assert_eq!(block.transactions, vec![first.clone()]);
+        assert_eq!(block.receipts.len(), 3);
+        assert!(block.receipts[0].success);
+        assert_eq!(
+            block.receipts[1].error.as_deref(),
+            Some("Insufficient balance")
+        );
+        // The skipped nonce leaves a gap for the transaction after it
+        assert_eq!(
+            block.receipts[2].error.as_deref(),
+            Some("Nonce gap: expected 1, got 2")
+        );
+        assert_eq!(block.receipts[1].state_root, block.receipts[0].state_root);
+        assert_eq!(block.receipts[2].state_root, block.state_root);
+        assert_eq!(block.receipts_root, receipts_root(&block.receipts));
+
+        assert_eq!(rollup.get_balance(&sender), 400.0);
+        assert_eq!(rollup.get_balance(&receiver), 600.0);
+        assert_eq!(rollup.state_manager.get_account(&sender).unwrap().nonce, 1);
+        assert_eq!(rollup.state_manager.journal_depth(), 0);
+        assert!(!rollup.get_receipt(&second.hash()).unwrap().success);
+        assert!(rollup.get_receipt(&first.hash()).unwrap().success);
+    }
+
+    #[test]
+    fn test_block_without_valid_transactions_leaves_state_untouched() {
+        let mut rollup = funded_rollup();
+        rollup
+            .add_transaction(signed("user1", "user2", 100.0, 0))
+            .unwrap();
+        // The sender is drained after admission, so nothing in the batch can execute
+        rollup.initialize_account(user("user1").address().to_string(), 10.0);
+        let root_before = rollup.get_state_root().to_string();
+
+        let result = rollup.submit_batch();
+        assert_eq!(result.unwrap_err(), RollupError::BatchSubmissionFailed);
+        assert_eq!(rollup.get_state_root(), root_before);
+        assert_eq!(rollup.pending_transaction_count(), 0);
+        assert!(rollup.batches.is_empty());
+        assert_eq!(rollup.state_manager.journal_depth(), 0);
+    }
+
+    #[test]
+    fn test_batch_carries_receipts_root() {
+        let mut rollup = funded_rollup();
+        rollup
+            .add_transaction(signed("user1", "user2", 100.0, 0))
+            .unwrap();
+        rollup
+            .add_transaction(signed("user1", "user2", 950.0, 1))
+            .unwrap();
+
+        let batch = rollup.submit_batch().unwrap();
+        assert_eq!(batch.transactions.len(), 1);
+        assert_eq!(batch.intermediate_state_roots.len(), 1);
+        assert_eq!(batch.receipts.len(), 2);
+        assert_eq!(batch.receipts_root, receipts_root(&batch.receipts));
+        assert!(L2Rollup::verify_batch_signature(&batch));
+    }
+
+    #[test]
+    fn test_nested_journal_reverts_inner_frame_only() {
+        let mut rollup = funded_rollup();
+        let state = &mut rollup.state_manager;
+        let sender = user("user1").address().to_string();
+        let root_before = state.get_state_root().to_string();
+
+        state.begin();
+        let mut account = state.get_account(&sender).unwrap().clone();
+        account.balance = 10.0;
+        state.update_account(account);
+        let root_outer = state.get_state_root().to_string();
+
+        state.begin();
+        let mut account = state.get_account(&sender).unwrap().clone();
+        account.balance = 5.0;
+        state.update_account(account);
+        state.update_account(L2Account {
+            address: "fresh".to_string(),
+            balance: 1.0,
+            nonce: 0,
+        });
+        state.revert();
+
+        assert_eq!(state.get_state_root(), root_outer);
+        assert!(state.get_account("fresh").is_none());
+        assert_eq!(state.get_account(&sender).unwrap().balance, 10.0);
+
+        state.revert();
+        assert_eq!(state.get_state_root(), root_before);
+        assert_eq!(state.get_account(&sender).unwrap().balance, 1000.0);
+        assert_eq!(state.journal_depth(), 0);
+    }
+
+    #[test]
+    fn test_deposit_is_credited_and_receipted() {
+        let config = RollupConfig {
+            chain_id: "test-chain".to_string(),
+            operator_address: "operator1".to_string(),
+            batch_submission_interval: 300,
+            max_batch_size: 100,
+            gas_price: 0.001,
+        };
+
+        let mut rollup = L2Rollup::new(config);
+        let alice = user("alice").address().to_string();
+        rollup.initialize_account(alice.clone(), 10.0);
+
+        let receipt = rollup.deposit("lock-1", &alice, 90.0).unwrap();
+        assert!(receipt.success);
+        assert_eq!(receipt.state_root, rollup.get_state_root());
+        assert_eq!(rollup.get_receipt(&receipt.tx_hash), Some(&receipt));
+        assert_eq!(rollup.get_balance(&alice), 100.0);
+
+        // Deposits credit accounts that don't exist yet
+        let bob = user("bob").address().to_string();
+        rollup.deposit("lock-2", &bob, 5.0).unwrap();
+        assert_eq!(rollup.get_balance(&bob), 5.0);
+
+        // The same deposit can't be credited twice, nor a nonsensical amount
+        assert_eq!(
+            rollup.deposit("lock-1", &alice, 90.0),
+            Err(RollupError::DuplicateTransaction)
+        );
+        assert_eq!(
+            rollup.deposit("lock-3", &alice, -1.0),
+            Err(RollupError::InvalidTransaction)
+        );
+        assert_eq!(
+            rollup.deposit("lock-3", &alice, f64::INFINITY),
+            Err(RollupError::InvalidTransaction)
+        );
+        assert_eq!(rollup.get_balance(&alice), 100.0);
+    }
+
+    #[test]
+    fn test_system_transfers_are_batched_and_survive_rollback() {
+        let config = RollupConfig {
+            chain_id: "test-chain".to_string(),
+            operator_address: "operator1".to_string(),
+            batch_submission_interval: 300,
+            max_batch_size: 100,
+            gas_price: 0.001,
+        };
+
+        let mut rollup = L2Rollup::new(config);
+        let alice = user("alice").address().to_string();
+        let bob = user("bob").address().to_string();
+        let carol = user("carol").address().to_string();
+        rollup.initialize_account(alice.clone(), 1000.0);
+        rollup.initialize_account(bob.clone(), 500.0);
+
+        rollup.deposit("in-1", &carol, 40.0).unwrap();
+        rollup
+            .system_transfer("lock-1", Some(&alice), "escrow", 100.0)
+            .unwrap();
+        assert_eq!(
+            rollup.system_transfer("lock-2", Some(&carol), "escrow", 41.0),
+            Err(RollupError::InsufficientBalance)
+        );
+        rollup
+            .add_transaction(signed("alice", "bob", 10.0, 0))
+            .unwrap();
+        let first = rollup.submit_batch().unwrap();
+        assert_eq!(first.system_transfers.len(), 2);
+        assert!(L2Rollup::verify_batch_signature(&first));
+        assert!(rollup.system_transfers.is_empty());
+
+        rollup.deposit("in-2", &carol, 5.0).unwrap();
+        rollup
+            .add_transaction(signed("alice", "bob", 100.0, 1))
+            .unwrap();
+        let second = rollup.submit_batch().unwrap();
+        assert_eq!(second.system_transfers.len(), 1);
+
+        // Made after the batch it depends on, so the rollback can't cover it again
+        let lock = rollup
+            .system_transfer("lock-3", Some(&bob), "escrow", 600.0)
+            .unwrap();
+        rollup.rollback_to_checkpoint(second.batch_number).unwrap();
+
+        // The restored state already holds the deposit of the reverted batch, which waits
+        // to be committed again; the uncovered lock is dropped with a receipt
+        assert_eq!(rollup.get_balance(&alice), 890.0);
+        assert_eq!(rollup.get_balance(&bob), 510.0);
+        assert_eq!(rollup.get_balance(&carol), 45.0);
+        assert_eq!(rollup.get_balance("escrow"), 100.0);
+        let dropped = rollup.get_receipt(&lock.tx_hash).unwrap();
+        assert!(!dropped.success);
+        assert_eq!(rollup.system_transfers.len(), 1);
+
+        // A batch of bridge transfers alone commits them
+        let third = rollup.submit_batch().unwrap();
+        assert!(third.transactions.is_empty());
+        assert_eq!(third.system_transfers[0].transfer_id, "in-2");
+        assert_eq!(third.state_root_before, rollup.get_state_root());
+        assert!(rollup.submit_batch().is_err());
+    }
+}
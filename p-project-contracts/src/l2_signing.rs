@@ -0,0 +1,212 @@
+use crate::l2_rollup::{L2Transaction, RollupError, L2_EXIT_ADDRESS};
+use chrono::NaiveDateTime;
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
+
+// Domain separator mixed into every transaction signing hash
+const TX_SIGNING_DOMAIN: &[u8] = b"P-Project L2 transaction";
+
+// Length of an encoded recoverable signature: r (32) || s (32) || recovery id (1)
+pub const SIGNATURE_LENGTH: usize = 65;
+
+/// Hash that the sender signs. Binds the transaction to a chain so a signature
+/// cannot be replayed on another rollup instance.
+pub fn transaction_signing_hash(chain_id: &str, transaction: &L2Transaction) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(TX_SIGNING_DOMAIN);
+    for field in [
+        chain_id.as_bytes(),
+        transaction.from.as_bytes(),
+        transaction.to.as_bytes(),
+    ] {
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field);
+    }
+    hasher.update(transaction.amount.to_le_bytes());
+    hasher.update(transaction.fee.to_le_bytes());
+    hasher.update(transaction.nonce.to_le_bytes());
+    hasher.update(transaction.timestamp.and_utc().timestamp().to_le_bytes());
+    hasher.update(
+        transaction
+            .timestamp
+            .and_utc()
+            .timestamp_subsec_nanos()
+            .to_le_bytes(),
+    );
+    hasher.finalize().into()
+}
+
+/// L2 address of a public key: last 20 bytes of the Keccak256 of the uncompressed key
+pub fn address_from_verifying_key(key: &VerifyingKey) -> String {
+    let encoded = key.to_encoded_point(false);
+    let hash = Keccak256::digest(&encoded.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+/// Recover the address that produced the transaction signature
+pub fn recover_signer(chain_id: &str, transaction: &L2Transaction) -> Result<String, RollupError> {
+    let raw = transaction
+        .signature
+        .strip_prefix("0x")
+        .unwrap_or(&transaction.signature);
+    let bytes = hex::decode(raw).map_err(|_| RollupError::InvalidSignature)?;
+    if bytes.len() != SIGNATURE_LENGTH {
+        return Err(RollupError::InvalidSignature);
+    }
+
+    let signature =
+        Signature::from_slice(&bytes[..64]).map_err(|_| RollupError::InvalidSignature)?;
+    // Reject high-s signatures so a valid signature cannot be made malleable
+    if signature.normalize_s().is_some() {
+        return Err(RollupError::InvalidSignature);
+    }
+    let recovery_id = RecoveryId::from_byte(bytes[64]).ok_or(RollupError::InvalidSignature)?;
+
+    let hash = transaction_signing_hash(chain_id, transaction);
+    let key = VerifyingKey::recover_from_prehash(&hash, &signature, recovery_id)
+        .map_err(|_| RollupError::InvalidSignature)?;
+    Ok(address_from_verifying_key(&key))
+}
+
+/// Check that the transaction was signed by the key behind its `from` address
+pub fn verify_transaction_signature(
+    chain_id: &str,
+    transaction: &L2Transaction,
+) -> Result<(), RollupError> {
+    let signer = recover_signer(chain_id, transaction)?;
+    if signer == transaction.from {
+        Ok(())
+    } else {
+        Err(RollupError::InvalidSignature)
+    }
+}
+
+// secp256k1 key pair controlling an L2 address
+#[derive(Clone)]
+pub struct L2Keypair {
+    signing_key: SigningKey,
+    address: String,
+}
+
+impl L2Keypair {
+    /// Load a key pair from a 32-byte secret key
+    pub fn from_secret_bytes(secret: &[u8]) -> Result<Self, RollupError> {
+        let signing_key =
+            SigningKey::from_slice(secret).map_err(|_| RollupError::InvalidSignature)?;
+        Ok(Self::from_signing_key(signing_key))
+    }
+
+    /// Load a key pair from a hex encoded secret key
+    pub fn from_secret_hex(secret: &str) -> Result<Self, RollupError> {
+        let bytes = hex::decode(secret.strip_prefix("0x").unwrap_or(secret))
+            .map_err(|_| RollupError::InvalidSignature)?;
+        Self::from_secret_bytes(&bytes)
+    }
+
+    /// Deterministic key pair derived from a seed phrase; intended for tests and tooling
+    pub fn from_seed(seed: &str) -> Self {
+        let mut secret: [u8; 32] = Keccak256::digest(seed.as_bytes()).into();
+        loop {
+            if let Ok(signing_key) = SigningKey::from_slice(&secret) {
+                return Self::from_signing_key(signing_key);
+            }
+            secret = Keccak256::digest(secret).into();
+        }
+    }
+
+    fn from_signing_key(signing_key: SigningKey) -> Self {
+        let address = address_from_verifying_key(signing_key.verifying_key());
+        Self {
+            signing_key,
+            address,
+        }
+    }
+
+    /// L2 address controlled by this key
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Compressed SEC1 public key, hex encoded
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(
+            self.signing_key
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes(),
+        )
+    }
+
+    /// Sign a transaction in place
+    pub fn sign_transaction(
+        &self,
+        chain_id: &str,
+        transaction: &mut L2Transaction,
+    ) -> Result<(), RollupError> {
+        let hash = transaction_signing_hash(chain_id, transaction);
+        let (signature, recovery_id) = self
+            .signing_key
+            .sign_prehash_recoverable(&hash)
+            .map_err(|_| RollupError::InvalidSignature)?;
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(recovery_id.to_byte());
+        transaction.signature = hex::encode(bytes);
+        Ok(())
+    }
+
+    /// Build and sign a transfer from this key's address
+    pub fn signed_transfer(
+        &self,
+        chain_id: &str,
+        to: &str,
+        amount: f64,
+        nonce: u64,
+        timestamp: NaiveDateTime,
+    ) -> Result<L2Transaction, RollupError> {
+        self.signed_transfer_with_fee(chain_id, to, amount, 0.0, nonce, timestamp)
+    }
+
+    /// Build and sign a transfer paying a priority fee
+    pub fn signed_transfer_with_fee(
+        &self,
+        chain_id: &str,
+        to: &str,
+        amount: f64,
+        fee: f64,
+        nonce: u64,
+        timestamp: NaiveDateTime,
+    ) -> Result<L2Transaction, RollupError> {
+        let mut transaction = L2Transaction {
+            from: self.address.clone(),
+            to: to.to_string(),
+            amount,
+            fee,
+            nonce,
+            signature: String::new(),
+            timestamp,
+        };
+        self.sign_transaction(chain_id, &mut transaction)?;
+        Ok(transaction)
+    }
+
+    /// Build and sign a withdrawal that burns `amount` on L2 to exit it to L1
+    pub fn signed_withdrawal(
+        &self,
+        chain_id: &str,
+        amount: f64,
+        fee: f64,
+        nonce: u64,
+        timestamp: NaiveDateTime,
+    ) -> Result<L2Transaction, RollupError> {
+        self.signed_transfer_with_fee(chain_id, L2_EXIT_ADDRESS, amount, fee, nonce, timestamp)
+    }
+}
+
+impl std::fmt::Debug for L2Keypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the secret key
+        f.debug_struct("L2Keypair")
+            .field("address", &self.address)
+            .finish()
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use super::super::l2_rollup::RollupError;
+    use super::super::l2_signing::{
+        recover_signer, transaction_signing_hash, verify_transaction_signature, L2Keypair,
+    };
+    use chrono::{DateTime, NaiveDateTime};
+
+    fn ts() -> NaiveDateTime {
+        DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .naive_utc()
+    }
+
+    #[test]
+    fn test_known_key_address() {
+        // Secret key 1 maps to the well known Ethereum address of the generator point
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let keypair = L2Keypair::from_secret_bytes(&secret).unwrap();
+        assert_eq!(
+            keypair.address(),
+            "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf"
+        );
+        assert_eq!(
+            keypair.public_key_hex(),
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+
+        assert!(L2Keypair::from_secret_bytes(&[0u8; 32]).is_err());
+        assert!(L2Keypair::from_secret_hex("zz").is_err());
+        assert_eq!(
+            L2Keypair::from_secret_hex(&format!("0x{}", hex::encode(secret)))
+                .unwrap()
+                .address(),
+            keypair.address()
+        );
+    }
+
+    #[test]
+    fn test_sign_and_recover() {
+        let alice = L2Keypair::from_seed("alice");
+        let bob = L2Keypair::from_seed("bob");
+        assert_ne!(alice.address(), bob.address());
+        assert_eq!(alice.address(), L2Keypair::from_seed("alice").address());
+
+        let tx = alice
+            .signed_transfer("chain-a", bob.address(), 12.5, 3, ts())
+            .unwrap();
+        assert_eq!(tx.signature.len(), 130);
+        assert_eq!(recover_signer("chain-a", &tx).unwrap(), alice.address());
+        assert!(verify_transaction_signature("chain-a", &tx).is_ok());
+
+        // A transaction claiming another sender does not verify
+        let mut spoofed = tx.clone();
+        spoofed.from = bob.address().to_string();
+        assert_eq!(
+            verify_transaction_signature("chain-a", &spoofed),
+            Err(RollupError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_signing_hash_covers_every_field() {
+        let alice = L2Keypair::from_seed("alice");
+        let tx = alice
+            .signed_transfer("chain-a", "0xbeef", 1.0, 0, ts())
+            .unwrap();
+        let base = transaction_signing_hash("chain-a", &tx);
+
+        assert_ne!(base, transaction_signing_hash("chain-b", &tx));
+
+        let mut changed = tx.clone();
+        changed.to = "0xbeee".to_string();
+        assert_ne!(base, transaction_signing_hash("chain-a", &changed));
+
+        let mut changed = tx.clone();
+        changed.amount = 1.0000001;
+        assert_ne!(base, transaction_signing_hash("chain-a", &changed));
+
+        let mut changed = tx.clone();
+        changed.nonce = 1;
+        assert_ne!(base, transaction_signing_hash("chain-a", &changed));
+
+        let mut changed = tx.clone();
+        changed.timestamp = ts() + chrono::Duration::nanoseconds(1);
+        assert_ne!(base, transaction_signing_hash("chain-a", &changed));
+
+        // The signature itself is not part of the signed payload
+        let mut changed = tx.clone();
+        changed.signature = String::new();
+        assert_eq!(base, transaction_signing_hash("chain-a", &changed));
+    }
+
+    #[test]
+    fn test_rejects_malformed_signatures() {
+        let alice = L2Keypair::from_seed("alice");
+        let tx = alice
+            .signed_transfer("chain-a", "0xbeef", 1.0, 0, ts())
+            .unwrap();
+
+        for signature in ["", "00", "not hex", &tx.signature[..128]] {
+            let mut bad = tx.clone();
+            bad.signature = signature.to_string();
+            assert_eq!(
+                verify_transaction_signature("chain-a", &bad),
+                Err(RollupError::InvalidSignature)
+            );
+        }
+
+        // Invalid recovery id
+        let mut bad = tx.clone();
+        bad.signature = format!("{}09", &tx.signature[..128]);
+        assert!(recover_signer("chain-a", &bad).is_err());
+
+        // A flipped recovery id recovers some other key
+        let mut flipped = tx.clone();
+        let v = if tx.signature.ends_with("00") {
+            "01"
+        } else {
+            "00"
+        };
+        flipped.signature = format!("{}{}", &tx.signature[..128], v);
+        assert!(verify_transaction_signature("chain-a", &flipped).is_err());
+
+        // Prefixed hex is accepted
+        let mut prefixed = tx.clone();
+        prefixed.signature = format!("0x{}", tx.signature);
+        assert!(verify_transaction_signature("chain-a", &prefixed).is_ok());
+    }
+
+    #[test]
+    fn test_debug_hides_secret() {
+        let alice = L2Keypair::from_seed("alice");
+        let debug = format!("{:?}", alice);
+        assert!(debug.contains(alice.address()));
+        assert!(!debug.contains("signing_key"));
+    }
+}
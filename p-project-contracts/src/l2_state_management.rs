@@ -2,6 +2,7 @@ use crate::l2_rollup::{L2Account, RollupError};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
 // Merkle tree node for state commitments
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,79 +12,394 @@ pub struct MerkleNode {
     pub right: Option<Box<MerkleNode>>,
 }
 
-// Sparse Merkle tree implementation for efficient state storage
+/// Hash of a single account leaf: keccak(address || balance || nonce)
+pub fn account_leaf_hash(account: &L2Account) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update((account.address.len() as u64).to_le_bytes());
+    hasher.update(account.address.as_bytes());
+    hasher.update(account.balance.to_le_bytes());
+    hasher.update(account.nonce.to_le_bytes());
+    hasher.finalize().into()
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Root of a binary Merkle tree over an ordered list of leaves. An odd node is
+/// paired with itself; an empty list hashes to zero.
+pub fn ordered_merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0u8; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+    }
+    level[0]
+}
+
+/// Sibling path of the leaf at `index` in the tree built by `ordered_merkle_root`
+pub fn ordered_merkle_proof(leaves: &[[u8; 32]], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut position = index;
+    while level.len() > 1 {
+        let sibling = level.get(position ^ 1).unwrap_or(&level[position]);
+        proof.push(*sibling);
+        level = level
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+        position /= 2;
+    }
+    Some(proof)
+}
+
+/// Root implied by a leaf, its index and its sibling path
+pub fn ordered_merkle_root_from_proof(
+    leaf: [u8; 32],
+    index: usize,
+    proof: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    if proof.len() < usize::BITS as usize && index >> proof.len() != 0 {
+        return None;
+    }
+    let mut node = leaf;
+    for (height, sibling) in proof.iter().enumerate() {
+        node = if (index >> height) & 1 == 1 {
+            hash_pair(sibling, &node)
+        } else {
+            hash_pair(&node, sibling)
+        };
+    }
+    Some(node)
+}
+
+fn key_bit(key: &[u8; 32], bit: usize) -> bool {
+    key[bit / 8] & (0x80 >> (bit % 8)) != 0
+}
+
+// Key with every bit from `bits` onwards cleared: identifies the subtree containing it
+fn key_prefix(key: &[u8; 32], bits: usize) -> [u8; 32] {
+    let mut prefix = *key;
+    for (i, byte) in prefix.iter_mut().enumerate() {
+        let start = i * 8;
+        if start >= bits {
+            *byte = 0;
+        } else if start + 8 > bits {
+            *byte &= 0xffu8 << (start + 8 - bits);
+        }
+    }
+    prefix
+}
+
+fn flip_bit(key: &[u8; 32], bit: usize) -> [u8; 32] {
+    let mut flipped = *key;
+    flipped[bit / 8] ^= 0x80 >> (bit % 8);
+    flipped
+}
+
+fn parse_hash(value: &str) -> Option<[u8; 32]> {
+    let bytes = hex::decode(value).ok()?;
+    bytes.try_into().ok()
+}
+
+// Maximum depth of a sparse Merkle tree (one level per key bit)
+pub const MAX_TREE_DEPTH: usize = 256;
+
+/// Hash of an empty subtree of the given height: zero for a leaf, then hashed pairwise.
+/// The table is computed once for every height up to `MAX_TREE_DEPTH`.
+pub fn empty_subtree_hash(height: usize) -> [u8; 32] {
+    static EMPTY_HASHES: OnceLock<Vec<[u8; 32]>> = OnceLock::new();
+    let hashes = EMPTY_HASHES.get_or_init(|| {
+        let mut hashes = vec![[0u8; 32]];
+        for height in 0..MAX_TREE_DEPTH {
+            let below = hashes[height];
+            hashes.push(hash_pair(&below, &below));
+        }
+        hashes
+    });
+    hashes[height.min(MAX_TREE_DEPTH)]
+}
+
+// Compressed sparse Merkle proof. Siblings that are empty subtrees are left out and
+// marked by a cleared bit in the bitmap, so a proof in a sparse tree holds only a few
+// hashes instead of one per level. The same proof shows inclusion of a leaf value or,
+// checked against an empty leaf, non-inclusion of the key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmtProof {
+    pub depth: usize,
+    pub bitmap: Vec<u8>, // Bit h is set when the sibling at height h is not empty
+    pub siblings: Vec<String>, // Non-empty siblings from the leaf up to the root
+}
+
+impl SmtProof {
+    /// Compress a full proof (one sibling per level, leaf level first)
+    pub fn compress(proof: &[String]) -> Option<Self> {
+        let depth = proof.len();
+        if depth > MAX_TREE_DEPTH {
+            return None;
+        }
+        let mut bitmap = vec![0u8; depth.div_ceil(8)];
+        let mut siblings = Vec::new();
+        for (height, sibling) in proof.iter().enumerate() {
+            let hash = parse_hash(sibling)?;
+            if hash != empty_subtree_hash(height) {
+                bitmap[height / 8] |= 0x80 >> (height % 8);
+                siblings.push(hex::encode(hash));
+            }
+        }
+        Some(Self {
+            depth,
+            bitmap,
+            siblings,
+        })
+    }
+
+    /// Expand back into a full proof with one sibling per level
+    pub fn decompress(&self) -> Option<Vec<String>> {
+        if self.depth > MAX_TREE_DEPTH || self.bitmap.len() != self.depth.div_ceil(8) {
+            return None;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut proof = Vec::with_capacity(self.depth);
+        for height in 0..self.depth {
+            if self.has_sibling(height) {
+                proof.push(siblings.next()?.clone());
+            } else {
+                proof.push(hex::encode(empty_subtree_hash(height)));
+            }
+        }
+        // Every listed sibling must be accounted for by the bitmap
+        if siblings.next().is_some() {
+            return None;
+        }
+        Some(proof)
+    }
+
+    fn has_sibling(&self, height: usize) -> bool {
+        self.bitmap[height / 8] & (0x80 >> (height % 8)) != 0
+    }
+
+    /// Recompute the root from a leaf value (None for an empty slot)
+    pub fn compute_root(&self, index: &str, leaf: Option<[u8; 32]>) -> Option<String> {
+        SparseMerkleTree::compute_root(self.depth, index, leaf, &self.decompress()?)
+    }
+
+    /// Verify the leaf value at `index` against a root
+    pub fn verify(&self, root: &str, index: &str, leaf: [u8; 32]) -> bool {
+        self.compute_root(index, Some(leaf))
+            .is_some_and(|computed| computed == root)
+    }
+
+    /// Verify that nothing is stored at `index` under the given root
+    pub fn verify_non_inclusion(&self, root: &str, index: &str) -> bool {
+        self.compute_root(index, None)
+            .is_some_and(|computed| computed == root)
+    }
+
+    /// Compact binary encoding: depth (u16 LE), bitmap, then the 32-byte siblings
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RollupError> {
+        let mut bytes = Vec::with_capacity(2 + self.bitmap.len() + self.siblings.len() * 32);
+        bytes.extend_from_slice(&(self.depth as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.bitmap);
+        for sibling in &self.siblings {
+            let hash = parse_hash(sibling).ok_or(RollupError::MerkleTreeError)?;
+            bytes.extend_from_slice(&hash);
+        }
+        Ok(bytes)
+    }
+
+    /// Decode a proof produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RollupError> {
+        let invalid = || RollupError::SerializationError("invalid SMT proof".to_string());
+        let depth = u16::from_le_bytes(bytes.get(..2).ok_or_else(invalid)?.try_into().unwrap());
+        let depth = depth as usize;
+        if depth > MAX_TREE_DEPTH {
+            return Err(invalid());
+        }
+        let bitmap_end = 2 + depth.div_ceil(8);
+        let bitmap = bytes.get(2..bitmap_end).ok_or_else(invalid)?.to_vec();
+        let count: usize = bitmap.iter().map(|b| b.count_ones() as usize).sum();
+        let rest = &bytes[bitmap_end..];
+        if rest.len() != count * 32 {
+            return Err(invalid());
+        }
+        let proof = Self {
+            depth,
+            bitmap,
+            siblings: rest.chunks(32).map(hex::encode).collect(),
+        };
+        // Reject bitmaps marking levels beyond the depth
+        proof.decompress().ok_or_else(invalid)?;
+        Ok(proof)
+    }
+}
+
+// Sparse Merkle tree implementation for efficient state storage.
+// Leaves live at the path given by the first `depth` bits of keccak(index); empty
+// subtrees hash to the precomputed `empty_subtree_hash`. Only non-empty nodes are
+// stored, so updates and proofs touch one node per level.
 pub struct SparseMerkleTree {
-    pub root: Option<MerkleNode>,
     pub depth: usize,
+    leaves: HashMap<[u8; 32], [u8; 32]>,
+    nodes: HashMap<(usize, [u8; 32]), [u8; 32]>, // (height, key prefix) -> non-empty node hash
 }
 
 impl SparseMerkleTree {
     pub fn new(depth: usize) -> Self {
-        Self { root: None, depth }
+        Self {
+            depth: depth.min(MAX_TREE_DEPTH),
+            leaves: HashMap::new(),
+            nodes: HashMap::new(),
+        }
     }
 
-    /// Insert a leaf node at the given index
-    pub fn insert(&mut self, index: &str, value: &str) -> Result<(), RollupError> {
-        // For simplicity, we'll just return Ok
-        // In a real implementation, this would be more complex
-        Ok(())
+    /// Tree key for an index string
+    pub fn key_for(index: &str) -> [u8; 32] {
+        Keccak256::digest(index.as_bytes()).into()
     }
 
-    /// Get a leaf node at the given index
-    pub fn get(&self, index: &str) -> Option<String> {
-        // For simplicity, we'll just return None
-        // In a real implementation, this would be more complex
-        None
+    /// Insert a leaf hash at the given index (an all-zero value removes it)
+    pub fn insert(&mut self, index: &str, value: [u8; 32]) {
+        self.update_batch(&[(index, value)]);
     }
 
-    /// Generate Merkle proof for a leaf node
-    pub fn generate_proof(&self, index: &str) -> Option<Vec<String>> {
-        // For simplicity, we'll just return None
-        // In a real implementation, this would be more complex
-        None
+    /// Apply several leaf updates at once. Shared ancestors are rehashed once per
+    /// batch rather than once per leaf; later updates of the same index win.
+    pub fn update_batch(&mut self, updates: &[(&str, [u8; 32])]) {
+        let mut dirty: Vec<[u8; 32]> = Vec::with_capacity(updates.len());
+        for (index, value) in updates {
+            let key = key_prefix(&Self::key_for(index), self.depth);
+            if *value == [0u8; 32] {
+                self.leaves.remove(&key);
+            } else {
+                self.leaves.insert(key, *value);
+            }
+            dirty.push(key);
+        }
+
+        for height in 0..self.depth {
+            // Prefixes of the parents at height + 1 that need rehashing
+            let bit = self.depth - height - 1;
+            dirty = dirty.iter().map(|key| key_prefix(key, bit)).collect();
+            dirty.sort_unstable();
+            dirty.dedup();
+            for parent in &dirty {
+                let left = self.node(height, parent);
+                let right = self.node(height, &flip_bit(parent, bit));
+                let hash = hash_pair(&left, &right);
+                if hash == empty_subtree_hash(height + 1) {
+                    self.nodes.remove(&(height + 1, *parent));
+                } else {
+                    self.nodes.insert((height + 1, *parent), hash);
+                }
+            }
+        }
     }
 
-    fn key_to_path(&self, key: &str) -> String {
-        // For simplicity, we'll just return an empty string
-        // In a real implementation, this would be more complex
-        String::new()
+    // Hash of the node at `height` whose subtree contains `prefix`
+    fn node(&self, height: usize, prefix: &[u8; 32]) -> [u8; 32] {
+        let stored = if height == 0 {
+            self.leaves.get(prefix)
+        } else {
+            self.nodes.get(&(height, *prefix))
+        };
+        stored
+            .copied()
+            .unwrap_or_else(|| empty_subtree_hash(height))
     }
 
-    fn insert_recursive(
-        &mut self,
-        node: Option<MerkleNode>,
-        path: &str,
-        depth: usize,
-        value: &str,
-    ) -> Result<MerkleNode, RollupError> {
-        // For simplicity, we'll just return a default node
-        // In a real implementation, this would be more complex
-        Ok(MerkleNode {
-            hash: String::new(),
-            left: None,
-            right: None,
-        })
+    /// Get a leaf hash at the given index
+    pub fn get(&self, index: &str) -> Option<[u8; 32]> {
+        self.leaves
+            .get(&key_prefix(&Self::key_for(index), self.depth))
+            .copied()
     }
 
-    fn get_recursive(&self, node: Option<&MerkleNode>, path: &str, depth: usize) -> Option<String> {
-        // For simplicity, we'll just return None
-        // In a real implementation, this would be more complex
-        None
+    /// Number of non-empty leaves
+    pub fn len(&self) -> usize {
+        self.leaves.len()
     }
 
-    fn generate_proof_recursive(
-        &self,
-        node: Option<&MerkleNode>,
-        path: &str,
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Current root hash as hex
+    pub fn root(&self) -> String {
+        hex::encode(self.node(self.depth, &[0u8; 32]))
+    }
+
+    /// Generate Merkle proof (sibling hashes from leaf to root) for a leaf node.
+    /// Works for absent leaves too, proving the slot is empty.
+    pub fn generate_proof(&self, index: &str) -> Option<Vec<String>> {
+        let key = key_prefix(&Self::key_for(index), self.depth);
+        let proof = (0..self.depth)
+            .map(|height| {
+                let bit = self.depth - height - 1;
+                let sibling = flip_bit(&key_prefix(&key, bit + 1), bit);
+                hex::encode(self.node(height, &sibling))
+            })
+            .collect();
+        Some(proof)
+    }
+
+    /// Generate a compressed proof for a present or absent leaf
+    pub fn generate_compressed_proof(&self, index: &str) -> Option<SmtProof> {
+        SmtProof::compress(&self.generate_proof(index)?)
+    }
+
+    /// Recompute the root from a leaf value (None for an empty slot) and its proof
+    pub fn compute_root(
         depth: usize,
-        proof: &mut Vec<String>,
-    ) -> Option<()> {
-        // For simplicity, we'll just return None
-        // In a real implementation, this would be more complex
-        None
+        index: &str,
+        leaf: Option<[u8; 32]>,
+        proof: &[String],
+    ) -> Option<String> {
+        if proof.len() != depth || depth > MAX_TREE_DEPTH {
+            return None;
+        }
+        let key = Self::key_for(index);
+        let mut node = leaf.unwrap_or([0u8; 32]);
+        for (height, sibling) in proof.iter().enumerate() {
+            let sibling = parse_hash(sibling)?;
+            node = if key_bit(&key, depth - height - 1) {
+                hash_pair(&sibling, &node)
+            } else {
+                hash_pair(&node, &sibling)
+            };
+        }
+        Some(hex::encode(node))
+    }
+
+    /// Verify a proof for a leaf value (None proves absence) against a root
+    pub fn verify_proof(
+        root: &str,
+        depth: usize,
+        index: &str,
+        leaf: Option<[u8; 32]>,
+        proof: &[String],
+    ) -> bool {
+        Self::compute_root(depth, index, leaf, proof)
+            .map(|computed| computed == root)
+            .unwrap_or(false)
     }
 }
 
+// Depth of the account tree (one level per bit of keccak(address))
+pub const ACCOUNT_TREE_DEPTH: usize = MAX_TREE_DEPTH;
+
 // State manager for L2 accounts using Merkle tree
 pub struct L2StateManager {
     pub accounts: HashMap<String, L2Account>,
@@ -93,30 +409,40 @@ pub struct L2StateManager {
 
 impl L2StateManager {
     pub fn new() -> Self {
+        let merkle_tree = SparseMerkleTree::new(ACCOUNT_TREE_DEPTH);
+        let state_root = merkle_tree.root();
         Self {
             accounts: HashMap::new(),
-            merkle_tree: SparseMerkleTree::new(256), // 256-bit depth
-            state_root: "0".repeat(64),              // Empty root
+            merkle_tree,
+            state_root,
         }
     }
 
+    /// Build a state manager holding exactly the given accounts
+    pub fn from_accounts(accounts: &[L2Account]) -> Self {
+        let mut manager = Self::new();
+        manager.update_accounts(accounts.to_vec());
+        manager
+    }
+
     /// Get account by address
     pub fn get_account(&self, address: &str) -> Option<&L2Account> {
         self.accounts.get(address)
     }
 
+    /// Get account balance (zero for unknown accounts)
+    pub fn get_balance(&self, address: &str) -> f64 {
+        self.accounts.get(address).map(|a| a.balance).unwrap_or(0.0)
+    }
+
     /// Update account and merkle tree
     pub fn update_account(&mut self, account: L2Account) -> Result<(), RollupError> {
-        // Update account in hashmap
-        self.accounts
-            .insert(account.address.clone(), account.clone());
-
-        // Serialize account data
-        let account_data = serde_json::to_string(&account)
-            .map_err(|e| RollupError::SerializationError(e.to_string()))?;
-
         // Insert into merkle tree
-        self.merkle_tree.insert(&account.address, &account_data)?;
+        self.merkle_tree
+            .insert(&account.address, account_leaf_hash(&account));
+
+        // Update account in hashmap
+        self.accounts.insert(account.address.clone(), account);
 
         // Update state root
         self.update_state_root();
@@ -124,24 +450,23 @@ impl L2StateManager {
         Ok(())
     }
 
-    /// Update state root from merkle tree
-    pub fn update_state_root(&mut self) {
-        // In a real implementation, we would get the root from the merkle tree
-        // For now, we'll create a simple hash of all accounts
-        let mut hasher = Keccak256::new();
-
-        // Sort accounts by address for consistent hashing
-        let mut accounts: Vec<(&String, &L2Account)> = self.accounts.iter().collect();
-        accounts.sort_by(|a, b| a.0.cmp(b.0));
-
-        for (_, account) in accounts {
-            hasher.update(account.address.as_bytes());
-            hasher.update(&account.balance.to_le_bytes());
-            hasher.update(&account.nonce.to_le_bytes());
+    /// Update several accounts with a single batched tree update
+    pub fn update_accounts(&mut self, accounts: Vec<L2Account>) {
+        let leaves: Vec<(String, [u8; 32])> = accounts
+            .iter()
+            .map(|a| (a.address.clone(), account_leaf_hash(a)))
+            .collect();
+        let updates: Vec<(&str, [u8; 32])> = leaves.iter().map(|(a, h)| (a.as_str(), *h)).collect();
+        self.merkle_tree.update_batch(&updates);
+        for account in accounts {
+            self.accounts.insert(account.address.clone(), account);
         }
+        self.update_state_root();
+    }
 
-        let result = hasher.finalize();
-        self.state_root = format!("{:x}", result);
+    /// Update state root from merkle tree
+    pub fn update_state_root(&mut self) {
+        self.state_root = self.merkle_tree.root();
     }
 
     /// Get current state root
@@ -149,34 +474,68 @@ impl L2StateManager {
         &self.state_root
     }
 
-    /// Generate Merkle proof for an account
+    /// Generate Merkle proof for an account (absent accounts get a proof of the empty slot)
     pub fn generate_account_proof(&self, address: &str) -> Option<Vec<String>> {
         self.merkle_tree.generate_proof(address)
     }
 
-    /// Verify account proof
+    /// Compressed Merkle proof for an account; for an absent account it proves non-inclusion
+    pub fn generate_compressed_account_proof(&self, address: &str) -> Option<SmtProof> {
+        self.merkle_tree.generate_compressed_proof(address)
+    }
+
+    /// Verify a compressed account proof (None proves the account does not exist) against any root
+    pub fn verify_compressed_proof_against_root(
+        root: &str,
+        address: &str,
+        account: Option<&L2Account>,
+        proof: &SmtProof,
+    ) -> bool {
+        proof.depth == ACCOUNT_TREE_DEPTH
+            && proof
+                .compute_root(address, account.map(account_leaf_hash))
+                .is_some_and(|computed| computed == root)
+    }
+
+    /// Verify account proof against the current state root
     pub fn verify_account_proof(
         &self,
         address: &str,
         account: &L2Account,
         proof: &[String],
     ) -> bool {
-        // Serialize account data
-        let account_data = match serde_json::to_string(account) {
-            Ok(data) => data,
-            Err(_) => return false,
-        };
+        if account.address != address {
+            return false;
+        }
+        Self::verify_proof_against_root(&self.state_root, address, Some(account), proof)
+    }
 
-        // Hash the account data
-        let mut hasher = Keccak256::new();
-        hasher.update(account_data.as_bytes());
-        let account_hash = format!("{:x}", hasher.finalize());
+    /// Verify an account proof (None proves the account does not exist) against any root
+    pub fn verify_proof_against_root(
+        root: &str,
+        address: &str,
+        account: Option<&L2Account>,
+        proof: &[String],
+    ) -> bool {
+        SparseMerkleTree::verify_proof(
+            root,
+            ACCOUNT_TREE_DEPTH,
+            address,
+            account.map(account_leaf_hash),
+            proof,
+        )
+    }
 
-        // Verify the proof against the current state root
-        // In a real implementation, we would reconstruct the root from the proof
-        // and compare it with the current state root
-        // For now, we'll just do a basic check
-        !proof.is_empty()
+    /// Root obtained by replacing the proven leaf with a new account value
+    pub fn root_with_account(
+        address: &str,
+        account: &L2Account,
+        proof: &SmtProof,
+    ) -> Option<String> {
+        if proof.depth != ACCOUNT_TREE_DEPTH {
+            return None;
+        }
+        proof.compute_root(address, Some(account_leaf_hash(account)))
     }
 
     /// Initialize account with balance
@@ -195,6 +554,12 @@ impl L2StateManager {
     }
 }
 
+impl Default for L2StateManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // State snapshot for checkpointing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateSnapshot {
@@ -202,6 +567,12 @@ pub struct StateSnapshot {
     pub block_number: u64,
     pub timestamp: u64,
     pub accounts_hash: String, // Hash of all accounts for quick verification
+    #[serde(default)]
+    pub batch_number: u64, // Number of batches submitted when the snapshot was taken
+    #[serde(default)]
+    pub accounts: Vec<L2Account>, // Full account state, sorted by address (empty for root-only snapshots)
+    #[serde(default)]
+    pub system_sequence: u64, // System transfers applied to the rollup when the snapshot was taken
 }
 
 impl StateSnapshot {
@@ -222,8 +593,38 @@ impl StateSnapshot {
             block_number,
             timestamp,
             accounts_hash,
+            batch_number: 0,
+            accounts: Vec::new(),
+            system_sequence: 0,
         }
     }
+
+    /// Create a snapshot carrying the full account state so it can be restored
+    pub fn with_accounts(
+        state_root: String,
+        block_number: u64,
+        batch_number: u64,
+        accounts: Vec<L2Account>,
+    ) -> Self {
+        let mut accounts = accounts;
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+
+        let mut snapshot = Self::new(state_root, block_number);
+        let mut hasher = Keccak256::new();
+        hasher.update(snapshot.accounts_hash.as_bytes());
+        for account in &accounts {
+            hasher.update(account_leaf_hash(account));
+        }
+        snapshot.accounts_hash = format!("{:x}", hasher.finalize());
+        snapshot.batch_number = batch_number;
+        snapshot.accounts = accounts;
+        snapshot
+    }
+
+    /// Whether the snapshot carries account state that can be restored
+    pub fn is_restorable(&self) -> bool {
+        !self.accounts.is_empty() || self.state_root == L2StateManager::new().get_state_root()
+    }
 }
 
 // State checkpoint manager
@@ -240,9 +641,19 @@ impl StateCheckpointManager {
         }
     }
 
+    /// Checkpoint manager that never evicts on its own; snapshots are only dropped by
+    /// `prune_before_batch`
+    pub fn unbounded() -> Self {
+        Self::new(usize::MAX)
+    }
+
     /// Create a new snapshot
     pub fn create_snapshot(&mut self, state_root: String, block_number: u64) {
-        let snapshot = StateSnapshot::new(state_root, block_number);
+        self.add_snapshot(StateSnapshot::new(state_root, block_number));
+    }
+
+    /// Store a prepared snapshot (e.g. one carrying account state)
+    pub fn add_snapshot(&mut self, snapshot: StateSnapshot) {
         self.snapshots.push(snapshot);
 
         // Keep only the latest snapshots
@@ -251,6 +662,19 @@ impl StateCheckpointManager {
         }
     }
 
+    /// Latest restorable snapshot taken before the given batch number was submitted
+    pub fn get_snapshot_before_batch(&self, batch_number: u64) -> Option<&StateSnapshot> {
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|s| s.batch_number < batch_number && s.is_restorable())
+    }
+
+    /// Drop every snapshot taken before `batch_number` was submitted
+    pub fn prune_before_batch(&mut self, batch_number: u64) {
+        self.snapshots.retain(|s| s.batch_number >= batch_number);
+    }
+
     /// Get latest snapshot
     pub fn get_latest_snapshot(&self) -> Option<&StateSnapshot> {
         self.snapshots.last()
@@ -265,6 +689,14 @@ impl StateCheckpointManager {
 
     /// Verify snapshot integrity
     pub fn verify_snapshot(&self, snapshot: &StateSnapshot) -> bool {
+        // Full snapshots must reproduce their state root
+        if !snapshot.accounts.is_empty() {
+            let restored = L2StateManager::from_accounts(&snapshot.accounts);
+            if restored.get_state_root() != snapshot.state_root {
+                return false;
+            }
+        }
+
         // Verify timestamp is not in the future
         let current_time = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
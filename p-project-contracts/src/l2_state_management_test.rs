@@ -1,14 +1,47 @@
 #[cfg(test)]
 mod tests {
-    use super::super::l2_state_management::{L2StateManager, StateCheckpointManager};
     use super::super::l2_rollup::L2Account;
+    use super::super::l2_state_management::{
+        account_leaf_hash, empty_subtree_hash, L2StateManager, SmtProof, SparseMerkleTree,
+        StateCheckpointManager,
+    };
+    use sha3::{Digest, Keccak256};
+
+    fn leaf(i: u8) -> [u8; 32] {
+        [i.max(1); 32]
+    }
+
+    // Reference root: hash every level of a full tree of the given (small) depth
+    fn naive_root(depth: usize, leaves: &[(&str, [u8; 32])]) -> String {
+        let mut level = vec![[0u8; 32]; 1 << depth];
+        for (index, value) in leaves {
+            let key = SparseMerkleTree::key_for(index);
+            let position = (u16::from_be_bytes([key[0], key[1]]) >> (16 - depth)) as usize;
+            level[position] = *value;
+        }
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| {
+                    let mut hasher = Keccak256::new();
+                    hasher.update(pair[0]);
+                    hasher.update(pair[1]);
+                    hasher.finalize().into()
+                })
+                .collect();
+        }
+        hex::encode(level[0])
+    }
 
     #[test]
     fn test_create_state_manager() {
         let state_manager = L2StateManager::new();
 
         assert_eq!(state_manager.accounts.len(), 0);
-        assert_eq!(state_manager.state_root, "0".repeat(64));
+        assert_eq!(
+            state_manager.state_root,
+            hex::encode(empty_subtree_hash(256))
+        );
     }
 
     #[test]
@@ -46,7 +79,9 @@ mod tests {
     fn test_get_account() {
         let mut state_manager = L2StateManager::new();
 
-        state_manager.initialize_account("user1".to_string(), 1000.0).unwrap();
+        state_manager
+            .initialize_account("user1".to_string(), 1000.0)
+            .unwrap();
 
         let account = state_manager.get_account("user1");
         assert!(account.is_some());
@@ -60,8 +95,12 @@ mod tests {
     fn test_get_balance() {
         let mut state_manager = L2StateManager::new();
 
-        state_manager.initialize_account("user1".to_string(), 1000.0).unwrap();
-        state_manager.initialize_account("user2".to_string(), 500.0).unwrap();
+        state_manager
+            .initialize_account("user1".to_string(), 1000.0)
+            .unwrap();
+        state_manager
+            .initialize_account("user2".to_string(), 500.0)
+            .unwrap();
 
         assert_eq!(state_manager.get_balance("user1"), 1000.0);
         assert_eq!(state_manager.get_balance("user2"), 500.0);
@@ -71,11 +110,15 @@ mod tests {
     #[test]
     fn test_update_state_root() {
         let mut state_manager = L2StateManager::new();
+        let old_state_root = state_manager.state_root.clone();
 
-        state_manager.initialize_account("user1".to_string(), 1000.0).unwrap();
-        state_manager.initialize_account("user2".to_string(), 500.0).unwrap();
+        state_manager
+            .initialize_account("user1".to_string(), 1000.0)
+            .unwrap();
+        state_manager
+            .initialize_account("user2".to_string(), 500.0)
+            .unwrap();
 
-        let old_state_root = state_manager.state_root.clone();
         state_manager.update_state_root();
         let new_state_root = state_manager.state_root.clone();
 
@@ -88,8 +131,12 @@ mod tests {
     fn test_get_all_accounts() {
         let mut state_manager = L2StateManager::new();
 
-        state_manager.initialize_account("user1".to_string(), 1000.0).unwrap();
-        state_manager.initialize_account("user2".to_string(), 500.0).unwrap();
+        state_manager
+            .initialize_account("user1".to_string(), 1000.0)
+            .unwrap();
+        state_manager
+            .initialize_account("user2".to_string(), 500.0)
+            .unwrap();
 
         let accounts = state_manager.get_all_accounts();
         assert_eq!(accounts.len(), 2);
@@ -101,7 +148,9 @@ mod tests {
     fn test_generate_account_proof() {
         let mut state_manager = L2StateManager::new();
 
-        state_manager.initialize_account("user1".to_string(), 1000.0).unwrap();
+        state_manager
+            .initialize_account("user1".to_string(), 1000.0)
+            .unwrap();
 
         let proof = state_manager.generate_account_proof("user1");
         // In our simplified implementation, we just check that proof exists
@@ -110,15 +159,165 @@ mod tests {
 
     #[test]
     fn test_sparse_merkle_tree() {
-        let mut tree = super::super::l2_state_management::SparseMerkleTree::new(4);
+        let mut tree = SparseMerkleTree::new(4);
+        let empty_root = tree.root();
+
+        tree.insert("key1", leaf(1));
+        assert_eq!(tree.get("key1"), Some(leaf(1)));
+        assert_eq!(tree.get("key2"), None);
+        assert_ne!(tree.root(), empty_root);
+
+        // Removing the only leaf brings back the empty root
+        tree.insert("key1", [0u8; 32]);
+        assert_eq!(tree.get("key1"), None);
+        assert_eq!(tree.root(), empty_root);
+        assert!(tree.is_empty());
+    }
 
-        let result = tree.insert("key1", "value1");
-        assert!(result.is_ok());
+    #[test]
+    fn test_root_matches_full_tree() {
+        let leaves: Vec<(String, [u8; 32])> =
+            (0..40u8).map(|i| (format!("user{}", i), leaf(i))).collect();
+        let leaves: Vec<(&str, [u8; 32])> = leaves.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+
+        let mut tree = SparseMerkleTree::new(8);
+        for (index, value) in &leaves {
+            tree.insert(index, *value);
+        }
+        assert_eq!(tree.root(), naive_root(8, &leaves));
+        assert_eq!(
+            SparseMerkleTree::new(8).root(),
+            hex::encode(empty_subtree_hash(8))
+        );
+    }
 
-        let value = tree.get("key1");
-        // In our simplified implementation, we don't actually store values
-        // This test just verifies the structure works
-        assert!(value.is_some() || value.is_none()); // Either is fine for our simplified implementation
+    #[test]
+    fn test_batch_update_matches_sequential_inserts() {
+        let mut sequential = SparseMerkleTree::new(256);
+        for i in 0..50u8 {
+            sequential.insert(&format!("account{}", i), leaf(i));
+        }
+        sequential.insert("account7", leaf(99));
+        sequential.insert("account8", [0u8; 32]);
+
+        let keys: Vec<String> = (0..50u8).map(|i| format!("account{}", i)).collect();
+        let mut updates: Vec<(&str, [u8; 32])> = keys
+            .iter()
+            .zip(0..)
+            .map(|(k, i)| (k.as_str(), leaf(i)))
+            .collect();
+        updates.push(("account7", leaf(99)));
+        updates.push(("account8", [0u8; 32]));
+        let mut batched = SparseMerkleTree::new(256);
+        batched.update_batch(&updates);
+
+        assert_eq!(batched.root(), sequential.root());
+        assert_eq!(batched.len(), 49);
+        assert_eq!(batched.get("account7"), Some(leaf(99)));
+    }
+
+    #[test]
+    fn test_inclusion_and_non_inclusion_proofs() {
+        let mut tree = SparseMerkleTree::new(256);
+        for i in 0..20u8 {
+            tree.insert(&format!("user{}", i), leaf(i));
+        }
+        let root = tree.root();
+
+        let proof = tree.generate_compressed_proof("user3").unwrap();
+        // Only the few non-empty siblings near the leaves are carried
+        assert!(proof.siblings.len() < 16);
+        assert!(proof.verify(&root, "user3", leaf(3)));
+        assert!(!proof.verify(&root, "user3", leaf(4)));
+        assert!(!proof.verify(&root, "user4", leaf(3)));
+        assert!(!proof.verify_non_inclusion(&root, "user3"));
+
+        let absent = tree.generate_compressed_proof("nobody").unwrap();
+        assert!(absent.verify_non_inclusion(&root, "nobody"));
+        assert!(!absent.verify(&root, "nobody", leaf(1)));
+
+        // Compressed and full proofs are interchangeable
+        let full = tree.generate_proof("user3").unwrap();
+        assert_eq!(full.len(), 256);
+        assert_eq!(SmtProof::compress(&full).unwrap(), proof);
+        assert_eq!(proof.decompress().unwrap(), full);
+        assert!(SparseMerkleTree::verify_proof(
+            &root,
+            256,
+            "user3",
+            Some(leaf(3)),
+            &full
+        ));
+    }
+
+    #[test]
+    fn test_proof_serialization() {
+        let mut tree = SparseMerkleTree::new(256);
+        for i in 0..10u8 {
+            tree.insert(&format!("user{}", i), leaf(i));
+        }
+        let root = tree.root();
+        let proof = tree.generate_compressed_proof("user5").unwrap();
+
+        let bytes = proof.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 2 + 32 + proof.siblings.len() * 32);
+        let decoded = SmtProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(decoded.verify(&root, "user5", leaf(5)));
+
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<SmtProof>(&json).unwrap(), proof);
+
+        // Truncated data and a bitmap that disagrees with the siblings are rejected
+        assert!(SmtProof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(SmtProof::from_bytes(&[]).is_err());
+        let mut extra_bit = proof.clone();
+        let height = (0..256)
+            .find(|h| extra_bit.bitmap[h / 8] & (0x80 >> (h % 8)) == 0)
+            .unwrap();
+        extra_bit.bitmap[height / 8] |= 0x80 >> (height % 8);
+        assert!(extra_bit.decompress().is_none());
+        assert!(!extra_bit.verify(&root, "user5", leaf(5)));
+    }
+
+    #[test]
+    fn test_account_proofs_against_state_root() {
+        let accounts: Vec<L2Account> = (0..5)
+            .map(|i| L2Account {
+                address: format!("user{}", i),
+                balance: 100.0 * i as f64,
+                nonce: i,
+            })
+            .collect();
+        let manager = L2StateManager::from_accounts(&accounts);
+        let mut sequential = L2StateManager::new();
+        for account in &accounts {
+            sequential.update_account(account.clone()).unwrap();
+        }
+        assert_eq!(manager.state_root, sequential.state_root);
+
+        let root = manager.get_state_root();
+        let proof = manager.generate_compressed_account_proof("user2").unwrap();
+        assert!(L2StateManager::verify_compressed_proof_against_root(
+            root,
+            "user2",
+            Some(&accounts[2]),
+            &proof
+        ));
+        assert!(proof.verify(root, "user2", account_leaf_hash(&accounts[2])));
+
+        let absent = manager.generate_compressed_account_proof("user9").unwrap();
+        assert!(L2StateManager::verify_compressed_proof_against_root(
+            root, "user9", None, &absent
+        ));
+
+        // Updating the proven leaf yields the root of the updated state
+        let mut updated = accounts[2].clone();
+        updated.balance += 1.0;
+        let expected = L2StateManager::root_with_account("user2", &updated, &proof).unwrap();
+        let mut next = L2StateManager::from_accounts(&accounts);
+        next.update_account(updated).unwrap();
+        assert_eq!(next.state_root, expected);
     }
 
     #[test]
@@ -147,9 +346,10 @@ mod tests {
     #[test]
     fn test_verify_snapshot() {
         let checkpoint_manager = StateCheckpointManager::new(3);
-        let snapshot = super::super::l2_state_management::StateSnapshot::new("root1".to_string(), 1);
+        let snapshot =
+            super::super::l2_state_management::StateSnapshot::new("root1".to_string(), 1);
 
         let result = checkpoint_manager.verify_snapshot(&snapshot);
         assert!(result);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,343 @@
+use crate::l2_rollup::{
+    BatchStatus, L2Account, L2Batch, L2Block, RollupError, SystemTransfer, TransactionReceipt,
+};
+use crate::l2_state_management::StateSnapshot;
+use crate::l2_withdrawal::Withdrawal;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Number of snapshot files kept by the file storage
+pub const DEFAULT_SNAPSHOTS_KEPT: usize = 3;
+
+// Records appended between two chain snapshots
+pub const DEFAULT_SNAPSHOT_INTERVAL: u64 = 256;
+
+// One entry of the rollup's append-only log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum StorageRecord {
+    // Account values written since the previous record, with the resulting state root
+    Accounts {
+        updated: Vec<L2Account>,
+        removed: Vec<String>,
+        state_root: String,
+    },
+    Block(L2Block),
+    Batch(L2Batch),
+    Checkpoint(StateSnapshot),
+    BatchStatus {
+        batch_id: String,
+        status: BatchStatus,
+    },
+    Rollback {
+        batch_number: u64,
+    },
+    // A bridge credit or escrow move and its receipt; the preceding Accounts record
+    // holds the accounts it changed
+    SystemTransfer {
+        transfer: SystemTransfer,
+        receipt: TransactionReceipt,
+    },
+}
+
+// Full rollup state covering every log record up to `sequence`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainSnapshot {
+    pub sequence: u64,
+    pub state_root: String,
+    pub accounts: Vec<L2Account>,
+    pub blocks: Vec<L2Block>,
+    pub batches: Vec<L2Batch>,
+    pub checkpoints: Vec<StateSnapshot>,
+    pub receipts: Vec<TransactionReceipt>,
+    pub withdrawal_queue: Vec<Withdrawal>,
+    #[serde(default)]
+    pub system_transfers: Vec<SystemTransfer>,
+    #[serde(default)]
+    pub system_sequence: u64,
+    pub latest_block_number: u64,
+    pub latest_batch_id: u64,
+}
+
+// Pluggable persistence for the rollup: an ordered record log plus snapshots
+pub trait L2Storage {
+    /// Append records to the log; returns the sequence number of the last one
+    fn append(&mut self, records: &[StorageRecord]) -> Result<u64, RollupError>;
+
+    /// Records with a sequence number greater than `sequence`, in order
+    fn read_after(&self, sequence: u64) -> Result<Vec<(u64, StorageRecord)>, RollupError>;
+
+    /// Sequence number of the last appended record (0 when the log is empty)
+    fn last_sequence(&self) -> u64;
+
+    /// Persist a snapshot of the chain
+    fn save_snapshot(&mut self, snapshot: &ChainSnapshot) -> Result<(), RollupError>;
+
+    /// Most recent readable snapshot, if any
+    fn latest_snapshot(&self) -> Result<Option<ChainSnapshot>, RollupError>;
+}
+
+// Storage kept in memory; useful for tests and ephemeral nodes
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    pub records: Vec<(u64, StorageRecord)>,
+    pub snapshots: Vec<ChainSnapshot>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl L2Storage for MemoryStorage {
+    fn append(&mut self, records: &[StorageRecord]) -> Result<u64, RollupError> {
+        for record in records {
+            let sequence = self.last_sequence() + 1;
+            self.records.push((sequence, record.clone()));
+        }
+        Ok(self.last_sequence())
+    }
+
+    fn read_after(&self, sequence: u64) -> Result<Vec<(u64, StorageRecord)>, RollupError> {
+        Ok(self
+            .records
+            .iter()
+            .filter(|(s, _)| *s > sequence)
+            .cloned()
+            .collect())
+    }
+
+    fn last_sequence(&self) -> u64 {
+        self.records.last().map(|(s, _)| *s).unwrap_or(0)
+    }
+
+    fn save_snapshot(&mut self, snapshot: &ChainSnapshot) -> Result<(), RollupError> {
+        self.snapshots.push(snapshot.clone());
+        Ok(())
+    }
+
+    fn latest_snapshot(&self) -> Result<Option<ChainSnapshot>, RollupError> {
+        Ok(self.snapshots.last().cloned())
+    }
+}
+
+// Line of the log file: the record with its sequence number and checksum
+#[derive(Serialize, Deserialize)]
+struct LogEntry {
+    sequence: u64,
+    checksum: String,
+    record: StorageRecord,
+}
+
+fn record_checksum(record: &StorageRecord) -> Result<String, RollupError> {
+    let bytes =
+        serde_json::to_vec(record).map_err(|e| RollupError::SerializationError(e.to_string()))?;
+    Ok(format!("{:x}", Keccak256::digest(bytes)))
+}
+
+fn io_error(e: std::io::Error) -> RollupError {
+    RollupError::StorageError(e.to_string())
+}
+
+// File-backed storage: `log.jsonl` holds one checksummed record per line and
+// `snapshot-<sequence>.json` files hold periodic chain snapshots. A torn last line
+// left by a crash during append is discarded when the storage is opened.
+pub struct FileStorage {
+    dir: PathBuf,
+    log: File,
+    last_sequence: u64,
+    pub snapshots_kept: usize,
+}
+
+impl FileStorage {
+    /// Open (or create) the storage in a directory
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, RollupError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(io_error)?;
+        let log_path = dir.join("log.jsonl");
+
+        let (entries, valid_len) = if log_path.exists() {
+            Self::scan_log(&log_path)?
+        } else {
+            (Vec::new(), 0)
+        };
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .map_err(io_error)?;
+        // Drop a partially written trailing record
+        if log.metadata().map_err(io_error)?.len() > valid_len {
+            log.set_len(valid_len).map_err(io_error)?;
+        }
+
+        Ok(Self {
+            dir,
+            log,
+            last_sequence: entries.last().map(|e| e.sequence).unwrap_or(0),
+            snapshots_kept: DEFAULT_SNAPSHOTS_KEPT,
+        })
+    }
+
+    /// Directory holding the log and snapshots
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // Read every complete entry and the byte length they occupy. Only the last line
+    // may be damaged (or missing its newline); anything else is reported as corruption.
+    fn scan_log(path: &Path) -> Result<(Vec<LogEntry>, u64), RollupError> {
+        let bytes = fs::read(path).map_err(io_error)?;
+        let mut entries: Vec<LogEntry> = Vec::new();
+        let mut valid_len = 0u64;
+        let mut lines = bytes.split_inclusive(|b| *b == b'\n').peekable();
+        while let Some(line) = lines.next() {
+            let entry = line
+                .strip_suffix(b"\n")
+                .and_then(|json| serde_json::from_slice::<LogEntry>(json).ok())
+                .filter(|e| record_checksum(&e.record).ok().as_deref() == Some(&e.checksum));
+            let Some(entry) = entry else {
+                if lines.peek().is_some() {
+                    return Err(RollupError::StorageError(
+                        "corrupted record in the middle of the log".to_string(),
+                    ));
+                }
+                break;
+            };
+            let expected = entries.last().map(|e| e.sequence + 1).unwrap_or(1);
+            if entry.sequence != expected {
+                return Err(RollupError::StorageError(format!(
+                    "log sequence gap: expected {}, found {}",
+                    expected, entry.sequence
+                )));
+            }
+            valid_len += line.len() as u64;
+            entries.push(entry);
+        }
+        Ok((entries, valid_len))
+    }
+
+    fn snapshot_files(&self) -> Result<Vec<(u64, PathBuf)>, RollupError> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            let sequence = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix("snapshot-"))
+                .and_then(|n| n.strip_suffix(".json"))
+                .and_then(|n| n.parse::<u64>().ok());
+            if let Some(sequence) = sequence {
+                files.push((sequence, path));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+impl L2Storage for FileStorage {
+    fn append(&mut self, records: &[StorageRecord]) -> Result<u64, RollupError> {
+        let mut buffer = Vec::new();
+        let mut sequence = self.last_sequence;
+        for record in records {
+            sequence += 1;
+            let entry = LogEntry {
+                sequence,
+                checksum: record_checksum(record)?,
+                record: record.clone(),
+            };
+            serde_json::to_writer(&mut buffer, &entry)
+                .map_err(|e| RollupError::SerializationError(e.to_string()))?;
+            buffer.push(b'\n');
+        }
+        let length = self.log.metadata().map_err(io_error)?.len();
+        if let Err(e) = self
+            .log
+            .write_all(&buffer)
+            .and_then(|_| self.log.sync_data())
+        {
+            // Never leave a partial record in front of the next append
+            let _ = self.log.set_len(length);
+            return Err(io_error(e));
+        }
+        self.last_sequence = sequence;
+        Ok(sequence)
+    }
+
+    fn read_after(&self, sequence: u64) -> Result<Vec<(u64, StorageRecord)>, RollupError> {
+        let (entries, _) = Self::scan_log(&self.dir.join("log.jsonl"))?;
+        Ok(entries
+            .into_iter()
+            .filter(|e| e.sequence > sequence)
+            .map(|e| (e.sequence, e.record))
+            .collect())
+    }
+
+    fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    fn save_snapshot(&mut self, snapshot: &ChainSnapshot) -> Result<(), RollupError> {
+        let bytes = serde_json::to_vec(snapshot)
+            .map_err(|e| RollupError::SerializationError(e.to_string()))?;
+        let path = self
+            .dir
+            .join(format!("snapshot-{:020}.json", snapshot.sequence));
+        let tmp = path.with_extension("json.tmp");
+        {
+            let mut file = File::create(&tmp).map_err(io_error)?;
+            file.write_all(&bytes).map_err(io_error)?;
+            file.sync_all().map_err(io_error)?;
+        }
+        // Rename so a crash never leaves a half-written snapshot under the final name
+        fs::rename(&tmp, &path).map_err(io_error)?;
+
+        let files = self.snapshot_files()?;
+        let excess = files.len().saturating_sub(self.snapshots_kept.max(1));
+        for (_, old) in files.into_iter().take(excess) {
+            fs::remove_file(old).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    fn latest_snapshot(&self) -> Result<Option<ChainSnapshot>, RollupError> {
+        // Fall back to an older snapshot if the newest one cannot be read
+        for (_, path) in self.snapshot_files()?.into_iter().rev() {
+            let snapshot = fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<ChainSnapshot>(&bytes).ok());
+            if snapshot.is_some() {
+                return Ok(snapshot);
+            }
+        }
+        Ok(None)
+    }
+}
+
+// Storage attached to a running rollup, with the records not yet written
+pub struct RollupPersistence {
+    pub storage: Box<dyn L2Storage>,
+    pub snapshot_interval: u64,
+    pub(crate) pending: Vec<StorageRecord>,
+    pub(crate) last_snapshot: u64, // Sequence covered by the latest snapshot
+}
+
+impl RollupPersistence {
+    pub fn new(storage: Box<dyn L2Storage>, snapshot_interval: u64) -> Self {
+        Self {
+            storage,
+            snapshot_interval: snapshot_interval.max(1),
+            pending: Vec::new(),
+            last_snapshot: 0,
+        }
+    }
+
+    /// Records buffered since the last flush
+    pub fn pending_records(&self) -> usize {
+        self.pending.len()
+    }
+}
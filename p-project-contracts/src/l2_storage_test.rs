@@ -0,0 +1,249 @@
+#[cfg(test)]
+mod tests {
+    use super::super::l2_rollup::{BatchStatus, L2Rollup, RollupError};
+    use super::super::l2_storage::{FileStorage, L2Storage, MemoryStorage, StorageRecord};
+    use super::super::l2_test_support::{self as support, addr, config, transfer, withdraw};
+    use chrono::{Duration, Utc};
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn rollup() -> L2Rollup {
+        let mut rollup = support::rollup();
+        rollup.set_challenge_period(60);
+        rollup
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "p-project-l2-storage-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn json<T: serde::Serialize>(value: &T) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    fn assert_same_chain(a: &L2Rollup, b: &L2Rollup) {
+        assert_eq!(a.get_state_root(), b.get_state_root());
+        assert_eq!(a.blocks, b.blocks);
+        assert_eq!(json(&a.batches), json(&b.batches));
+        assert_eq!(a.latest_block_number, b.latest_block_number);
+        assert_eq!(a.latest_batch_id, b.latest_batch_id);
+        assert_eq!(a.withdrawal_queue, b.withdrawal_queue);
+        assert_eq!(a.system_transfers, b.system_transfers);
+        assert_eq!(a.receipts, b.receipts);
+        assert_eq!(
+            json(&a.checkpoints.snapshots),
+            json(&b.checkpoints.snapshots)
+        );
+    }
+
+    #[test]
+    fn test_memory_storage_recovers_full_chain() {
+        let mut rollup = rollup();
+        rollup
+            .attach_storage(Box::new(MemoryStorage::new()), 1000)
+            .unwrap();
+
+        transfer(&mut rollup, "alice", "bob", 100.0);
+        rollup.create_block().unwrap();
+        let first = withdraw(&mut rollup, "bob", 50.0);
+        rollup.submit_batch().unwrap();
+
+        transfer(&mut rollup, "bob", "carol", 25.0);
+        rollup.create_block().unwrap();
+        rollup.deposit("lock-1", &addr("carol"), 4.0).unwrap();
+        withdraw(&mut rollup, "alice", 10.0);
+        let second = rollup.submit_batch().unwrap();
+        rollup.finalize_batches(Utc::now().naive_utc() + Duration::seconds(120));
+
+        transfer(&mut rollup, "carol", "alice", 5.0);
+        rollup.submit_batch().unwrap();
+        rollup.deposit("lock-2", &addr("carol"), 2.0).unwrap();
+        rollup.rollback_to_checkpoint(3).unwrap();
+        transfer(&mut rollup, "alice", "carol", 1.0);
+        rollup.create_block().unwrap();
+
+        let storage = rollup.detach_storage().unwrap().unwrap();
+        let recovered = L2Rollup::recover(config(), storage, 1000).unwrap();
+        assert_same_chain(&rollup, &recovered);
+        assert_eq!(recovered.get_balance(&addr("carol")), 32.0);
+        assert_eq!(recovered.system_transfers.len(), 1);
+        assert_eq!(
+            recovered.get_batch(&second.batch_id).unwrap().status,
+            BatchStatus::Finalized
+        );
+        assert_eq!(recovered.batches[2].status, BatchStatus::Reverted);
+        assert!(recovered.generate_withdrawal_proof(&first).is_ok());
+    }
+
+    #[test]
+    fn test_recovered_rollup_keeps_persisting() {
+        let mut rollup = rollup();
+        rollup
+            .attach_storage(Box::new(MemoryStorage::new()), 1000)
+            .unwrap();
+        transfer(&mut rollup, "alice", "bob", 100.0);
+        rollup.submit_batch().unwrap();
+
+        let storage = rollup.detach_storage().unwrap().unwrap();
+        let mut recovered = L2Rollup::recover(config(), storage, 1000).unwrap();
+        assert_eq!(recovered.next_nonce(&addr("alice")), 1);
+        transfer(&mut recovered, "alice", "bob", 1.0);
+        recovered.submit_batch().unwrap();
+
+        let storage = recovered.detach_storage().unwrap().unwrap();
+        let again = L2Rollup::recover(config(), storage, 1000).unwrap();
+        assert_same_chain(&recovered, &again);
+        assert_eq!(again.get_balance(&addr("bob")), 601.0);
+    }
+
+    #[test]
+    fn test_attach_requires_empty_storage() {
+        let mut rollup = rollup();
+        let mut storage = MemoryStorage::new();
+        storage
+            .append(&[StorageRecord::Rollback { batch_number: 1 }])
+            .unwrap();
+        assert!(matches!(
+            rollup.attach_storage(Box::new(storage), 10),
+            Err(RollupError::StorageError(_))
+        ));
+    }
+
+    #[test]
+    fn test_file_storage_restart_with_periodic_snapshots() {
+        let dir = temp_dir("restart");
+        let mut rollup = rollup();
+        rollup
+            .attach_storage(Box::new(FileStorage::open(&dir).unwrap()), 4)
+            .unwrap();
+        for round in 0..6 {
+            transfer(&mut rollup, "alice", "bob", 10.0 + round as f64);
+            rollup.create_block().unwrap();
+            if round % 2 == 1 {
+                transfer(&mut rollup, "bob", "alice", 1.0);
+                rollup.submit_batch().unwrap();
+            }
+        }
+        let sequence = rollup.flush_storage().unwrap();
+        drop(rollup.detach_storage().unwrap());
+
+        let storage = FileStorage::open(&dir).unwrap();
+        assert_eq!(storage.last_sequence(), sequence);
+        let snapshot = storage.latest_snapshot().unwrap().unwrap();
+        assert!(snapshot.sequence > 0);
+        assert!(sequence - snapshot.sequence < 4);
+        let snapshots = fs::read_dir(&dir)
+            .unwrap()
+            .filter(|e| {
+                let name = e.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("snapshot-")
+            })
+            .count();
+        assert_eq!(snapshots, 3);
+
+        let recovered = L2Rollup::recover(config(), Box::new(storage), 4).unwrap();
+        assert_same_chain(&rollup, &recovered);
+        assert_eq!(recovered.get_balance(&addr("bob")), 500.0 + 75.0 - 3.0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_storage_discards_torn_tail() {
+        let dir = temp_dir("torn");
+        let mut rollup = rollup();
+        rollup
+            .attach_storage(Box::new(FileStorage::open(&dir).unwrap()), 1000)
+            .unwrap();
+        transfer(&mut rollup, "alice", "bob", 100.0);
+        rollup.submit_batch().unwrap();
+        let sequence = rollup.flush_storage().unwrap();
+        drop(rollup.detach_storage().unwrap());
+
+        // Crash in the middle of writing the next record
+        let log_path = dir.join("log.jsonl");
+        let length = fs::metadata(&log_path).unwrap().len();
+        let mut log = OpenOptions::new().append(true).open(&log_path).unwrap();
+        log.write_all(b"{\"sequence\":99,\"checksum\":\"ab")
+            .unwrap();
+        drop(log);
+
+        let mut storage = FileStorage::open(&dir).unwrap();
+        assert_eq!(storage.last_sequence(), sequence);
+        assert_eq!(fs::metadata(&log_path).unwrap().len(), length);
+        storage
+            .append(&[StorageRecord::BatchStatus {
+                batch_id: "batch_1".to_string(),
+                status: BatchStatus::Finalized,
+            }])
+            .unwrap();
+
+        let recovered = L2Rollup::recover(config(), Box::new(storage), 1000).unwrap();
+        assert_eq!(recovered.get_state_root(), rollup.get_state_root());
+        assert_eq!(recovered.batches[0].status, BatchStatus::Finalized);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_storage_rejects_corrupted_log() {
+        let dir = temp_dir("corrupt");
+        let mut rollup = rollup();
+        rollup
+            .attach_storage(Box::new(FileStorage::open(&dir).unwrap()), 1000)
+            .unwrap();
+        transfer(&mut rollup, "alice", "bob", 100.0);
+        rollup.submit_batch().unwrap();
+        drop(rollup.detach_storage().unwrap());
+
+        let log_path = dir.join("log.jsonl");
+        let log = fs::read_to_string(&log_path).unwrap();
+        assert!(log.lines().count() > 1);
+        fs::write(
+            &log_path,
+            log.replacen("\"balance\":1000", "\"balance\":9000", 1),
+        )
+        .unwrap();
+        assert!(matches!(
+            FileStorage::open(&dir),
+            Err(RollupError::StorageError(_))
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_recovery_detects_state_root_mismatch() {
+        let mut rollup = rollup();
+        rollup
+            .attach_storage(Box::new(MemoryStorage::new()), 1000)
+            .unwrap();
+        transfer(&mut rollup, "alice", "bob", 100.0);
+        rollup.create_block().unwrap();
+        drop(rollup.detach_storage());
+
+        let mut storage = MemoryStorage::new();
+        storage.save_snapshot(&rollup.chain_snapshot(0)).unwrap();
+        let mut forged = rollup.chain_snapshot(0);
+        forged.accounts[0].balance += 1.0;
+        forged.sequence = 1;
+        storage.save_snapshot(&forged).unwrap();
+        assert!(matches!(
+            L2Rollup::recover(config(), Box::new(storage), 1000),
+            Err(RollupError::StateRootMismatch)
+        ));
+
+        let mut storage = MemoryStorage::new();
+        storage
+            .append(&[StorageRecord::Block(rollup.blocks[0].clone())])
+            .unwrap();
+        assert!(matches!(
+            L2Rollup::recover(config(), Box::new(storage), 1000),
+            Err(RollupError::StateRootMismatch)
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+// Fixtures shared by the L2 test modules: deterministic keys for named users and a
+// small rollup with alice and bob funded
+use crate::l2_rollup::{L2Rollup, L2Transaction, RollupConfig};
+use crate::l2_signing::L2Keypair;
+use chrono::Utc;
+
+pub fn key(name: &str) -> L2Keypair {
+    L2Keypair::from_seed(name)
+}
+
+pub fn addr(name: &str) -> String {
+    key(name).address().to_string()
+}
+
+pub fn config() -> RollupConfig {
+    RollupConfig {
+        chain_id: "test-chain".to_string(),
+        operator_address: "operator1".to_string(),
+        batch_submission_interval: 300,
+        max_batch_size: 100,
+        gas_price: 0.001,
+    }
+}
+
+pub fn rollup() -> L2Rollup {
+    let mut rollup = L2Rollup::new(config());
+    rollup.initialize_account(addr("alice"), 1000.0);
+    rollup.initialize_account(addr("bob"), 500.0);
+    rollup
+}
+
+/// Transfer signed by `from` with an explicit nonce
+pub fn signed_transfer(from: &str, to: &str, amount: f64, nonce: u64) -> L2Transaction {
+    key(from)
+        .signed_transfer(
+            "test-chain",
+            &addr(to),
+            amount,
+            nonce,
+            Utc::now().naive_utc(),
+        )
+        .unwrap()
+}
+
+/// Queue a transfer from `from` at its next nonce
+pub fn transfer(rollup: &mut L2Rollup, from: &str, to: &str, amount: f64) {
+    let nonce = rollup.next_nonce(&addr(from));
+    rollup
+        .add_transaction(signed_transfer(from, to, amount, nonce))
+        .unwrap();
+}
+
+/// Request a withdrawal of `amount` by `name` and return its id
+pub fn withdraw(rollup: &mut L2Rollup, name: &str, amount: f64) -> String {
+    let nonce = rollup.next_nonce(&addr(name));
+    let tx = key(name)
+        .signed_withdrawal("test-chain", amount, 0.0, nonce, Utc::now().naive_utc())
+        .unwrap();
+    rollup.request_withdrawal(tx).unwrap()
+}
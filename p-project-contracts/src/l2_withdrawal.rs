@@ -0,0 +1,366 @@
+use crate::l2_rollup::{L2Account, L2Batch, L2Rollup, L2Transaction, RollupError};
+use crate::l2_signing::verify_transaction_signature;
+use crate::l2_state_management::{
+    ordered_merkle_proof, ordered_merkle_root, ordered_merkle_root_from_proof, L2StateManager,
+    SmtProof,
+};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Default time the operator has to include a forced exit before the rollup freezes (1 day)
+pub const DEFAULT_FORCED_INCLUSION_SECS: i64 = 24 * 60 * 60;
+
+// Funds burned on L2 that can be claimed on L1 once their batch is final
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Withdrawal {
+    pub withdrawal_id: String, // Hash of the L2 transaction that burned the funds
+    pub l2_sender: String,
+    pub l1_recipient: String, // L2 addresses are secp256k1 addresses, so the same key owns it on L1
+    pub amount: f64,
+    pub nonce: u64,
+}
+
+impl Withdrawal {
+    /// Withdrawal created by an executed transfer to the exit address
+    pub fn from_transaction(transaction: &L2Transaction) -> Self {
+        Self {
+            withdrawal_id: transaction.hash(),
+            l2_sender: transaction.from.clone(),
+            l1_recipient: transaction.from.clone(),
+            amount: transaction.amount,
+            nonce: transaction.nonce,
+        }
+    }
+
+    /// Leaf hash of the withdrawal in the withdrawal tree
+    pub fn leaf_hash(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        for field in [
+            self.withdrawal_id.as_bytes(),
+            self.l2_sender.as_bytes(),
+            self.l1_recipient.as_bytes(),
+        ] {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field);
+        }
+        hasher.update(self.amount.to_le_bytes());
+        hasher.update(self.nonce.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
+
+/// Merkle root over the withdrawals of a batch, in execution order
+pub fn withdrawal_root(withdrawals: &[Withdrawal]) -> String {
+    let leaves: Vec<[u8; 32]> = withdrawals.iter().map(|w| w.leaf_hash()).collect();
+    hex::encode(ordered_merkle_root(&leaves))
+}
+
+// Inclusion proof of a withdrawal in the withdrawal root posted with its batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawalProof {
+    pub batch_number: u64,
+    pub withdrawal: Withdrawal,
+    pub index: usize,
+    pub siblings: Vec<String>,
+}
+
+impl WithdrawalProof {
+    /// Build the proof for a withdrawal of the given batch, if the batch contains it
+    pub fn build(batch: &L2Batch, withdrawal_id: &str) -> Option<Self> {
+        let index = batch
+            .withdrawals
+            .iter()
+            .position(|w| w.withdrawal_id == withdrawal_id)?;
+        let leaves: Vec<[u8; 32]> = batch.withdrawals.iter().map(|w| w.leaf_hash()).collect();
+        let siblings = ordered_merkle_proof(&leaves, index)?;
+        Some(Self {
+            batch_number: batch.batch_number,
+            withdrawal: batch.withdrawals[index].clone(),
+            index,
+            siblings: siblings.iter().map(hex::encode).collect(),
+        })
+    }
+
+    /// Withdrawal root implied by the proof
+    pub fn compute_root(&self) -> Option<String> {
+        let siblings = self
+            .siblings
+            .iter()
+            .map(|s| hex::decode(s).ok()?.try_into().ok())
+            .collect::<Option<Vec<[u8; 32]>>>()?;
+        ordered_merkle_root_from_proof(self.withdrawal.leaf_hash(), self.index, &siblings)
+            .map(hex::encode)
+    }
+}
+
+// L1 verifier configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawalVerifierConfig {
+    pub chain_id: String,
+    pub forced_inclusion_secs: i64, // Deadline for the operator to include a forced exit
+}
+
+impl WithdrawalVerifierConfig {
+    pub fn new(chain_id: String) -> Self {
+        Self {
+            chain_id,
+            forced_inclusion_secs: DEFAULT_FORCED_INCLUSION_SECS,
+        }
+    }
+}
+
+// Batch commitment as recorded by the L1 contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostedBatch {
+    pub batch_number: u64,
+    pub state_root: String,
+    pub withdrawal_root: String,
+    pub posted_at: NaiveDateTime,
+    pub challenge_deadline: NaiveDateTime,
+    pub reverted: bool,
+}
+
+// Withdrawal submitted directly on L1 that the operator must include
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForcedExitRequest {
+    pub transaction: L2Transaction,
+    pub requested_at: NaiveDateTime,
+    pub included_in: Option<u64>, // Batch that included the withdrawal
+}
+
+// L1 side of the exit flow: tracks posted batch roots, pays out proven withdrawals
+// once their batch is final, and opens an escape hatch when the operator ignores
+// forced exits.
+pub struct L1WithdrawalVerifier {
+    pub config: WithdrawalVerifierConfig,
+    pub batches: BTreeMap<u64, PostedBatch>,
+    pub forced_exits: Vec<ForcedExitRequest>,
+    pub payouts: HashMap<String, f64>, // L1 recipient -> amount released
+    pub total_paid: f64,
+    claimed: HashSet<String>, // Withdrawal ids already paid
+    escaped: HashSet<String>, // L2 addresses that exited through the escape hatch
+}
+
+impl L1WithdrawalVerifier {
+    pub fn new(config: WithdrawalVerifierConfig) -> Self {
+        Self {
+            config,
+            batches: BTreeMap::new(),
+            forced_exits: Vec::new(),
+            payouts: HashMap::new(),
+            total_paid: 0.0,
+            claimed: HashSet::new(),
+            escaped: HashSet::new(),
+        }
+    }
+
+    /// Time at which the rollup froze because a forced exit was not included in time
+    pub fn frozen_at(&self) -> Option<NaiveDateTime> {
+        self.forced_exits
+            .iter()
+            .filter(|r| r.included_in.is_none())
+            .map(|r| r.requested_at + Duration::seconds(self.config.forced_inclusion_secs))
+            .min()
+    }
+
+    /// Whether the operator missed a forced exit deadline at `now`
+    pub fn is_frozen(&self, now: NaiveDateTime) -> bool {
+        self.frozen_at().is_some_and(|at| now > at)
+    }
+
+    /// Record a batch posted by the operator
+    pub fn post_batch(&mut self, batch: &L2Batch, now: NaiveDateTime) -> Result<(), RollupError> {
+        if self.is_frozen(now) {
+            return Err(RollupError::RollupFrozen);
+        }
+        if batch.chain_id != self.config.chain_id || !L2Rollup::verify_batch_signature(batch) {
+            return Err(RollupError::BatchSubmissionFailed);
+        }
+        if self
+            .batches
+            .keys()
+            .next_back()
+            .is_some_and(|last| batch.batch_number <= *last)
+        {
+            return Err(RollupError::BatchSubmissionFailed);
+        }
+        if withdrawal_root(&batch.withdrawals) != batch.withdrawal_root {
+            return Err(RollupError::InvalidWithdrawalProof);
+        }
+
+        let included: HashSet<&str> = batch
+            .withdrawals
+            .iter()
+            .map(|w| w.withdrawal_id.as_str())
+            .collect();
+        for request in self.forced_exits.iter_mut() {
+            if request.included_in.is_none()
+                && included.contains(request.transaction.hash().as_str())
+            {
+                request.included_in = Some(batch.batch_number);
+            }
+        }
+
+        self.batches.insert(
+            batch.batch_number,
+            PostedBatch {
+                batch_number: batch.batch_number,
+                state_root: batch.state_root_after.clone(),
+                withdrawal_root: batch.withdrawal_root.clone(),
+                posted_at: now,
+                challenge_deadline: batch.challenge_deadline,
+                reverted: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Revert a batch proven fraudulent together with every later batch
+    pub fn revert_batch(&mut self, batch_number: u64) -> Vec<u64> {
+        let mut reverted = Vec::new();
+        for batch in self.batches.range_mut(batch_number..).map(|(_, b)| b) {
+            if !batch.reverted {
+                batch.reverted = true;
+                reverted.push(batch.batch_number);
+            }
+        }
+        // Forced exits included only in reverted batches are open again
+        for request in self.forced_exits.iter_mut() {
+            if request.included_in.is_some_and(|n| n >= batch_number) {
+                request.included_in = None;
+            }
+        }
+        reverted
+    }
+
+    /// Whether a batch is final at `now`. Once the rollup is frozen nothing becomes
+    /// final anymore, so the escape hatch and regular claims see the same state.
+    pub fn is_finalized(&self, batch_number: u64, now: NaiveDateTime) -> bool {
+        let cutoff = match self.frozen_at() {
+            Some(frozen_at) if frozen_at < now => frozen_at,
+            _ => now,
+        };
+        self.batches
+            .get(&batch_number)
+            .is_some_and(|b| !b.reverted && cutoff > b.challenge_deadline)
+    }
+
+    /// Check a withdrawal proof against the root posted with a final batch
+    pub fn verify_withdrawal(
+        &self,
+        proof: &WithdrawalProof,
+        now: NaiveDateTime,
+    ) -> Result<(), RollupError> {
+        let batch = self
+            .batches
+            .get(&proof.batch_number)
+            .ok_or(RollupError::BatchNotFound)?;
+        if !self.is_finalized(proof.batch_number, now) {
+            return Err(RollupError::BatchNotFinalized);
+        }
+        match proof.compute_root() {
+            Some(root) if root == batch.withdrawal_root => Ok(()),
+            _ => Err(RollupError::InvalidWithdrawalProof),
+        }
+    }
+
+    /// Pay out a proven withdrawal. Each withdrawal can be claimed once.
+    pub fn claim_withdrawal(
+        &mut self,
+        proof: &WithdrawalProof,
+        now: NaiveDateTime,
+    ) -> Result<f64, RollupError> {
+        self.verify_withdrawal(proof, now)?;
+        if !self.claimed.insert(proof.withdrawal.withdrawal_id.clone()) {
+            return Err(RollupError::WithdrawalAlreadyClaimed);
+        }
+        self.pay(&proof.withdrawal.l1_recipient, proof.withdrawal.amount);
+        Ok(proof.withdrawal.amount)
+    }
+
+    /// Whether a withdrawal has been paid out
+    pub fn is_claimed(&self, withdrawal_id: &str) -> bool {
+        self.claimed.contains(withdrawal_id)
+    }
+
+    /// Submit a signed withdrawal on L1 that the operator must include within the
+    /// forced inclusion window. Returns the withdrawal id.
+    pub fn request_forced_exit(
+        &mut self,
+        transaction: L2Transaction,
+        now: NaiveDateTime,
+    ) -> Result<String, RollupError> {
+        if !transaction.is_withdrawal() {
+            return Err(RollupError::InvalidTransaction);
+        }
+        verify_transaction_signature(&self.config.chain_id, &transaction)?;
+        let withdrawal_id = transaction.hash();
+        if self
+            .forced_exits
+            .iter()
+            .any(|r| r.transaction.hash() == withdrawal_id)
+        {
+            return Err(RollupError::DuplicateTransaction);
+        }
+        self.forced_exits.push(ForcedExitRequest {
+            transaction,
+            requested_at: now,
+            included_in: None,
+        });
+        Ok(withdrawal_id)
+    }
+
+    /// Forced exits the operator still has to include
+    pub fn pending_forced_exits(&self) -> Vec<&L2Transaction> {
+        self.forced_exits
+            .iter()
+            .filter(|r| r.included_in.is_none())
+            .map(|r| &r.transaction)
+            .collect()
+    }
+
+    /// Latest batch that was final when the rollup froze
+    pub fn escape_batch(&self, now: NaiveDateTime) -> Option<&PostedBatch> {
+        self.batches
+            .values()
+            .rev()
+            .find(|b| self.is_finalized(b.batch_number, now))
+    }
+
+    /// Escape hatch: once the rollup is frozen, withdraw an account's full balance by
+    /// proving it against the state root of the last final batch. Each address can
+    /// exit this way once.
+    pub fn escape(
+        &mut self,
+        account: &L2Account,
+        proof: &SmtProof,
+        now: NaiveDateTime,
+    ) -> Result<f64, RollupError> {
+        if !self.is_frozen(now) {
+            return Err(RollupError::ForcedExitUnavailable);
+        }
+        let batch = self
+            .escape_batch(now)
+            .ok_or(RollupError::BatchNotFinalized)?;
+        if !L2StateManager::verify_compressed_proof_against_root(
+            &batch.state_root,
+            &account.address,
+            Some(account),
+            proof,
+        ) {
+            return Err(RollupError::InvalidWithdrawalProof);
+        }
+        if !self.escaped.insert(account.address.clone()) {
+            return Err(RollupError::WithdrawalAlreadyClaimed);
+        }
+        self.pay(&account.address, account.balance);
+        Ok(account.balance)
+    }
+
+    fn pay(&mut self, recipient: &str, amount: f64) {
+        *self.payouts.entry(recipient.to_string()).or_insert(0.0) += amount;
+        self.total_paid += amount;
+    }
+}
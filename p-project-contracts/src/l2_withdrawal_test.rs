@@ -0,0 +1,273 @@
+#[cfg(test)]
+mod tests {
+    use super::super::l2_fraud_proof::{DisputeGame, DisputeVerdict};
+    use super::super::l2_rollup::{L2Rollup, RollupError, L2_EXIT_ADDRESS};
+    use super::super::l2_state_management::{
+        ordered_merkle_proof, ordered_merkle_root, ordered_merkle_root_from_proof,
+    };
+    use super::super::l2_test_support::{self as support, addr, key, transfer, withdraw};
+    use super::super::l2_withdrawal::{L1WithdrawalVerifier, WithdrawalVerifierConfig};
+    use chrono::{Duration, NaiveDateTime, Utc};
+
+    fn now() -> NaiveDateTime {
+        Utc::now().naive_utc()
+    }
+
+    fn rollup() -> L2Rollup {
+        let mut rollup = support::rollup();
+        rollup.set_challenge_period(60);
+        rollup
+    }
+
+    fn verifier() -> L1WithdrawalVerifier {
+        let mut config = WithdrawalVerifierConfig::new("test-chain".to_string());
+        config.forced_inclusion_secs = 3600;
+        L1WithdrawalVerifier::new(config)
+    }
+
+    #[test]
+    fn test_ordered_merkle_proofs() {
+        for count in 1..=9u8 {
+            let leaves: Vec<[u8; 32]> = (0..count).map(|i| [i; 32]).collect();
+            let root = ordered_merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = ordered_merkle_proof(&leaves, index).unwrap();
+                assert_eq!(
+                    ordered_merkle_root_from_proof(*leaf, index, &proof),
+                    Some(root)
+                );
+                assert_ne!(
+                    ordered_merkle_root_from_proof([0xff; 32], index, &proof),
+                    Some(root)
+                );
+            }
+            assert!(ordered_merkle_proof(&leaves, count as usize).is_none());
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_burns_and_is_claimable_after_finality() {
+        let mut rollup = rollup();
+        transfer(&mut rollup, "alice", "bob", 100.0);
+        let withdrawal_id = withdraw(&mut rollup, "alice", 250.0);
+        assert_eq!(rollup.pending_transaction_count(), 2);
+
+        let batch = rollup.submit_batch().unwrap();
+        assert_eq!(rollup.get_balance(&addr("alice")), 650.0);
+        assert_eq!(rollup.get_balance(L2_EXIT_ADDRESS), 0.0);
+        assert_eq!(batch.withdrawals.len(), 1);
+        assert_eq!(batch.withdrawals[0].l1_recipient, addr("alice"));
+        assert!(L2Rollup::verify_batch_signature(&batch));
+
+        let mut verifier = verifier();
+        verifier.post_batch(&batch, now()).unwrap();
+
+        let proof = rollup.generate_withdrawal_proof(&withdrawal_id).unwrap();
+        assert_eq!(proof.compute_root().unwrap(), batch.withdrawal_root);
+        assert_eq!(
+            verifier.claim_withdrawal(&proof, now()).unwrap_err(),
+            RollupError::BatchNotFinalized
+        );
+
+        let later = now() + Duration::seconds(120);
+        assert_eq!(verifier.claim_withdrawal(&proof, later).unwrap(), 250.0);
+        assert!(verifier.is_claimed(&withdrawal_id));
+        assert_eq!(verifier.payouts[&addr("alice")], 250.0);
+        assert_eq!(
+            verifier.claim_withdrawal(&proof, later).unwrap_err(),
+            RollupError::WithdrawalAlreadyClaimed
+        );
+    }
+
+    #[test]
+    fn test_tampered_withdrawal_proof_is_rejected() {
+        let mut rollup = rollup();
+        let first = withdraw(&mut rollup, "alice", 10.0);
+        withdraw(&mut rollup, "bob", 20.0);
+        withdraw(&mut rollup, "alice", 30.0);
+        let batch = rollup.submit_batch().unwrap();
+        assert_eq!(batch.withdrawals.len(), 3);
+
+        let mut l1 = verifier();
+        l1.post_batch(&batch, now()).unwrap();
+        let later = now() + Duration::seconds(120);
+
+        let mut inflated = rollup.generate_withdrawal_proof(&first).unwrap();
+        inflated.withdrawal.amount = 500.0;
+        assert_eq!(
+            l1.claim_withdrawal(&inflated, later).unwrap_err(),
+            RollupError::InvalidWithdrawalProof
+        );
+
+        let mut redirected = rollup.generate_withdrawal_proof(&first).unwrap();
+        redirected.withdrawal.l1_recipient = addr("mallory");
+        assert!(l1.verify_withdrawal(&redirected, later).is_err());
+        assert_eq!(l1.total_paid, 0.0);
+
+        // A batch whose withdrawal list does not match its root is refused on L1
+        let mut forged = batch.clone();
+        forged.withdrawals[0].amount = 999.0;
+        assert_eq!(
+            verifier().post_batch(&forged, now()).unwrap_err(),
+            RollupError::InvalidWithdrawalProof
+        );
+    }
+
+    #[test]
+    fn test_block_withdrawals_are_committed_in_next_batch() {
+        let mut rollup = rollup();
+        let withdrawal_id = withdraw(&mut rollup, "bob", 50.0);
+        let block = rollup.create_block().unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(rollup.withdrawal_queue.len(), 1);
+        assert_eq!(rollup.get_balance(&addr("bob")), 450.0);
+
+        transfer(&mut rollup, "alice", "bob", 1.0);
+        let batch = rollup.submit_batch().unwrap();
+        assert!(rollup.withdrawal_queue.is_empty());
+        assert_eq!(batch.withdrawals[0].withdrawal_id, withdrawal_id);
+        assert!(rollup.generate_withdrawal_proof(&withdrawal_id).is_ok());
+    }
+
+    #[test]
+    fn test_withdrawal_transition_is_provable() {
+        let mut rollup = rollup();
+        withdraw(&mut rollup, "alice", 40.0);
+        transfer(&mut rollup, "bob", "carol", 5.0);
+        let batch_id = rollup.submit_batch().unwrap().batch_id;
+
+        assert_eq!(
+            DisputeGame::find_fraudulent_transaction(&rollup, &batch_id).unwrap(),
+            None
+        );
+
+        // Claiming the burned amount was never debited is caught by re-execution
+        let forged = rollup
+            .get_batch(&batch_id)
+            .unwrap()
+            .state_root_before
+            .clone();
+        let batch = rollup
+            .batches
+            .iter_mut()
+            .find(|b| b.batch_id == batch_id)
+            .unwrap();
+        batch.intermediate_state_roots[0] = forged;
+        let proof = DisputeGame::build_fraud_proof(&rollup, &batch_id, 0).unwrap();
+        assert!(matches!(
+            DisputeGame::verify_fraud_proof(rollup.get_batch(&batch_id).unwrap(), &proof).unwrap(),
+            DisputeVerdict::StateRootMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn test_forced_exit_included_by_operator() {
+        let mut rollup = rollup();
+        let mut verifier = verifier();
+        let start = now();
+
+        let tx = key("bob")
+            .signed_withdrawal("test-chain", 100.0, 0.0, 0, start)
+            .unwrap();
+        let withdrawal_id = verifier.request_forced_exit(tx.clone(), start).unwrap();
+        assert_eq!(
+            verifier.request_forced_exit(tx, start).unwrap_err(),
+            RollupError::DuplicateTransaction
+        );
+
+        let mut unsigned = key("bob")
+            .signed_withdrawal("test-chain", 1.0, 0.0, 1, start)
+            .unwrap();
+        unsigned.amount = 2.0;
+        assert_eq!(
+            verifier.request_forced_exit(unsigned, start).unwrap_err(),
+            RollupError::InvalidSignature
+        );
+
+        // The operator picks the request up from L1 and includes it
+        for tx in verifier.pending_forced_exits() {
+            rollup.request_withdrawal(tx.clone()).unwrap();
+        }
+        let batch = rollup.submit_batch().unwrap();
+        verifier.post_batch(&batch, start).unwrap();
+        assert!(verifier.pending_forced_exits().is_empty());
+
+        let much_later = start + Duration::seconds(7200);
+        assert!(!verifier.is_frozen(much_later));
+        let proof = rollup.generate_withdrawal_proof(&withdrawal_id).unwrap();
+        assert_eq!(
+            verifier.claim_withdrawal(&proof, much_later).unwrap(),
+            100.0
+        );
+    }
+
+    #[test]
+    fn test_escape_hatch_when_operator_goes_silent() {
+        let mut rollup = rollup();
+        let mut verifier = verifier();
+        let start = now();
+
+        transfer(&mut rollup, "alice", "bob", 200.0);
+        let batch = rollup.submit_batch().unwrap();
+        verifier.post_batch(&batch, start).unwrap();
+
+        let tx = key("bob")
+            .signed_withdrawal("test-chain", 100.0, 0.0, 0, start)
+            .unwrap();
+        verifier.request_forced_exit(tx, start).unwrap();
+
+        let (bob, proof) = rollup
+            .account_proof_at_batch(batch.batch_number, &addr("bob"))
+            .unwrap();
+        let bob = bob.unwrap();
+        assert_eq!(bob.balance, 700.0);
+        assert_eq!(
+            verifier.escape(&bob, &proof, start).unwrap_err(),
+            RollupError::ForcedExitUnavailable
+        );
+
+        // The operator ignores the request past the inclusion window
+        let frozen = start + Duration::seconds(3601);
+        assert!(verifier.is_frozen(frozen));
+        transfer(&mut rollup, "alice", "bob", 1.0);
+        let late = rollup.submit_batch().unwrap();
+        assert_eq!(
+            verifier.post_batch(&late, frozen).unwrap_err(),
+            RollupError::RollupFrozen
+        );
+
+        let mut inflated = bob.clone();
+        inflated.balance = 5000.0;
+        assert_eq!(
+            verifier.escape(&inflated, &proof, frozen).unwrap_err(),
+            RollupError::InvalidWithdrawalProof
+        );
+        assert_eq!(verifier.escape(&bob, &proof, frozen).unwrap(), 700.0);
+        assert_eq!(
+            verifier.escape(&bob, &proof, frozen).unwrap_err(),
+            RollupError::WithdrawalAlreadyClaimed
+        );
+        assert_eq!(verifier.total_paid, 700.0);
+    }
+
+    #[test]
+    fn test_reverted_batch_withdrawals_cannot_be_claimed() {
+        let mut rollup = rollup();
+        let withdrawal_id = withdraw(&mut rollup, "alice", 10.0);
+        let batch = rollup.submit_batch().unwrap();
+        let mut verifier = verifier();
+        verifier.post_batch(&batch, now()).unwrap();
+        let proof = rollup.generate_withdrawal_proof(&withdrawal_id).unwrap();
+
+        assert_eq!(verifier.revert_batch(batch.batch_number), vec![1]);
+        rollup.rollback_to_checkpoint(batch.batch_number).unwrap();
+        assert_eq!(rollup.get_balance(&addr("alice")), 1000.0);
+        assert!(rollup.generate_withdrawal_proof(&withdrawal_id).is_err());
+
+        let later = now() + Duration::seconds(120);
+        assert_eq!(
+            verifier.claim_withdrawal(&proof, later).unwrap_err(),
+            RollupError::BatchNotFinalized
+        );
+    }
+}
@@ -2,25 +2,37 @@ pub mod advanced_cryptography; // Add the new advanced cryptography module
 pub mod advanced_verification; // Add the new advanced verification module
 pub mod airdrop;
 pub mod audit_registry; // Audit metadata registry
+pub mod batch_auction; // Epoch-based uniform-price batch auctions for pools
 pub mod charity; // Add the new charity allocator module
 pub mod community_liquidity; // Community liquidity incentives
 pub mod compliance; // Compliance and regulatory controls
+pub mod concentrated_liquidity_pool; // Tick-based concentrated liquidity pools
 pub mod comprehensive_test_suite; // Add the new comprehensive test suite module
 pub mod comprehensive_verification; // Add the new comprehensive verification module
 pub mod cross_chain_liquidity; // Cross-chain liquidity orchestration
 pub mod dex_listing; // DEX listing orchestration helpers
 pub mod router; // Router with automatic liquidity provision
+pub mod fee_policy; // Pluggable dynamic swap fees for pools
 pub mod formal_verification; // Add the new formal verification module
 pub mod l2_batching; // Add the new L2 batching module
+pub mod l2_compression; // Binary batch codec for L1 submission
 pub mod l2_cross_chain; // Add the new L2 cross-chain module
+pub mod l2_fraud_proof; // Optimistic rollup dispute game
+pub mod l2_mempool; // Fee-priority sequencer mempool
 pub mod l2_model_checking; // Add the new L2 model checking module
 pub mod l2_rollup; // Add the new L2 rollup module
+pub mod l2_signing; // secp256k1 signing for L2 transactions
 pub mod l2_state_management; // Add the new L2 state management module
+pub mod l2_storage; // Persistent L2 chain storage and recovery
+pub mod l2_withdrawal; // L2 to L1 exits with withdrawal proofs
 pub mod liquidity_pool; // Add the new liquidity pool module
 pub mod load_testing;
 pub mod metaverse;
+pub mod multi_asset_stable_pool; // N-token StableSwap pools
 pub mod nft; // Add the new NFT module
+pub mod order_book; // Conditional limit, stop-loss and TWAP orders
 pub mod ownership;
+pub mod price_oracle; // Time-weighted average price oracle for pools
 pub mod price_simulation; // Add the new price simulation module
 pub mod savings_vault;
 pub mod security_compliance; // Additional security controls
@@ -35,28 +47,48 @@ pub mod vesting; // Add the new load testing module // Ownable + renounce mechan
 // Re-export the main contract types
 pub use advanced_cryptography::{post_quantum, threshold_signatures, zero_knowledge}; // Re-export advanced cryptography types
 pub use airdrop::{AirdropContract, MerkleTree};
+pub use batch_auction::{BatchAuction, BatchFill, BatchOrder, BatchSettlement}; // Re-export batch auction types
 pub use charity::{
     AidVoucher, Allocation, AuditEvent, CharityAllocator, CharityError, CreditTransaction,
     CrowdfundCampaign, DashboardSummary, DistributionRule, DonationRecord, DonorReputation,
     LeaderboardEntry, NGOImpactRecord, PeaceReliefCredit, ProofOfPeaceBadge, NGO,
 }; // Re-export charity allocator types
+pub use concentrated_liquidity_pool::{
+    ConcentratedLiquidityPool, ConcentratedPoolConfig, ConcentratedPoolError, ConcentratedPosition,
+}; // Re-export concentrated liquidity types
+pub use fee_policy::{BuiltinFeePolicy, FeeContext, FeePolicy, VolatilityFeePolicy}; // Re-export fee policy types
 pub use l2_batching::{
-    BatchAggregator, BatchConfig, BatchSubmissionResult, BatchSubmitter, TransactionBatch,
+    BatchAggregator, BatchCompressor, BatchConfig, BatchFeeCalculator, BatchSubmissionResult,
+    BatchSubmitter, TransactionBatch,
 }; // Re-export L2 batching types
 pub use l2_cross_chain::{CrossChainMessage, L2CrossChainProtocol}; // Re-export L2 cross-chain types
-pub use l2_rollup::{L2Account, L2Block, L2Rollup, L2Transaction, RollupConfig, RollupError}; // Re-export L2 rollup types
+pub use l2_fraud_proof::{DisputeConfig, DisputeGame, DisputeVerdict, FraudProof}; // Re-export L2 dispute types
+pub use l2_mempool::{Mempool, MempoolConfig, MempoolInsert}; // Re-export L2 mempool types
+pub use l2_rollup::{
+    BatchStatus, L2Account, L2Batch, L2Block, L2Rollup, L2Transaction, RollupConfig, RollupError,
+    TransactionReceipt,
+}; // Re-export L2 rollup types
+pub use l2_signing::L2Keypair; // Re-export L2 signing key type
 pub use l2_state_management::{
-    L2StateManager, SparseMerkleTree, StateCheckpointManager, StateSnapshot,
+    L2StateManager, SmtProof, SparseMerkleTree, StateCheckpointManager, StateSnapshot,
 }; // Re-export L2 state management types
+pub use l2_storage::{ChainSnapshot, FileStorage, L2Storage, MemoryStorage, StorageRecord}; // Re-export L2 storage types
+pub use l2_withdrawal::{
+    L1WithdrawalVerifier, Withdrawal, WithdrawalProof, WithdrawalVerifierConfig,
+}; // Re-export L2 withdrawal types
 pub use liquidity_pool::{
-    LiquidityMechanisms, LiquidityPool, LiquidityPoolConfig, LiquidityPosition, PoolStats,
+    FlashCallback, LiquidityMechanisms, LiquidityPool, LiquidityPoolConfig, LiquidityPosition,
+    PoolStats, PositionPnl,
 }; // Re-export liquidity pool types
 pub use load_testing::{LoadTestConfig, LoadTestResult, LoadTester};
 pub use metaverse::{Building, BuildingType, LandParcel, MetaverseError, PeaceIsland};
+pub use multi_asset_stable_pool::{MultiAssetStablePool, MultiAssetStablePoolConfig}; // Re-export N-token stable pool types
 pub use nft::{MarketplaceListing, NFTCollection, NFTContract, NFTMetadata, NFT}; // Re-export NFT types
+pub use order_book::{ConditionalOrder, OrderFill, OrderKeeper, OrderKind, TwapSchedule}; // Re-export order book types
+pub use price_oracle::{Observation, OracleError, OracleQuote, PriceOracle}; // Re-export price oracle types
 pub use price_simulation::{CompletePriceSimulation, PriceSimulation}; // Re-export price simulation types
 pub use savings_vault::{SavingsConfig, SavingsError, SavingsVault};
-pub use stable_liquidity_pool::{StableLiquidityPool, StablePoolConfig}; // Re-export stable LP types
+pub use stable_liquidity_pool::{AmplificationRamp, StableLiquidityPool, StablePoolConfig}; // Re-export stable LP types
 pub use staking::StakingContract;
 pub use supply_chain::{
     AidShipment, AlertSeverity, AntiCorruptionAlert, DonationItem, LogisticsEvent, SupplyCategory,
@@ -123,6 +155,12 @@ mod charity_stable_payout_test; // Add NGO stable payout tests
 #[cfg(test)]
 mod stable_liquidity_pool_test; // Add stable liquidity pool tests
 
+#[cfg(test)]
+mod concentrated_liquidity_pool_test; // Add concentrated liquidity pool tests
+
+#[cfg(test)]
+mod multi_asset_stable_pool_test; // Add N-token stable pool tests
+
 #[cfg(test)]
 mod cross_chain_liquidity_test; // Add cross-chain liquidity tests
 
@@ -155,3 +193,45 @@ mod load_testing_test; // Add load testing tests
 
 #[cfg(test)]
 mod social_proof_test; // Add social proof & impact tests
+
+#[cfg(test)]
+mod l2_compression_test; // Add L2 batch codec tests
+
+#[cfg(test)]
+mod l2_cross_chain_test; // Add L2 cross-chain tests
+
+#[cfg(test)]
+mod l2_fraud_proof_test; // Add L2 dispute game tests
+
+#[cfg(test)]
+mod l2_rollup_test; // Add L2 rollup tests
+
+#[cfg(test)]
+mod l2_signing_test; // Add L2 transaction signing tests
+
+#[cfg(test)]
+mod l2_mempool_test; // Add L2 mempool tests
+
+#[cfg(test)]
+mod l2_withdrawal_test; // Add L2 withdrawal tests
+
+#[cfg(test)]
+mod l2_state_management_test; // Add L2 state management tests
+
+#[cfg(test)]
+mod l2_storage_test; // Add L2 storage and recovery tests
+
+#[cfg(test)]
+mod l2_test_support; // Shared L2 test fixtures
+
+#[cfg(test)]
+mod price_oracle_test; // Add price oracle tests
+
+#[cfg(test)]
+mod order_book_test; // Add order book tests
+
+#[cfg(test)]
+mod batch_auction_test; // Add batch auction tests
+
+#[cfg(test)]
+mod fee_policy_test; // Add dynamic fee policy tests
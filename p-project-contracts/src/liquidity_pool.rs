@@ -1,3 +1,6 @@
+use crate::batch_auction::{self, BatchAuction, BatchFill, BatchOrder, BatchSettlement};
+use crate::fee_policy::{FeeContext, FeePolicy};
+use crate::price_oracle::{OracleError, OracleQuote, PriceOracle};
 use chrono::{NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -16,6 +19,13 @@ pub enum LiquidityPoolError {
     InsufficientRewards,
     LiquidityLocked,
     SlippageExceeded,
+    InsufficientShares,
+    FlashLoanNotRepaid,
+    FlashCallbackFailed(String),
+    BatchAuctionActive,
+    BatchAuctionInactive,
+    EpochNotEnded,
+    EpochEnded,
 }
 
 impl fmt::Display for LiquidityPoolError {
@@ -35,12 +45,45 @@ impl fmt::Display for LiquidityPoolError {
             LiquidityPoolError::InsufficientRewards => write!(f, "Insufficient rewards available"),
             LiquidityPoolError::LiquidityLocked => write!(f, "Liquidity is locked"),
             LiquidityPoolError::SlippageExceeded => write!(f, "Slippage tolerance exceeded"),
+            LiquidityPoolError::InsufficientShares => write!(f, "Not enough LP shares"),
+            LiquidityPoolError::FlashLoanNotRepaid => {
+                write!(f, "Flash loan was not repaid with its fee")
+            }
+            LiquidityPoolError::FlashCallbackFailed(msg) => {
+                write!(f, "Flash callback failed: {}", msg)
+            }
+            LiquidityPoolError::BatchAuctionActive => {
+                write!(f, "Pool only trades through its batch auction")
+            }
+            LiquidityPoolError::BatchAuctionInactive => write!(f, "Batch auction is not enabled"),
+            LiquidityPoolError::EpochNotEnded => write!(f, "Batch epoch has not ended yet"),
+            LiquidityPoolError::EpochEnded => {
+                write!(f, "Batch epoch has ended and awaits settlement")
+            }
         }
     }
 }
 
 impl std::error::Error for LiquidityPoolError {}
 
+// Shares burned on the first deposit so the pool can never be fully drained, which
+// keeps the share price from being manipulated while supply is tiny
+pub const MINIMUM_LIQUIDITY: f64 = 0.001;
+
+/// Receiver of tokens lent out by `LiquidityPool::flash_loan` and `flash_swap`.
+pub trait FlashCallback {
+    /// Called with `amount` of `token` already taken out of the pool. Returns how much
+    /// of `repay_token` is paid back; the pool requires at least `owed`. Returning an
+    /// error or too little reverts the pool to its state before the call.
+    fn on_flash(
+        &mut self,
+        token: &str,
+        amount: f64,
+        repay_token: &str,
+        owed: f64,
+    ) -> Result<f64, String>;
+}
+
 /// Optional protection and automation knobs for LP behavior.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiquidityMechanisms {
@@ -86,6 +129,32 @@ pub struct LiquidityPosition {
     pub accumulated_rewards: f64,
     pub last_reward_time: NaiveDateTime,
     pub claimed_rewards: f64, // Rewards already claimed by the user
+    #[serde(default)]
+    pub entry_price: f64, // token_b per token_a paid on entry, weighted by token_a deposited
+    #[serde(default)]
+    pub entry_reserve_a: f64, // Pool reserves right after the latest deposit
+    #[serde(default)]
+    pub entry_reserve_b: f64,
+}
+
+impl LiquidityPosition {
+    /// Price the position was entered at. Positions saved before entry prices were
+    /// tracked fall back to the ratio of their deposits.
+    pub fn entry_price(&self) -> f64 {
+        if self.entry_price > 0.0 || self.token_a_amount <= 0.0 {
+            return self.entry_price;
+        }
+        self.token_b_amount / self.token_a_amount
+    }
+
+    /// Fold `token_a_amount` entered at `price` into the weighted entry price
+    fn blend_entry_price(&mut self, token_a_amount: f64, price: f64) {
+        let total = self.token_a_amount + token_a_amount;
+        if total > 0.0 {
+            self.entry_price =
+                (self.entry_price() * self.token_a_amount + price * token_a_amount) / total;
+        }
+    }
 }
 
 // Liquidity pool struct
@@ -100,6 +169,14 @@ pub struct LiquidityPool {
     pub total_volume: f64, // Total volume traded through the pool
     pub total_fees: f64,   // Total fees collected
     pub mechanisms: LiquidityMechanisms,
+    #[serde(default)]
+    pub locked_liquidity: f64, // Shares held by no one (see MINIMUM_LIQUIDITY)
+    #[serde(default)]
+    pub oracle: PriceOracle, // TWAP of token_a priced in token_b
+    #[serde(default)]
+    pub batch_auction: Option<BatchAuction>, // Set while swaps settle in batches
+    #[serde(default, with = "crate::fee_policy::serde_policy")]
+    pub fee_policy: Option<Box<dyn FeePolicy>>, // Replaces fee_tier on swaps when set
 }
 
 impl LiquidityPool {
@@ -136,10 +213,17 @@ impl LiquidityPool {
             total_volume: 0.0,
             total_fees: 0.0,
             mechanisms: LiquidityMechanisms::default(),
+            locked_liquidity: 0.0,
+            oracle: PriceOracle::default(),
+            batch_auction: None,
+            fee_policy: None,
         }
     }
 
-    /// Add liquidity to the pool
+    /// Add liquidity to the pool and mint LP shares. The first deposit mints
+    /// `sqrt(a * b)` shares, `MINIMUM_LIQUIDITY` of which are locked forever; later
+    /// deposits mint in proportion to the reserves, using the smaller of the two ratios
+    /// so that any excess of one token is left to the existing LPs.
     pub fn add_liquidity(
         &mut self,
         user_id: String,
@@ -156,89 +240,204 @@ impl LiquidityPool {
         }
 
         let locked_duration = duration_days.max(self.mechanisms.lp_lock_days);
-        let liquidity_amount = (token_a_amount * token_b_amount).sqrt();
+        let entry_price = self.spot_price().unwrap_or(token_b_amount / token_a_amount);
+        let liquidity_amount = if self.total_liquidity == 0.0 {
+            let minted = (token_a_amount * token_b_amount).sqrt();
+            if minted <= MINIMUM_LIQUIDITY {
+                return Err(LiquidityPoolError::InsufficientLiquidity);
+            }
+            self.locked_liquidity += MINIMUM_LIQUIDITY;
+            self.total_liquidity += MINIMUM_LIQUIDITY;
+            minted - MINIMUM_LIQUIDITY
+        } else {
+            (token_a_amount / self.total_token_a).min(token_b_amount / self.total_token_b)
+                * self.total_liquidity
+        };
 
-        // If this is the first liquidity added, initialize the k constant
-        if self.total_liquidity == 0.0 {
-            self.k_constant = token_a_amount * token_b_amount;
+        // Settle rewards at the old share balance before it changes
+        if self.liquidity_positions.contains_key(&user_id) {
+            self.update_rewards(&user_id)?;
         }
 
         // Update pool totals
         self.total_token_a += token_a_amount;
         self.total_token_b += token_b_amount;
         self.total_liquidity += liquidity_amount;
+        self.k_constant = self.total_token_a * self.total_token_b;
 
         // Create or update liquidity position
         let start_time = Utc::now().naive_utc();
-
-        // Check if user already has a position
-        let position = if let Some(existing_position) = self.liquidity_positions.get_mut(&user_id) {
-            // Update existing position
-            existing_position.liquidity_amount += liquidity_amount;
-            existing_position.token_a_amount += token_a_amount;
-            existing_position.token_b_amount += token_b_amount;
-            existing_position.duration_days = existing_position.duration_days.max(locked_duration);
-            existing_position.clone()
-        } else {
-            // Create new position
-            LiquidityPosition {
-                user_id: user_id.clone(),
-                pool_id: self.config.pool_id.clone(),
-                liquidity_amount,
-                token_a_amount,
-                token_b_amount,
+        let pool_id = self.config.pool_id.clone();
+        let position = self
+            .liquidity_positions
+            .entry(user_id.clone())
+            .or_insert_with(|| LiquidityPosition {
+                user_id,
+                pool_id,
+                liquidity_amount: 0.0,
+                token_a_amount: 0.0,
+                token_b_amount: 0.0,
                 start_time,
                 duration_days: locked_duration,
                 accumulated_rewards: 0.0,
                 last_reward_time: start_time,
                 claimed_rewards: 0.0,
-            }
-        };
-
-        self.liquidity_positions.insert(user_id, position);
+                entry_price: 0.0,
+                entry_reserve_a: 0.0,
+                entry_reserve_b: 0.0,
+            });
+        position.blend_entry_price(token_a_amount, entry_price);
+        position.entry_reserve_a = self.total_token_a;
+        position.entry_reserve_b = self.total_token_b;
+        position.liquidity_amount += liquidity_amount;
+        position.token_a_amount += token_a_amount;
+        position.token_b_amount += token_b_amount;
+        position.duration_days = position.duration_days.max(locked_duration);
+        self.record_price();
 
         Ok(liquidity_amount)
     }
 
-    /// Remove liquidity from the pool
+    /// Remove all of a user's liquidity from the pool and close the position.
+    /// Unclaimed rewards are forfeited, so claim them first.
     pub fn remove_liquidity(&mut self, user_id: &str) -> Result<(f64, f64), LiquidityPoolError> {
+        // Returns (token_a_amount, token_b_amount)
+        let shares = self.shares_of(user_id);
+        let returned = self.burn_shares(user_id, shares)?;
+        self.liquidity_positions.remove(user_id);
+        Ok(returned)
+    }
+
+    /// Burn part of a user's LP shares for a proportional slice of both reserves.
+    /// A position emptied this way is kept until its pending rewards are claimed.
+    pub fn burn_shares(
+        &mut self,
+        user_id: &str,
+        shares: f64,
+    ) -> Result<(f64, f64), LiquidityPoolError> {
         // Returns (token_a_amount, token_b_amount)
         let position = self
             .liquidity_positions
             .get(user_id)
-            .ok_or(LiquidityPoolError::UserNotInPool)?
-            .clone();
+            .ok_or(LiquidityPoolError::UserNotInPool)?;
 
         // Enforce time-based liquidity locking based on the position's duration
-        let now = Utc::now().naive_utc();
-        let lock_end = position.start_time + chrono::Duration::days(position.duration_days.max(0));
-        if now < lock_end {
+        if Utc::now().naive_utc() < Self::lock_end(position) {
             return Err(LiquidityPoolError::LiquidityLocked);
         }
+        if shares <= 0.0 || !shares.is_finite() {
+            return Err(LiquidityPoolError::InvalidAmount);
+        }
+        if shares > position.liquidity_amount {
+            return Err(LiquidityPoolError::InsufficientShares);
+        }
+
+        self.update_rewards(user_id)?;
 
         // Calculate proportional amounts to return
-        let liquidity_ratio = position.liquidity_amount / self.total_liquidity;
+        let liquidity_ratio = shares / self.total_liquidity;
         let token_a_return = self.total_token_a * liquidity_ratio;
         let token_b_return = self.total_token_b * liquidity_ratio;
 
         // Update pool totals
         self.total_token_a -= token_a_return;
         self.total_token_b -= token_b_return;
-        self.total_liquidity -= position.liquidity_amount;
+        self.total_liquidity -= shares;
+        self.k_constant = self.total_token_a * self.total_token_b;
 
-        // Update k constant
-        if self.total_liquidity > 0.0 {
-            self.k_constant = self.total_token_a * self.total_token_b;
-        } else {
-            self.k_constant = 0.0;
+        if let Some(pos) = self.liquidity_positions.get_mut(user_id) {
+            Self::scale_position(pos, (pos.liquidity_amount - shares) / pos.liquidity_amount);
+            pos.liquidity_amount -= shares;
         }
-
-        // Remove position
-        self.liquidity_positions.remove(user_id);
+        self.record_price();
 
         Ok((token_a_return, token_b_return))
     }
 
+    /// Move LP shares to another user, creating their position if needed. Both sides'
+    /// rewards are settled first, so rewards earned before the transfer stay with the
+    /// sender. The recipient's lock is extended to the sender's if that ends later.
+    pub fn transfer_shares(
+        &mut self,
+        from: &str,
+        to: &str,
+        shares: f64,
+    ) -> Result<(), LiquidityPoolError> {
+        if shares <= 0.0 || !shares.is_finite() || from == to {
+            return Err(LiquidityPoolError::InvalidAmount);
+        }
+        if shares > self.shares_of(from) {
+            return Err(if self.liquidity_positions.contains_key(from) {
+                LiquidityPoolError::InsufficientShares
+            } else {
+                LiquidityPoolError::UserNotInPool
+            });
+        }
+
+        self.update_rewards(from)?;
+        if self.liquidity_positions.contains_key(to) {
+            self.update_rewards(to)?;
+        }
+
+        let Some(sender) = self.liquidity_positions.get_mut(from) else {
+            return Err(LiquidityPoolError::UserNotInPool);
+        };
+        let ratio = shares / sender.liquidity_amount;
+        let (moved_a, moved_b) = (sender.token_a_amount * ratio, sender.token_b_amount * ratio);
+        Self::scale_position(sender, 1.0 - ratio);
+        sender.liquidity_amount -= shares;
+        let sender = sender.clone();
+
+        let recipient = self
+            .liquidity_positions
+            .entry(to.to_string())
+            .or_insert_with(|| LiquidityPosition {
+                user_id: to.to_string(),
+                liquidity_amount: 0.0,
+                token_a_amount: 0.0,
+                token_b_amount: 0.0,
+                accumulated_rewards: 0.0,
+                claimed_rewards: 0.0,
+                ..sender.clone()
+            });
+        if Self::lock_end(&sender) > Self::lock_end(recipient) {
+            recipient.start_time = sender.start_time;
+            recipient.duration_days = sender.duration_days;
+        }
+        recipient.blend_entry_price(moved_a, sender.entry_price());
+        recipient.liquidity_amount += shares;
+        recipient.token_a_amount += moved_a;
+        recipient.token_b_amount += moved_b;
+        Ok(())
+    }
+
+    /// LP shares held by a user (0 if they have no position)
+    pub fn shares_of(&self, user_id: &str) -> f64 {
+        self.liquidity_positions
+            .get(user_id)
+            .map(|p| p.liquidity_amount)
+            .unwrap_or(0.0)
+    }
+
+    /// Reserves currently backing `shares`, as (token_a, token_b)
+    pub fn share_value(&self, shares: f64) -> (f64, f64) {
+        if self.total_liquidity <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let ratio = shares / self.total_liquidity;
+        (self.total_token_a * ratio, self.total_token_b * ratio)
+    }
+
+    fn lock_end(position: &LiquidityPosition) -> NaiveDateTime {
+        position.start_time + chrono::Duration::days(position.duration_days.max(0))
+    }
+
+    /// Scale the deposit amounts recorded on a position when part of it leaves
+    fn scale_position(position: &mut LiquidityPosition, ratio: f64) {
+        position.token_a_amount *= ratio;
+        position.token_b_amount *= ratio;
+    }
+
     /// Get liquidity position for a user
     pub fn get_position(&self, user_id: &str) -> Option<&LiquidityPosition> {
         self.liquidity_positions.get(user_id)
@@ -249,12 +448,52 @@ impl LiquidityPool {
         &self.liquidity_positions
     }
 
+    /// Price swaps with `policy` instead of the fixed fee tier. The pool saves built-in
+    /// policies with it; a custom one makes serializing the pool fail until it's cleared.
+    pub fn set_fee_policy(&mut self, policy: Box<dyn FeePolicy>) {
+        self.fee_policy = Some(policy);
+    }
+
+    /// Go back to charging the fixed fee tier
+    pub fn clear_fee_policy(&mut self) -> Option<Box<dyn FeePolicy>> {
+        self.fee_policy.take()
+    }
+
+    /// Fee rate charged on a swap of `input_amount` of `input_token`
+    pub fn swap_fee(&self, input_token: &str, input_amount: f64) -> f64 {
+        let Some(policy) = &self.fee_policy else {
+            return self.config.fee_tier;
+        };
+        let input_reserve = if input_token == self.config.token_a {
+            self.total_token_a
+        } else {
+            self.total_token_b
+        };
+        policy.fee_rate(&FeeContext {
+            base_fee: self.config.fee_tier,
+            input_amount,
+            input_reserve,
+            oracle: &self.oracle,
+            now: Utc::now().naive_utc(),
+        })
+    }
+
     /// Calculate swap output amount
     pub fn calculate_swap_output(
         &self,
         input_token: &str,
         input_amount: f64,
     ) -> Result<f64, LiquidityPoolError> {
+        self.quote_swap(input_token, input_amount)
+            .map(|(output_amount, _)| output_amount)
+    }
+
+    /// Swap output and the fee rate it was priced with
+    fn quote_swap(
+        &self,
+        input_token: &str,
+        input_amount: f64,
+    ) -> Result<(f64, f64), LiquidityPoolError> {
         if input_amount <= 0.0 {
             return Err(LiquidityPoolError::InvalidAmount);
         }
@@ -272,7 +511,8 @@ impl LiquidityPool {
         }
 
         // Apply fee
-        let input_amount_with_fee = input_amount * (1.0 - self.config.fee_tier);
+        let fee_rate = self.swap_fee(input_token, input_amount);
+        let input_amount_with_fee = input_amount * (1.0 - fee_rate);
 
         // Constant product formula: (x + dx) * (y - dy) = x * y
         // Solving for dy: dy = y - (x * y) / (x + dx)
@@ -283,16 +523,27 @@ impl LiquidityPool {
             return Err(LiquidityPoolError::InsufficientLiquidity);
         }
 
-        Ok(output_amount)
+        Ok((output_amount, fee_rate))
     }
 
-    /// Execute a swap
+    /// Execute a swap. Not available while the pool is in batch-auction mode.
     pub fn swap(
         &mut self,
         input_token: &str,
         input_amount: f64,
     ) -> Result<f64, LiquidityPoolError> {
-        let output_amount = self.calculate_swap_output(input_token, input_amount)?;
+        if self.batch_auction.is_some() {
+            return Err(LiquidityPoolError::BatchAuctionActive);
+        }
+        self.execute_swap(input_token, input_amount)
+    }
+
+    fn execute_swap(
+        &mut self,
+        input_token: &str,
+        input_amount: f64,
+    ) -> Result<f64, LiquidityPoolError> {
+        let (output_amount, fee_rate) = self.quote_swap(input_token, input_amount)?;
 
         // Update reserves
         if input_token == self.config.token_a {
@@ -310,13 +561,153 @@ impl LiquidityPool {
 
         // Update volume and fees tracking
         self.total_volume += input_amount;
-        let fee_amount = input_amount * self.config.fee_tier;
+        let fee_amount = input_amount * fee_rate;
         self.total_fees += fee_amount;
         self.apply_auto_liquidity(fee_amount);
+        self.record_price();
 
         Ok(output_amount)
     }
 
+    /// Fee charged on a flash loan of `amount`
+    pub fn flash_fee(&self, amount: f64) -> f64 {
+        amount * self.config.fee_tier
+    }
+
+    /// Lend `amount` of `token` to `receiver` for the duration of its callback. It must
+    /// pay back the amount plus `flash_fee`, which stays in the reserves for the LPs.
+    /// Returns the fee charged.
+    pub fn flash_loan(
+        &mut self,
+        receiver: &mut dyn FlashCallback,
+        token: &str,
+        amount: f64,
+    ) -> Result<f64, LiquidityPoolError> {
+        if amount <= 0.0 || !amount.is_finite() {
+            return Err(LiquidityPoolError::InvalidAmount);
+        }
+        let is_a = self.token_side(token)?;
+        let reserve = if is_a {
+            self.total_token_a
+        } else {
+            self.total_token_b
+        };
+        if amount >= reserve {
+            return Err(LiquidityPoolError::InsufficientLiquidity);
+        }
+
+        let fee = self.flash_fee(amount);
+        let repaid = self.lend(is_a, amount, receiver, token, token, amount + fee)?;
+        if is_a {
+            self.total_token_a += repaid;
+        } else {
+            self.total_token_b += repaid;
+        }
+        self.k_constant = self.total_token_a * self.total_token_b;
+        self.total_fees += repaid - amount;
+        self.record_price();
+        Ok(repaid - amount)
+    }
+
+    /// Receive `output_amount` of `output_token` before paying for it: `receiver` gets
+    /// the tokens first and pays back the other token, at least as much as a regular
+    /// swap for that output would cost. Returns the input amount paid.
+    pub fn flash_swap(
+        &mut self,
+        receiver: &mut dyn FlashCallback,
+        output_token: &str,
+        output_amount: f64,
+    ) -> Result<f64, LiquidityPoolError> {
+        if output_amount <= 0.0 || !output_amount.is_finite() {
+            return Err(LiquidityPoolError::InvalidAmount);
+        }
+        if self.batch_auction.is_some() {
+            return Err(LiquidityPoolError::BatchAuctionActive);
+        }
+        let out_is_a = self.token_side(output_token)?;
+        let (input_reserve, output_reserve, input_token) = if out_is_a {
+            (
+                self.total_token_b,
+                self.total_token_a,
+                self.config.token_b.clone(),
+            )
+        } else {
+            (
+                self.total_token_a,
+                self.total_token_b,
+                self.config.token_a.clone(),
+            )
+        };
+        if output_amount >= output_reserve {
+            return Err(LiquidityPoolError::InsufficientLiquidity);
+        }
+
+        // Input whose fee-adjusted amount keeps x * y = k for this output, with the fee
+        // rate sized on the fee-free input
+        let fee_free = input_reserve * output_amount / (output_reserve - output_amount);
+        let fee_rate = self.swap_fee(&input_token, fee_free);
+        let owed = fee_free / (1.0 - fee_rate);
+        let repaid = self.lend(
+            out_is_a,
+            output_amount,
+            receiver,
+            output_token,
+            &input_token,
+            owed,
+        )?;
+        if out_is_a {
+            self.total_token_b += repaid;
+        } else {
+            self.total_token_a += repaid;
+        }
+        self.k_constant = self.total_token_a * self.total_token_b;
+        self.total_volume += repaid;
+        let fee_amount = repaid * fee_rate;
+        self.total_fees += fee_amount;
+        self.apply_auto_liquidity(fee_amount);
+        self.record_price();
+        Ok(repaid)
+    }
+
+    /// Whether `token` is token_a (true) or token_b (false)
+    fn token_side(&self, token: &str) -> Result<bool, LiquidityPoolError> {
+        if token == self.config.token_a {
+            Ok(true)
+        } else if token == self.config.token_b {
+            Ok(false)
+        } else {
+            Err(LiquidityPoolError::InvalidAmount)
+        }
+    }
+
+    /// Take `amount` out of one side, run the callback and check the repayment,
+    /// putting the reserves back if it fails
+    fn lend(
+        &mut self,
+        from_a: bool,
+        amount: f64,
+        receiver: &mut dyn FlashCallback,
+        token: &str,
+        repay_token: &str,
+        owed: f64,
+    ) -> Result<f64, LiquidityPoolError> {
+        let reserves = (self.total_token_a, self.total_token_b);
+        if from_a {
+            self.total_token_a -= amount;
+        } else {
+            self.total_token_b -= amount;
+        }
+        let result = match receiver.on_flash(token, amount, repay_token, owed) {
+            Ok(repaid) if repaid >= owed && repaid.is_finite() => Ok(repaid),
+            Ok(_) => Err(LiquidityPoolError::FlashLoanNotRepaid),
+            Err(msg) => Err(LiquidityPoolError::FlashCallbackFailed(msg)),
+        };
+        if result.is_err() {
+            (self.total_token_a, self.total_token_b) = reserves;
+        }
+        result
+    }
+
     /// Get pool reserves
     pub fn get_reserves(&self) -> (f64, f64) {
         (self.total_token_a, self.total_token_b)
@@ -327,6 +718,25 @@ impl LiquidityPool {
         &self.config
     }
 
+    /// Marginal price of token_a in units of token_b, if both reserves are funded
+    pub fn spot_price(&self) -> Option<f64> {
+        if self.total_token_a <= 0.0 || self.total_token_b <= 0.0 {
+            return None;
+        }
+        Some(self.total_token_b / self.total_token_a)
+    }
+
+    /// Time-weighted average and geometric-mean prices of token_a over the last `window`
+    pub fn consult(&self, window: chrono::Duration) -> Result<OracleQuote, OracleError> {
+        self.oracle.consult(window, Utc::now().naive_utc())
+    }
+
+    fn record_price(&mut self) {
+        if let Some(price) = self.spot_price() {
+            let _ = self.oracle.record(Utc::now().naive_utc(), price);
+        }
+    }
+
     /// Calculate yield for a liquidity position based on time and APR
     pub fn calculate_yield(&self, position: &LiquidityPosition) -> f64 {
         let now = Utc::now().naive_utc();
@@ -430,6 +840,11 @@ impl LiquidityPool {
 
         self.config.distributed_rewards += claimable;
 
+        // Positions emptied by burns or transfers only stay around to be claimed
+        if self.shares_of(user_id) <= 0.0 {
+            self.liquidity_positions.remove(user_id);
+        }
+
         Ok(claimable)
     }
 
@@ -447,15 +862,64 @@ impl LiquidityPool {
         Ok(fee_distribution)
     }
 
+    /// Compare a position with holding the tokens it deposited, valued in token_b at
+    /// the current spot price. Claimed rewards count towards net PnL at
+    /// `reward_price` (token_b per reward token).
+    pub fn position_pnl(
+        &self,
+        user_id: &str,
+        reward_price: f64,
+    ) -> Result<PositionPnl, LiquidityPoolError> {
+        let position = self
+            .liquidity_positions
+            .get(user_id)
+            .ok_or(LiquidityPoolError::UserNotInPool)?;
+        let current_price = self
+            .spot_price()
+            .ok_or(LiquidityPoolError::InsufficientLiquidity)?;
+
+        let entry_price = position.entry_price();
+        let entry_value = position.token_a_amount * entry_price + position.token_b_amount;
+        let hold_value = position.token_a_amount * current_price + position.token_b_amount;
+        let (share_a, share_b) = self.share_value(position.liquidity_amount);
+        let position_value = share_a * current_price + share_b;
+        let impermanent_loss = hold_value - position_value;
+
+        Ok(PositionPnl {
+            user_id: user_id.to_string(),
+            entry_price,
+            current_price,
+            entry_value,
+            hold_value,
+            position_value,
+            impermanent_loss,
+            impermanent_loss_pct: percent_of(impermanent_loss, hold_value),
+            claimed_rewards: position.claimed_rewards,
+            net_pnl: position_value - entry_value + position.claimed_rewards * reward_price,
+        })
+    }
+
     /// Get pool statistics
     pub fn get_pool_stats(&self) -> PoolStats {
-        let total_providers = self.liquidity_positions.len() as f64;
+        let total_providers = self
+            .liquidity_positions
+            .values()
+            .filter(|p| p.liquidity_amount > 0.0)
+            .count() as f64;
         let avg_liquidity = if total_providers > 0.0 {
             self.total_liquidity / total_providers
         } else {
             0.0
         };
 
+        let (hold_value, impermanent_loss) = self
+            .liquidity_positions
+            .keys()
+            .filter_map(|user_id| self.position_pnl(user_id, 0.0).ok())
+            .fold((0.0, 0.0), |(hold, loss), pnl| {
+                (hold + pnl.hold_value, loss + pnl.impermanent_loss)
+            });
+
         PoolStats {
             total_liquidity: self.total_liquidity,
             total_volume: self.total_volume,
@@ -463,6 +927,8 @@ impl LiquidityPool {
             total_providers: total_providers as usize,
             avg_liquidity,
             apr_rate: self.config.apr_rate,
+            total_impermanent_loss: impermanent_loss,
+            impermanent_loss_pct: percent_of(impermanent_loss, hold_value),
         }
     }
 }
@@ -491,6 +957,210 @@ impl LiquidityPool {
         Ok(output)
     }
 
+    /// Switch to batch-auction mode: from `now` on, swaps are only accepted through
+    /// `submit_batch_swap` and settle together at the end of each `epoch_secs` epoch
+    pub fn enable_batch_auction(&mut self, epoch_secs: i64, now: NaiveDateTime) {
+        if self.batch_auction.is_none() {
+            self.batch_auction = Some(BatchAuction::new(epoch_secs, now));
+        }
+    }
+
+    /// Leave batch-auction mode, returning the unsettled orders so their input can be
+    /// refunded
+    pub fn disable_batch_auction(&mut self) -> Vec<BatchOrder> {
+        self.batch_auction
+            .take()
+            .map(|auction| auction.orders)
+            .unwrap_or_default()
+    }
+
+    /// Queue a swap for the current epoch. `min_output` is a limit: the order is left
+    /// unfilled if the clearing price would pay less. Returns the order id.
+    pub fn submit_batch_swap(
+        &mut self,
+        user_id: &str,
+        input_token: &str,
+        input_amount: f64,
+        min_output: f64,
+        now: NaiveDateTime,
+    ) -> Result<u64, LiquidityPoolError> {
+        if input_amount <= 0.0 || !input_amount.is_finite() || min_output < 0.0 {
+            return Err(LiquidityPoolError::InvalidAmount);
+        }
+        self.token_side(input_token)?;
+        let auction = self
+            .batch_auction
+            .as_mut()
+            .ok_or(LiquidityPoolError::BatchAuctionInactive)?;
+        if now >= auction.epoch_end() {
+            return Err(LiquidityPoolError::EpochEnded);
+        }
+
+        let order_id = auction.next_order_id;
+        auction.next_order_id += 1;
+        auction.orders.push(BatchOrder {
+            order_id,
+            user_id: user_id.to_string(),
+            input_token: input_token.to_string(),
+            input_amount,
+            min_output,
+        });
+        Ok(order_id)
+    }
+
+    /// Settle the ended epoch. Every filled order trades at one clearing price:
+    /// opposite orders are matched with each other and only the net imbalance is
+    /// swapped through the pool, so there is no ordering within a batch to exploit.
+    /// Orders whose limit is not met are dropped one at a time, most demanding first,
+    /// and the price recomputed without them.
+    pub fn settle_batch(
+        &mut self,
+        now: NaiveDateTime,
+    ) -> Result<BatchSettlement, LiquidityPoolError> {
+        let auction = self
+            .batch_auction
+            .as_ref()
+            .ok_or(LiquidityPoolError::BatchAuctionInactive)?;
+        let (epoch_start, epoch_end) = (auction.epoch_start, auction.epoch_end());
+        if now < epoch_end {
+            return Err(LiquidityPoolError::EpochNotEnded);
+        }
+        let orders = auction.orders.clone();
+        let next_epoch_start = auction.next_epoch_start(now);
+
+        let sells_a: Vec<bool> = orders
+            .iter()
+            .map(|o| o.input_token == self.config.token_a)
+            .collect();
+        let mut active = vec![true; orders.len()];
+        let (clearing, outputs) = loop {
+            let total = |side_a: bool| -> f64 {
+                orders
+                    .iter()
+                    .zip(&active)
+                    .zip(&sells_a)
+                    .filter(|((_, on), is_a)| **on && **is_a == side_a)
+                    .map(|((o, _), _)| o.input_amount)
+                    .sum()
+            };
+            let (sell_a, sell_b) = (total(true), total(false));
+            let clearing = self.spot_price().and_then(|spot| {
+                batch_auction::clear(
+                    sell_a,
+                    sell_b,
+                    spot * (1.0 - self.swap_fee(&self.config.token_a, 0.0)),
+                    (1.0 - self.swap_fee(&self.config.token_b, 0.0)) / spot,
+                    |amount| {
+                        self.calculate_swap_output(&self.config.token_a, amount)
+                            .ok()
+                    },
+                    |amount| {
+                        self.calculate_swap_output(&self.config.token_b, amount)
+                            .ok()
+                    },
+                )
+            });
+            let Some(c) = clearing else {
+                break (None, vec![0.0; orders.len()]);
+            };
+
+            // Each side shares what the other side and the pool leave for it pro rata
+            let out_b = sell_b - c.pool_input_b
+                + if c.pool_input_a > 0.0 {
+                    c.pool_output
+                } else {
+                    0.0
+                };
+            let out_a = sell_a - c.pool_input_a
+                + if c.pool_input_b > 0.0 {
+                    c.pool_output
+                } else {
+                    0.0
+                };
+            let outputs: Vec<f64> = orders
+                .iter()
+                .zip(&active)
+                .zip(&sells_a)
+                .map(|((o, on), is_a)| match (on, is_a) {
+                    (false, _) => 0.0,
+                    (true, true) => out_b * o.input_amount / sell_a,
+                    (true, false) => out_a * o.input_amount / sell_b,
+                })
+                .collect();
+            // Drop the most demanding order whose limit is missed, the larger one on a
+            // tie; the others may fit once it is gone
+            let worst = orders
+                .iter()
+                .enumerate()
+                .filter(|(i, o)| active[*i] && outputs[*i] < o.min_output)
+                .max_by(|(_, x), (_, y)| {
+                    (x.min_output / x.input_amount)
+                        .total_cmp(&(y.min_output / y.input_amount))
+                        .then(x.input_amount.total_cmp(&y.input_amount))
+                })
+                .map(|(i, _)| i);
+            match worst {
+                Some(i) => active[i] = false,
+                None => break (Some(c), outputs),
+            }
+        };
+
+        let mut settlement = BatchSettlement {
+            epoch_start,
+            epoch_end,
+            clearing_price: clearing.map(|c| c.price),
+            matched_token_a: 0.0,
+            pool_input_token: None,
+            pool_input_amount: 0.0,
+            pool_output_amount: 0.0,
+            fills: Vec::with_capacity(orders.len()),
+        };
+        if let Some(c) = clearing {
+            let sell_a: f64 = orders
+                .iter()
+                .zip(&active)
+                .zip(&sells_a)
+                .filter(|((_, on), is_a)| **on && **is_a)
+                .map(|((o, _), _)| o.input_amount)
+                .sum();
+            settlement.matched_token_a = sell_a - c.pool_input_a;
+            let pool_input = if c.pool_input_a > 0.0 {
+                Some((self.config.token_a.clone(), c.pool_input_a))
+            } else if c.pool_input_b > 0.0 {
+                Some((self.config.token_b.clone(), c.pool_input_b))
+            } else {
+                None
+            };
+            if let Some((token, amount)) = pool_input {
+                settlement.pool_output_amount = self.execute_swap(&token, amount)?;
+                settlement.pool_input_amount = amount;
+                settlement.pool_input_token = Some(token);
+            }
+        }
+
+        for (i, order) in orders.into_iter().enumerate() {
+            let output_token = if sells_a[i] {
+                self.config.token_b.clone()
+            } else {
+                self.config.token_a.clone()
+            };
+            settlement.fills.push(BatchFill {
+                order_id: order.order_id,
+                user_id: order.user_id,
+                input_token: order.input_token,
+                input_amount: order.input_amount,
+                output_token,
+                output_amount: outputs[i],
+                filled: active[i] && outputs[i] > 0.0,
+            });
+        }
+        if let Some(auction) = self.batch_auction.as_mut() {
+            auction.orders.clear();
+            auction.epoch_start = next_epoch_start;
+        }
+        Ok(settlement)
+    }
+
     fn apply_auto_liquidity(&mut self, fee_amount: f64) {
         let rate = self.mechanisms.auto_liquidity_rate_bps as f64 / 10_000.0;
         if rate <= 0.0 || fee_amount <= 0.0 {
@@ -522,4 +1192,32 @@ pub struct PoolStats {
     pub total_providers: usize,
     pub avg_liquidity: f64,
     pub apr_rate: f64,
+    #[serde(default)]
+    pub total_impermanent_loss: f64, // Summed over positions, in token_b
+    #[serde(default)]
+    pub impermanent_loss_pct: f64, // Percent of the value of holding every deposit
+}
+
+// How a liquidity position compares with holding its deposits. Values are in token_b
+// at the current spot price; a positive impermanent loss means holding did better.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionPnl {
+    pub user_id: String,
+    pub entry_price: f64,
+    pub current_price: f64,
+    pub entry_value: f64,    // Deposits valued at the entry price
+    pub hold_value: f64,     // Deposits valued at the current price
+    pub position_value: f64, // Share of the reserves, fees included
+    pub impermanent_loss: f64,
+    pub impermanent_loss_pct: f64, // Percent of hold_value
+    pub claimed_rewards: f64,
+    pub net_pnl: f64, // position_value - entry_value + claimed rewards at reward_price
+}
+
+fn percent_of(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 {
+        part / whole * 100.0
+    } else {
+        0.0
+    }
 }
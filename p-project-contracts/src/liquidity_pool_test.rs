@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use super::super::liquidity_pool::{LiquidityMechanisms, LiquidityPool, LiquidityPoolError};
+    use super::super::liquidity_pool::{
+        FlashCallback, LiquidityMechanisms, LiquidityPool, LiquidityPoolError, MINIMUM_LIQUIDITY,
+    };
+    use super::super::stable_liquidity_pool::StableLiquidityPool;
     use chrono::Utc;
     use std::collections::HashMap;
 
@@ -49,10 +52,14 @@ mod tests {
 
         assert!(result.is_ok());
         let liquidity_amount = result.unwrap();
-        assert_eq!(liquidity_amount, (1000.0f64 * 2000.0f64).sqrt());
+        assert_eq!(
+            liquidity_amount,
+            (1000.0f64 * 2000.0f64).sqrt() - MINIMUM_LIQUIDITY
+        );
         assert_eq!(pool.total_token_a, 1000.0);
         assert_eq!(pool.total_token_b, 2000.0);
-        assert_eq!(pool.total_liquidity, liquidity_amount);
+        assert_eq!(pool.total_liquidity, liquidity_amount + MINIMUM_LIQUIDITY);
+        assert_eq!(pool.locked_liquidity, MINIMUM_LIQUIDITY);
         assert_eq!(pool.k_constant, 1000.0 * 2000.0);
 
         // Check position was created
@@ -162,12 +169,13 @@ mod tests {
         let result = pool.remove_liquidity("user1");
         assert!(result.is_ok());
         let (token_a_return, token_b_return) = result.unwrap();
-        assert_eq!(token_a_return, 1000.0);
-        assert_eq!(token_b_return, 2000.0);
-        assert_eq!(pool.total_token_a, 0.0);
-        assert_eq!(pool.total_token_b, 0.0);
-        assert_eq!(pool.total_liquidity, 0.0);
-        assert_eq!(pool.k_constant, 0.0);
+        // Everything but the reserves backing the locked minimum liquidity comes back
+        let locked_ratio = MINIMUM_LIQUIDITY / (1000.0f64 * 2000.0f64).sqrt();
+        assert!((token_a_return - 1000.0 * (1.0 - locked_ratio)).abs() < 1e-9);
+        assert!((token_b_return - 2000.0 * (1.0 - locked_ratio)).abs() < 1e-9);
+        assert!((pool.total_token_a - 1000.0 * locked_ratio).abs() < 1e-9);
+        assert!((pool.total_token_b - 2000.0 * locked_ratio).abs() < 1e-9);
+        assert!((pool.total_liquidity - MINIMUM_LIQUIDITY).abs() < 1e-12);
 
         // Check position was removed
         assert!(pool.get_position("user1").is_none());
@@ -604,4 +612,353 @@ mod tests {
         assert_eq!(pool.config.token_a, "P-COIN");
         assert_eq!(pool.config.token_b, "USDC");
     }
+
+    fn funded_pool() -> LiquidityPool {
+        let mut pool = LiquidityPool::new(
+            "pool1".to_string(),
+            "TOKENA".to_string(),
+            "TOKENB".to_string(),
+            0.003,
+            "REWARD".to_string(),
+            100000.0,
+            0.12,
+        );
+        pool.add_liquidity("alice".to_string(), 10_000.0, 40_000.0, 30)
+            .unwrap();
+        pool
+    }
+
+    fn unlock(pool: &mut LiquidityPool, user: &str) {
+        let pos = pool.liquidity_positions.get_mut(user).unwrap();
+        pos.start_time = pos.start_time - chrono::Duration::days(pos.duration_days + 1);
+    }
+
+    #[test]
+    fn test_shares_minted_in_proportion_to_reserves() {
+        let mut pool = funded_pool();
+        assert!(pool
+            .add_liquidity("dust".to_string(), 1e-4, 1e-4, 30)
+            .is_ok());
+        let mut empty = LiquidityPool::new(
+            "empty".to_string(),
+            "A".to_string(),
+            "B".to_string(),
+            0.003,
+            "REWARD".to_string(),
+            0.0,
+            0.0,
+        );
+        assert_eq!(
+            empty.add_liquidity("dust".to_string(), 1e-4, 1e-4, 30),
+            Err(LiquidityPoolError::InsufficientLiquidity)
+        );
+
+        // Swaps move the price away from the first deposit's ratio
+        pool.swap("TOKENA", 1_000.0).unwrap();
+        let supply = pool.total_liquidity;
+        let (ra, rb) = pool.get_reserves();
+        let minted = pool
+            .add_liquidity("bob".to_string(), ra / 10.0, rb / 10.0, 30)
+            .unwrap();
+        assert!((minted - supply / 10.0).abs() < 1e-9);
+        assert_eq!(pool.k_constant, pool.total_token_a * pool.total_token_b);
+
+        // An unbalanced deposit is credited for its smaller side only
+        let (ra, rb) = pool.get_reserves();
+        let supply = pool.total_liquidity;
+        let minted = pool
+            .add_liquidity("carol".to_string(), ra / 10.0, rb, 30)
+            .unwrap();
+        assert!((minted - supply / 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_partial_burn() {
+        let mut pool = funded_pool();
+        let shares = pool.shares_of("alice");
+        assert_eq!(
+            pool.burn_shares("alice", shares / 2.0),
+            Err(LiquidityPoolError::LiquidityLocked)
+        );
+        unlock(&mut pool, "alice");
+        assert_eq!(
+            pool.burn_shares("alice", shares * 2.0),
+            Err(LiquidityPoolError::InsufficientShares)
+        );
+
+        let expected = pool.share_value(shares / 4.0);
+        let (a, b) = pool.burn_shares("alice", shares / 4.0).unwrap();
+        assert_eq!((a, b), expected);
+        assert!((a - 2_500.0).abs() < 1e-3);
+        assert!((pool.shares_of("alice") - shares * 0.75).abs() < 1e-9);
+        let position = pool.get_position("alice").unwrap();
+        assert!((position.token_a_amount - 7_500.0).abs() < 1e-9);
+        assert!((position.token_b_amount - 30_000.0).abs() < 1e-9);
+        assert_eq!(pool.k_constant, pool.total_token_a * pool.total_token_b);
+    }
+
+    #[test]
+    fn test_transfer_shares_keeps_pending_rewards_with_sender() {
+        let mut pool = funded_pool();
+        let shares = pool.shares_of("alice");
+        // Alice has been earning for 30 days
+        {
+            let pos = pool.liquidity_positions.get_mut("alice").unwrap();
+            pos.last_reward_time = pos.last_reward_time - chrono::Duration::days(30);
+        }
+        let earned = pool.get_claimable_rewards("alice").unwrap();
+        assert!(earned > 0.0);
+
+        assert_eq!(
+            pool.transfer_shares("alice", "bob", shares * 2.0),
+            Err(LiquidityPoolError::InsufficientShares)
+        );
+        assert_eq!(
+            pool.transfer_shares("bob", "alice", 1.0),
+            Err(LiquidityPoolError::UserNotInPool)
+        );
+        pool.transfer_shares("alice", "bob", shares).unwrap();
+
+        // Bob holds the shares (and their lock) but none of the past rewards
+        assert_eq!(pool.shares_of("alice"), 0.0);
+        assert_eq!(pool.shares_of("bob"), shares);
+        let bob = pool.get_position("bob").unwrap();
+        assert!((bob.token_a_amount - 10_000.0).abs() < 1e-9);
+        assert_eq!(bob.duration_days, 30);
+        assert!(pool.get_claimable_rewards("bob").unwrap() < 1e-6);
+        assert!((pool.get_claimable_rewards("alice").unwrap() - earned).abs() < 1e-6);
+        assert_eq!(pool.get_pool_stats().total_providers, 1);
+        assert_eq!(
+            pool.burn_shares("bob", shares),
+            Err(LiquidityPoolError::LiquidityLocked)
+        );
+
+        // Alice's emptied position goes away once she has claimed
+        let claimed = pool.claim_rewards("alice").unwrap();
+        assert!((claimed - earned).abs() < 1e-6);
+        assert!(pool.get_position("alice").is_none());
+
+        unlock(&mut pool, "bob");
+        pool.transfer_shares("bob", "alice", shares / 2.0).unwrap();
+        let (a, _) = pool.remove_liquidity("bob").unwrap();
+        assert!((a - 5_000.0).abs() < 1e-3);
+        assert!(pool.get_position("bob").is_none());
+    }
+
+    // Pays back `owed` scaled by `repay_ratio`, or fails outright
+    struct Borrower {
+        repay_ratio: f64,
+        fail: bool,
+        received: Vec<(String, f64)>,
+    }
+
+    impl FlashCallback for Borrower {
+        fn on_flash(
+            &mut self,
+            token: &str,
+            amount: f64,
+            _repay_token: &str,
+            owed: f64,
+        ) -> Result<f64, String> {
+            self.received.push((token.to_string(), amount));
+            if self.fail {
+                return Err("trade failed".to_string());
+            }
+            Ok(owed * self.repay_ratio)
+        }
+    }
+
+    #[test]
+    fn test_flash_loan_repaid_with_fee_or_reverted() {
+        let mut pool = funded_pool();
+        let before = pool.get_reserves();
+        let mut borrower = Borrower {
+            repay_ratio: 1.0,
+            fail: false,
+            received: Vec::new(),
+        };
+
+        let fee = pool.flash_loan(&mut borrower, "TOKENA", 5_000.0).unwrap();
+        assert_eq!(fee, pool.flash_fee(5_000.0));
+        assert!((fee - 15.0).abs() < 1e-9);
+        assert_eq!(borrower.received, vec![("TOKENA".to_string(), 5_000.0)]);
+        // The fee stays in the reserves, so every share is worth more
+        assert_eq!(pool.get_reserves(), (before.0 + fee, before.1));
+        assert_eq!(pool.total_fees, fee);
+        assert_eq!(pool.k_constant, (before.0 + fee) * before.1);
+
+        let after_loan = pool.clone();
+        borrower.repay_ratio = 0.999;
+        assert_eq!(
+            pool.flash_loan(&mut borrower, "TOKENB", 1_000.0),
+            Err(LiquidityPoolError::FlashLoanNotRepaid)
+        );
+        borrower.fail = true;
+        assert_eq!(
+            pool.flash_loan(&mut borrower, "TOKENB", 1_000.0),
+            Err(LiquidityPoolError::FlashCallbackFailed(
+                "trade failed".to_string()
+            ))
+        );
+        assert_eq!(pool.get_reserves(), after_loan.get_reserves());
+        assert_eq!(pool.total_fees, after_loan.total_fees);
+        assert_eq!(
+            pool.flash_loan(&mut borrower, "TOKENB", 40_000.0),
+            Err(LiquidityPoolError::InsufficientLiquidity)
+        );
+    }
+
+    // Buys USDT cheaply in a stable pool with USDC borrowed from a constant pool that
+    // overprices USDT, then repays the constant pool in USDT
+    struct Arbitrageur {
+        stable: StableLiquidityPool,
+        profit: f64,
+    }
+
+    impl FlashCallback for Arbitrageur {
+        fn on_flash(
+            &mut self,
+            token: &str,
+            amount: f64,
+            repay_token: &str,
+            owed: f64,
+        ) -> Result<f64, String> {
+            let bought = self.stable.swap(token, amount).map_err(|e| e.to_string())?;
+            assert_eq!(repay_token, "USDT");
+            if bought < owed {
+                return Err("unprofitable".to_string());
+            }
+            self.profit = bought - owed;
+            Ok(owed)
+        }
+    }
+
+    #[test]
+    fn test_flash_swap_arbitrage_between_pools() {
+        let mut constant = LiquidityPool::new(
+            "usdc_usdt".to_string(),
+            "USDC".to_string(),
+            "USDT".to_string(),
+            0.003,
+            "REWARD".to_string(),
+            0.0,
+            0.0,
+        );
+        constant
+            .add_liquidity("lp".to_string(), 105_000.0, 100_000.0, 30)
+            .unwrap();
+        let mut stable = StableLiquidityPool::new(
+            "usdc_usdt_stable".to_string(),
+            "USDC".to_string(),
+            "USDT".to_string(),
+            0.0004,
+            200.0,
+            "REWARD".to_string(),
+            0.0,
+            0.0,
+        )
+        .unwrap();
+        stable
+            .add_liquidity("lp".to_string(), 1_000_000.0, 1_000_000.0, 30)
+            .unwrap();
+
+        let untouched = constant.clone();
+        let mut bot = Arbitrageur {
+            stable,
+            profit: 0.0,
+        };
+        let paid = constant.flash_swap(&mut bot, "USDC", 2_000.0).unwrap();
+        // Same terms as a regular swap: paying `paid` USDT buys the borrowed 2,000 USDC
+        let regular = untouched.calculate_swap_output("USDT", paid).unwrap();
+        assert!((regular - 2_000.0).abs() < 1e-6);
+        assert!(bot.profit > 40.0);
+        assert_eq!(constant.get_reserves(), (103_000.0, 100_000.0 + paid));
+        assert!(constant.k_constant > 105_000.0 * 100_000.0);
+        assert!((constant.total_fees - paid * 0.003).abs() < 1e-9);
+        assert!((constant.total_volume - paid).abs() < 1e-9);
+
+        // Once the prices line up the trade no longer pays and the pool is left as it was
+        let before = constant.get_reserves();
+        assert!(matches!(
+            constant.flash_swap(&mut bot, "USDC", 10_000.0),
+            Err(LiquidityPoolError::FlashCallbackFailed(_))
+        ));
+        assert_eq!(constant.get_reserves(), before);
+    }
+
+    #[test]
+    fn test_impermanent_loss_against_holding() {
+        let mut pool = LiquidityPool::new(
+            "pool1".to_string(),
+            "TOKENA".to_string(),
+            "TOKENB".to_string(),
+            0.0,
+            "REWARD".to_string(),
+            100000.0,
+            0.12,
+        );
+        pool.add_liquidity("alice".to_string(), 10_000.0, 40_000.0, 30)
+            .unwrap();
+        let pnl = pool.position_pnl("alice", 1.0).unwrap();
+        assert_eq!(pnl.entry_price, 4.0);
+        // Only the locked MINIMUM_LIQUIDITY sets the position apart from holding
+        assert!(pnl.impermanent_loss > 0.0 && pnl.impermanent_loss < 1e-2);
+        assert_eq!(
+            pool.position_pnl("bob", 1.0),
+            Err(LiquidityPoolError::UserNotInPool)
+        );
+
+        // Price falls from 4 to 1: the textbook loss is 1 - 2 * sqrt(r) / (1 + r) = 20%
+        pool.swap("TOKENA", 10_000.0).unwrap();
+        let pnl = pool.position_pnl("alice", 1.0).unwrap();
+        assert!((pnl.current_price - 1.0).abs() < 1e-12);
+        assert!((pnl.hold_value - 50_000.0).abs() < 1e-6);
+        assert!((pnl.impermanent_loss - 10_000.0).abs() < 1e-2);
+        assert!((pnl.impermanent_loss_pct - 20.0).abs() < 1e-4);
+        assert!((pnl.net_pnl - (pnl.position_value - 80_000.0)).abs() < 1e-9);
+        let position = pool.get_position("alice").unwrap();
+        assert_eq!(
+            (position.entry_reserve_a, position.entry_reserve_b),
+            (10_000.0, 40_000.0)
+        );
+
+        // Claimed rewards count towards net PnL at the given price
+        pool.liquidity_positions
+            .get_mut("alice")
+            .unwrap()
+            .claimed_rewards = 500.0;
+        let with_rewards = pool.position_pnl("alice", 2.0).unwrap();
+        assert!((with_rewards.net_pnl - pnl.net_pnl - 1_000.0).abs() < 1e-9);
+        assert_eq!(with_rewards.impermanent_loss, pnl.impermanent_loss);
+    }
+
+    #[test]
+    fn test_pool_stats_aggregate_impermanent_loss() {
+        let mut pool = funded_pool();
+        pool.swap("TOKENA", 5_000.0).unwrap();
+        // Bob enters at the new price, and carol inherits alice's entry with her shares
+        let (ra, rb) = pool.get_reserves();
+        pool.add_liquidity("bob".to_string(), ra / 10.0, rb / 10.0, 30)
+            .unwrap();
+        unlock(&mut pool, "alice");
+        let shares = pool.shares_of("alice");
+        pool.transfer_shares("alice", "carol", shares / 2.0)
+            .unwrap();
+
+        let bob = pool.position_pnl("bob", 0.0).unwrap();
+        assert!((bob.entry_price - rb / ra).abs() < 1e-12);
+        assert!(bob.impermanent_loss.abs() < 1e-6);
+        let alice = pool.position_pnl("alice", 0.0).unwrap();
+        let carol = pool.position_pnl("carol", 0.0).unwrap();
+        assert_eq!(carol.entry_price, 4.0);
+        assert!((carol.impermanent_loss - alice.impermanent_loss).abs() < 1e-6);
+        assert!(alice.impermanent_loss > 0.0);
+
+        let stats = pool.get_pool_stats();
+        let total = alice.impermanent_loss + bob.impermanent_loss + carol.impermanent_loss;
+        assert!((stats.total_impermanent_loss - total).abs() < 1e-6);
+        let hold = alice.hold_value + bob.hold_value + carol.hold_value;
+        assert!((stats.impermanent_loss_pct - total / hold * 100.0).abs() < 1e-9);
+        assert!(stats.impermanent_loss_pct < alice.impermanent_loss_pct);
+    }
 }
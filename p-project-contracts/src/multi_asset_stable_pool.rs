@@ -0,0 +1,495 @@
+use crate::stable_liquidity_pool::{
+    stableswap_d, stableswap_y, AmplificationRamp, StablePoolError,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Largest number of tokens a multi-asset pool can hold
+pub const MAX_POOL_TOKENS: usize = 8;
+
+// Largest supported token precision
+pub const MAX_TOKEN_DECIMALS: u32 = 36;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiAssetStablePoolConfig {
+    pub pool_id: String,
+    pub tokens: Vec<String>, // e.g., USDC, USDT, DAI
+    pub decimals: Vec<u32>,  // Decimal places of each token's raw amounts
+    pub fee_tier: f64,
+    pub start_date: NaiveDateTime,
+    pub amplification: f64, // Target A while ramping
+    pub reward_token: String,
+    pub total_reward_allocation: f64,
+    pub distributed_rewards: f64,
+    pub apr_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiAssetStablePosition {
+    pub user_id: String,
+    pub pool_id: String,
+    pub liquidity_amount: f64,
+    pub token_amounts: Vec<f64>, // Raw amounts deposited, per pool token
+    pub start_time: NaiveDateTime,
+    pub duration_days: i64,
+}
+
+// StableSwap pool over any number of pegged tokens (3pool-style). Amounts passed in and
+// out are raw token amounts; the invariant works on balances normalized by decimals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiAssetStablePool {
+    pub config: MultiAssetStablePoolConfig,
+    pub total_liquidity: f64,
+    pub balances: Vec<f64>, // Raw balances, in `config.tokens` order
+    pub positions: HashMap<String, MultiAssetStablePosition>,
+    pub total_volume: f64,
+    pub total_fees: f64, // Normalized units
+    pub amplification_ramp: Option<AmplificationRamp>,
+}
+
+impl MultiAssetStablePool {
+    /// Create a pool from `(token, decimals)` pairs
+    pub fn new(
+        pool_id: String,
+        tokens: Vec<(String, u32)>,
+        fee_tier: f64,
+        amplification: f64,
+        reward_token: String,
+        total_reward_allocation: f64,
+        apr_rate: f64,
+    ) -> Result<Self, StablePoolError> {
+        if amplification < 1.0 {
+            return Err(StablePoolError::InvalidAmplification);
+        }
+        let distinct: HashSet<&String> = tokens.iter().map(|(t, _)| t).collect();
+        if tokens.len() < 2
+            || tokens.len() > MAX_POOL_TOKENS
+            || distinct.len() != tokens.len()
+            || tokens.iter().any(|(_, d)| *d > MAX_TOKEN_DECIMALS)
+        {
+            return Err(StablePoolError::InvalidTokens);
+        }
+
+        let count = tokens.len();
+        let (tokens, decimals) = tokens.into_iter().unzip();
+        let config = MultiAssetStablePoolConfig {
+            pool_id,
+            tokens,
+            decimals,
+            fee_tier,
+            start_date: Utc::now().naive_utc(),
+            amplification,
+            reward_token,
+            total_reward_allocation,
+            distributed_rewards: 0.0,
+            apr_rate,
+        };
+
+        Ok(Self {
+            config,
+            total_liquidity: 0.0,
+            balances: vec![0.0; count],
+            positions: HashMap::new(),
+            total_volume: 0.0,
+            total_fees: 0.0,
+            amplification_ramp: None,
+        })
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.config.tokens
+    }
+
+    pub fn get_reserves(&self) -> &[f64] {
+        &self.balances
+    }
+
+    /// Raw balance of one token
+    pub fn reserve_of(&self, token: &str) -> Option<f64> {
+        self.token_index(token).ok().map(|i| self.balances[i])
+    }
+
+    fn token_index(&self, token: &str) -> Result<usize, StablePoolError> {
+        self.config
+            .tokens
+            .iter()
+            .position(|t| t == token)
+            .ok_or(StablePoolError::InvalidAmount)
+    }
+
+    /// Factor turning a raw amount of token `i` into pool units
+    fn rate(&self, i: usize) -> f64 {
+        10f64.powi(-(self.config.decimals[i] as i32))
+    }
+
+    fn normalize(&self, raw: &[f64]) -> Vec<f64> {
+        raw.iter()
+            .enumerate()
+            .map(|(i, x)| x * self.rate(i))
+            .collect()
+    }
+
+    pub fn amplification_at(&self, now: NaiveDateTime) -> f64 {
+        self.amplification_ramp
+            .as_ref()
+            .and_then(|ramp| ramp.value_at(now))
+            .unwrap_or(self.config.amplification)
+    }
+
+    pub fn current_amplification(&self) -> f64 {
+        self.amplification_at(Utc::now().naive_utc())
+    }
+
+    /// Gradually move the amplification to `future_amplification`, reaching it at
+    /// `end_time` (see `AmplificationRamp::new` for the limits)
+    pub fn ramp_amplification(
+        &mut self,
+        future_amplification: f64,
+        end_time: NaiveDateTime,
+        now: NaiveDateTime,
+    ) -> Result<(), StablePoolError> {
+        let ramp = AmplificationRamp::new(
+            self.amplification_at(now),
+            future_amplification,
+            now,
+            end_time,
+        )?;
+        self.amplification_ramp = Some(ramp);
+        self.config.amplification = future_amplification;
+        Ok(())
+    }
+
+    /// Freeze the amplification at its current value
+    pub fn stop_amplification_ramp(&mut self, now: NaiveDateTime) {
+        self.config.amplification = self.amplification_at(now);
+        self.amplification_ramp = None;
+    }
+
+    fn invariant_of(&self, normalized: &[f64]) -> Result<f64, StablePoolError> {
+        stableswap_d(normalized, self.current_amplification())
+            .ok_or(StablePoolError::InsufficientLiquidity)
+    }
+
+    /// Current value of the StableSwap invariant D, in pool units
+    pub fn invariant(&self) -> Result<f64, StablePoolError> {
+        self.invariant_of(&self.normalize(&self.balances))
+    }
+
+    /// Value of one LP share in pool units; grows as fees accrue
+    pub fn virtual_price(&self) -> Result<f64, StablePoolError> {
+        if self.total_liquidity == 0.0 {
+            return Err(StablePoolError::InsufficientLiquidity);
+        }
+        Ok(self.invariant()? / self.total_liquidity)
+    }
+
+    fn imbalance_fee_rate(&self) -> f64 {
+        let n = self.balances.len() as f64;
+        self.config.fee_tier * n / (4.0 * (n - 1.0))
+    }
+
+    /// Normalized balances with the imbalance fee on the deviation from the pool's
+    /// proportions deducted, and the total fee taken
+    fn after_imbalance_fees(&self, old: &[f64], new: &[f64], d0: f64, d1: f64) -> (Vec<f64>, f64) {
+        let fee = self.imbalance_fee_rate();
+        let mut total = 0.0;
+        let adjusted = old
+            .iter()
+            .zip(new)
+            .map(|(o, n)| {
+                let charged = fee * (d1 * o / d0 - n).abs();
+                total += charged;
+                n - charged
+            })
+            .collect();
+        (adjusted, total)
+    }
+
+    fn validate_amounts(&self, amounts: &[f64]) -> Result<(), StablePoolError> {
+        if amounts.len() != self.balances.len()
+            || amounts.iter().any(|a| *a < 0.0 || !a.is_finite())
+            || amounts.iter().all(|a| *a == 0.0)
+        {
+            return Err(StablePoolError::InvalidAmount);
+        }
+        Ok(())
+    }
+
+    fn ensure_unlocked(position: &MultiAssetStablePosition) -> Result<(), StablePoolError> {
+        let lock_end = position.start_time + chrono::Duration::days(position.duration_days.max(0));
+        if Utc::now().naive_utc() < lock_end {
+            return Err(StablePoolError::LiquidityLocked);
+        }
+        Ok(())
+    }
+
+    /// Shares held by `user_id`, once its lock has expired
+    fn withdrawable_shares(&self, user_id: &str) -> Result<f64, StablePoolError> {
+        let position = self
+            .positions
+            .get(user_id)
+            .ok_or(StablePoolError::UserNotInPool)?;
+        Self::ensure_unlocked(position)?;
+        Ok(position.liquidity_amount)
+    }
+
+    /// Reduce a position by `shares`, scaling its recorded deposits accordingly
+    fn burn_position(&mut self, user_id: &str, shares: f64) {
+        if let Some(position) = self.positions.get_mut(user_id) {
+            let remaining = (position.liquidity_amount - shares).max(0.0);
+            let ratio = remaining / position.liquidity_amount;
+            for amount in position.token_amounts.iter_mut() {
+                *amount *= ratio;
+            }
+            position.liquidity_amount = remaining;
+            if remaining <= 0.0 {
+                self.positions.remove(user_id);
+            }
+        }
+    }
+
+    /// Output of swapping `input_amount` of one pool token for another
+    pub fn calculate_swap_output(
+        &self,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+    ) -> Result<f64, StablePoolError> {
+        if input_amount <= 0.0 {
+            return Err(StablePoolError::InvalidAmount);
+        }
+        let i = self.token_index(input_token)?;
+        let j = self.token_index(output_token)?;
+        if i == j {
+            return Err(StablePoolError::InvalidAmount);
+        }
+
+        let xp = self.normalize(&self.balances);
+        if xp.contains(&0.0) {
+            return Err(StablePoolError::InsufficientLiquidity);
+        }
+        let amplification = self.current_amplification();
+        let d = stableswap_d(&xp, amplification).ok_or(StablePoolError::InsufficientLiquidity)?;
+        let mut new = xp.clone();
+        new[i] += input_amount * (1.0 - self.config.fee_tier) * self.rate(i);
+        let y = stableswap_y(&new, j, d, amplification)
+            .ok_or(StablePoolError::InsufficientLiquidity)?;
+        let out = (xp[j] - y) / self.rate(j);
+        if out <= 0.0 || out > self.balances[j] {
+            return Err(StablePoolError::InsufficientLiquidity);
+        }
+        Ok(out)
+    }
+
+    pub fn swap(
+        &mut self,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+    ) -> Result<f64, StablePoolError> {
+        let output_amount = self.calculate_swap_output(input_token, output_token, input_amount)?;
+        let i = self.token_index(input_token)?;
+        let j = self.token_index(output_token)?;
+        self.balances[i] += input_amount;
+        self.balances[j] -= output_amount;
+
+        let normalized_input = input_amount * self.rate(i);
+        self.total_volume += normalized_input;
+        self.total_fees += normalized_input * self.config.fee_tier;
+        Ok(output_amount)
+    }
+
+    /// Deposit raw `amounts` of the pool tokens; any mix works, including a single token.
+    /// The first deposit must include every token and mints D shares. Later deposits
+    /// mint in proportion to the growth of D after the imbalance fee.
+    pub fn add_liquidity(
+        &mut self,
+        user_id: String,
+        amounts: &[f64],
+        duration_days: i64,
+    ) -> Result<f64, StablePoolError> {
+        self.validate_amounts(amounts)?;
+        if duration_days <= 0 {
+            return Err(StablePoolError::InvalidDuration);
+        }
+
+        let new_raw: Vec<f64> = self
+            .balances
+            .iter()
+            .zip(amounts)
+            .map(|(b, a)| b + a)
+            .collect();
+        let old = self.normalize(&self.balances);
+        let new = self.normalize(&new_raw);
+        let (liquidity_amount, fee) = if self.total_liquidity == 0.0 {
+            if amounts.contains(&0.0) {
+                return Err(StablePoolError::InvalidAmount);
+            }
+            (self.invariant_of(&new)?, 0.0)
+        } else {
+            let d0 = self.invariant_of(&old)?;
+            let d1 = self.invariant_of(&new)?;
+            let (adjusted, fee) = self.after_imbalance_fees(&old, &new, d0, d1);
+            let d2 = self.invariant_of(&adjusted)?;
+            (self.total_liquidity * (d2 - d0) / d0, fee)
+        };
+        if liquidity_amount <= 0.0 {
+            return Err(StablePoolError::InvalidAmount);
+        }
+
+        // Fees stay in the pool and accrue to existing LPs
+        self.balances = new_raw;
+        self.total_liquidity += liquidity_amount;
+        self.total_fees += fee;
+
+        let pool_id = self.config.pool_id.clone();
+        let position =
+            self.positions
+                .entry(user_id.clone())
+                .or_insert_with(|| MultiAssetStablePosition {
+                    user_id,
+                    pool_id,
+                    liquidity_amount: 0.0,
+                    token_amounts: vec![0.0; amounts.len()],
+                    start_time: Utc::now().naive_utc(),
+                    duration_days,
+                });
+        position.liquidity_amount += liquidity_amount;
+        for (held, added) in position.token_amounts.iter_mut().zip(amounts) {
+            *held += added;
+        }
+        Ok(liquidity_amount)
+    }
+
+    /// Burn `shares` for a proportional slice of every balance; no fee is charged
+    pub fn remove_liquidity(
+        &mut self,
+        user_id: &str,
+        shares: f64,
+    ) -> Result<Vec<f64>, StablePoolError> {
+        let held = self.withdrawable_shares(user_id)?;
+        if shares <= 0.0 {
+            return Err(StablePoolError::InvalidAmount);
+        }
+        if shares > held {
+            return Err(StablePoolError::InsufficientShares);
+        }
+
+        let ratio = shares / self.total_liquidity;
+        let amounts: Vec<f64> = self.balances.iter().map(|b| b * ratio).collect();
+        for (balance, amount) in self.balances.iter_mut().zip(&amounts) {
+            *balance -= amount;
+        }
+        self.total_liquidity -= shares;
+        self.burn_position(user_id, shares);
+        Ok(amounts)
+    }
+
+    /// Withdraw exact raw `amounts`, burning as many shares as the resulting drop in D
+    /// requires (including the imbalance fee). Fails if more than `max_burn` shares or
+    /// more than the position holds would be burned. Returns the shares burned.
+    pub fn remove_liquidity_imbalance(
+        &mut self,
+        user_id: &str,
+        amounts: &[f64],
+        max_burn: f64,
+    ) -> Result<f64, StablePoolError> {
+        let held = self.withdrawable_shares(user_id)?;
+        self.validate_amounts(amounts)?;
+
+        let new_raw: Vec<f64> = self
+            .balances
+            .iter()
+            .zip(amounts)
+            .map(|(b, a)| b - a)
+            .collect();
+        if new_raw.iter().any(|b| *b <= 0.0) {
+            return Err(StablePoolError::InsufficientLiquidity);
+        }
+        let old = self.normalize(&self.balances);
+        let new = self.normalize(&new_raw);
+        let d0 = self.invariant_of(&old)?;
+        let d1 = self.invariant_of(&new)?;
+        let (adjusted, fee) = self.after_imbalance_fees(&old, &new, d0, d1);
+        let d2 = self.invariant_of(&adjusted)?;
+        let burn = self.total_liquidity * (d0 - d2) / d0;
+        if burn > held {
+            return Err(StablePoolError::InsufficientShares);
+        }
+        if burn > max_burn {
+            return Err(StablePoolError::SlippageExceeded);
+        }
+
+        self.balances = new_raw;
+        self.total_liquidity -= burn;
+        self.total_fees += fee;
+        self.burn_position(user_id, burn);
+        Ok(burn)
+    }
+
+    /// Raw amount of `token` received for burning `shares` in a single-token withdrawal,
+    /// and the fee retained by the pool (in pool units)
+    pub fn calculate_withdraw_one_token(
+        &self,
+        shares: f64,
+        token: &str,
+    ) -> Result<(f64, f64), StablePoolError> {
+        if shares <= 0.0 || shares > self.total_liquidity {
+            return Err(StablePoolError::InvalidAmount);
+        }
+        let i = self.token_index(token)?;
+        let amplification = self.current_amplification();
+        let xp = self.normalize(&self.balances);
+        let d0 = self.invariant_of(&xp)?;
+        let d1 = d0 - shares * d0 / self.total_liquidity;
+        let new_y = stableswap_y(&xp, i, d1, amplification)
+            .ok_or(StablePoolError::InsufficientLiquidity)?;
+
+        // Withdrawing one token is an imbalanced withdrawal of the whole share
+        let fee = self.imbalance_fee_rate();
+        let reduced: Vec<f64> = xp
+            .iter()
+            .enumerate()
+            .map(|(j, x)| {
+                let expected = if j == i {
+                    x * d1 / d0 - new_y
+                } else {
+                    x - x * d1 / d0
+                };
+                x - fee * expected
+            })
+            .collect();
+        let y = stableswap_y(&reduced, i, d1, amplification)
+            .ok_or(StablePoolError::InsufficientLiquidity)?;
+        let amount = reduced[i] - y;
+        if amount <= 0.0 || amount > xp[i] {
+            return Err(StablePoolError::InsufficientLiquidity);
+        }
+        Ok((amount / self.rate(i), xp[i] - new_y - amount))
+    }
+
+    /// Burn `shares` for a single token. Fails if less than `min_amount` would be received.
+    pub fn remove_liquidity_one_token(
+        &mut self,
+        user_id: &str,
+        shares: f64,
+        token: &str,
+        min_amount: f64,
+    ) -> Result<f64, StablePoolError> {
+        if shares > self.withdrawable_shares(user_id)? {
+            return Err(StablePoolError::InsufficientShares);
+        }
+        let (amount, fee) = self.calculate_withdraw_one_token(shares, token)?;
+        if amount < min_amount {
+            return Err(StablePoolError::SlippageExceeded);
+        }
+
+        let i = self.token_index(token)?;
+        self.balances[i] -= amount;
+        self.total_liquidity -= shares;
+        self.total_fees += fee;
+        self.burn_position(user_id, shares);
+        Ok(amount)
+    }
+}
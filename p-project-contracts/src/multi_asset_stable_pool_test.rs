@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod tests {
+    use super::super::multi_asset_stable_pool::MultiAssetStablePool;
+    use super::super::stable_liquidity_pool::{StablePoolError, MIN_RAMP_SECS};
+    use chrono::{Duration, Utc};
+
+    // USDC and USDT with 6 decimals, DAI with 18
+    fn three_pool(amplification: f64) -> MultiAssetStablePool {
+        let mut pool = MultiAssetStablePool::new(
+            "3pool".to_string(),
+            vec![
+                ("USDC".to_string(), 6),
+                ("USDT".to_string(), 6),
+                ("DAI".to_string(), 18),
+            ],
+            0.0004,
+            amplification,
+            "REWARD".to_string(),
+            100000.0,
+            0.10,
+        )
+        .unwrap();
+        pool.add_liquidity(
+            "lp1".to_string(),
+            &[1_000_000.0e6, 1_000_000.0e6, 1_000_000.0e18],
+            30,
+        )
+        .unwrap();
+        pool
+    }
+
+    fn unlock(pool: &mut MultiAssetStablePool, user: &str) {
+        let pos = pool.positions.get_mut(user).unwrap();
+        pos.start_time = pos.start_time - Duration::days(pos.duration_days + 1);
+    }
+
+    #[test]
+    fn test_multi_pool_rejects_invalid_tokens() {
+        let make = |tokens: Vec<(&str, u32)>| {
+            MultiAssetStablePool::new(
+                "bad".to_string(),
+                tokens
+                    .into_iter()
+                    .map(|(t, d)| (t.to_string(), d))
+                    .collect(),
+                0.0004,
+                100.0,
+                "REWARD".to_string(),
+                0.0,
+                0.0,
+            )
+        };
+        assert!(matches!(
+            make(vec![("USDC", 6)]),
+            Err(StablePoolError::InvalidTokens)
+        ));
+        assert!(matches!(
+            make(vec![("USDC", 6), ("USDC", 6)]),
+            Err(StablePoolError::InvalidTokens)
+        ));
+        let nine: Vec<(String, u32)> = (0..9).map(|i| (format!("T{}", i), 6)).collect();
+        assert!(matches!(
+            make(nine.iter().map(|(t, d)| (t.as_str(), *d)).collect()),
+            Err(StablePoolError::InvalidTokens)
+        ));
+        assert!(make(vec![("USDC", 6), ("USDT", 6), ("DAI", 18)]).is_ok());
+    }
+
+    #[test]
+    fn test_multi_pool_normalizes_decimals() {
+        let pool = three_pool(200.0);
+        // First deposit mints D in normalized units: three balanced million-dollar legs
+        assert!((pool.total_liquidity - 3_000_000.0).abs() < 1e-3);
+        assert!((pool.virtual_price().unwrap() - 1.0).abs() < 1e-9);
+
+        let dai_out = pool
+            .calculate_swap_output("USDC", "DAI", 1_000.0e6)
+            .unwrap();
+        assert!(dai_out > 999.0e18 && dai_out < 1_000.0e18);
+        let usdt_out = pool
+            .calculate_swap_output("DAI", "USDT", 1_000.0e18)
+            .unwrap();
+        assert!(usdt_out > 999.0e6 && usdt_out < 1_000.0e6);
+        assert!(pool.calculate_swap_output("USDC", "USDC", 1.0e6).is_err());
+        assert!(pool.calculate_swap_output("USDC", "FRAX", 1.0e6).is_err());
+    }
+
+    #[test]
+    fn test_multi_pool_swaps_between_any_pair() {
+        let mut pool = three_pool(200.0);
+        let d0 = pool.invariant().unwrap();
+        let out = pool.swap("USDT", "DAI", 50_000.0e6).unwrap();
+        assert_eq!(pool.reserve_of("USDT").unwrap(), 1_050_000.0e6);
+        assert_eq!(pool.reserve_of("DAI").unwrap(), 1_000_000.0e18 - out);
+        assert_eq!(pool.reserve_of("USDC").unwrap(), 1_000_000.0e6);
+        // Fees stay in the pool, so the invariant and share price grow
+        assert!(pool.invariant().unwrap() > d0);
+        assert!(pool.virtual_price().unwrap() > 1.0);
+        assert!((pool.total_volume - 50_000.0).abs() < 1e-6);
+        assert!((pool.total_fees - 20.0).abs() < 1e-6);
+
+        // DAI is now the scarce side, so it sells for more than it costs
+        let sell_dai = pool
+            .calculate_swap_output("DAI", "USDT", 10_000.0e18)
+            .unwrap()
+            / 1e6;
+        let buy_dai = pool
+            .calculate_swap_output("USDT", "DAI", 10_000.0e6)
+            .unwrap()
+            / 1e18;
+        assert!(sell_dai > buy_dai);
+    }
+
+    #[test]
+    fn test_multi_pool_single_coin_deposit_and_withdrawal() {
+        let mut pool = three_pool(200.0);
+        let balanced = pool
+            .add_liquidity("lp2".to_string(), &[10_000.0e6, 10_000.0e6, 10_000.0e18], 1)
+            .unwrap();
+        let single = pool
+            .add_liquidity("lp3".to_string(), &[0.0, 30_000.0e6, 0.0], 1)
+            .unwrap();
+        // Same value deposited, but the lopsided deposit pays the imbalance fee
+        assert!(single < balanced);
+        assert!(balanced - single < 30.0);
+        assert!(pool.total_fees > 0.0);
+
+        assert!(matches!(
+            pool.remove_liquidity_one_token("lp3", single, "DAI", 0.0),
+            Err(StablePoolError::LiquidityLocked)
+        ));
+        unlock(&mut pool, "lp3");
+        let (quote, _) = pool.calculate_withdraw_one_token(single, "DAI").unwrap();
+        assert!(matches!(
+            pool.remove_liquidity_one_token("lp3", single, "DAI", quote + 1.0e18),
+            Err(StablePoolError::SlippageExceeded)
+        ));
+        let dai = pool
+            .remove_liquidity_one_token("lp3", single, "DAI", quote)
+            .unwrap();
+        assert_eq!(dai, quote);
+        assert!(dai > 29_900.0e18 && dai < 30_000.0e18);
+        assert!(!pool.positions.contains_key("lp3"));
+    }
+
+    #[test]
+    fn test_multi_pool_proportional_and_imbalanced_withdrawal() {
+        let mut pool = three_pool(100.0);
+        unlock(&mut pool, "lp1");
+        let shares = pool.total_liquidity;
+
+        let amounts = pool.remove_liquidity("lp1", shares / 10.0).unwrap();
+        assert!((amounts[0] - 100_000.0e6).abs() < 1.0);
+        assert!((amounts[2] - 100_000.0e18).abs() < 1.0e12);
+        assert!((pool.virtual_price().unwrap() - 1.0).abs() < 1e-9);
+
+        let exact = [50_000.0e6, 0.0, 10_000.0e18];
+        assert!(matches!(
+            pool.remove_liquidity_imbalance("lp1", &exact, 60_000.0),
+            Err(StablePoolError::SlippageExceeded)
+        ));
+        let burned = pool
+            .remove_liquidity_imbalance("lp1", &exact, 60_100.0)
+            .unwrap();
+        assert!(burned > 60_000.0);
+        assert_eq!(pool.reserve_of("USDC").unwrap(), 850_000.0e6);
+        assert_eq!(pool.reserve_of("USDT").unwrap(), 900_000.0e6);
+        assert!(matches!(
+            pool.remove_liquidity("lp1", pool.total_liquidity + 1.0),
+            Err(StablePoolError::InsufficientShares)
+        ));
+    }
+
+    #[test]
+    fn test_multi_pool_amplification_ramp() {
+        let mut pool = three_pool(100.0);
+        let now = Utc::now().naive_utc();
+        let end = now + Duration::seconds(MIN_RAMP_SECS * 2);
+        assert!(matches!(
+            pool.ramp_amplification(2_000.0, end, now),
+            Err(StablePoolError::InvalidAmplification)
+        ));
+        assert!(matches!(
+            pool.ramp_amplification(400.0, now + Duration::seconds(60), now),
+            Err(StablePoolError::InvalidRamp)
+        ));
+        pool.ramp_amplification(400.0, end, now).unwrap();
+        let midway = pool.amplification_at(now + Duration::seconds(MIN_RAMP_SECS));
+        assert!((midway - 250.0).abs() < 1e-9);
+        assert_eq!(pool.amplification_at(end), 400.0);
+        pool.stop_amplification_ramp(now + Duration::seconds(MIN_RAMP_SECS));
+        assert!((pool.config.amplification - 250.0).abs() < 1e-9);
+        assert!(pool.amplification_ramp.is_none());
+    }
+}
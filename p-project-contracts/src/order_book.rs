@@ -0,0 +1,406 @@
+use crate::router::{Router, RouterError};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Errors returned when placing or managing conditional orders
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderError {
+    InvalidAmount,
+    InvalidPrice,
+    InvalidSchedule,
+    InvalidTokens,
+    OrderNotFound,
+    NotOrderOwner,
+    OrderClosed,
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::InvalidAmount => write!(f, "Amount must be positive"),
+            OrderError::InvalidPrice => write!(f, "Price must be positive"),
+            OrderError::InvalidSchedule => write!(f, "TWAP schedule needs slices and an interval"),
+            OrderError::InvalidTokens => write!(f, "Input and output tokens must differ"),
+            OrderError::OrderNotFound => write!(f, "Order not found"),
+            OrderError::NotOrderOwner => write!(f, "Caller does not own the order"),
+            OrderError::OrderClosed => write!(f, "Order is no longer open"),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+// Fraction of an order's size quoted to read the marginal price of its route
+pub const PRICE_PROBE_FRACTION: f64 = 1e-6;
+
+// Slices of a TWAP order: slice k becomes due at start + k * interval_secs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TwapSchedule {
+    pub start: NaiveDateTime,
+    pub slices: u32,
+    pub interval_secs: i64,
+    pub min_price: f64, // Lowest output per input accepted for a slice (0 for none)
+}
+
+impl TwapSchedule {
+    /// Number of slices due at `now`
+    pub fn slices_due(&self, now: NaiveDateTime) -> u32 {
+        if now < self.start {
+            return 0;
+        }
+        let elapsed = (now - self.start).num_seconds() / self.interval_secs;
+        (elapsed + 1).min(self.slices as i64) as u32
+    }
+}
+
+// Prices are always quoted as output tokens received per input token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderKind {
+    // Fill the whole remainder once it can be sold at `limit_price` or better
+    Limit {
+        limit_price: f64,
+    },
+    // Sell at market once the marginal price falls to `stop_price`, accepting at most
+    // `max_slippage_bps` below that marginal price
+    StopLoss {
+        stop_price: f64,
+        max_slippage_bps: u32,
+    },
+    // Sell in equal slices on a fixed schedule
+    Twap(TwapSchedule),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderStatus {
+    Open,
+    Filled,
+    Cancelled,
+    Expired,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConditionalOrder {
+    pub order_id: u64,
+    pub owner: String,
+    pub input_token: String,
+    pub output_token: String,
+    pub input_amount: f64, // Total size of the order
+    pub kind: OrderKind,
+    pub expires_at: Option<NaiveDateTime>,
+    pub status: OrderStatus,
+    pub filled_input: f64,
+    pub filled_output: f64,
+}
+
+impl ConditionalOrder {
+    /// Input still to be sold
+    pub fn remaining(&self) -> f64 {
+        (self.input_amount - self.filled_input).max(0.0)
+    }
+
+    /// Average output per input over the fills so far
+    pub fn average_price(&self) -> Option<f64> {
+        if self.filled_input <= 0.0 {
+            return None;
+        }
+        Some(self.filled_output / self.filled_input)
+    }
+}
+
+// A swap executed for an order by the keeper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderFill {
+    pub order_id: u64,
+    pub owner: String,
+    pub timestamp: NaiveDateTime,
+    pub input_token: String,
+    pub output_token: String,
+    pub input_amount: f64,
+    pub output_amount: f64,
+    pub path: Vec<String>,
+}
+
+// Book of conditional orders executed against the pools of a router. The keeper does
+// not custody funds: callers set aside an order's input when placing it and settle
+// the fills returned by `poke`.
+pub struct OrderKeeper {
+    router: Router,
+    orders: BTreeMap<u64, ConditionalOrder>,
+    next_order_id: u64,
+}
+
+impl OrderKeeper {
+    pub fn new(router: Router) -> Self {
+        Self {
+            router,
+            orders: BTreeMap::new(),
+            next_order_id: 1,
+        }
+    }
+
+    pub fn router(&self) -> &Router {
+        &self.router
+    }
+
+    pub fn router_mut(&mut self) -> &mut Router {
+        &mut self.router
+    }
+
+    pub fn get_order(&self, order_id: u64) -> Option<&ConditionalOrder> {
+        self.orders.get(&order_id)
+    }
+
+    /// Open orders in placement order
+    pub fn open_orders(&self) -> Vec<&ConditionalOrder> {
+        self.orders
+            .values()
+            .filter(|o| o.status == OrderStatus::Open)
+            .collect()
+    }
+
+    /// Sell `input_amount` once the whole of it fetches at least `limit_price`
+    pub fn place_limit_order(
+        &mut self,
+        owner: &str,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+        limit_price: f64,
+    ) -> Result<u64, OrderError> {
+        if limit_price <= 0.0 || !limit_price.is_finite() {
+            return Err(OrderError::InvalidPrice);
+        }
+        self.place(
+            owner,
+            input_token,
+            output_token,
+            input_amount,
+            OrderKind::Limit { limit_price },
+        )
+    }
+
+    /// Sell `input_amount` at market once its marginal price drops to `stop_price`
+    pub fn place_stop_loss(
+        &mut self,
+        owner: &str,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+        stop_price: f64,
+        max_slippage_bps: u32,
+    ) -> Result<u64, OrderError> {
+        if stop_price <= 0.0 || !stop_price.is_finite() || max_slippage_bps > 10_000 {
+            return Err(OrderError::InvalidPrice);
+        }
+        self.place(
+            owner,
+            input_token,
+            output_token,
+            input_amount,
+            OrderKind::StopLoss {
+                stop_price,
+                max_slippage_bps,
+            },
+        )
+    }
+
+    /// Sell `input_amount` in `schedule.slices` equal parts. Slices missed by a late or
+    /// failed poke are caught up together on the next one.
+    pub fn place_twap_order(
+        &mut self,
+        owner: &str,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+        schedule: TwapSchedule,
+    ) -> Result<u64, OrderError> {
+        if schedule.slices == 0 || schedule.interval_secs <= 0 {
+            return Err(OrderError::InvalidSchedule);
+        }
+        if schedule.min_price < 0.0 || !schedule.min_price.is_finite() {
+            return Err(OrderError::InvalidPrice);
+        }
+        self.place(
+            owner,
+            input_token,
+            output_token,
+            input_amount,
+            OrderKind::Twap(schedule),
+        )
+    }
+
+    fn place(
+        &mut self,
+        owner: &str,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+        kind: OrderKind,
+    ) -> Result<u64, OrderError> {
+        if input_amount <= 0.0 || !input_amount.is_finite() {
+            return Err(OrderError::InvalidAmount);
+        }
+        if input_token == output_token {
+            return Err(OrderError::InvalidTokens);
+        }
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        self.orders.insert(
+            order_id,
+            ConditionalOrder {
+                order_id,
+                owner: owner.to_string(),
+                input_token: input_token.to_string(),
+                output_token: output_token.to_string(),
+                input_amount,
+                kind,
+                expires_at: None,
+                status: OrderStatus::Open,
+                filled_input: 0.0,
+                filled_output: 0.0,
+            },
+        );
+        Ok(order_id)
+    }
+
+    /// Expire an open order at `expires_at`; it is closed by the first poke from then on
+    pub fn set_order_expiry(
+        &mut self,
+        owner: &str,
+        order_id: u64,
+        expires_at: Option<NaiveDateTime>,
+    ) -> Result<(), OrderError> {
+        self.open_order_mut(owner, order_id)?.expires_at = expires_at;
+        Ok(())
+    }
+
+    /// Cancel an open order, returning the input that was never sold
+    pub fn cancel_order(&mut self, owner: &str, order_id: u64) -> Result<f64, OrderError> {
+        let order = self.open_order_mut(owner, order_id)?;
+        order.status = OrderStatus::Cancelled;
+        Ok(order.remaining())
+    }
+
+    fn open_order_mut(
+        &mut self,
+        owner: &str,
+        order_id: u64,
+    ) -> Result<&mut ConditionalOrder, OrderError> {
+        let order = self
+            .orders
+            .get_mut(&order_id)
+            .ok_or(OrderError::OrderNotFound)?;
+        if order.owner != owner {
+            return Err(OrderError::NotOrderOwner);
+        }
+        if order.status != OrderStatus::Open {
+            return Err(OrderError::OrderClosed);
+        }
+        Ok(order)
+    }
+
+    /// Output per input at the margin of the best route for an order of `size`
+    pub fn marginal_price(
+        &self,
+        input_token: &str,
+        output_token: &str,
+        size: f64,
+    ) -> Result<f64, RouterError> {
+        let probe = size * PRICE_PROBE_FRACTION;
+        let quote = self.router.best_quote(input_token, output_token, probe)?;
+        Ok(quote.output_amount / probe)
+    }
+
+    /// Expire stale orders, then check every open order against the current pool prices
+    /// and execute the eligible ones through the router, oldest first. Each swap settles
+    /// before the next order is checked. An order whose swap fails stays open.
+    pub fn poke(&mut self, now: NaiveDateTime) -> Vec<OrderFill> {
+        let ids: Vec<u64> = self
+            .orders
+            .values()
+            .filter(|o| o.status == OrderStatus::Open)
+            .map(|o| o.order_id)
+            .collect();
+
+        let mut fills = Vec::new();
+        for order_id in ids {
+            let order = self.orders[&order_id].clone();
+            if order.expires_at.is_some_and(|expiry| now >= expiry) {
+                self.close(order_id, OrderStatus::Expired);
+                continue;
+            }
+            let Some((amount, min_output)) = self.eligible_amount(&order, now) else {
+                continue;
+            };
+            let Ok(result) = self.router.swap_best_route(
+                &order.input_token,
+                &order.output_token,
+                amount,
+                min_output,
+                None,
+            ) else {
+                continue;
+            };
+
+            let order = self.orders.get_mut(&order_id).expect("order exists");
+            order.filled_input += result.input_amount;
+            order.filled_output += result.output_amount;
+            if order.remaining() <= order.input_amount * 1e-12 {
+                order.status = OrderStatus::Filled;
+            }
+            fills.push(OrderFill {
+                order_id,
+                owner: order.owner.clone(),
+                timestamp: now,
+                path: result.path(),
+                input_token: result.input_token,
+                output_token: result.output_token,
+                input_amount: result.input_amount,
+                output_amount: result.output_amount,
+            });
+        }
+        fills
+    }
+
+    fn close(&mut self, order_id: u64, status: OrderStatus) {
+        if let Some(order) = self.orders.get_mut(&order_id) {
+            order.status = status;
+        }
+    }
+
+    /// Input to sell now and the least output to accept for it, if the order is eligible
+    fn eligible_amount(&self, order: &ConditionalOrder, now: NaiveDateTime) -> Option<(f64, f64)> {
+        let remaining = order.remaining();
+        match &order.kind {
+            OrderKind::Limit { limit_price } => Some((remaining, remaining * limit_price)),
+            OrderKind::StopLoss {
+                stop_price,
+                max_slippage_bps,
+            } => {
+                let price = self
+                    .marginal_price(&order.input_token, &order.output_token, remaining)
+                    .ok()?;
+                if price > *stop_price {
+                    return None;
+                }
+                let tolerance = *max_slippage_bps as f64 / 10_000.0;
+                Some((remaining, remaining * price * (1.0 - tolerance)))
+            }
+            OrderKind::Twap(schedule) => {
+                let due = schedule.slices_due(now);
+                let target = if due >= schedule.slices {
+                    order.input_amount
+                } else {
+                    order.input_amount * due as f64 / schedule.slices as f64
+                };
+                let amount = target - order.filled_input;
+                if amount <= order.input_amount * 1e-12 {
+                    return None;
+                }
+                Some((amount, amount * schedule.min_price))
+            }
+        }
+    }
+}
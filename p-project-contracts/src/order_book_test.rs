@@ -0,0 +1,229 @@
+#[cfg(test)]
+mod tests {
+    use super::super::liquidity_pool::LiquidityPool;
+    use super::super::order_book::{OrderError, OrderKeeper, OrderStatus, TwapSchedule};
+    use super::super::router::Router;
+    use super::super::token::PProjectToken;
+    use super::super::treasury::{Treasury, TreasuryError};
+    use chrono::{Duration, NaiveDateTime};
+
+    fn at(secs: i64) -> NaiveDateTime {
+        NaiveDateTime::UNIX_EPOCH + Duration::seconds(1_700_000_000 + secs)
+    }
+
+    // P trades at 0.01 USDC in a single constant product pool
+    fn keeper() -> OrderKeeper {
+        let mut pool = LiquidityPool::new(
+            "p_usdc".to_string(),
+            "P".to_string(),
+            "USDC".to_string(),
+            0.003,
+            "RWD".to_string(),
+            0.0,
+            0.0,
+        );
+        pool.add_liquidity("lp".into(), 10_000_000.0, 100_000.0, 30)
+            .unwrap();
+        let mut router = Router::new();
+        router.register_constant_pool("p_usdc".to_string(), pool);
+        OrderKeeper::new(router)
+    }
+
+    fn reserves(keeper: &OrderKeeper) -> (f64, f64) {
+        keeper
+            .router()
+            .get_constant_pool("p_usdc")
+            .unwrap()
+            .get_reserves()
+    }
+
+    #[test]
+    fn test_limit_order_waits_for_price() {
+        let mut keeper = keeper();
+        assert_eq!(
+            keeper.place_limit_order("alice", "P", "P", 1.0, 0.01),
+            Err(OrderError::InvalidTokens)
+        );
+        assert_eq!(
+            keeper.place_limit_order("alice", "P", "USDC", 1.0, 0.0),
+            Err(OrderError::InvalidPrice)
+        );
+        // Sell 10k P for at least 0.0105 USDC each, above the current price
+        let sell = keeper
+            .place_limit_order("alice", "P", "USDC", 10_000.0, 0.0105)
+            .unwrap();
+        assert!(keeper.poke(at(0)).is_empty());
+        assert_eq!(keeper.get_order(sell).unwrap().status, OrderStatus::Open);
+
+        // A large buy pushes the price up and the order fills on the next poke
+        keeper
+            .router_mut()
+            .swap_best_route("USDC", "P", 10_000.0, 0.0, None)
+            .unwrap();
+        let fills = keeper.poke(at(60));
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order_id, sell);
+        assert_eq!(fills[0].path, vec!["P".to_string(), "USDC".to_string()]);
+        assert!(fills[0].output_amount >= 10_000.0 * 0.0105);
+        let order = keeper.get_order(sell).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(
+            order.average_price(),
+            Some(fills[0].output_amount / 10_000.0)
+        );
+        assert!(keeper.poke(at(120)).is_empty());
+    }
+
+    #[test]
+    fn test_stop_loss_triggers_on_marginal_price() {
+        let mut keeper = keeper();
+        let stop = keeper
+            .place_stop_loss("bob", "P", "USDC", 50_000.0, 0.009, 200)
+            .unwrap();
+        // The order's own slippage does not trigger it while the price holds
+        assert!(keeper.poke(at(0)).is_empty());
+
+        // A dump to below 0.009 USDC trips the stop
+        keeper
+            .router_mut()
+            .swap_best_route("P", "USDC", 700_000.0, 0.0, None)
+            .unwrap();
+        let price = keeper.marginal_price("P", "USDC", 50_000.0).unwrap();
+        assert!(price < 0.009);
+        let fills = keeper.poke(at(60));
+        assert_eq!(fills.len(), 1);
+        assert!(fills[0].output_amount >= 50_000.0 * price * 0.98);
+        assert_eq!(keeper.get_order(stop).unwrap().status, OrderStatus::Filled);
+    }
+
+    #[test]
+    fn test_twap_order_slices_over_intervals() {
+        let mut keeper = keeper();
+        let schedule = TwapSchedule {
+            start: at(0),
+            slices: 4,
+            interval_secs: 3_600,
+            min_price: 0.0,
+        };
+        assert_eq!(
+            keeper.place_twap_order(
+                "carol",
+                "USDC",
+                "P",
+                1_000.0,
+                TwapSchedule {
+                    slices: 0,
+                    ..schedule.clone()
+                }
+            ),
+            Err(OrderError::InvalidSchedule)
+        );
+        let twap = keeper
+            .place_twap_order("carol", "USDC", "P", 1_000.0, schedule)
+            .unwrap();
+
+        assert!(keeper.poke(at(-1)).is_empty());
+        let fills = keeper.poke(at(0));
+        assert_eq!(fills.len(), 1);
+        assert!((fills[0].input_amount - 250.0).abs() < 1e-9);
+        // Nothing more until the next interval
+        assert!(keeper.poke(at(1_800)).is_empty());
+        assert_eq!(reserves(&keeper).1, 100_250.0);
+
+        // A missed interval is caught up on the next poke
+        let fills = keeper.poke(at(7_200));
+        assert!((fills[0].input_amount - 500.0).abs() < 1e-9);
+        let order = keeper.get_order(twap).unwrap();
+        assert_eq!(order.status, OrderStatus::Open);
+        assert!((order.remaining() - 250.0).abs() < 1e-9);
+
+        let fills = keeper.poke(at(100_000));
+        assert!((fills[0].input_amount - 250.0).abs() < 1e-9);
+        let order = keeper.get_order(twap).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert!((reserves(&keeper).1 - 101_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cancel_and_expire_orders() {
+        let mut keeper = keeper();
+        let limit = keeper
+            .place_limit_order("alice", "P", "USDC", 1_000.0, 1.0)
+            .unwrap();
+        let other = keeper
+            .place_limit_order("alice", "P", "USDC", 1_000.0, 1.0)
+            .unwrap();
+        assert_eq!(
+            keeper.cancel_order("bob", limit),
+            Err(OrderError::NotOrderOwner)
+        );
+        assert_eq!(keeper.cancel_order("alice", limit), Ok(1_000.0));
+        assert_eq!(
+            keeper.cancel_order("alice", limit),
+            Err(OrderError::OrderClosed)
+        );
+        assert_eq!(
+            keeper.cancel_order("alice", 99),
+            Err(OrderError::OrderNotFound)
+        );
+
+        keeper
+            .set_order_expiry("alice", other, Some(at(3_600)))
+            .unwrap();
+        keeper.poke(at(0));
+        assert_eq!(keeper.open_orders().len(), 1);
+        keeper.poke(at(3_600));
+        assert_eq!(
+            keeper.get_order(other).unwrap().status,
+            OrderStatus::Expired
+        );
+        assert!(keeper.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_treasury_buyback_as_twap_order() {
+        let mut keeper = keeper();
+        let mut treasury = Treasury::new();
+        let mut token = PProjectToken::new(350_000_000.0, 0.01, 0.005);
+        treasury.add_funds("USDC".to_string(), 10_000.0).unwrap();
+        let schedule = TwapSchedule {
+            start: at(0),
+            slices: 2,
+            interval_secs: 86_400,
+            min_price: 0.0,
+        };
+        assert!(matches!(
+            treasury.schedule_twap_buyback(&mut keeper, "USDC", "P", 20_000.0, schedule.clone()),
+            Err(TreasuryError::InsufficientFunds)
+        ));
+        let order = treasury
+            .schedule_twap_buyback(&mut keeper, "USDC", "P", 4_000.0, schedule)
+            .unwrap();
+        // Funds are set aside when the order is placed
+        assert_eq!(treasury.get_balance("USDC"), 6_000.0);
+
+        // Another user's fills are not the treasury's
+        keeper
+            .place_limit_order("alice", "USDC", "P", 10.0, 1.0)
+            .unwrap();
+        let supply = token.get_total_supply();
+        let fills = keeper.poke(at(0));
+        assert_eq!(fills.len(), 2);
+        let bought = treasury.settle_twap_buybacks(&mut token, &fills);
+        assert_eq!(bought, fills[0].output_amount);
+        assert_eq!(token.get_total_supply(), supply - bought);
+        assert_eq!(treasury.get_total_buybacks(), 2_000.0);
+        assert_eq!(treasury.get_buyback_records().len(), 1);
+
+        // Cancelling returns the unspent half
+        assert_eq!(
+            treasury.cancel_twap_buyback(&mut keeper, order).unwrap(),
+            2_000.0
+        );
+        assert_eq!(treasury.get_balance("USDC"), 8_000.0);
+        assert!(matches!(
+            treasury.cancel_twap_buyback(&mut keeper, order + 1),
+            Err(TreasuryError::BuybackOrderNotFound)
+        ));
+    }
+}
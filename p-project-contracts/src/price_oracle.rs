@@ -0,0 +1,240 @@
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Errors returned when consulting a price oracle
+#[derive(Debug, Clone, PartialEq)]
+pub enum OracleError {
+    InvalidPrice,
+    InvalidWindow,
+    NoObservations,
+    InsufficientHistory,
+}
+
+impl fmt::Display for OracleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OracleError::InvalidPrice => write!(f, "Price must be positive and finite"),
+            OracleError::InvalidWindow => write!(f, "Window must be positive"),
+            OracleError::NoObservations => write!(f, "No price observations recorded"),
+            OracleError::InsufficientHistory => {
+                write!(f, "Window reaches past the oldest observation")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OracleError {}
+
+// Observations kept per pool unless a larger buffer is requested
+pub const DEFAULT_OBSERVATION_CAPACITY: usize = 64;
+
+// Running price integrals at a point in time. Between two observations the price is
+// constant, so the accumulators grow linearly and can be interpolated exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    pub timestamp: NaiveDateTime,
+    pub price_cumulative: f64,     // Σ price · seconds
+    pub log_price_cumulative: f64, // Σ ln(price) · seconds
+}
+
+// Prices averaged over a consulted window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OracleQuote {
+    pub twap: f64,           // Arithmetic time-weighted average price
+    pub geometric_mean: f64, // Time-weighted geometric mean price
+    pub window_secs: f64,
+}
+
+// Time-weighted average price oracle fed by a pool on every reserve change. Each
+// observation accumulates the price that was in effect since the previous one, so a
+// price pushed by a single trade only counts for as long as it survives.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceOracle {
+    pub capacity: usize,
+    pub observations: Vec<Observation>, // Ring buffer, oldest overwritten first
+    pub latest: usize,                  // Index of the newest observation
+    pub last_price: Option<f64>,        // Price in effect since the newest observation
+}
+
+impl Default for PriceOracle {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_OBSERVATION_CAPACITY)
+    }
+}
+
+// Pool updates can land within the same millisecond, so spans keep full precision
+fn seconds_between(from: NaiveDateTime, to: NaiveDateTime) -> f64 {
+    let span = to - from;
+    match span.num_nanoseconds() {
+        Some(nanos) => nanos as f64 / 1e9,
+        None => span.num_milliseconds() as f64 / 1000.0,
+    }
+}
+
+impl PriceOracle {
+    /// Create an oracle keeping at most `capacity` observations (at least one)
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            observations: Vec::new(),
+            latest: 0,
+            last_price: None,
+        }
+    }
+
+    /// Enlarge the ring buffer, keeping the recorded history. Never shrinks it.
+    pub fn grow(&mut self, capacity: usize) {
+        if capacity <= self.capacity {
+            return;
+        }
+        self.observations = self.chronological().copied().collect();
+        self.latest = self.observations.len().saturating_sub(1);
+        self.capacity = capacity;
+    }
+
+    /// Record the pool price after a reserve change at `now`. The accumulators advance
+    /// by the previous price for the time elapsed; several updates at the same instant
+    /// only keep the last price.
+    pub fn record(&mut self, now: NaiveDateTime, price: f64) -> Result<(), OracleError> {
+        if price <= 0.0 || !price.is_finite() {
+            return Err(OracleError::InvalidPrice);
+        }
+        let Some(newest) = self.newest().copied() else {
+            self.observations.push(Observation {
+                timestamp: now,
+                price_cumulative: 0.0,
+                log_price_cumulative: 0.0,
+            });
+            self.latest = 0;
+            self.last_price = Some(price);
+            return Ok(());
+        };
+        if now > newest.timestamp {
+            let observation = self.extrapolate(&newest, now);
+            if self.observations.len() < self.capacity {
+                self.observations.push(observation);
+                self.latest = self.observations.len() - 1;
+            } else {
+                self.latest = (self.latest + 1) % self.capacity;
+                self.observations[self.latest] = observation;
+            }
+        }
+        self.last_price = Some(price);
+        Ok(())
+    }
+
+    /// Most recent observation, if any
+    pub fn newest(&self) -> Option<&Observation> {
+        self.observations.get(self.latest)
+    }
+
+    /// Oldest observation still held in the buffer
+    pub fn oldest(&self) -> Option<&Observation> {
+        self.chronological().next()
+    }
+
+    /// Observations from oldest to newest
+    pub fn chronological(&self) -> impl Iterator<Item = &Observation> {
+        let len = self.observations.len();
+        let start = if len < self.capacity {
+            0
+        } else {
+            (self.latest + 1) % len
+        };
+        (0..len).map(move |k| &self.observations[(start + k) % len])
+    }
+
+    fn extrapolate(&self, from: &Observation, to: NaiveDateTime) -> Observation {
+        let price = self.last_price.unwrap_or(0.0);
+        let elapsed = seconds_between(from.timestamp, to);
+        Observation {
+            timestamp: to,
+            price_cumulative: from.price_cumulative + price * elapsed,
+            log_price_cumulative: from.log_price_cumulative + price.ln() * elapsed,
+        }
+    }
+
+    /// Accumulator values at `at`, interpolated between the observations around it or
+    /// extrapolated from the newest one with the current price
+    pub fn observe(&self, at: NaiveDateTime) -> Result<Observation, OracleError> {
+        let newest = self.newest().ok_or(OracleError::NoObservations)?;
+        if at >= newest.timestamp {
+            return Ok(self.extrapolate(newest, at));
+        }
+
+        let mut previous: Option<&Observation> = None;
+        for observation in self.chronological() {
+            if observation.timestamp >= at {
+                let Some(before) = previous else {
+                    return if observation.timestamp == at {
+                        Ok(*observation)
+                    } else {
+                        Err(OracleError::InsufficientHistory)
+                    };
+                };
+                let span = seconds_between(before.timestamp, observation.timestamp);
+                let t = seconds_between(before.timestamp, at) / span;
+                return Ok(Observation {
+                    timestamp: at,
+                    price_cumulative: before.price_cumulative
+                        + (observation.price_cumulative - before.price_cumulative) * t,
+                    log_price_cumulative: before.log_price_cumulative
+                        + (observation.log_price_cumulative - before.log_price_cumulative) * t,
+                });
+            }
+            previous = Some(observation);
+        }
+        Err(OracleError::InsufficientHistory)
+    }
+
+    /// Realized volatility over the `window` ending at `now`: the square root of the sum
+    /// of squared log returns of the price changes recorded in it. Zero without changes.
+    pub fn realized_volatility(&self, window: Duration, now: NaiveDateTime) -> f64 {
+        let start = now - window;
+        let observations: Vec<&Observation> = self.chronological().collect();
+        // Price in effect from each observation until the next one
+        let mut prices: Vec<(NaiveDateTime, f64)> = observations
+            .windows(2)
+            .filter_map(|pair| {
+                let span = seconds_between(pair[0].timestamp, pair[1].timestamp);
+                (span > 0.0).then(|| {
+                    (
+                        pair[0].timestamp,
+                        (pair[1].price_cumulative - pair[0].price_cumulative) / span,
+                    )
+                })
+            })
+            .collect();
+        if let (Some(newest), Some(price)) = (observations.last(), self.last_price) {
+            prices.push((newest.timestamp, price));
+        }
+        prices
+            .windows(2)
+            .filter(|pair| pair[1].0 > start && pair[1].0 <= now)
+            .map(|pair| (pair[1].1 / pair[0].1).ln().powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// Arithmetic and geometric time-weighted average prices over the `window` ending
+    /// at `now`. Fails if the buffer does not reach back to the start of the window.
+    pub fn consult(
+        &self,
+        window: Duration,
+        now: NaiveDateTime,
+    ) -> Result<OracleQuote, OracleError> {
+        let window_secs = window.num_milliseconds() as f64 / 1000.0;
+        if window_secs <= 0.0 {
+            return Err(OracleError::InvalidWindow);
+        }
+        let start = self.observe(now - window)?;
+        let end = self.observe(now)?;
+        Ok(OracleQuote {
+            twap: (end.price_cumulative - start.price_cumulative) / window_secs,
+            geometric_mean: ((end.log_price_cumulative - start.log_price_cumulative) / window_secs)
+                .exp(),
+            window_secs,
+        })
+    }
+}
@@ -0,0 +1,154 @@
+#[cfg(test)]
+mod tests {
+    use super::super::liquidity_pool::LiquidityPool;
+    use super::super::price_oracle::{OracleError, PriceOracle};
+    use super::super::stable_liquidity_pool::StableLiquidityPool;
+    use super::super::treasury::MarketSnapshot;
+    use chrono::{Duration, NaiveDateTime, Utc};
+
+    fn at(secs: i64) -> NaiveDateTime {
+        NaiveDateTime::UNIX_EPOCH + Duration::seconds(1_700_000_000 + secs)
+    }
+
+    #[test]
+    fn test_twap_and_geometric_mean() {
+        let mut oracle = PriceOracle::default();
+        assert_eq!(
+            oracle.consult(Duration::seconds(60), at(0)),
+            Err(OracleError::NoObservations)
+        );
+        assert_eq!(oracle.record(at(0), 0.0), Err(OracleError::InvalidPrice));
+
+        // 1.0 for 100s, then 4.0 for 100s
+        oracle.record(at(0), 1.0).unwrap();
+        oracle.record(at(100), 4.0).unwrap();
+        let quote = oracle.consult(Duration::seconds(200), at(200)).unwrap();
+        assert!((quote.twap - 2.5).abs() < 1e-12);
+        assert!((quote.geometric_mean - 2.0).abs() < 1e-12);
+        assert_eq!(quote.window_secs, 200.0);
+
+        // Windows may start and end between observations
+        let quote = oracle.consult(Duration::seconds(100), at(150)).unwrap();
+        assert!((quote.twap - 2.5).abs() < 1e-12);
+        let quote = oracle.consult(Duration::seconds(50), at(50)).unwrap();
+        assert!((quote.twap - 1.0).abs() < 1e-12);
+
+        assert_eq!(
+            oracle.consult(Duration::seconds(201), at(200)),
+            Err(OracleError::InsufficientHistory)
+        );
+        assert_eq!(
+            oracle.consult(Duration::zero(), at(200)),
+            Err(OracleError::InvalidWindow)
+        );
+    }
+
+    #[test]
+    fn test_short_lived_price_spike_barely_moves_twap() {
+        let mut oracle = PriceOracle::default();
+        oracle.record(at(0), 1.0).unwrap();
+        // Pushed 10x and back within two seconds of a one-hour window
+        oracle.record(at(3_597), 10.0).unwrap();
+        oracle.record(at(3_597), 12.0).unwrap();
+        oracle.record(at(3_599), 1.0).unwrap();
+        let quote = oracle.consult(Duration::hours(1), at(3_600)).unwrap();
+        assert!((quote.twap - (3_598.0 + 24.0) / 3_600.0).abs() < 1e-12);
+        assert!(quote.geometric_mean < quote.twap);
+        // Several updates at the same instant only write one observation
+        assert_eq!(oracle.observations.len(), 3);
+    }
+
+    #[test]
+    fn test_ring_buffer_overwrites_oldest() {
+        let mut oracle = PriceOracle::with_capacity(3);
+        for i in 0..5 {
+            oracle.record(at(i * 10), (i + 1) as f64).unwrap();
+        }
+        assert_eq!(oracle.observations.len(), 3);
+        assert_eq!(oracle.oldest().unwrap().timestamp, at(20));
+        assert_eq!(oracle.newest().unwrap().timestamp, at(40));
+        assert_eq!(
+            oracle.consult(Duration::seconds(25), at(40)),
+            Err(OracleError::InsufficientHistory)
+        );
+        let quote = oracle.consult(Duration::seconds(20), at(40)).unwrap();
+        assert!((quote.twap - 3.5).abs() < 1e-12);
+
+        // Growing keeps the history in order and stops overwriting
+        oracle.grow(5);
+        oracle.record(at(50), 6.0).unwrap();
+        oracle.record(at(60), 7.0).unwrap();
+        let timestamps: Vec<_> = oracle.chronological().map(|o| o.timestamp).collect();
+        assert_eq!(timestamps, vec![at(20), at(30), at(40), at(50), at(60)]);
+        let quote = oracle.consult(Duration::seconds(40), at(60)).unwrap();
+        assert!((quote.twap - 4.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_pools_record_on_swaps_and_liquidity_changes() {
+        let mut pool = LiquidityPool::new(
+            "p_usdt".to_string(),
+            "P".to_string(),
+            "USDT".to_string(),
+            0.003,
+            "REWARD".to_string(),
+            0.0,
+            0.0,
+        );
+        pool.add_liquidity("lp".into(), 100_000.0, 1_000.0, 30)
+            .unwrap();
+        assert_eq!(pool.oracle.last_price, Some(0.01));
+        pool.swap("USDT", 100.0).unwrap();
+        let spot = pool.spot_price().unwrap();
+        assert!(spot > 0.01);
+        assert_eq!(pool.oracle.last_price, Some(spot));
+        // Until the next update the latest price is extrapolated
+        let later = Utc::now().naive_utc() + Duration::hours(1);
+        let quote = pool.oracle.consult(Duration::minutes(30), later).unwrap();
+        assert!((quote.twap - spot).abs() < 1e-12);
+        assert!(pool.consult(Duration::hours(1)).is_err());
+
+        let mut stable = StableLiquidityPool::new(
+            "usdc_usdt".to_string(),
+            "USDC".to_string(),
+            "USDT".to_string(),
+            0.0004,
+            100.0,
+            "REWARD".to_string(),
+            0.0,
+            0.0,
+        )
+        .unwrap();
+        stable
+            .add_liquidity("lp".into(), 1_000_000.0, 1_000_000.0, 30)
+            .unwrap();
+        assert!((stable.spot_price().unwrap() - 1.0).abs() < 1e-12);
+        stable.swap("USDC", 100_000.0).unwrap();
+        let spot = stable.spot_price().unwrap();
+        // Selling USDC into the pool cheapens it, but far less than a constant product
+        assert!(spot < 1.0 && spot > 0.99);
+        assert_eq!(stable.oracle.last_price, Some(spot));
+        let out = stable.calculate_swap_output("USDC", 1.0).unwrap();
+        assert!((out / (1.0 - 0.0004) - spot).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_market_snapshot_from_oracle() {
+        let mut oracle = PriceOracle::default();
+        oracle.record(at(0), 0.010).unwrap();
+        oracle.record(at(3_600), 0.008).unwrap();
+        // A last-second dump to 0.001 hardly registers
+        oracle.record(at(7_199), 0.001).unwrap();
+        let snapshot =
+            MarketSnapshot::from_oracle(&oracle, Duration::hours(1), at(7_200), 1_000.0, 5.0)
+                .unwrap();
+        let expected = (0.008 * 3_599.0 + 0.001) / 3_600.0;
+        assert!((snapshot.price - expected).abs() < 1e-12);
+        assert!((snapshot.price_change_percentage - (expected / 0.010 - 1.0) * 100.0).abs() < 1e-9);
+        assert!(snapshot.price_change_percentage > -21.0);
+        assert_eq!(snapshot.volume, 1_000.0);
+        assert!(
+            MarketSnapshot::from_oracle(&oracle, Duration::hours(2), at(7_200), 0.0, 0.0).is_err()
+        );
+    }
+}
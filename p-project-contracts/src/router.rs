@@ -1,4 +1,6 @@
+use crate::concentrated_liquidity_pool::{ConcentratedLiquidityPool, ConcentratedPoolError};
 use crate::liquidity_pool::{LiquidityPool, LiquidityPoolError};
+use crate::multi_asset_stable_pool::MultiAssetStablePool;
 use crate::stable_liquidity_pool::{StableLiquidityPool, StablePoolError};
 use std::collections::HashMap;
 
@@ -15,10 +17,19 @@ pub enum RouterError {
 pub enum PoolKind {
     Constant,
     Stable,
+    MultiStable,
+    Concentrated,
 }
 
+// Default limit on the number of pools a route may go through
+pub const DEFAULT_MAX_HOPS: usize = 3;
+
+// Number of equal slices a split order is allocated in
+pub const SPLIT_STEPS: usize = 100;
+
+// One leg of a route: a swap through a single pool
 #[derive(Debug, Clone)]
-pub struct RouterQuote {
+pub struct RouteHop {
     pub pool_id: String,
     pub kind: PoolKind,
     pub input_token: String,
@@ -27,27 +38,121 @@ pub struct RouterQuote {
     pub output_amount: f64,
 }
 
+#[derive(Debug, Clone)]
+pub struct RouterQuote {
+    pub hops: Vec<RouteHop>, // Legs in execution order
+    pub input_token: String,
+    pub output_token: String,
+    pub input_amount: f64,
+    pub output_amount: f64,
+}
+
+impl RouterQuote {
+    /// Tokens visited by the route, from input to output
+    pub fn path(&self) -> Vec<String> {
+        route_path(&self.input_token, &self.hops)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SwapResult {
-    pub pool_id: String,
-    pub kind: PoolKind,
+    pub hops: Vec<RouteHop>, // Executed legs with their actual amounts
+    pub input_token: String,
+    pub output_token: String,
+    pub input_amount: f64,
+    pub output_amount: f64,
+}
+
+impl SwapResult {
+    /// Tokens visited by the route, from input to output
+    pub fn path(&self) -> Vec<String> {
+        route_path(&self.input_token, &self.hops)
+    }
+}
+
+// Input amount divided across parallel pools of the same pair
+#[derive(Debug, Clone)]
+pub struct SplitPlan {
+    pub legs: Vec<RouteHop>, // One leg per pool receiving a share
     pub input_token: String,
     pub output_token: String,
     pub input_amount: f64,
     pub output_amount: f64,
 }
 
+impl SplitPlan {
+    /// Fraction of the input sent to each pool
+    pub fn shares(&self) -> Vec<(String, f64)> {
+        self.legs
+            .iter()
+            .map(|leg| (leg.pool_id.clone(), leg.input_amount / self.input_amount))
+            .collect()
+    }
+}
+
+// Copies of the pools touched by a multi-pool operation, put back if it fails
+struct PoolBackup {
+    constant: Vec<(String, LiquidityPool)>,
+    stable: Vec<(String, StableLiquidityPool)>,
+    multi_stable: Vec<(String, MultiAssetStablePool)>,
+    concentrated: Vec<(String, ConcentratedLiquidityPool)>,
+}
+
+fn route_path(input_token: &str, hops: &[RouteHop]) -> Vec<String> {
+    std::iter::once(input_token.to_string())
+        .chain(hops.iter().map(|h| h.output_token.clone()))
+        .collect()
+}
+
+fn constant_pool_error(e: LiquidityPoolError) -> RouterError {
+    match e {
+        LiquidityPoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
+        LiquidityPoolError::InvalidAmount => RouterError::InvalidAmount,
+        _ => RouterError::PoolError(format!("{:?}", e)),
+    }
+}
+
+fn concentrated_pool_error(e: ConcentratedPoolError) -> RouterError {
+    match e {
+        ConcentratedPoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
+        ConcentratedPoolError::InvalidAmount => RouterError::InvalidAmount,
+        _ => RouterError::PoolError(format!("{:?}", e)),
+    }
+}
+
+fn stable_pool_error(e: StablePoolError) -> RouterError {
+    match e {
+        StablePoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
+        StablePoolError::InvalidAmount => RouterError::InvalidAmount,
+        _ => RouterError::PoolError(format!("{:?}", e)),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AutoProvisionConfig {
     pub lp_user: String,
-    pub min_reserve_per_side: f64,
+    pub min_reserve_per_side: f64, // Each side is topped up to this before the swap
     pub duration_days: i64,
 }
 
-#[derive(Default)]
 pub struct Router {
     constant_pools: HashMap<String, LiquidityPool>,
     stable_pools: HashMap<String, StableLiquidityPool>,
+    multi_stable_pools: HashMap<String, MultiAssetStablePool>,
+    concentrated_pools: HashMap<String, ConcentratedLiquidityPool>,
+    max_hops: usize,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self {
+            constant_pools: HashMap::new(),
+            stable_pools: HashMap::new(),
+            multi_stable_pools: HashMap::new(),
+            concentrated_pools: HashMap::new(),
+            max_hops: DEFAULT_MAX_HOPS,
+        }
+    }
 }
 
 impl Router {
@@ -55,6 +160,15 @@ impl Router {
         Self::default()
     }
 
+    /// Limit the number of pools a route may go through (at least one)
+    pub fn set_max_hops(&mut self, max_hops: usize) {
+        self.max_hops = max_hops.max(1);
+    }
+
+    pub fn max_hops(&self) -> usize {
+        self.max_hops
+    }
+
     pub fn register_constant_pool(&mut self, id: String, pool: LiquidityPool) {
         self.constant_pools.insert(id, pool);
     }
@@ -63,6 +177,14 @@ impl Router {
         self.stable_pools.insert(id, pool);
     }
 
+    pub fn register_multi_stable_pool(&mut self, id: String, pool: MultiAssetStablePool) {
+        self.multi_stable_pools.insert(id, pool);
+    }
+
+    pub fn register_concentrated_pool(&mut self, id: String, pool: ConcentratedLiquidityPool) {
+        self.concentrated_pools.insert(id, pool);
+    }
+
     pub fn get_constant_pool(&self, id: &str) -> Option<&LiquidityPool> {
         self.constant_pools.get(id)
     }
@@ -71,6 +193,14 @@ impl Router {
         self.stable_pools.get(id)
     }
 
+    pub fn get_multi_stable_pool(&self, id: &str) -> Option<&MultiAssetStablePool> {
+        self.multi_stable_pools.get(id)
+    }
+
+    pub fn get_concentrated_pool(&self, id: &str) -> Option<&ConcentratedLiquidityPool> {
+        self.concentrated_pools.get(id)
+    }
+
     fn matching_constant_pool_ids(&self, a: &str, b: &str) -> Vec<String> {
         self.constant_pools
             .iter()
@@ -93,69 +223,209 @@ impl Router {
             .collect()
     }
 
-    pub fn best_quote(
+    /// Every registered pool with the tokens it trades, sorted so that routing is
+    /// deterministic when several routes give the same output
+    fn pool_graph(&self) -> Vec<(PoolKind, String, Vec<String>)> {
+        let mut pools: Vec<(PoolKind, String, Vec<String>)> = self
+            .constant_pools
+            .iter()
+            .map(|(id, p)| {
+                (
+                    PoolKind::Constant,
+                    id.clone(),
+                    vec![p.config.token_a.clone(), p.config.token_b.clone()],
+                )
+            })
+            .chain(self.stable_pools.iter().map(|(id, p)| {
+                (
+                    PoolKind::Stable,
+                    id.clone(),
+                    vec![
+                        p.get_config().token_a.clone(),
+                        p.get_config().token_b.clone(),
+                    ],
+                )
+            }))
+            .chain(
+                self.multi_stable_pools
+                    .iter()
+                    .map(|(id, p)| (PoolKind::MultiStable, id.clone(), p.tokens().to_vec())),
+            )
+            .chain(self.concentrated_pools.iter().map(|(id, p)| {
+                (
+                    PoolKind::Concentrated,
+                    id.clone(),
+                    vec![p.config.token_a.clone(), p.config.token_b.clone()],
+                )
+            }))
+            .collect();
+        pools.sort_by(|a, b| a.1.cmp(&b.1));
+        pools
+    }
+
+    /// Output of a single swap through one pool, without executing it
+    fn quote_hop(
         &self,
+        kind: &PoolKind,
+        pool_id: &str,
         input_token: &str,
         output_token: &str,
         input_amount: f64,
-    ) -> Result<RouterQuote, RouterError> {
-        if input_amount <= 0.0 {
-            return Err(RouterError::InvalidAmount);
+    ) -> Result<f64, RouterError> {
+        match kind {
+            PoolKind::Constant => self
+                .constant_pools
+                .get(pool_id)
+                .ok_or(RouterError::NoRoute)?
+                .calculate_swap_output(input_token, input_amount)
+                .map_err(constant_pool_error),
+            PoolKind::Stable => self
+                .stable_pools
+                .get(pool_id)
+                .ok_or(RouterError::NoRoute)?
+                .calculate_swap_output(input_token, input_amount)
+                .map_err(stable_pool_error),
+            PoolKind::MultiStable => self
+                .multi_stable_pools
+                .get(pool_id)
+                .ok_or(RouterError::NoRoute)?
+                .calculate_swap_output(input_token, output_token, input_amount)
+                .map_err(stable_pool_error),
+            PoolKind::Concentrated => self
+                .concentrated_pools
+                .get(pool_id)
+                .ok_or(RouterError::NoRoute)?
+                .calculate_swap_output(input_token, input_amount)
+                .map_err(concentrated_pool_error),
         }
+    }
 
-        let mut best: Option<RouterQuote> = None;
+    /// Execute a single swap through one pool
+    fn swap_hop(
+        &mut self,
+        kind: &PoolKind,
+        pool_id: &str,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+    ) -> Result<f64, RouterError> {
+        match kind {
+            PoolKind::Constant => self
+                .constant_pools
+                .get_mut(pool_id)
+                .ok_or(RouterError::NoRoute)?
+                .swap(input_token, input_amount)
+                .map_err(constant_pool_error),
+            PoolKind::Stable => self
+                .stable_pools
+                .get_mut(pool_id)
+                .ok_or(RouterError::NoRoute)?
+                .swap(input_token, input_amount)
+                .map_err(stable_pool_error),
+            PoolKind::MultiStable => self
+                .multi_stable_pools
+                .get_mut(pool_id)
+                .ok_or(RouterError::NoRoute)?
+                .swap(input_token, output_token, input_amount)
+                .map_err(stable_pool_error),
+            PoolKind::Concentrated => self
+                .concentrated_pools
+                .get_mut(pool_id)
+                .ok_or(RouterError::NoRoute)?
+                .swap(input_token, input_amount)
+                .map_err(concentrated_pool_error),
+        }
+    }
 
-        for (id, p) in &self.constant_pools {
-            let ta = &p.config.token_a;
-            let tb = &p.config.token_b;
-            let out = if input_token == ta && output_token == tb {
-                p.calculate_swap_output(input_token, input_amount)
-            } else if input_token == tb && output_token == ta {
-                p.calculate_swap_output(input_token, input_amount)
-            } else {
-                continue;
-            };
-            if let Ok(o) = out {
-                let q = RouterQuote {
-                    pool_id: id.clone(),
-                    kind: PoolKind::Constant,
-                    input_token: input_token.to_string(),
-                    output_token: output_token.to_string(),
-                    input_amount,
-                    output_amount: o,
-                };
-                if best.as_ref().map(|b| o > b.output_amount).unwrap_or(true) {
-                    best = Some(q);
-                }
+    /// Depth-first search over routes that never revisit a token or a pool. `start` is
+    /// the route's input token and amount; `hops` holds the legs taken so far.
+    fn search_routes(
+        &self,
+        pools: &[(PoolKind, String, Vec<String>)],
+        start: (&str, f64),
+        output_token: &str,
+        hops: &mut Vec<RouteHop>,
+        best: &mut Option<Vec<RouteHop>>,
+    ) {
+        let (token, amount) = hops
+            .last()
+            .map(|h| (h.output_token.clone(), h.output_amount))
+            .unwrap_or((start.0.to_string(), start.1));
+        if token == output_token {
+            let better = best
+                .as_ref()
+                .and_then(|b| b.last())
+                .map(|b| amount > b.output_amount)
+                .unwrap_or(true);
+            if better {
+                *best = Some(hops.clone());
             }
+            return;
+        }
+        if hops.len() >= self.max_hops {
+            return;
         }
 
-        for (id, p) in &self.stable_pools {
-            let ta = &p.get_config().token_a;
-            let tb = &p.get_config().token_b;
-            let out = if input_token == ta && output_token == tb {
-                p.calculate_swap_output(input_token, input_amount)
-            } else if input_token == tb && output_token == ta {
-                p.calculate_swap_output(input_token, input_amount)
-            } else {
+        for (kind, pool_id, tokens) in pools {
+            if !tokens.contains(&token) || hops.iter().any(|h| &h.pool_id == pool_id) {
                 continue;
-            };
-            if let Ok(o) = out {
-                let q = RouterQuote {
-                    pool_id: id.clone(),
-                    kind: PoolKind::Stable,
-                    input_token: input_token.to_string(),
-                    output_token: output_token.to_string(),
-                    input_amount,
-                    output_amount: o,
-                };
-                if best.as_ref().map(|b| o > b.output_amount).unwrap_or(true) {
-                    best = Some(q);
+            }
+            for next in tokens {
+                let visited = next == start.0 || hops.iter().any(|h| &h.output_token == next);
+                if *next == token || visited {
+                    continue;
                 }
+                let Ok(out) = self.quote_hop(kind, pool_id, &token, next, amount) else {
+                    continue;
+                };
+                hops.push(RouteHop {
+                    pool_id: pool_id.clone(),
+                    kind: kind.clone(),
+                    input_token: token.clone(),
+                    output_token: next.clone(),
+                    input_amount: amount,
+                    output_amount: out,
+                });
+                self.search_routes(pools, start, output_token, hops, best);
+                hops.pop();
             }
         }
+    }
 
-        best.ok_or(RouterError::NoRoute)
+    /// Best route from `input_token` to `output_token` across all registered pools,
+    /// going through at most `max_hops` pools
+    pub fn best_quote(
+        &self,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+    ) -> Result<RouterQuote, RouterError> {
+        if input_amount <= 0.0 {
+            return Err(RouterError::InvalidAmount);
+        }
+        if input_token == output_token {
+            return Err(RouterError::NoRoute);
+        }
+
+        let pools = self.pool_graph();
+        let mut best = None;
+        self.search_routes(
+            &pools,
+            (input_token, input_amount),
+            output_token,
+            &mut Vec::new(),
+            &mut best,
+        );
+
+        let hops = best.ok_or(RouterError::NoRoute)?;
+        let output_amount = hops.last().map(|h| h.output_amount).unwrap_or(0.0);
+        Ok(RouterQuote {
+            hops,
+            input_token: input_token.to_string(),
+            output_token: output_token.to_string(),
+            input_amount,
+            output_amount,
+        })
     }
 
     fn ensure_liquidity(
@@ -234,6 +504,9 @@ impl Router {
                 }
                 Ok(false)
             }
+            // Multi-asset and ranged pools are seeded explicitly; auto-provisioning only
+            // tops up full-range pairs
+            PoolKind::MultiStable | PoolKind::Concentrated => Ok(false),
         }
     }
 
@@ -250,6 +523,118 @@ impl Router {
         ct.first().map(|id| (PoolKind::Constant, id.clone()))
     }
 
+    fn backup_pools(&self, hops: &[RouteHop]) -> PoolBackup {
+        PoolBackup {
+            constant: hops
+                .iter()
+                .filter(|h| h.kind == PoolKind::Constant)
+                .filter_map(|h| {
+                    self.constant_pools
+                        .get(&h.pool_id)
+                        .map(|p| (h.pool_id.clone(), p.clone()))
+                })
+                .collect(),
+            stable: hops
+                .iter()
+                .filter(|h| h.kind == PoolKind::Stable)
+                .filter_map(|h| {
+                    self.stable_pools
+                        .get(&h.pool_id)
+                        .map(|p| (h.pool_id.clone(), p.clone()))
+                })
+                .collect(),
+            multi_stable: hops
+                .iter()
+                .filter(|h| h.kind == PoolKind::MultiStable)
+                .filter_map(|h| {
+                    self.multi_stable_pools
+                        .get(&h.pool_id)
+                        .map(|p| (h.pool_id.clone(), p.clone()))
+                })
+                .collect(),
+            concentrated: hops
+                .iter()
+                .filter(|h| h.kind == PoolKind::Concentrated)
+                .filter_map(|h| {
+                    self.concentrated_pools
+                        .get(&h.pool_id)
+                        .map(|p| (h.pool_id.clone(), p.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    fn restore_pools(&mut self, backup: PoolBackup) {
+        for (id, pool) in backup.constant {
+            self.constant_pools.insert(id, pool);
+        }
+        for (id, pool) in backup.stable {
+            self.stable_pools.insert(id, pool);
+        }
+        for (id, pool) in backup.multi_stable {
+            self.multi_stable_pools.insert(id, pool);
+        }
+        for (id, pool) in backup.concentrated {
+            self.concentrated_pools.insert(id, pool);
+        }
+    }
+
+    /// Execute the legs of a quote in order, feeding each leg the actual output of the
+    /// previous one. All touched pools are restored if any leg fails or the final
+    /// output falls below `min_output`; intermediate legs carry no slippage check.
+    fn execute_route(
+        &mut self,
+        quote: &RouterQuote,
+        min_output: f64,
+    ) -> Result<SwapResult, RouterError> {
+        let backup = self.backup_pools(&quote.hops);
+        let mut executed = Vec::with_capacity(quote.hops.len());
+        let mut amount = quote.input_amount;
+        let mut failure = None;
+        for hop in &quote.hops {
+            match self.swap_hop(
+                &hop.kind,
+                &hop.pool_id,
+                &hop.input_token,
+                &hop.output_token,
+                amount,
+            ) {
+                Ok(out) => {
+                    executed.push(RouteHop {
+                        input_amount: amount,
+                        output_amount: out,
+                        ..hop.clone()
+                    });
+                    amount = out;
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        if failure.is_none() && amount < min_output {
+            failure = Some(RouterError::SlippageExceeded);
+        }
+
+        if let Some(e) = failure {
+            self.restore_pools(backup);
+            return Err(e);
+        }
+
+        Ok(SwapResult {
+            hops: executed,
+            input_token: quote.input_token.clone(),
+            output_token: quote.output_token.clone(),
+            input_amount: quote.input_amount,
+            output_amount: amount,
+        })
+    }
+
+    /// Swap along the best route, possibly through several pools. The whole route is
+    /// executed atomically and `min_output` applies to the amount received at the end.
+    /// With `auto`, thin pools on the route (or the preferred direct pool when no route
+    /// exists) are topped up before giving up.
     pub fn swap_best_route(
         &mut self,
         input_token: &str,
@@ -266,7 +651,8 @@ impl Router {
 
         if quote.is_err() {
             if let Some(cfg) = &auto {
-                if let Some((kind, id)) = self.pick_preferred_matching_pool(input_token, output_token)
+                if let Some((kind, id)) =
+                    self.pick_preferred_matching_pool(input_token, output_token)
                 {
                     let _ = self.ensure_liquidity(&id, &kind, input_token, output_token, cfg)?;
                     quote = self.best_quote(input_token, output_token, input_amount);
@@ -274,111 +660,160 @@ impl Router {
             }
         }
 
-        let q = quote?;
+        let mut q = quote?;
+
+        if q.output_amount < min_output {
+            let cfg = auto.as_ref().ok_or(RouterError::SlippageExceeded)?;
+            let mut changed = false;
+            for hop in &q.hops {
+                changed |= self.ensure_liquidity(
+                    &hop.pool_id,
+                    &hop.kind,
+                    &hop.input_token,
+                    &hop.output_token,
+                    cfg,
+                )?;
+            }
+            if !changed {
+                return Err(RouterError::SlippageExceeded);
+            }
+            q = self.best_quote(input_token, output_token, input_amount)?;
+            if q.output_amount < min_output {
+                return Err(RouterError::SlippageExceeded);
+            }
+        }
 
-        match q.kind {
-            PoolKind::Constant => {
-                let p = self
-                    .constant_pools
-                    .get_mut(&q.pool_id)
-                    .ok_or(RouterError::NoRoute)?;
+        self.execute_route(&q, min_output)
+    }
 
-                let expected = p
-                    .calculate_swap_output(input_token, input_amount)
-                    .map_err(|e| match e {
-                        LiquidityPoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
-                        LiquidityPoolError::InvalidAmount => RouterError::InvalidAmount,
-                        _ => RouterError::PoolError(format!("{:?}", e)),
-                    })?;
-
-                if expected < min_output {
-                    if let Some(cfg) = &auto {
-                        let changed = self.ensure_liquidity(&q.pool_id, &q.kind, input_token, output_token, cfg)?;
-                        if changed {
-                            let re = p
-                                .calculate_swap_output(input_token, input_amount)
-                                .map_err(|e| match e {
-                                    LiquidityPoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
-                                    LiquidityPoolError::InvalidAmount => RouterError::InvalidAmount,
-                                    _ => RouterError::PoolError(format!("{:?}", e)),
-                                })?;
-                            if re < min_output {
-                                return Err(RouterError::SlippageExceeded);
-                            }
-                        } else {
-                            return Err(RouterError::SlippageExceeded);
-                        }
-                    } else {
-                        return Err(RouterError::SlippageExceeded);
-                    }
-                }
+    /// Split `input_amount` across every pool that directly pairs the two tokens so
+    /// that the total output is maximized. The amount is handed out in `SPLIT_STEPS`
+    /// slices, each going to the pool with the best marginal output for it; since
+    /// pool output is concave in the input this converges on the optimal split.
+    pub fn best_split(
+        &self,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+    ) -> Result<SplitPlan, RouterError> {
+        if input_amount <= 0.0 {
+            return Err(RouterError::InvalidAmount);
+        }
 
-                let out = p.swap(input_token, input_amount).map_err(|e| match e {
-                    LiquidityPoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
-                    LiquidityPoolError::InvalidAmount => RouterError::InvalidAmount,
-                    _ => RouterError::PoolError(format!("{:?}", e)),
-                })?;
-
-                Ok(SwapResult {
-                    pool_id: q.pool_id,
-                    kind: q.kind,
-                    input_token: input_token.to_string(),
-                    output_token: output_token.to_string(),
-                    input_amount,
-                    output_amount: out,
+        let pools: Vec<(PoolKind, String)> = self
+            .pool_graph()
+            .into_iter()
+            .filter(|(_, _, tokens)| {
+                input_token != output_token
+                    && tokens.iter().any(|t| t == input_token)
+                    && tokens.iter().any(|t| t == output_token)
+            })
+            .map(|(kind, id, _)| (kind, id))
+            .collect();
+        if pools.is_empty() {
+            return Err(RouterError::NoRoute);
+        }
+
+        let mut allocated = vec![0.0; pools.len()];
+        let mut outputs = vec![0.0; pools.len()];
+        let slice = input_amount / SPLIT_STEPS as f64;
+        for step in 0..SPLIT_STEPS {
+            let amount = if step + 1 == SPLIT_STEPS {
+                input_amount - slice * step as f64
+            } else {
+                slice
+            };
+            let best = pools
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (kind, id))| {
+                    self.quote_hop(kind, id, input_token, output_token, allocated[i] + amount)
+                        .ok()
+                        .map(|out| (i, out))
                 })
-            }
-            PoolKind::Stable => {
-                let p = self
-                    .stable_pools
-                    .get_mut(&q.pool_id)
-                    .ok_or(RouterError::NoRoute)?;
+                .max_by(|a, b| (a.1 - outputs[a.0]).total_cmp(&(b.1 - outputs[b.0])));
+            let (i, out) = best.ok_or(RouterError::InsufficientLiquidity)?;
+            allocated[i] += amount;
+            outputs[i] = out;
+        }
 
-                let expected = p
-                    .calculate_swap_output(input_token, input_amount)
-                    .map_err(|e| match e {
-                        StablePoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
-                        StablePoolError::InvalidAmount => RouterError::InvalidAmount,
-                        _ => RouterError::PoolError(format!("{:?}", e)),
-                    })?;
-
-                if expected < min_output {
-                    if let Some(cfg) = &auto {
-                        let changed = self.ensure_liquidity(&q.pool_id, &q.kind, input_token, output_token, cfg)?;
-                        if changed {
-                            let re = p
-                                .calculate_swap_output(input_token, input_amount)
-                                .map_err(|e| match e {
-                                    StablePoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
-                                    StablePoolError::InvalidAmount => RouterError::InvalidAmount,
-                                    _ => RouterError::PoolError(format!("{:?}", e)),
-                                })?;
-                            if re < min_output {
-                                return Err(RouterError::SlippageExceeded);
-                            }
-                        } else {
-                            return Err(RouterError::SlippageExceeded);
-                        }
-                    } else {
-                        return Err(RouterError::SlippageExceeded);
-                    }
-                }
+        let legs: Vec<RouteHop> = pools
+            .into_iter()
+            .zip(allocated.into_iter().zip(outputs))
+            .filter(|(_, (amount, _))| *amount > 0.0)
+            .map(|((kind, pool_id), (amount, out))| RouteHop {
+                pool_id,
+                kind,
+                input_token: input_token.to_string(),
+                output_token: output_token.to_string(),
+                input_amount: amount,
+                output_amount: out,
+            })
+            .collect();
+        Ok(SplitPlan {
+            output_amount: legs.iter().map(|l| l.output_amount).sum(),
+            legs,
+            input_token: input_token.to_string(),
+            output_token: output_token.to_string(),
+            input_amount,
+        })
+    }
 
-                let out = p.swap(input_token, input_amount).map_err(|e| match e {
-                    StablePoolError::InsufficientLiquidity => RouterError::InsufficientLiquidity,
-                    StablePoolError::InvalidAmount => RouterError::InvalidAmount,
-                    _ => RouterError::PoolError(format!("{:?}", e)),
-                })?;
-
-                Ok(SwapResult {
-                    pool_id: q.pool_id,
-                    kind: q.kind,
-                    input_token: input_token.to_string(),
-                    output_token: output_token.to_string(),
-                    input_amount,
+    /// Execute every leg of a split plan. The plan is all-or-nothing: if a leg fails or
+    /// the combined output is below `min_output`, every pool is left untouched.
+    /// Returns the plan with the amounts actually received.
+    pub fn execute_split(
+        &mut self,
+        plan: &SplitPlan,
+        min_output: f64,
+    ) -> Result<SplitPlan, RouterError> {
+        let backup = self.backup_pools(&plan.legs);
+        let mut executed = Vec::with_capacity(plan.legs.len());
+        let mut failure = None;
+        for leg in &plan.legs {
+            match self.swap_hop(
+                &leg.kind,
+                &leg.pool_id,
+                &leg.input_token,
+                &leg.output_token,
+                leg.input_amount,
+            ) {
+                Ok(out) => executed.push(RouteHop {
                     output_amount: out,
-                })
+                    ..leg.clone()
+                }),
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
             }
         }
+        let output_amount: f64 = executed.iter().map(|l| l.output_amount).sum();
+        if failure.is_none() && output_amount < min_output {
+            failure = Some(RouterError::SlippageExceeded);
+        }
+
+        if let Some(e) = failure {
+            self.restore_pools(backup);
+            return Err(e);
+        }
+
+        Ok(SplitPlan {
+            legs: executed,
+            output_amount,
+            ..plan.clone()
+        })
+    }
+
+    /// Compute the best split for an order and execute it atomically
+    pub fn swap_split(
+        &mut self,
+        input_token: &str,
+        output_token: &str,
+        input_amount: f64,
+        min_output: f64,
+    ) -> Result<SplitPlan, RouterError> {
+        let plan = self.best_split(input_token, output_token, input_amount)?;
+        self.execute_split(&plan, min_output)
     }
 }
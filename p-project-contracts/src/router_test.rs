@@ -1,8 +1,37 @@
 #[cfg(test)]
 mod tests {
+    use super::super::concentrated_liquidity_pool::ConcentratedLiquidityPool;
     use super::super::liquidity_pool::LiquidityPool;
-    use super::super::stable_liquidity_pool::StableLiquidityPool;
+    use super::super::multi_asset_stable_pool::MultiAssetStablePool;
     use super::super::router::{AutoProvisionConfig, PoolKind, Router, RouterError};
+    use super::super::stable_liquidity_pool::StableLiquidityPool;
+
+    fn constant_pool(id: &str, a: &str, b: &str, ra: f64, rb: f64) -> LiquidityPool {
+        let mut pool = LiquidityPool::new(
+            id.to_string(),
+            a.to_string(),
+            b.to_string(),
+            0.003,
+            "RWD".to_string(),
+            100_000.0,
+            0.10,
+        );
+        pool.add_liquidity("lp".into(), ra, rb, 30).unwrap();
+        pool
+    }
+
+    fn multi_hop_router() -> Router {
+        let mut router = Router::new();
+        router.register_constant_pool(
+            "p_usdc".to_string(),
+            constant_pool("p_usdc", "P-COIN", "USDC", 1_000_000.0, 500_000.0),
+        );
+        router.register_constant_pool(
+            "usdc_eth".to_string(),
+            constant_pool("usdc_eth", "USDC", "ETH", 2_000_000.0, 1_000.0),
+        );
+        router
+    }
 
     #[test]
     fn picks_best_route_between_constant_and_stable() {
@@ -17,7 +46,8 @@ mod tests {
             100_000.0,
             0.10,
         );
-        cp.add_liquidity("lp1".into(), 1_000_000.0, 1_000_000.0, 30).unwrap();
+        cp.add_liquidity("lp1".into(), 1_000_000.0, 1_000_000.0, 30)
+            .unwrap();
         router.register_constant_pool("cp".to_string(), cp);
 
         let mut sp = StableLiquidityPool::new(
@@ -40,7 +70,8 @@ mod tests {
             .expect("quote must exist");
 
         // With lower fee and amplification, stable should typically provide better output.
-        assert_eq!(q.kind, PoolKind::Stable);
+        assert_eq!(q.hops.len(), 1);
+        assert_eq!(q.hops[0].kind, PoolKind::Stable);
     }
 
     #[test]
@@ -72,11 +103,204 @@ mod tests {
 
         let res = router.swap_best_route("P-COIN", "USDC", 10_000.0, 1.0, Some(cfg));
         assert!(res.is_ok(), "swap should succeed after auto-provision");
+        let out = res.unwrap().output_amount;
 
-        // Verify pool now has reserves > 0
+        // The floor is provisioned before the swap, which then moves the reserves off
+        // it: P-COIN grows by the amount swapped in and USDC shrinks by the amount paid
         let p = router.get_constant_pool("cp").unwrap();
         let (ra, rb) = p.get_reserves();
-        assert!(ra >= 100_000.0 && rb >= 100_000.0);
+        assert!((ra - 110_000.0).abs() < 1e-6);
+        assert!((rb - (100_000.0 - out)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn finds_multi_hop_route_through_intermediate_token() {
+        let mut router = multi_hop_router();
+        let q = router.best_quote("P-COIN", "ETH", 10_000.0).unwrap();
+        assert_eq!(q.path(), vec!["P-COIN", "USDC", "ETH"]);
+        assert_eq!(q.hops[0].pool_id, "p_usdc");
+        assert_eq!(q.hops[1].input_amount, q.hops[0].output_amount);
+        assert_eq!(q.output_amount, q.hops[1].output_amount);
+
+        router.set_max_hops(1);
+        assert_eq!(
+            router.best_quote("P-COIN", "ETH", 10_000.0).err(),
+            Some(RouterError::NoRoute)
+        );
+    }
+
+    #[test]
+    fn prefers_multi_hop_when_direct_pool_is_thin() {
+        let mut router = multi_hop_router();
+        router.register_constant_pool(
+            "p_eth".to_string(),
+            constant_pool("p_eth", "P-COIN", "ETH", 10_000.0, 2.0),
+        );
+        let q = router.best_quote("P-COIN", "ETH", 10_000.0).unwrap();
+        assert_eq!(q.hops.len(), 2);
+
+        let direct = router
+            .get_constant_pool("p_eth")
+            .unwrap()
+            .calculate_swap_output("P-COIN", 10_000.0)
+            .unwrap();
+        assert!(q.output_amount > direct);
+    }
+
+    #[test]
+    fn multi_hop_swap_is_atomic_and_checks_final_output() {
+        let mut router = multi_hop_router();
+        let before_first = router.get_constant_pool("p_usdc").unwrap().get_reserves();
+        let before_second = router.get_constant_pool("usdc_eth").unwrap().get_reserves();
+        let q = router.best_quote("P-COIN", "ETH", 10_000.0).unwrap();
+
+        // The intermediate USDC amount far exceeds min_output; only the ETH leg counts
+        let res = router.swap_best_route("P-COIN", "ETH", 10_000.0, q.output_amount * 1.01, None);
+        assert_eq!(res.err(), Some(RouterError::SlippageExceeded));
+        assert_eq!(
+            router.get_constant_pool("p_usdc").unwrap().get_reserves(),
+            before_first
+        );
+        assert_eq!(
+            router.get_constant_pool("usdc_eth").unwrap().get_reserves(),
+            before_second
+        );
+
+        let res = router
+            .swap_best_route("P-COIN", "ETH", 10_000.0, q.output_amount * 0.99, None)
+            .unwrap();
+        assert_eq!(res.path(), vec!["P-COIN", "USDC", "ETH"]);
+        assert!((res.output_amount - q.output_amount).abs() < 1e-9);
+        let (p, usdc) = router.get_constant_pool("p_usdc").unwrap().get_reserves();
+        assert_eq!(p, before_first.0 + 10_000.0);
+        assert_eq!(usdc, before_first.1 - res.hops[0].output_amount);
+    }
+
+    #[test]
+    fn splits_large_order_across_parallel_pools() {
+        let mut router = Router::new();
+        router.register_constant_pool(
+            "deep".to_string(),
+            constant_pool("deep", "P-COIN", "USDC", 3_000_000.0, 3_000_000.0),
+        );
+        router.register_constant_pool(
+            "shallow".to_string(),
+            constant_pool("shallow", "P-COIN", "USDC", 1_000_000.0, 1_000_000.0),
+        );
+
+        let single = router.best_quote("P-COIN", "USDC", 400_000.0).unwrap();
+        let plan = router.best_split("P-COIN", "USDC", 400_000.0).unwrap();
+        assert_eq!(plan.legs.len(), 2);
+        assert!(plan.output_amount > single.output_amount);
+        let shares = plan.shares();
+        let deep = shares.iter().find(|(id, _)| id == "deep").unwrap().1;
+        // Equal-priced pools are filled in proportion to their depth
+        assert!((deep - 0.75).abs() < 0.02);
+        let total: f64 = plan.legs.iter().map(|l| l.input_amount).sum();
+        assert!((total - 400_000.0).abs() < 1e-6);
+
+        let fill = router.execute_split(&plan, plan.output_amount).unwrap();
+        assert!((fill.output_amount - plan.output_amount).abs() < 1e-9);
+        let (p, _) = router.get_constant_pool("shallow").unwrap().get_reserves();
+        assert!(p > 1_000_000.0);
+    }
+
+    #[test]
+    fn split_execution_is_atomic() {
+        let mut router = Router::new();
+        router.register_constant_pool(
+            "a".to_string(),
+            constant_pool("a", "P-COIN", "USDC", 1_000_000.0, 1_000_000.0),
+        );
+        router.register_constant_pool(
+            "b".to_string(),
+            constant_pool("b", "P-COIN", "USDC", 1_000_000.0, 1_000_000.0),
+        );
+        let plan = router.best_split("P-COIN", "USDC", 100_000.0).unwrap();
+        let before = router.get_constant_pool("a").unwrap().get_reserves();
+
+        let res = router.execute_split(&plan, plan.output_amount * 1.01);
+        assert_eq!(res.err(), Some(RouterError::SlippageExceeded));
+        assert_eq!(
+            router.get_constant_pool("a").unwrap().get_reserves(),
+            before
+        );
+        assert_eq!(
+            router.best_split("P-COIN", "ETH", 1.0).err(),
+            Some(RouterError::NoRoute)
+        );
+    }
+
+    #[test]
+    fn routes_through_multi_asset_stable_pool() {
+        let mut router = multi_hop_router();
+        let mut pool = MultiAssetStablePool::new(
+            "3pool".to_string(),
+            vec![
+                ("USDC".to_string(), 0),
+                ("USDT".to_string(), 0),
+                ("DAI".to_string(), 0),
+            ],
+            0.0004,
+            200.0,
+            "RWD".to_string(),
+            100_000.0,
+            0.10,
+        )
+        .unwrap();
+        pool.add_liquidity("lp".into(), &[1_000_000.0; 3], 30)
+            .unwrap();
+        router.register_multi_stable_pool("3pool".to_string(), pool);
+
+        // DAI is only reachable through the 3pool, behind the P-COIN/USDC pool
+        let quote = router.best_quote("P-COIN", "DAI", 10_000.0).unwrap();
+        assert_eq!(quote.path(), vec!["P-COIN", "USDC", "DAI"]);
+        assert_eq!(quote.hops[1].kind, PoolKind::MultiStable);
+
+        let res = router
+            .swap_best_route("USDT", "DAI", 5_000.0, 4_990.0, None)
+            .unwrap();
+        assert_eq!(res.hops.len(), 1);
+        let pool = router.get_multi_stable_pool("3pool").unwrap();
+        assert_eq!(pool.reserve_of("USDT").unwrap(), 1_005_000.0);
+        assert_eq!(
+            pool.reserve_of("DAI").unwrap(),
+            1_000_000.0 - res.output_amount
+        );
+        assert_eq!(pool.reserve_of("USDC").unwrap(), 1_000_000.0);
     }
-}
 
+    #[test]
+    fn routes_and_splits_through_concentrated_pool() {
+        let mut router = Router::new();
+        router.register_constant_pool(
+            "p_usdt".to_string(),
+            constant_pool("p_usdt", "P", "USDT", 1_000_000.0, 10_000.0),
+        );
+        let mut cl = ConcentratedLiquidityPool::new(
+            "p_usdt_cl".to_string(),
+            "P".to_string(),
+            "USDT".to_string(),
+            0.003,
+            60,
+            0.01,
+        )
+        .unwrap();
+        let (lower, upper) = cl.tick_range_for_prices(0.008, 0.0125).unwrap();
+        cl.add_liquidity("lp".into(), lower, upper, 1_000_000.0, 10_000.0)
+            .unwrap();
+        router.register_concentrated_pool("p_usdt_cl".to_string(), cl);
+
+        let quote = router.best_quote("USDT", "P", 500.0).unwrap();
+        assert_eq!(quote.hops[0].kind, PoolKind::Concentrated);
+        assert_eq!(quote.hops[0].pool_id, "p_usdt_cl");
+
+        let plan = router.swap_split("USDT", "P", 5_000.0, 0.0).unwrap();
+        assert_eq!(plan.legs.len(), 2);
+        let shares = plan.shares();
+        let cl_share = shares.iter().find(|(id, _)| id == "p_usdt_cl").unwrap().1;
+        assert!(cl_share > 0.5);
+        let cl = router.get_concentrated_pool("p_usdt_cl").unwrap();
+        assert!(cl.price() > 0.01);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::fee_policy::{FeeContext, FeePolicy};
+use crate::price_oracle::{OracleError, OracleQuote, PriceOracle};
 use chrono::{NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -10,7 +12,11 @@ pub enum StablePoolError {
     UserNotInPool,
     InvalidDuration,
     InvalidAmplification,
+    InvalidRamp,
+    InvalidTokens,
     LiquidityLocked,
+    InsufficientShares,
+    SlippageExceeded,
 }
 
 impl fmt::Display for StablePoolError {
@@ -21,13 +27,94 @@ impl fmt::Display for StablePoolError {
             StablePoolError::UserNotInPool => write!(f, "User not in pool"),
             StablePoolError::InvalidDuration => write!(f, "Invalid duration specified"),
             StablePoolError::InvalidAmplification => write!(f, "Amplification must be >= 1.0"),
+            StablePoolError::InvalidRamp => {
+                write!(f, "Amplification ramp must last at least one day")
+            }
+            StablePoolError::InvalidTokens => {
+                write!(f, "Pool needs between 2 and 8 distinct tokens")
+            }
             StablePoolError::LiquidityLocked => write!(f, "Liquidity is locked"),
+            StablePoolError::InsufficientShares => write!(f, "Not enough LP shares"),
+            StablePoolError::SlippageExceeded => write!(f, "Slippage tolerance exceeded"),
         }
     }
 }
 
 impl std::error::Error for StablePoolError {}
 
+// Upper bound on the amplification coefficient
+pub const MAX_AMPLIFICATION: f64 = 1_000_000.0;
+
+// Largest factor by which a single ramp may raise or lower the amplification
+pub const MAX_AMPLIFICATION_CHANGE: f64 = 10.0;
+
+// Shortest allowed amplification ramp
+pub const MIN_RAMP_SECS: i64 = 86_400;
+
+// Newton iteration limit and relative tolerance for the invariant solvers
+const MAX_ITERATIONS: usize = 255;
+const CONVERGENCE: f64 = 1e-12;
+
+/// StableSwap invariant D of the given balances, solving
+/// `A·nⁿ·Σx + D = A·nⁿ·D + Dⁿ⁺¹ / (nⁿ·Πx)` by Newton iteration.
+/// Returns None if a balance is empty or the iteration does not converge.
+pub fn stableswap_d(balances: &[f64], amplification: f64) -> Option<f64> {
+    let n = balances.len() as f64;
+    let sum: f64 = balances.iter().sum();
+    if sum == 0.0 {
+        return Some(0.0);
+    }
+    if balances.iter().any(|x| *x <= 0.0 || !x.is_finite()) {
+        return None;
+    }
+
+    let ann = amplification * n.powi(balances.len() as i32);
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        let mut d_p = d;
+        for x in balances {
+            d_p = d_p * d / (x * n);
+        }
+        let previous = d;
+        d = (ann * sum + d_p * n) * d / ((ann - 1.0) * d + (n + 1.0) * d_p);
+        if (d - previous).abs() <= d * CONVERGENCE {
+            return Some(d);
+        }
+    }
+    None
+}
+
+/// Balance of coin `j` that keeps the invariant at `d` given the other balances
+/// (the current entry at `j` is ignored)
+pub fn stableswap_y(balances: &[f64], j: usize, d: f64, amplification: f64) -> Option<f64> {
+    let n = balances.len() as f64;
+    let ann = amplification * n.powi(balances.len() as i32);
+    let mut c = d;
+    let mut sum = 0.0;
+    for (k, x) in balances.iter().enumerate() {
+        if k == j {
+            continue;
+        }
+        if *x <= 0.0 || !x.is_finite() {
+            return None;
+        }
+        sum += x;
+        c = c * d / (x * n);
+    }
+    c = c * d / (ann * n);
+    let b = sum + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        y = (y * y + c) / (2.0 * y + b - d);
+        if (y - previous).abs() <= y * CONVERGENCE {
+            return Some(y);
+        }
+    }
+    None
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StablePoolConfig {
     pub pool_id: String,
@@ -35,13 +122,64 @@ pub struct StablePoolConfig {
     pub token_b: String, // e.g., USDC
     pub fee_tier: f64,   // e.g., 0.0005 for 5 bps
     pub start_date: NaiveDateTime,
-    pub amplification: f64, // amplification factor (A >= 1) to reduce slippage near peg
+    pub amplification: f64, // amplification factor (A >= 1) to reduce slippage near peg; target A while ramping
     pub reward_token: String,
     pub total_reward_allocation: f64,
     pub distributed_rewards: f64,
     pub apr_rate: f64,
 }
 
+// Linear change of the amplification coefficient between two points in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmplificationRamp {
+    pub initial_amplification: f64,
+    pub future_amplification: f64,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+}
+
+impl AmplificationRamp {
+    /// Ramp from `initial_amplification` at `now` to `future_amplification` at `end_time`.
+    /// Ramps last at least `MIN_RAMP_SECS` and change A by at most
+    /// `MAX_AMPLIFICATION_CHANGE` times so that prices cannot be shifted abruptly.
+    pub fn new(
+        initial_amplification: f64,
+        future_amplification: f64,
+        now: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Result<Self, StablePoolError> {
+        if !(1.0..=MAX_AMPLIFICATION).contains(&future_amplification) {
+            return Err(StablePoolError::InvalidAmplification);
+        }
+        if (end_time - now).num_seconds() < MIN_RAMP_SECS {
+            return Err(StablePoolError::InvalidRamp);
+        }
+        let change = future_amplification / initial_amplification;
+        if !(1.0 / MAX_AMPLIFICATION_CHANGE..=MAX_AMPLIFICATION_CHANGE).contains(&change) {
+            return Err(StablePoolError::InvalidAmplification);
+        }
+        Ok(Self {
+            initial_amplification,
+            future_amplification,
+            start_time: now,
+            end_time,
+        })
+    }
+
+    /// Amplification at `now`, interpolated linearly; None once the ramp has ended
+    pub fn value_at(&self, now: NaiveDateTime) -> Option<f64> {
+        if now >= self.end_time {
+            return None;
+        }
+        let total = (self.end_time - self.start_time).num_milliseconds() as f64;
+        let elapsed = (now - self.start_time).num_milliseconds().max(0) as f64;
+        Some(
+            self.initial_amplification
+                + (self.future_amplification - self.initial_amplification) * elapsed / total,
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StableLiquidityPosition {
     pub user_id: String,
@@ -63,6 +201,12 @@ pub struct StableLiquidityPool {
     pub k_constant: f64,
     pub total_volume: f64,
     pub total_fees: f64,
+    #[serde(default)]
+    pub amplification_ramp: Option<AmplificationRamp>,
+    #[serde(default)]
+    pub oracle: PriceOracle, // TWAP of token_a priced in token_b
+    #[serde(default, with = "crate::fee_policy::serde_policy")]
+    pub fee_policy: Option<Box<dyn FeePolicy>>, // Replaces fee_tier on swaps when set
 }
 
 impl StableLiquidityPool {
@@ -102,9 +246,150 @@ impl StableLiquidityPool {
             k_constant: 0.0,
             total_volume: 0.0,
             total_fees: 0.0,
+            amplification_ramp: None,
+            oracle: PriceOracle::default(),
+            fee_policy: None,
         })
     }
 
+    /// Effective amplification coefficient at `now`, interpolated while a ramp is active
+    pub fn amplification_at(&self, now: NaiveDateTime) -> f64 {
+        self.amplification_ramp
+            .as_ref()
+            .and_then(|ramp| ramp.value_at(now))
+            .unwrap_or(self.config.amplification)
+    }
+
+    pub fn current_amplification(&self) -> f64 {
+        self.amplification_at(Utc::now().naive_utc())
+    }
+
+    /// Gradually move the amplification to `future_amplification`, reaching it at
+    /// `end_time` (see `AmplificationRamp::new` for the limits)
+    pub fn ramp_amplification(
+        &mut self,
+        future_amplification: f64,
+        end_time: NaiveDateTime,
+        now: NaiveDateTime,
+    ) -> Result<(), StablePoolError> {
+        let ramp = AmplificationRamp::new(
+            self.amplification_at(now),
+            future_amplification,
+            now,
+            end_time,
+        )?;
+        self.amplification_ramp = Some(ramp);
+        self.config.amplification = future_amplification;
+        Ok(())
+    }
+
+    /// Freeze the amplification at its current value
+    pub fn stop_amplification_ramp(&mut self, now: NaiveDateTime) {
+        self.config.amplification = self.amplification_at(now);
+        self.amplification_ramp = None;
+    }
+
+    fn balances(&self) -> [f64; 2] {
+        [self.total_token_a, self.total_token_b]
+    }
+
+    /// Every swap and liquidity change goes through here, so it also feeds the oracle
+    fn set_balances(&mut self, balances: [f64; 2]) {
+        self.total_token_a = balances[0];
+        self.total_token_b = balances[1];
+        self.k_constant = self.total_token_a * self.total_token_b;
+        if let Some(price) = self.spot_price() {
+            let _ = self.oracle.record(Utc::now().naive_utc(), price);
+        }
+    }
+
+    fn token_index(&self, token: &str) -> Result<usize, StablePoolError> {
+        if token == self.config.token_a {
+            Ok(0)
+        } else if token == self.config.token_b {
+            Ok(1)
+        } else {
+            Err(StablePoolError::InvalidAmount)
+        }
+    }
+
+    fn invariant_of(&self, balances: &[f64]) -> Result<f64, StablePoolError> {
+        stableswap_d(balances, self.current_amplification())
+            .ok_or(StablePoolError::InsufficientLiquidity)
+    }
+
+    /// Current value of the StableSwap invariant D
+    pub fn invariant(&self) -> Result<f64, StablePoolError> {
+        self.invariant_of(&self.balances())
+    }
+
+    /// Marginal price of token_a in units of token_b at the current balances,
+    /// `∂D/∂x_a ÷ ∂D/∂x_b = x_b·(Ann·x_a + D_P) / (x_a·(Ann·x_b + D_P))`
+    /// with `D_P = Dⁿ⁺¹ / (nⁿ·Πx)`
+    pub fn spot_price(&self) -> Option<f64> {
+        let [x_a, x_b] = self.balances();
+        if x_a <= 0.0 || x_b <= 0.0 {
+            return None;
+        }
+        let amplification = self.current_amplification();
+        let d = stableswap_d(&[x_a, x_b], amplification)?;
+        let ann = amplification * 4.0;
+        let d_p = d * d * d / (4.0 * x_a * x_b);
+        Some(x_b * (ann * x_a + d_p) / (x_a * (ann * x_b + d_p)))
+    }
+
+    /// Time-weighted average and geometric-mean prices of token_a over the last `window`
+    pub fn consult(&self, window: chrono::Duration) -> Result<OracleQuote, OracleError> {
+        self.oracle.consult(window, Utc::now().naive_utc())
+    }
+
+    /// Value of one LP share in pool units; grows as fees accrue
+    pub fn virtual_price(&self) -> Result<f64, StablePoolError> {
+        if self.total_liquidity == 0.0 {
+            return Err(StablePoolError::InsufficientLiquidity);
+        }
+        Ok(self.invariant()? / self.total_liquidity)
+    }
+
+    /// Fees charged on the part of a deposit or withdrawal that deviates from the pool's
+    /// current proportions, moving it from `old` to `new` balances
+    fn imbalance_fees(&self, old: [f64; 2], new: [f64; 2], d0: f64, d1: f64) -> [f64; 2] {
+        let n = old.len() as f64;
+        let fee = self.config.fee_tier * n / (4.0 * (n - 1.0));
+        let mut fees = [0.0; 2];
+        for i in 0..old.len() {
+            let ideal = d1 * old[i] / d0;
+            fees[i] = fee * (ideal - new[i]).abs();
+        }
+        fees
+    }
+
+    fn ensure_unlocked(position: &StableLiquidityPosition) -> Result<(), StablePoolError> {
+        let now = Utc::now().naive_utc();
+        let lock_end = position.start_time + chrono::Duration::days(position.duration_days.max(0));
+        if now < lock_end {
+            return Err(StablePoolError::LiquidityLocked);
+        }
+        Ok(())
+    }
+
+    /// Reduce a position by `shares`, scaling its recorded deposits accordingly
+    fn burn_position(&mut self, user_id: &str, shares: f64) {
+        if let Some(position) = self.positions.get_mut(user_id) {
+            let remaining = (position.liquidity_amount - shares).max(0.0);
+            let ratio = remaining / position.liquidity_amount;
+            position.token_a_amount *= ratio;
+            position.token_b_amount *= ratio;
+            position.liquidity_amount = remaining;
+            if remaining <= 0.0 {
+                self.positions.remove(user_id);
+            }
+        }
+    }
+
+    /// Deposit any mix of the two tokens. The first deposit must seed both sides and
+    /// mints D shares; later deposits mint in proportion to the growth of D, after an
+    /// imbalance fee on the part that deviates from the pool's proportions.
     pub fn add_liquidity(
         &mut self,
         user_id: String,
@@ -112,21 +397,36 @@ impl StableLiquidityPool {
         token_b_amount: f64,
         duration_days: i64,
     ) -> Result<f64, StablePoolError> {
-        if token_a_amount <= 0.0 || token_b_amount <= 0.0 {
+        if !(token_a_amount >= 0.0 && token_b_amount >= 0.0)
+            || token_a_amount + token_b_amount <= 0.0
+        {
             return Err(StablePoolError::InvalidAmount);
         }
         if duration_days <= 0 {
             return Err(StablePoolError::InvalidDuration);
         }
 
-        let liquidity_amount = (token_a_amount * token_b_amount).sqrt();
-
-        if self.total_liquidity == 0.0 {
-            self.k_constant = token_a_amount * token_b_amount;
+        let old = self.balances();
+        let new = [old[0] + token_a_amount, old[1] + token_b_amount];
+        let (liquidity_amount, fee) = if self.total_liquidity == 0.0 {
+            if token_a_amount <= 0.0 || token_b_amount <= 0.0 {
+                return Err(StablePoolError::InvalidAmount);
+            }
+            (self.invariant_of(&new)?, 0.0)
+        } else {
+            let d0 = self.invariant_of(&old)?;
+            let d1 = self.invariant_of(&new)?;
+            let fees = self.imbalance_fees(old, new, d0, d1);
+            let d2 = self.invariant_of(&[new[0] - fees[0], new[1] - fees[1]])?;
+            (self.total_liquidity * (d2 - d0) / d0, fees.iter().sum())
+        };
+        if liquidity_amount <= 0.0 {
+            return Err(StablePoolError::InvalidAmount);
         }
 
-        self.total_token_a += token_a_amount;
-        self.total_token_b += token_b_amount;
+        // Fees stay in the pool and accrue to existing LPs
+        self.set_balances(new);
+        self.total_fees += fee;
         self.total_liquidity += liquidity_amount;
 
         let start_time = Utc::now().naive_utc();
@@ -158,30 +458,139 @@ impl StableLiquidityPool {
             .clone();
 
         // Enforce time-based liquidity locking based on the position's duration
-        let now = Utc::now().naive_utc();
-        let lock_end = position.start_time + chrono::Duration::days(position.duration_days.max(0));
-        if now < lock_end {
-            return Err(StablePoolError::LiquidityLocked);
-        }
+        Self::ensure_unlocked(&position)?;
 
         let liquidity_ratio = position.liquidity_amount / self.total_liquidity;
         let token_a_return = self.total_token_a * liquidity_ratio;
         let token_b_return = self.total_token_b * liquidity_ratio;
 
-        self.total_token_a -= token_a_return;
-        self.total_token_b -= token_b_return;
+        self.set_balances([
+            self.total_token_a - token_a_return,
+            self.total_token_b - token_b_return,
+        ]);
         self.total_liquidity -= position.liquidity_amount;
 
-        if self.total_liquidity > 0.0 {
-            self.k_constant = self.total_token_a * self.total_token_b;
-        } else {
-            self.k_constant = 0.0;
-        }
-
         self.positions.remove(user_id);
         Ok((token_a_return, token_b_return))
     }
 
+    /// Withdraw exact token amounts, burning as many shares as the resulting drop in D
+    /// requires (including the imbalance fee). Fails if more than `max_burn` shares or
+    /// more than the position holds would be burned. Returns the shares burned.
+    pub fn remove_liquidity_imbalance(
+        &mut self,
+        user_id: &str,
+        token_a_amount: f64,
+        token_b_amount: f64,
+        max_burn: f64,
+    ) -> Result<f64, StablePoolError> {
+        let position = self
+            .positions
+            .get(user_id)
+            .ok_or(StablePoolError::UserNotInPool)?;
+        Self::ensure_unlocked(position)?;
+        let held = position.liquidity_amount;
+        if !(token_a_amount >= 0.0 && token_b_amount >= 0.0)
+            || token_a_amount + token_b_amount <= 0.0
+        {
+            return Err(StablePoolError::InvalidAmount);
+        }
+
+        let old = self.balances();
+        let new = [old[0] - token_a_amount, old[1] - token_b_amount];
+        if new[0] <= 0.0 || new[1] <= 0.0 {
+            return Err(StablePoolError::InsufficientLiquidity);
+        }
+        let d0 = self.invariant_of(&old)?;
+        let d1 = self.invariant_of(&new)?;
+        let fees = self.imbalance_fees(old, new, d0, d1);
+        let d2 = self.invariant_of(&[new[0] - fees[0], new[1] - fees[1]])?;
+        let burn = self.total_liquidity * (d0 - d2) / d0;
+        if burn > held {
+            return Err(StablePoolError::InsufficientShares);
+        }
+        if burn > max_burn {
+            return Err(StablePoolError::SlippageExceeded);
+        }
+
+        self.set_balances(new);
+        self.total_liquidity -= burn;
+        self.total_fees += fees.iter().sum::<f64>();
+        self.burn_position(user_id, burn);
+        Ok(burn)
+    }
+
+    /// Amount of `token` received for burning `shares` in a single-sided withdrawal, and
+    /// the fee retained by the pool
+    pub fn calculate_withdraw_one_token(
+        &self,
+        shares: f64,
+        token: &str,
+    ) -> Result<(f64, f64), StablePoolError> {
+        if shares <= 0.0 || shares > self.total_liquidity {
+            return Err(StablePoolError::InvalidAmount);
+        }
+        let i = self.token_index(token)?;
+        let amplification = self.current_amplification();
+        let xp = self.balances();
+        let d0 = self.invariant_of(&xp)?;
+        let d1 = d0 - shares * d0 / self.total_liquidity;
+        let new_y = stableswap_y(&xp, i, d1, amplification)
+            .ok_or(StablePoolError::InsufficientLiquidity)?;
+
+        // Withdrawing one side is an imbalanced withdrawal of the whole share
+        let n = xp.len() as f64;
+        let fee = self.config.fee_tier * n / (4.0 * (n - 1.0));
+        let mut reduced = xp;
+        for (j, x) in reduced.iter_mut().enumerate() {
+            let expected = if j == i {
+                xp[j] * d1 / d0 - new_y
+            } else {
+                xp[j] - xp[j] * d1 / d0
+            };
+            *x -= fee * expected;
+        }
+        let y = stableswap_y(&reduced, i, d1, amplification)
+            .ok_or(StablePoolError::InsufficientLiquidity)?;
+        let amount = reduced[i] - y;
+        let without_fee = xp[i] - new_y;
+        if amount <= 0.0 || amount > xp[i] {
+            return Err(StablePoolError::InsufficientLiquidity);
+        }
+        Ok((amount, without_fee - amount))
+    }
+
+    /// Burn `shares` for a single token. Fails if less than `min_amount` would be received.
+    pub fn remove_liquidity_one_token(
+        &mut self,
+        user_id: &str,
+        shares: f64,
+        token: &str,
+        min_amount: f64,
+    ) -> Result<f64, StablePoolError> {
+        let position = self
+            .positions
+            .get(user_id)
+            .ok_or(StablePoolError::UserNotInPool)?;
+        Self::ensure_unlocked(position)?;
+        if shares > position.liquidity_amount {
+            return Err(StablePoolError::InsufficientShares);
+        }
+
+        let (amount, fee) = self.calculate_withdraw_one_token(shares, token)?;
+        if amount < min_amount {
+            return Err(StablePoolError::SlippageExceeded);
+        }
+
+        let mut balances = self.balances();
+        balances[self.token_index(token)?] -= amount;
+        self.set_balances(balances);
+        self.total_liquidity -= shares;
+        self.total_fees += fee;
+        self.burn_position(user_id, shares);
+        Ok(amount)
+    }
+
     pub fn get_reserves(&self) -> (f64, f64) {
         (self.total_token_a, self.total_token_b)
     }
@@ -190,53 +599,90 @@ impl StableLiquidityPool {
         &self.config
     }
 
-    /// Stable swap approximation: output = dx * y / (A*x + dx), with fee applied to dx
+    /// Price swaps with `policy` instead of the fixed fee tier. Imbalance fees on
+    /// liquidity changes keep using the fee tier. Only built-in policies can be saved
+    /// with the pool.
+    pub fn set_fee_policy(&mut self, policy: Box<dyn FeePolicy>) {
+        self.fee_policy = Some(policy);
+    }
+
+    /// Go back to charging the fixed fee tier
+    pub fn clear_fee_policy(&mut self) -> Option<Box<dyn FeePolicy>> {
+        self.fee_policy.take()
+    }
+
+    /// Fee rate charged on a swap of `input_amount` of `input_token`
+    pub fn swap_fee(&self, input_token: &str, input_amount: f64) -> f64 {
+        let Some(policy) = &self.fee_policy else {
+            return self.config.fee_tier;
+        };
+        let input_reserve = if input_token == self.config.token_a {
+            self.total_token_a
+        } else {
+            self.total_token_b
+        };
+        policy.fee_rate(&FeeContext {
+            base_fee: self.config.fee_tier,
+            input_amount,
+            input_reserve,
+            oracle: &self.oracle,
+            now: Utc::now().naive_utc(),
+        })
+    }
+
+    /// StableSwap output: the input (net of fee) is added to its balance and the
+    /// invariant is solved for the new output balance
     pub fn calculate_swap_output(
         &self,
         input_token: &str,
         input_amount: f64,
     ) -> Result<f64, StablePoolError> {
+        self.quote_swap(input_token, input_amount)
+            .map(|(out, _)| out)
+    }
+
+    /// Swap output and the fee rate it was priced with
+    fn quote_swap(
+        &self,
+        input_token: &str,
+        input_amount: f64,
+    ) -> Result<(f64, f64), StablePoolError> {
         if input_amount <= 0.0 {
             return Err(StablePoolError::InvalidAmount);
         }
 
-        let (input_reserve, output_reserve) = if input_token == self.config.token_a {
-            (self.total_token_a, self.total_token_b)
-        } else if input_token == self.config.token_b {
-            (self.total_token_b, self.total_token_a)
-        } else {
-            return Err(StablePoolError::InvalidAmount);
-        };
-
-        if input_reserve == 0.0 || output_reserve == 0.0 {
+        let i = self.token_index(input_token)?;
+        let j = 1 - i;
+        let xp = self.balances();
+        if xp[i] == 0.0 || xp[j] == 0.0 {
             return Err(StablePoolError::InsufficientLiquidity);
         }
 
-        let dx = input_amount * (1.0 - self.config.fee_tier);
-        let a = self.config.amplification;
-        let out = (dx * output_reserve) / (a * input_reserve + dx);
-        if out <= 0.0 || out > output_reserve {
+        let amplification = self.current_amplification();
+        let d = stableswap_d(&xp, amplification).ok_or(StablePoolError::InsufficientLiquidity)?;
+        let fee_rate = self.swap_fee(input_token, input_amount);
+        let mut new = xp;
+        new[i] += input_amount * (1.0 - fee_rate);
+        let y = stableswap_y(&new, j, d, amplification)
+            .ok_or(StablePoolError::InsufficientLiquidity)?;
+        let out = xp[j] - y;
+        if out <= 0.0 || out > xp[j] {
             return Err(StablePoolError::InsufficientLiquidity);
         }
-        Ok(out)
+        Ok((out, fee_rate))
     }
 
     pub fn swap(&mut self, input_token: &str, input_amount: f64) -> Result<f64, StablePoolError> {
-        let output_amount = self.calculate_swap_output(input_token, input_amount)?;
-
-        if input_token == self.config.token_a {
-            self.total_token_a += input_amount;
-            self.total_token_b -= output_amount;
-        } else if input_token == self.config.token_b {
-            self.total_token_b += input_amount;
-            self.total_token_a -= output_amount;
-        } else {
-            return Err(StablePoolError::InvalidAmount);
-        }
+        let (output_amount, fee_rate) = self.quote_swap(input_token, input_amount)?;
+
+        let i = self.token_index(input_token)?;
+        let mut balances = self.balances();
+        balances[i] += input_amount;
+        balances[1 - i] -= output_amount;
+        self.set_balances(balances);
 
-        self.k_constant = self.total_token_a * self.total_token_b;
         self.total_volume += input_amount;
-        self.total_fees += input_amount * self.config.fee_tier;
+        self.total_fees += input_amount * fee_rate;
         Ok(output_amount)
     }
 }
@@ -1,7 +1,32 @@
 #[cfg(test)]
 mod tests {
     use super::super::liquidity_pool::LiquidityPool;
-    use super::super::stable_liquidity_pool::StableLiquidityPool;
+    use super::super::stable_liquidity_pool::{
+        stableswap_d, StableLiquidityPool, StablePoolError, MIN_RAMP_SECS,
+    };
+    use chrono::{Duration, Utc};
+
+    fn stable_pool(amplification: f64, reserve_a: f64, reserve_b: f64) -> StableLiquidityPool {
+        let mut sp = StableLiquidityPool::new(
+            "pcoin_usdc_stable".to_string(),
+            "P-COIN".to_string(),
+            "USDC".to_string(),
+            0.0005,
+            amplification,
+            "REWARD".to_string(),
+            100000.0,
+            0.10,
+        )
+        .unwrap();
+        sp.add_liquidity("lp1".to_string(), reserve_a, reserve_b, 30)
+            .unwrap();
+        sp
+    }
+
+    fn unlock(sp: &mut StableLiquidityPool, user: &str) {
+        let pos = sp.positions.get_mut(user).unwrap();
+        pos.start_time = pos.start_time - Duration::days(pos.duration_days + 1);
+    }
 
     #[test]
     fn test_create_stable_pool() {
@@ -99,4 +124,163 @@ mod tests {
         let ok = sp.remove_liquidity("lp1");
         assert!(ok.is_ok());
     }
+
+    #[test]
+    fn test_invariant_matches_balanced_sum() {
+        // For balanced reserves D equals the sum of balances at any amplification
+        for amplification in [1.0, 10.0, 100.0, 5000.0] {
+            let d = stableswap_d(&[250_000.0, 250_000.0], amplification).unwrap();
+            assert!((d - 500_000.0).abs() < 1e-6);
+        }
+        let d = stableswap_d(&[900_000.0, 100_000.0], 100.0).unwrap();
+        assert!(d < 1_000_000.0 && d > 990_000.0);
+        assert!(stableswap_d(&[1.0, 0.0], 100.0).is_none());
+    }
+
+    #[test]
+    fn test_balanced_swap_is_near_one_to_one() {
+        let sp = stable_pool(100.0, 1_000_000.0, 1_000_000.0);
+        let out = sp.calculate_swap_output("P-COIN", 10_000.0).unwrap();
+        assert!(out < 10_000.0 * (1.0 - 0.0005));
+        assert!(out > 9_994.0);
+
+        // Higher amplification flattens the curve further
+        let flat = stable_pool(1000.0, 1_000_000.0, 1_000_000.0)
+            .calculate_swap_output("P-COIN", 200_000.0)
+            .unwrap();
+        let curved = stable_pool(10.0, 1_000_000.0, 1_000_000.0)
+            .calculate_swap_output("P-COIN", 200_000.0)
+            .unwrap();
+        assert!(flat > curved);
+
+        // The invariant never decreases across a swap since the fee stays in the pool
+        let mut sp = stable_pool(100.0, 1_000_000.0, 1_000_000.0);
+        let before = sp.invariant().unwrap();
+        sp.swap("USDC", 300_000.0).unwrap();
+        assert!(sp.invariant().unwrap() >= before);
+        assert!(sp.virtual_price().unwrap() > 1.0);
+    }
+
+    #[test]
+    fn test_amplification_ramp() {
+        let mut sp = stable_pool(100.0, 100_000.0, 100_000.0);
+        let now = Utc::now().naive_utc();
+        let end = now + Duration::days(2);
+
+        assert_eq!(
+            sp.ramp_amplification(200.0, now + Duration::seconds(MIN_RAMP_SECS - 1), now)
+                .unwrap_err(),
+            StablePoolError::InvalidRamp
+        );
+        assert_eq!(
+            sp.ramp_amplification(1001.0, end, now).unwrap_err(),
+            StablePoolError::InvalidAmplification
+        );
+
+        sp.ramp_amplification(200.0, end, now).unwrap();
+        assert!((sp.amplification_at(now) - 100.0).abs() < 1e-9);
+        assert!((sp.amplification_at(now + Duration::days(1)) - 150.0).abs() < 1e-9);
+        assert_eq!(sp.amplification_at(end + Duration::days(1)), 200.0);
+
+        sp.stop_amplification_ramp(now + Duration::days(1));
+        assert!((sp.current_amplification() - 150.0).abs() < 1e-9);
+        assert!(sp.amplification_ramp.is_none());
+    }
+
+    #[test]
+    fn test_imbalanced_deposit_pays_fee() {
+        let mut sp = stable_pool(100.0, 500_000.0, 500_000.0);
+        assert!((sp.total_liquidity - 1_000_000.0).abs() < 1e-6);
+
+        let balanced = sp
+            .clone()
+            .add_liquidity("lp2".to_string(), 50_000.0, 50_000.0, 30)
+            .unwrap();
+        assert!((balanced - 100_000.0).abs() < 1e-6);
+
+        let one_sided = sp
+            .add_liquidity("lp2".to_string(), 100_000.0, 0.0, 30)
+            .unwrap();
+        assert!(one_sided < balanced);
+        assert!(one_sided > balanced * 0.99);
+        assert!(sp.total_fees > 0.0);
+
+        // The first deposit has to seed both sides
+        let mut empty = stable_pool(100.0, 1.0, 1.0);
+        empty.total_liquidity = 0.0;
+        empty.total_token_a = 0.0;
+        empty.total_token_b = 0.0;
+        assert_eq!(
+            empty
+                .add_liquidity("lp".to_string(), 10.0, 0.0, 30)
+                .unwrap_err(),
+            StablePoolError::InvalidAmount
+        );
+    }
+
+    #[test]
+    fn test_imbalanced_withdrawal() {
+        let mut sp = stable_pool(100.0, 500_000.0, 500_000.0);
+        assert_eq!(
+            sp.remove_liquidity_imbalance("lp1", 10_000.0, 0.0, f64::MAX)
+                .unwrap_err(),
+            StablePoolError::LiquidityLocked
+        );
+        unlock(&mut sp, "lp1");
+
+        assert_eq!(
+            sp.remove_liquidity_imbalance("lp1", 10_000.0, 0.0, 10_000.0)
+                .unwrap_err(),
+            StablePoolError::SlippageExceeded
+        );
+        let burned = sp
+            .remove_liquidity_imbalance("lp1", 10_000.0, 0.0, 10_100.0)
+            .unwrap();
+        assert!(burned > 10_000.0);
+        assert_eq!(sp.get_reserves(), (490_000.0, 500_000.0));
+        let position = &sp.positions["lp1"];
+        assert!((position.liquidity_amount - (1_000_000.0 - burned)).abs() < 1e-6);
+        assert!((sp.total_liquidity - position.liquidity_amount).abs() < 1e-6);
+
+        assert_eq!(
+            sp.remove_liquidity_imbalance("lp1", 490_000.0, 10.0, f64::MAX)
+                .unwrap_err(),
+            StablePoolError::InsufficientLiquidity
+        );
+    }
+
+    #[test]
+    fn test_single_sided_withdrawal() {
+        let mut sp = stable_pool(100.0, 500_000.0, 500_000.0);
+        unlock(&mut sp, "lp1");
+
+        let (amount, fee) = sp.calculate_withdraw_one_token(100_000.0, "USDC").unwrap();
+        assert!(fee > 0.0);
+        assert!(amount < 100_000.0 && amount > 99_000.0);
+
+        assert_eq!(
+            sp.remove_liquidity_one_token("lp1", 100_000.0, "USDC", amount + 1.0)
+                .unwrap_err(),
+            StablePoolError::SlippageExceeded
+        );
+        let received = sp
+            .remove_liquidity_one_token("lp1", 100_000.0, "USDC", amount)
+            .unwrap();
+        assert!((received - amount).abs() < 1e-9);
+        let (ra, rb) = sp.get_reserves();
+        assert_eq!(ra, 500_000.0);
+        assert!((rb - (500_000.0 - amount)).abs() < 1e-6);
+        assert!((sp.positions["lp1"].liquidity_amount - 900_000.0).abs() < 1e-6);
+
+        assert_eq!(
+            sp.remove_liquidity_one_token("lp1", 2_000_000.0, "USDC", 0.0)
+                .unwrap_err(),
+            StablePoolError::InsufficientShares
+        );
+        assert_eq!(
+            sp.remove_liquidity_one_token("lp1", 1.0, "ETH", 0.0)
+                .unwrap_err(),
+            StablePoolError::InvalidAmount
+        );
+    }
 }
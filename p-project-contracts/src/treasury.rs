@@ -1,3 +1,6 @@
+use crate::order_book::{OrderFill, OrderKeeper, TwapSchedule};
+use crate::price_oracle::{OracleError, PriceOracle};
+use crate::router::Router;
 use crate::token::PProjectToken;
 use chrono::{NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -19,6 +22,8 @@ pub enum TreasuryError {
     DatabaseError(String),
     SerializationError(String),
     NGOAccountNotFound,
+    RoutingFailed(String),
+    BuybackOrderNotFound,
 }
 
 impl std::fmt::Display for TreasuryError {
@@ -54,6 +59,8 @@ impl std::fmt::Display for TreasuryError {
             TreasuryError::DatabaseError(msg) => write!(f, "Database error: {}", msg),
             TreasuryError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             TreasuryError::NGOAccountNotFound => write!(f, "NGO treasury account not found"),
+            TreasuryError::RoutingFailed(msg) => write!(f, "Buyback routing failed: {}", msg),
+            TreasuryError::BuybackOrderNotFound => write!(f, "Buyback order not found"),
         }
     }
 }
@@ -201,6 +208,9 @@ pub struct BuybackRecord {
     pub price_per_token: f64,
 }
 
+// Owner of the keeper orders placed by the treasury
+pub const TREASURY_ORDER_OWNER: &str = "treasury";
+
 // Structure for scheduled buybacks
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuybackSchedule {
@@ -228,6 +238,28 @@ pub struct MarketSnapshot {
     pub volume_change_percentage: f64,
 }
 
+impl MarketSnapshot {
+    /// Snapshot priced from a pool oracle rather than the spot price: `price` is the
+    /// TWAP over the `window` ending at `now`, and the price change compares it with
+    /// the TWAP of the window before, so a single trade cannot fire a trigger.
+    pub fn from_oracle(
+        oracle: &PriceOracle,
+        window: chrono::Duration,
+        now: NaiveDateTime,
+        volume: f64,
+        volume_change_percentage: f64,
+    ) -> Result<Self, OracleError> {
+        let current = oracle.consult(window, now)?;
+        let previous = oracle.consult(window, now - window)?;
+        Ok(Self {
+            price: current.twap,
+            volume,
+            price_change_percentage: (current.twap / previous.twap - 1.0) * 100.0,
+            volume_change_percentage,
+        })
+    }
+}
+
 // Structure for trigger-based buybacks
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuybackTrigger {
@@ -254,6 +286,7 @@ pub struct Treasury {
     scheduled_buybacks: Vec<BuybackSchedule>, // Scheduled buyback programs
     auto_buyback_enabled: bool,               // Whether auto buybacks are enabled
     buyback_triggers: Vec<BuybackTrigger>,    // Trigger-based buyback programs
+    twap_buyback_orders: Vec<u64>,            // Keeper orders buying back over time
     // Funding controls
     presale_program: PresaleProgram,
     reserve_allocation: ReserveAllocation,
@@ -315,6 +348,7 @@ impl Treasury {
             scheduled_buybacks: Vec::new(),
             auto_buyback_enabled: false,
             buyback_triggers: Vec::new(),
+            twap_buyback_orders: Vec::new(),
             presale_program: PresaleProgram::new(),
             reserve_allocation: ReserveAllocation::new(),
             development_fund: DevelopmentFund::new(),
@@ -643,6 +677,132 @@ impl Treasury {
         Ok(tokens_to_buy)
     }
 
+    /// Execute a buyback against on-chain liquidity: `amount_to_spend` of `spend_asset` is
+    /// split across every router pool pairing it with `token_symbol` so large orders move
+    /// the price as little as possible. Fails without spending anything if fewer than
+    /// `min_tokens_out` tokens would be received. Bought tokens are burned.
+    pub fn execute_buyback_via_router(
+        &mut self,
+        token: &mut PProjectToken,
+        router: &mut Router,
+        spend_asset: &str,
+        token_symbol: &str,
+        amount_to_spend: f64,
+        min_tokens_out: f64,
+    ) -> Result<f64, TreasuryError> {
+        if amount_to_spend <= 0.0 {
+            return Err(TreasuryError::InvalidAmount);
+        }
+
+        if amount_to_spend > self.get_balance(spend_asset) {
+            return Err(TreasuryError::InsufficientFunds);
+        }
+
+        let fill = router
+            .swap_split(spend_asset, token_symbol, amount_to_spend, min_tokens_out)
+            .map_err(|e| TreasuryError::RoutingFailed(format!("{:?}", e)))?;
+        let tokens_bought = fill.output_amount;
+
+        self.buyback_records.push(BuybackRecord {
+            timestamp: Utc::now().naive_utc(),
+            amount_spent: amount_to_spend,
+            tokens_bought,
+            price_per_token: amount_to_spend / tokens_bought,
+        });
+        self.total_buybacks += amount_to_spend;
+
+        let current_balance = self.get_balance(spend_asset);
+        self.reserves
+            .insert(spend_asset.to_string(), current_balance - amount_to_spend);
+
+        // The swap has already settled, so a failed burn is reported but not undone
+        if let Err(e) = token.burn_tokens(tokens_bought) {
+            println!("Warning: Failed to burn tokens: {}", e);
+        }
+
+        Ok(tokens_bought)
+    }
+
+    /// Place a buyback of `token_symbol` with `amount_to_spend` of `spend_asset` as a TWAP
+    /// order on the keeper, spread over the schedule's slices. The whole amount is set
+    /// aside from reserves now; pass the keeper's fills to `settle_twap_buybacks`.
+    pub fn schedule_twap_buyback(
+        &mut self,
+        keeper: &mut OrderKeeper,
+        spend_asset: &str,
+        token_symbol: &str,
+        amount_to_spend: f64,
+        schedule: TwapSchedule,
+    ) -> Result<u64, TreasuryError> {
+        if amount_to_spend <= 0.0 {
+            return Err(TreasuryError::InvalidAmount);
+        }
+        if amount_to_spend > self.get_balance(spend_asset) {
+            return Err(TreasuryError::InsufficientFunds);
+        }
+
+        let order_id = keeper
+            .place_twap_order(
+                TREASURY_ORDER_OWNER,
+                spend_asset,
+                token_symbol,
+                amount_to_spend,
+                schedule,
+            )
+            .map_err(|e| TreasuryError::RoutingFailed(e.to_string()))?;
+        let current_balance = self.get_balance(spend_asset);
+        self.reserves
+            .insert(spend_asset.to_string(), current_balance - amount_to_spend);
+        self.twap_buyback_orders.push(order_id);
+        Ok(order_id)
+    }
+
+    /// Record and burn the tokens bought by this treasury's TWAP buyback orders.
+    /// Fills of other orders are ignored. Returns the tokens bought.
+    pub fn settle_twap_buybacks(&mut self, token: &mut PProjectToken, fills: &[OrderFill]) -> f64 {
+        let mut total_tokens_bought = 0.0;
+        for fill in fills {
+            if !self.twap_buyback_orders.contains(&fill.order_id) {
+                continue;
+            }
+            self.buyback_records.push(BuybackRecord {
+                timestamp: fill.timestamp,
+                amount_spent: fill.input_amount,
+                tokens_bought: fill.output_amount,
+                price_per_token: fill.input_amount / fill.output_amount,
+            });
+            self.total_buybacks += fill.input_amount;
+            total_tokens_bought += fill.output_amount;
+
+            // The swap has already settled, so a failed burn is reported but not undone
+            if let Err(e) = token.burn_tokens(fill.output_amount) {
+                println!("Warning: Failed to burn tokens: {}", e);
+            }
+        }
+        total_tokens_bought
+    }
+
+    /// Cancel a TWAP buyback and return its unspent funds to reserves
+    pub fn cancel_twap_buyback(
+        &mut self,
+        keeper: &mut OrderKeeper,
+        order_id: u64,
+    ) -> Result<f64, TreasuryError> {
+        if !self.twap_buyback_orders.contains(&order_id) {
+            return Err(TreasuryError::BuybackOrderNotFound);
+        }
+        let refund = keeper
+            .cancel_order(TREASURY_ORDER_OWNER, order_id)
+            .map_err(|e| TreasuryError::RoutingFailed(e.to_string()))?;
+        let spend_asset = keeper
+            .get_order(order_id)
+            .map(|o| o.input_token.clone())
+            .unwrap_or_default();
+        let current_balance = self.get_balance(&spend_asset);
+        self.reserves.insert(spend_asset, current_balance + refund);
+        Ok(refund)
+    }
+
     /// Add a scheduled buyback
     pub fn add_scheduled_buyback(
         &mut self,
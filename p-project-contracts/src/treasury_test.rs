@@ -1,5 +1,7 @@
+use super::liquidity_pool::LiquidityPool;
+use super::router::Router;
 use super::token::PProjectToken;
-use super::treasury::{LiquidityMiningProgram, Treasury};
+use super::treasury::{LiquidityMiningProgram, Treasury, TreasuryError};
 
 #[test]
 fn test_treasury_creation() {
@@ -50,6 +52,58 @@ fn test_treasury_buyback() {
     assert_eq!(treasury.get_total_buybacks(), 100000.0);
 }
 
+#[test]
+fn test_treasury_buyback_via_router_splits_across_pools() {
+    let mut treasury = Treasury::new();
+    let mut token = PProjectToken::new(350000000.0, 0.01, 0.005);
+    treasury.add_funds("USDC".to_string(), 1000000.0).unwrap();
+
+    let mut router = Router::new();
+    for (id, usdc, p) in [
+        ("deep", 500000.0, 5000000.0),
+        ("shallow", 200000.0, 2000000.0),
+    ] {
+        let mut pool = LiquidityPool::new(
+            id.to_string(),
+            "USDC".to_string(),
+            "P".to_string(),
+            0.003,
+            "RWD".to_string(),
+            100000.0,
+            0.10,
+        );
+        pool.add_liquidity("lp".to_string(), usdc, p, 30).unwrap();
+        router.register_constant_pool(id.to_string(), pool);
+    }
+    let single_pool = router
+        .get_constant_pool("deep")
+        .unwrap()
+        .calculate_swap_output("USDC", 100000.0)
+        .unwrap();
+    let supply = token.get_total_supply();
+
+    let too_greedy = treasury.execute_buyback_via_router(
+        &mut token,
+        &mut router,
+        "USDC",
+        "P",
+        100000.0,
+        1000000.0,
+    );
+    assert!(matches!(too_greedy, Err(TreasuryError::RoutingFailed(_))));
+    assert_eq!(treasury.get_balance("USDC"), 1000000.0);
+
+    let bought = treasury
+        .execute_buyback_via_router(&mut token, &mut router, "USDC", "P", 100000.0, 0.0)
+        .unwrap();
+    assert!(bought > single_pool);
+    assert_eq!(treasury.get_balance("USDC"), 900000.0);
+    assert_eq!(treasury.get_total_buybacks(), 100000.0);
+    assert!((token.get_total_supply() - (supply - bought)).abs() < 1e-6);
+    let (usdc_in_shallow, _) = router.get_constant_pool("shallow").unwrap().get_reserves();
+    assert!(usdc_in_shallow > 200000.0);
+}
+
 #[test]
 fn test_liquidity_mining_program() {
     let mut program = LiquidityMiningProgram::new(
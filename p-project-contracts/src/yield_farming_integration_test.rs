@@ -125,12 +125,12 @@ mod tests {
             assert!(result.is_ok());
         }
 
-        // Step 14: Verify pool is empty
-        assert!(pool.total_liquidity.abs() < 1e-10); // Use epsilon comparison for floating point
-        assert!(pool.total_token_a.abs() < 1e-10);
-        assert!(pool.total_token_b.abs() < 1e-10);
+        // Step 14: Verify only the locked minimum liquidity is left
+        assert!((pool.total_liquidity - pool.locked_liquidity).abs() < 1e-10); // Use epsilon comparison for floating point
+        assert!(pool.total_token_a < 0.01);
+        assert!(pool.total_token_b < 0.01);
         assert_eq!(pool.liquidity_positions.len(), 0);
-        assert!(pool.k_constant.abs() < 1e-10);
+        assert!(pool.k_constant < 1e-4);
 
         println!("Complete yield farming workflow test passed!");
     }
@@ -141,14 +141,102 @@ impl MySqlDatabase {
                 dst_tx_hash VARCHAR(255) NULL,
                 status VARCHAR(32) NOT NULL,
                 error_msg TEXT NULL,
+                attempts INT UNSIGNED NOT NULL DEFAULT 0,
+                next_attempt_at TIMESTAMP NULL,
+                refund_tx_hash VARCHAR(255) NULL,
                 created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+                UNIQUE KEY uniq_bridge_lock_id (lock_id),
+                INDEX idx_bridge_status (from_chain, status)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Bring bridge_txs tables created before retries, refunds and lock_id
+        // idempotency up to date. The unique key fails to apply while two rows share a
+        // lock_id; those have to be resolved by hand first.
+        self.add_column_if_missing("bridge_txs", "attempts", "INT UNSIGNED NOT NULL DEFAULT 0")
+            .await?;
+        self.add_column_if_missing("bridge_txs", "next_attempt_at", "TIMESTAMP NULL")
+            .await?;
+        self.add_column_if_missing("bridge_txs", "refund_tx_hash", "VARCHAR(255) NULL")
+            .await?;
+        self.add_index_if_missing(
+            "bridge_txs",
+            "UNIQUE KEY",
+            "uniq_bridge_lock_id",
+            "(lock_id)",
+        )
+        .await?;
+        self.add_index_if_missing(
+            "bridge_txs",
+            "INDEX",
+            "idx_bridge_status",
+            "(from_chain, status)",
+        )
+        .await?;
+
+        // Create bridge_attestations table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS bridge_attestations (
+                lock_id VARCHAR(66) NOT NULL,
+                bridge_tx_id VARCHAR(255) NOT NULL,
+                signer VARCHAR(66) NOT NULL,
+                signature VARCHAR(255) NOT NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (lock_id, signer)
             )
             "#,
         )
         .execute(&self.pool)
         .await?;
 
+        // Create bridge_committees table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS bridge_committees (
+                epoch BIGINT UNSIGNED PRIMARY KEY,
+                members TEXT NOT NULL,
+                threshold INT UNSIGNED NOT NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Create bridge_route_pauses table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS bridge_route_pauses (
+                from_chain VARCHAR(64) NOT NULL,
+                to_chain VARCHAR(64) NOT NULL,
+                reason TEXT NOT NULL,
+                paused_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (from_chain, to_chain)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Single row new bridge txs lock while their volume limits are checked
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS bridge_volume_lock (
+                id TINYINT PRIMARY KEY
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query("INSERT IGNORE INTO bridge_volume_lock (id) VALUES (1)")
+            .execute(&self.pool)
+            .await?;
+
         // Create balances table
         sqlx::query(
             r#"
@@ -380,6 +468,58 @@ impl MySqlDatabase {
         Ok(())
     }
 
+    /// Add `column` to an existing `table` unless it is already there
+    async fn add_column_if_missing(
+        &self,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<(), sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS n FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND COLUMN_NAME = ?",
+        )
+        .bind(table)
+        .bind(column)
+        .fetch_one(&self.pool)
+        .await?;
+        if row.get::<i64, _>("n") == 0 {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Add the `kind` (INDEX, UNIQUE KEY, ...) named `index` over `columns` to an
+    /// existing `table` unless it is already there
+    async fn add_index_if_missing(
+        &self,
+        table: &str,
+        kind: &str,
+        index: &str,
+        columns: &str,
+    ) -> Result<(), sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) AS n FROM information_schema.STATISTICS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND INDEX_NAME = ?",
+        )
+        .bind(table)
+        .bind(index)
+        .fetch_one(&self.pool)
+        .await?;
+        if row.get::<i64, _>("n") == 0 {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD {} {} {}",
+                table, kind, index, columns
+            ))
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
     // Airdrop related database operations
     pub async fn create_airdrop(
         &self,
@@ -1603,81 +1743,365 @@ impl MySqlDatabase {
     }
 
     pub async fn fetch_bridge_tx(&self, id: &str) -> Result<crate::models::BridgeTx, sqlx::Error> {
-        let row = sqlx::query(
-            "SELECT id, user_id, token, from_chain, to_chain, amount, lock_id, src_tx_hash, dst_tx_hash, status, error_msg, created_at, updated_at FROM bridge_txs WHERE id = ?"
-        )
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM bridge_txs WHERE id = ?",
+            BRIDGE_TX_COLUMNS
+        ))
         .bind(id)
         .fetch_one(&self.pool)
         .await?;
+        Ok(bridge_tx_from_row(&row))
+    }
 
-        use crate::models::{BridgeTx, BridgeTxStatus};
+    pub async fn list_locked_without_dst(
+        &self,
+        from_chain: &str,
+    ) -> Result<Vec<crate::models::BridgeTx>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM bridge_txs WHERE status = 'Locked' AND from_chain = ? AND dst_tx_hash IS NULL",
+            BRIDGE_TX_COLUMNS
+        ))
+        .bind(from_chain)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(bridge_tx_from_row).collect())
+    }
 
-        let status_str: String = row.get("status");
-        let status = match status_str.as_str() {
-            "Pending" => BridgeTxStatus::Pending,
-            "Locked" => BridgeTxStatus::Locked,
-            "Minted" => BridgeTxStatus::Minted,
-            "Failed" => BridgeTxStatus::Failed,
-            _ => BridgeTxStatus::Pending,
-        };
+    pub async fn list_bridge_txs_with_status(
+        &self,
+        from_chain: &str,
+        status: &str,
+    ) -> Result<Vec<crate::models::BridgeTx>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM bridge_txs WHERE from_chain = ? AND status = ? ORDER BY created_at",
+            BRIDGE_TX_COLUMNS
+        ))
+        .bind(from_chain)
+        .bind(status)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(bridge_tx_from_row).collect())
+    }
 
-        Ok(BridgeTx {
-            id: row.get("id"),
-            user_id: row.get("user_id"),
-            token: row.get("token"),
-            from_chain: row.get("from_chain"),
-            to_chain: row.get("to_chain"),
-            amount: row.get("amount"),
-            lock_id: row.get("lock_id"),
-            src_tx_hash: row.get("src_tx_hash"),
-            dst_tx_hash: row.get("dst_tx_hash"),
-            status,
-            error_msg: row.get("error_msg"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        })
+    pub async fn list_bridge_txs_with_lock_id(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<crate::models::BridgeTx>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM bridge_txs WHERE lock_id = ?",
+            BRIDGE_TX_COLUMNS
+        ))
+        .bind(lock_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(bridge_tx_from_row).collect())
     }
 
-    pub async fn list_locked_without_dst(
+    pub async fn list_bridge_txs_for_token(
         &self,
-        from_chain: &str,
+        token: &str,
     ) -> Result<Vec<crate::models::BridgeTx>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM bridge_txs WHERE token = ? ORDER BY created_at",
+            BRIDGE_TX_COLUMNS
+        ))
+        .bind(token)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(bridge_tx_from_row).collect())
+    }
+
+    /// Move a bridge tx from `from_status` to `to_status` only if it is still in
+    /// `from_status`. Returns whether this call made the change, so concurrent
+    /// relayers cannot both claim the same transition.
+    pub async fn compare_and_set_bridge_status(
+        &self,
+        id: &str,
+        from_status: &str,
+        to_status: &str,
+        error_msg: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE bridge_txs SET status = ?, error_msg = ? WHERE id = ? AND status = ?",
+        )
+        .bind(to_status)
+        .bind(error_msg)
+        .bind(id)
+        .bind(from_status)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn update_bridge_retry(
+        &self,
+        id: &str,
+        attempts: u32,
+        next_attempt_at: Option<NaiveDateTime>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE bridge_txs SET attempts = ?, next_attempt_at = ? WHERE id = ?")
+            .bind(attempts)
+            .bind(next_attempt_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_bridge_refund_tx(
+        &self,
+        id: &str,
+        refund_tx_hash: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE bridge_txs SET refund_tx_hash = ? WHERE id = ?")
+            .bind(refund_tx_hash)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+const BRIDGE_TX_COLUMNS: &str = "id, user_id, token, from_chain, to_chain, amount, lock_id, src_tx_hash, dst_tx_hash, status, error_msg, attempts, next_attempt_at, refund_tx_hash, created_at, updated_at";
+
+fn bridge_tx_from_row(row: &sqlx::mysql::MySqlRow) -> crate::models::BridgeTx {
+    let status: String = row.get("status");
+    crate::models::BridgeTx {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        token: row.get("token"),
+        from_chain: row.get("from_chain"),
+        to_chain: row.get("to_chain"),
+        amount: row.get("amount"),
+        lock_id: row.get("lock_id"),
+        src_tx_hash: row.get("src_tx_hash"),
+        dst_tx_hash: row.get("dst_tx_hash"),
+        status: status.as_str().into(),
+        error_msg: row.get("error_msg"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        attempts: row.get("attempts"),
+        next_attempt_at: row.get("next_attempt_at"),
+        refund_tx_hash: row.get("refund_tx_hash"),
+    }
+}
+
+// Bridge committee and attestation operations
+impl MySqlDatabase {
+    /// Store a signer's attestation for a lock. Returns false if that signer already
+    /// attested the lock.
+    pub async fn insert_bridge_attestation(
+        &self,
+        attestation: &crate::models::BridgeAttestation,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT IGNORE INTO bridge_attestations (lock_id, bridge_tx_id, signer, signature) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&attestation.lock_id)
+        .bind(&attestation.bridge_tx_id)
+        .bind(&attestation.signer)
+        .bind(&attestation.signature)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn list_bridge_attestations_for_lock(
+        &self,
+        lock_id: &str,
+    ) -> Result<Vec<crate::models::BridgeAttestation>, sqlx::Error> {
         let rows = sqlx::query(
-            r#"SELECT id, user_id, token, from_chain, to_chain, amount, lock_id, src_tx_hash, dst_tx_hash, status, error_msg, created_at, updated_at FROM bridge_txs WHERE status = 'Locked' AND from_chain = ? AND dst_tx_hash IS NULL"#
+            "SELECT lock_id, bridge_tx_id, signer, signature, created_at FROM bridge_attestations WHERE lock_id = ? ORDER BY created_at",
         )
-        .bind(from_chain)
+        .bind(lock_id)
         .fetch_all(&self.pool)
         .await?;
-
-        use crate::models::{BridgeTx, BridgeTxStatus};
-        let mut out = Vec::new();
-        for row in rows {
-            let status_str: String = row.get("status");
-            let status = match status_str.as_str() {
-                "Pending" => BridgeTxStatus::Pending,
-                "Locked" => BridgeTxStatus::Locked,
-                "Minted" => BridgeTxStatus::Minted,
-                "Failed" => BridgeTxStatus::Failed,
-                _ => BridgeTxStatus::Pending,
-            };
-            out.push(BridgeTx {
-                id: row.get("id"),
-                user_id: row.get("user_id"),
-                token: row.get("token"),
-                from_chain: row.get("from_chain"),
-                to_chain: row.get("to_chain"),
-                amount: row.get("amount"),
+        Ok(rows
+            .iter()
+            .map(|row| crate::models::BridgeAttestation {
                 lock_id: row.get("lock_id"),
-                src_tx_hash: row.get("src_tx_hash"),
-                dst_tx_hash: row.get("dst_tx_hash"),
-                status,
-                error_msg: row.get("error_msg"),
+                bridge_tx_id: row.get("bridge_tx_id"),
+                signer: row.get("signer"),
+                signature: row.get("signature"),
                 created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            });
+            })
+            .collect())
+    }
+
+    pub async fn insert_bridge_committee(
+        &self,
+        committee: &crate::models::BridgeCommittee,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO bridge_committees (epoch, members, threshold) VALUES (?, ?, ?)")
+            .bind(committee.epoch)
+            .bind(committee.members.join(","))
+            .bind(committee.threshold)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_latest_bridge_committee(
+        &self,
+    ) -> Result<Option<crate::models::BridgeCommittee>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT epoch, members, threshold, created_at FROM bridge_committees ORDER BY epoch DESC LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|row| {
+            let members: String = row.get("members");
+            crate::models::BridgeCommittee {
+                epoch: row.get("epoch"),
+                members: members
+                    .split(',')
+                    .filter(|m| !m.is_empty())
+                    .map(|m| m.to_string())
+                    .collect(),
+                threshold: row.get("threshold"),
+                created_at: row.get("created_at"),
+            }
+        }))
+    }
+}
+
+// Bridge risk control operations
+impl MySqlDatabase {
+    /// Total amount bridged since `since`, optionally for one user and/or one route.
+    /// Failed and refunded transfers don't count.
+    pub async fn sum_bridge_volume_since(
+        &self,
+        user_id: Option<&str>,
+        route: Option<(&str, &str)>,
+        since: NaiveDateTime,
+    ) -> Result<f64, sqlx::Error> {
+        sum_bridge_volume(&self.pool, user_id, route, since).await
+    }
+
+    /// Insert `tx` unless it would take the volume counted by one of `limits` past the
+    /// limit. Callers take turns on the bridge_volume_lock row, so no other tx is
+    /// recorded between the sums and the insert. Returns the index of the first limit
+    /// exceeded and the volume it had already counted, inserting nothing.
+    pub async fn insert_bridge_tx_within_limits(
+        &self,
+        tx: &crate::models::BridgeTx,
+        limits: &[crate::models::BridgeVolumeLimit],
+    ) -> Result<Option<(usize, f64)>, sqlx::Error> {
+        let mut db_tx = self.pool.begin().await?;
+        sqlx::query("SELECT id FROM bridge_volume_lock WHERE id = 1 FOR UPDATE")
+            .fetch_optional(&mut *db_tx)
+            .await?;
+        // The sums are the transaction's first plain reads, so their snapshot is taken
+        // after the lock and includes the previous holder's insert
+        for (index, limit) in limits.iter().enumerate() {
+            let route = limit
+                .route
+                .as_ref()
+                .map(|(from_chain, to_chain)| (from_chain.as_str(), to_chain.as_str()));
+            let used = sum_bridge_volume(&mut *db_tx, limit.user_id.as_deref(), route, limit.since)
+                .await?;
+            if used + tx.amount > limit.limit {
+                return Ok(Some((index, used)));
+            }
         }
-        Ok(out)
+        sqlx::query(
+            "INSERT INTO bridge_txs (id, user_id, token, from_chain, to_chain, amount, status) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&tx.id)
+        .bind(&tx.user_id)
+        .bind(&tx.token)
+        .bind(&tx.from_chain)
+        .bind(&tx.to_chain)
+        .bind(tx.amount)
+        .bind(tx.status.as_str())
+        .execute(&mut *db_tx)
+        .await?;
+        db_tx.commit().await?;
+        Ok(None)
+    }
+
+    /// Pause a route, replacing the reason if it is already paused
+    pub async fn upsert_bridge_route_pause(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+        reason: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO bridge_route_pauses (from_chain, to_chain, reason) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE reason = VALUES(reason), paused_at = CURRENT_TIMESTAMP",
+        )
+        .bind(from_chain)
+        .bind(to_chain)
+        .bind(reason)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Lift a route pause. Returns false if the route was not paused.
+    pub async fn delete_bridge_route_pause(
+        &self,
+        from_chain: &str,
+        to_chain: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM bridge_route_pauses WHERE from_chain = ? AND to_chain = ?")
+                .bind(from_chain)
+                .bind(to_chain)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn list_bridge_route_pauses(
+        &self,
+    ) -> Result<Vec<crate::models::BridgeRoutePause>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT from_chain, to_chain, reason, paused_at FROM bridge_route_pauses ORDER BY paused_at",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(|row| crate::models::BridgeRoutePause {
+                from_chain: row.get("from_chain"),
+                to_chain: row.get("to_chain"),
+                reason: row.get("reason"),
+                paused_at: row.get("paused_at"),
+            })
+            .collect())
+    }
+}
+
+// Total amount bridged since `since`, optionally for one user and/or one route, read
+// through `executor` so it can run inside a transaction
+async fn sum_bridge_volume<'e, E>(
+    executor: E,
+    user_id: Option<&str>,
+    route: Option<(&str, &str)>,
+    since: NaiveDateTime,
+) -> Result<f64, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::MySql>,
+{
+    let mut sql = String::from(
+        "SELECT CAST(COALESCE(SUM(amount), 0) AS DOUBLE) AS volume FROM bridge_txs WHERE created_at >= ? AND status NOT IN ('Failed', 'Refunded')",
+    );
+    if user_id.is_some() {
+        sql.push_str(" AND user_id = ?");
+    }
+    if route.is_some() {
+        sql.push_str(" AND from_chain = ? AND to_chain = ?");
+    }
+    let mut query = sqlx::query(&sql).bind(since);
+    if let Some(user_id) = user_id {
+        query = query.bind(user_id);
+    }
+    if let Some((from_chain, to_chain)) = route {
+        query = query.bind(from_chain).bind(to_chain);
     }
+    let row = query.fetch_one(executor).await?;
+    Ok(row.get("volume"))
 }
 
 // Token contract state operations
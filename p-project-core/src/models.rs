@@ -129,14 +129,57 @@ pub enum ProposalExecutionType {
     StakingReward,   // Distribute staking rewards
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Lifecycle of a bridge transfer: Pending -> Locked -> Confirmed -> Minting -> Minted.
+// Failed mints are retried from Confirmed until they run out of attempts and land in
+// DeadLetter, from where an operator requeues or refunds them (Refunding -> Refunded).
+// Failed means the source lock itself never happened.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum BridgeTxStatus {
     Pending,
     Locked,
+    Confirmed,
+    Minting,
     Minted,
+    Refunding,
+    Refunded,
+    DeadLetter,
     Failed,
 }
 
+impl BridgeTxStatus {
+    /// Name stored in the `status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BridgeTxStatus::Pending => "Pending",
+            BridgeTxStatus::Locked => "Locked",
+            BridgeTxStatus::Confirmed => "Confirmed",
+            BridgeTxStatus::Minting => "Minting",
+            BridgeTxStatus::Minted => "Minted",
+            BridgeTxStatus::Refunding => "Refunding",
+            BridgeTxStatus::Refunded => "Refunded",
+            BridgeTxStatus::DeadLetter => "DeadLetter",
+            BridgeTxStatus::Failed => "Failed",
+        }
+    }
+}
+
+impl From<&str> for BridgeTxStatus {
+    // Unknown names read back as Pending
+    fn from(status: &str) -> Self {
+        match status {
+            "Locked" => BridgeTxStatus::Locked,
+            "Confirmed" => BridgeTxStatus::Confirmed,
+            "Minting" => BridgeTxStatus::Minting,
+            "Minted" => BridgeTxStatus::Minted,
+            "Refunding" => BridgeTxStatus::Refunding,
+            "Refunded" => BridgeTxStatus::Refunded,
+            "DeadLetter" => BridgeTxStatus::DeadLetter,
+            "Failed" => BridgeTxStatus::Failed,
+            _ => BridgeTxStatus::Pending,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BridgeTx {
     pub id: String,
@@ -152,6 +195,52 @@ pub struct BridgeTx {
     pub error_msg: Option<String>,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    #[serde(default)]
+    pub attempts: u32, // Mint or refund attempts that failed so far
+    #[serde(default)]
+    pub next_attempt_at: Option<chrono::NaiveDateTime>, // Earliest retry after a failure
+    #[serde(default)]
+    pub refund_tx_hash: Option<String>,
+}
+
+// A committee member's signature over a bridge transfer's lock, collected until the
+// committee threshold is met
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeAttestation {
+    pub lock_id: String,
+    pub bridge_tx_id: String,
+    pub signer: String,    // Relayer address
+    pub signature: String, // Hex-encoded signature over the transfer digest
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// Relayers allowed to attest bridge mints; each rotation starts a new epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeCommittee {
+    pub epoch: u64,
+    pub members: Vec<String>,
+    pub threshold: u32, // Signatures needed before a mint
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// A bridge route that is not accepting transfers, paused by an operator or by the
+// circuit breaker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeRoutePause {
+    pub from_chain: String,
+    pub to_chain: String,
+    pub reason: String,
+    pub paused_at: chrono::NaiveDateTime,
+}
+
+// A rolling volume limit checked as a bridge tx is recorded: the volume bridged since
+// `since`, optionally by one user and/or on one route, may not exceed `limit`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeVolumeLimit {
+    pub user_id: Option<String>,
+    pub route: Option<(String, String)>,
+    pub since: chrono::NaiveDateTime,
+    pub limit: f64,
 }
 
 // Community Events (AMAs & Events)